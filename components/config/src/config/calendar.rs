@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Calendar {
+    /// The sections whose dated pages are written to `events.ics`, relative to the `content`
+    /// directory, eg `["meetups"]`. No calendar is generated if it's empty
+    pub sections: Vec<String>,
+}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use errors::{bail, Result};
+
+/// The extensions Zola has a built-in converter for
+pub const BUILTIN_CONVERTERS: &[&str] = &["org", "rst"];
+
+/// What a converter outputs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConverterOutput {
+    /// Markdown, rendered like the content of the Markdown pages
+    Markdown,
+    /// HTML, used as the content of the page as it is
+    Html,
+}
+
+impl Default for ConverterOutput {
+    fn default() -> Self {
+        ConverterOutput::Markdown
+    }
+}
+
+/// How the pages written in another format than Markdown are converted
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Converter {
+    /// The program and its arguments, given the content of the file without its front matter on
+    /// its standard input and writing the content of the page on its standard output. The
+    /// built-in converter of the extension is used if it's empty.
+    pub command: Vec<String>,
+    /// What the command outputs. Defaults to Markdown
+    pub output: ConverterOutput,
+}
+
+/// Checks the converters of the config, keyed by the extension of the files they convert
+pub fn validate(converters: &HashMap<String, Converter>) -> Result<()> {
+    for (extension, converter) in converters {
+        if extension == "md" || extension.starts_with('.') {
+            bail!(
+                "`converters.{}` is invalid: the key is the extension of the files without the \
+                 dot, and Markdown files don't need a converter",
+                extension
+            );
+        }
+        if converter.command.is_empty() && converter.output == ConverterOutput::Html {
+            bail!("`converters.{}` needs a `command` to output HTML", extension);
+        }
+        if converter.command.is_empty() && !BUILTIN_CONVERTERS.contains(&extension.as_str()) {
+            bail!(
+                "`converters.{}` needs a `command`: Zola only has built-in converters for {}",
+                extension,
+                BUILTIN_CONVERTERS.join(" and ")
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Deploy {
+    /// The target used when `zola deploy` isn't given one: `rsync`, `s3` or `github-pages`
+    pub target: Option<String>,
+    pub rsync: Option<Rsync>,
+    pub s3: Option<S3>,
+    pub github_pages: GithubPages,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rsync {
+    /// Where to copy the site, eg `user@example.com:/var/www/site`
+    pub destination: String,
+    /// Extra arguments passed to rsync, eg `["-e", "ssh -p 2222"]`
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct S3 {
+    /// The name of the bucket
+    pub bucket: String,
+    /// The prefix of the keys in the bucket, for sites not at the root of it
+    #[serde(default)]
+    pub prefix: String,
+    /// The endpoint of S3-compatible services, eg `https://<account>.r2.cloudflarestorage.com`
+    pub endpoint: Option<String>,
+    /// The region of the bucket, if it's not the one of the AWS CLI configuration
+    pub region: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GithubPages {
+    /// The git remote to push to. Defaults to `origin`
+    pub remote: String,
+    /// The branch GitHub Pages is publishing. Defaults to `gh-pages`
+    pub branch: String,
+    /// The message of the deploy commits
+    pub message: String,
+}
+
+impl Default for GithubPages {
+    fn default() -> Self {
+        GithubPages {
+            remote: "origin".to_string(),
+            branch: "gh-pages".to_string(),
+            message: "Deploy with zola".to_string(),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Drafts {
+    /// Whether drafts loaded with `--drafts` are rendered under an unguessable token path
+    /// and left out of the sitemap, feeds, sections and taxonomies. Defaults to false
+    pub preview_tokens: bool,
+    /// The secret the tokens are derived from. If not set, a random one is used and the
+    /// preview links change on every build
+    pub preview_secret: Option<String>,
+}
@@ -0,0 +1,150 @@
+use libs::toml::Value as Toml;
+
+use errors::{bail, Result};
+
+/// Replaces the `${NAME}` and `${NAME:-default}` in the strings of the config by the value of
+/// the environment variable `NAME`, so secrets and URLs changing between environments don't
+/// have to be written in `config.toml`. `$${` is kept as a literal `${`.
+pub(crate) fn interpolate_env_vars(value: &mut Toml) -> Result<()> {
+    interpolate_value(value, "", &|name| std::env::var(name).ok())
+}
+
+fn interpolate_value(
+    value: &mut Toml,
+    key: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<()> {
+    match value {
+        Toml::String(s) if s.contains('$') => *s = interpolate(s, key, lookup)?,
+        Toml::Array(values) => {
+            for (i, v) in values.iter_mut().enumerate() {
+                interpolate_value(v, &format!("{}[{}]", key, i), lookup)?;
+            }
+        }
+        Toml::Table(table) => {
+            for (k, v) in table.iter_mut() {
+                let key = if key.is_empty() { k.to_string() } else { format!("{}.{}", key, k) };
+                interpolate_value(v, &key, lookup)?;
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn interpolate(s: &str, key: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String> {
+    let mut res = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(start) = rest.find('$') {
+        res.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(escaped) = rest.strip_prefix("$${") {
+            res.push_str("${");
+            rest = escaped;
+            continue;
+        }
+        let inner = match rest.strip_prefix("${") {
+            Some(inner) => inner,
+            None => {
+                res.push('$');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        let end = match inner.find('}') {
+            Some(end) => end,
+            None => bail!("Unclosed `${{` in the value of `{}` in the config", key),
+        };
+        let (name, default) = match inner[..end].split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (&inner[..end], None),
+        };
+        if !is_valid_name(name) {
+            bail!(
+                "`{}` in the value of `{}` in the config is not a valid environment variable name",
+                name,
+                key
+            );
+        }
+
+        match (lookup(name).filter(|v| !v.is_empty()), default) {
+            (Some(v), _) => res.push_str(&v),
+            (None, Some(default)) => res.push_str(default),
+            (None, None) => bail!(
+                "The environment variable `{}` used in the value of `{}` in the config is not set",
+                name,
+                key
+            ),
+        }
+        rest = &inner[end + 1..];
+    }
+    res.push_str(rest);
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "BASE_URL" => Some("https://staging.example.com".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn can_interpolate_env_vars() {
+        let tests = vec![
+            ("${BASE_URL}", "https://staging.example.com"),
+            ("${BASE_URL}/blog", "https://staging.example.com/blog"),
+            ("${MISSING:-https://example.com}", "https://example.com"),
+            ("${EMPTY:-default}", "default"),
+            ("${MISSING:-}", ""),
+            ("${BASE_URL:-https://example.com}", "https://staging.example.com"),
+            ("costs $5", "costs $5"),
+            ("$${BASE_URL}", "${BASE_URL}"),
+            ("no variables", "no variables"),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(interpolate(input, "base_url", &lookup).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn errors_on_invalid_interpolation() {
+        let err = interpolate("${MISSING}", "extra.api_key", &lookup).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The environment variable `MISSING` used in the value of `extra.api_key` in the config is not set"
+        );
+        assert!(interpolate("${BASE_URL", "base_url", &lookup).is_err());
+        assert!(interpolate("${1NVALID}", "base_url", &lookup).is_err());
+    }
+
+    #[test]
+    fn can_interpolate_nested_values() {
+        let mut value: Toml = libs::toml::from_str(
+            r#"
+base_url = "${BASE_URL}"
+[extra]
+urls = ["${BASE_URL}/a", "${MISSING:-b}"]
+"#,
+        )
+        .unwrap();
+        interpolate_value(&mut value, "", &lookup).unwrap();
+        assert_eq!(value["base_url"].as_str(), Some("https://staging.example.com"));
+        assert_eq!(value["extra"]["urls"][0].as_str(), Some("https://staging.example.com/a"));
+        assert_eq!(value["extra"]["urls"][1].as_str(), Some("b"));
+    }
+}
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+use errors::{bail, Result};
+
+/// An icon generated from the source image, with the `<link>` tag pointing to it if any
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Icon {
+    pub filename: &'static str,
+    pub size: u32,
+    /// The `rel` of its `<link>` tag, the icons only listed in the web manifest not having one
+    pub rel: Option<&'static str>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Favicons {
+    /// The image the icons are generated from, relative to the root of the site, eg
+    /// `static/logo.png`. Nothing is generated if it is not set.
+    pub source: Option<String>,
+    /// The name of the site in the web manifest. Defaults to the title of the site
+    pub name: Option<String>,
+    /// The name shown when there isn't enough room for `name`. Defaults to `name`
+    pub short_name: Option<String>,
+    /// The color of the browser UI around the site when installed
+    pub theme_color: String,
+    /// The color shown while the installed site loads
+    pub background_color: String,
+    /// How the installed site is shown: `standalone`, `fullscreen`, `minimal-ui` or `browser`
+    pub display: String,
+}
+
+impl Favicons {
+    /// The PNG icons written next to `favicon.ico`
+    pub const ICONS: &'static [Icon] = &[
+        Icon { filename: "favicon-16x16.png", size: 16, rel: Some("icon") },
+        Icon { filename: "favicon-32x32.png", size: 32, rel: Some("icon") },
+        Icon { filename: "apple-touch-icon.png", size: 180, rel: Some("apple-touch-icon") },
+        Icon { filename: "android-chrome-192x192.png", size: 192, rel: None },
+        Icon { filename: "android-chrome-512x512.png", size: 512, rel: None },
+    ];
+    /// The sizes of the images of `favicon.ico`
+    pub const ICO_SIZES: &'static [u32] = &[16, 32, 48];
+
+    pub fn is_enabled(&self) -> bool {
+        self.source.is_some()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        const DISPLAYS: &[&str] = &["standalone", "fullscreen", "minimal-ui", "browser"];
+        if !DISPLAYS.contains(&self.display.as_str()) {
+            bail!(
+                "`favicons.display` is `{}`, it must be one of `{}`",
+                self.display,
+                DISPLAYS.join("`, `")
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Default for Favicons {
+    fn default() -> Self {
+        Favicons {
+            source: None,
+            name: None,
+            short_name: None,
+            theme_color: "#ffffff".to_string(),
+            background_color: "#ffffff".to_string(),
+            display: "standalone".to_string(),
+        }
+    }
+}
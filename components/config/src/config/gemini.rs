@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Gemini {
+    /// Whether to render the pages and sections to gemtext as well. Defaults to false
+    pub generate: bool,
+    /// Where the gemtext files are written, relative to the site directory
+    pub output_dir: String,
+}
+
+impl Default for Gemini {
+    fn default() -> Self {
+        Gemini { generate: false, output_dir: "public-gemini".to_string() }
+    }
+}
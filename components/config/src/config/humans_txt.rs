@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HumansTxt {
+    /// Whether to render `/humans.txt`. Defaults to false
+    pub generate: bool,
+    /// The people behind the site, one entry per person
+    pub team: Vec<String>,
+    /// The people to thank, one entry per person
+    pub thanks: Vec<String>,
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Images {
+    /// The widths images are resized to for their `srcset` by `responsive_image`.
+    /// Empty by default, in which case `responsive_image` needs to be given its widths.
+    pub breakpoints: Vec<u32>,
+    /// The `sizes` attribute to use alongside the `srcset`, if any
+    pub sizes: Option<String>,
+    /// Whether to render the images colocated with a page and referenced in its markdown
+    /// with the `image` shortcode, to get a `srcset`. Defaults to false
+    pub markdown_srcset: bool,
+    /// Whether to also generate WebP versions of the responsive images, rendered in a `<picture>`
+    /// by the markdown images. Defaults to false
+    pub webp: bool,
+    /// Whether to remove the EXIF metadata of the JPEG files colocated with pages and sections
+    /// when copying them, rotating them according to their orientation first. Defaults to false
+    pub strip_exif: bool,
+}
+
+impl Images {
+    /// Whether the images referenced in markdown get a `srcset`
+    pub fn has_markdown_srcset(&self) -> bool {
+        self.markdown_srcset && !self.breakpoints.is_empty()
+    }
+}
@@ -1,6 +1,7 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use errors::{bail, Result};
+use libs::chrono::Locale;
 use libs::unic_langid::LanguageIdentifier;
 use serde::{Deserialize, Serialize};
 
@@ -14,6 +15,12 @@ pub struct LanguageOptions {
     pub title: Option<String>,
     /// Description of the site. Defaults to None
     pub description: Option<String>,
+    /// Whether the language is written from right to left, like Arabic or Hebrew.
+    /// Defaults to `false`
+    pub rtl: bool,
+    /// The locale of the names of months and days in its dates, eg `fr_FR`. English names are
+    /// used if not set
+    pub locale: Option<String>,
     /// Whether to generate a feed for that language, defaults to `false`
     pub generate_feed: bool,
     /// The filename to use for feeds. Used to find the template, too.
@@ -27,7 +34,18 @@ pub struct LanguageOptions {
     /// A toml crate `Table` with String key representing term and value
     /// another `String` representing its translation.
     /// Use `get_translation()` method for translating key into different languages.
-    pub translations: HashMap<String, String>,
+    pub translations: BTreeMap<String, String>,
+}
+
+impl LanguageOptions {
+    /// The value of the `dir` HTML attribute for that language: `rtl` or `ltr`
+    pub fn text_direction(&self) -> &'static str {
+        if self.rtl {
+            "rtl"
+        } else {
+            "ltr"
+        }
+    }
 }
 
 /// We want to ensure the language codes are valid ones
@@ -38,3 +56,18 @@ pub fn validate_code(code: &str) -> Result<()> {
 
     Ok(())
 }
+
+/// The dates can only be formatted in the locales chrono knows the names of months and days of
+pub fn validate_locale(code: &str, locale: Option<&str>) -> Result<()> {
+    if let Some(locale) = locale {
+        if Locale::try_from(locale).is_err() {
+            bail!(
+                "The locale `{}` of the language `{}` is not supported, it should look like `fr_FR`",
+                locale,
+                code
+            );
+        }
+    }
+
+    Ok(())
+}
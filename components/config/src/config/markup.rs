@@ -1,4 +1,7 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use libs::syntect::{
     highlighting::{Theme, ThemeSet},
@@ -8,10 +11,13 @@ use libs::syntect::{
 use serde::{Deserialize, Serialize};
 
 use errors::{bail, Result};
+use utils::types::RawHtml;
 
 use crate::highlighting::{CLASS_STYLE, THEME_SET};
 
 pub const DEFAULT_HIGHLIGHT_THEME: &str = "base16-ocean-dark";
+/// The directory, relative to the site root, whose syntaxes and themes are loaded without configuration
+pub const DEFAULT_SYNTAXES_DIR: &str = "syntaxes";
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(default)]
@@ -27,6 +33,8 @@ pub struct ThemeCss {
 pub struct Markdown {
     /// Whether to highlight all code blocks found in markdown files. Defaults to false
     pub highlight_code: bool,
+    /// Whether to highlight inline code hinted with a language, like `rust:Vec<u8>`. Defaults to false
+    pub highlight_inline_code: bool,
     /// Which themes to use for code highlighting. See Readme for supported themes
     /// Defaults to "base16-ocean-dark"
     pub highlight_theme: String,
@@ -44,7 +52,18 @@ pub struct Markdown {
     /// Whether smart punctuation is enabled (changing quotes, dashes, dots etc in their typographic form)
     pub smart_punctuation: bool,
     /// A list of directories to search for additional `.sublime-syntax` and `.tmTheme` files in.
+    /// The `syntaxes` directory of the site is always searched if it exists.
     pub extra_syntaxes_and_themes: Vec<String>,
+    /// What to do with the raw HTML of the Markdown content, unless a section sets its own
+    /// `raw_html` policy. Defaults to `pass`
+    pub raw_html: RawHtml,
+    /// Whether the sections can only set a stricter `raw_html` policy than this one, so the authors
+    /// of their content can't loosen it. Defaults to false
+    pub raw_html_locked: bool,
+    /// The tags kept by the `sanitize` policy, replacing the default list if not empty
+    pub raw_html_allowed_tags: Vec<String>,
+    /// The attributes kept on any tag by the `sanitize` policy, on top of the default ones
+    pub raw_html_allowed_attributes: Vec<String>,
     /// The compiled extra syntaxes into a syntax set
     #[serde(skip_serializing, skip_deserializing)] // not a typo, 2 are need
     pub extra_syntax_set: Option<SyntaxSet>,
@@ -54,6 +73,18 @@ pub struct Markdown {
 }
 
 impl Markdown {
+    /// The tags whose content is removed by the `sanitize` raw HTML policy, which can't be allowed
+    pub const RAW_HTML_CLEANED_TAGS: &'static [&'static str] = &["script", "style"];
+
+    pub fn validate(&self) -> Result<()> {
+        for tag in &self.raw_html_allowed_tags {
+            if Self::RAW_HTML_CLEANED_TAGS.contains(&tag.to_lowercase().as_str()) {
+                bail!("`markdown.raw_html_allowed_tags` can't contain `{}`", tag);
+            }
+        }
+        Ok(())
+    }
+
     /// Gets the configured highlight theme from the THEME_SET or the config's extra_theme_set
     /// Returns None if the configured highlighting theme is set to use css
     pub fn get_highlight_theme(&self) -> Option<&Theme> {
@@ -72,20 +103,47 @@ impl Markdown {
             .or_else(|| THEME_SET.themes.get(theme_name))
     }
 
-    /// Attempt to load any extra syntaxes and themes found in the extra_syntaxes_and_themes folders
+    /// The folders to load extra syntaxes and themes from: the extra_syntaxes_and_themes ones
+    /// and the `syntaxes` one if it exists.
+    /// The folders are searched recursively so the ones inside another one are left out, as they
+    /// would be loaded twice
+    fn extra_syntaxes_and_themes_dirs(&self, base_path: &Path) -> Vec<PathBuf> {
+        let default_dir = base_path.join(DEFAULT_SYNTAXES_DIR);
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for dir in self
+            .extra_syntaxes_and_themes
+            .iter()
+            .map(|dir| base_path.join(dir))
+            .chain(Some(default_dir).filter(|dir| dir.is_dir()))
+        {
+            // A folder that doesn't exist is kept as is for the loading to error on
+            let dir = dir.canonicalize().unwrap_or(dir);
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        dirs.iter()
+            .filter(|dir| !dirs.iter().any(|other| other != *dir && dir.starts_with(other)))
+            .cloned()
+            .collect()
+    }
+
+    /// Attempt to load any extra syntaxes and themes found in the extra_syntaxes_and_themes
+    /// folders and in the `syntaxes` folder
     pub fn load_extra_syntaxes_and_highlight_themes(
         &self,
         base_path: &Path,
     ) -> Result<(Option<SyntaxSet>, Option<ThemeSet>)> {
-        if self.extra_syntaxes_and_themes.is_empty() {
+        let dirs = self.extra_syntaxes_and_themes_dirs(base_path);
+        if dirs.is_empty() {
             return Ok((None, None));
         }
 
         let mut ss = SyntaxSetBuilder::new();
         let mut ts = ThemeSet::new();
-        for dir in &self.extra_syntaxes_and_themes {
-            ss.add_from_folder(base_path.join(dir), true)?;
-            ts.add_from_folder(base_path.join(dir))?;
+        for dir in dirs {
+            ss.add_from_folder(&dir, true)?;
+            ts.add_from_folder(&dir)?;
         }
         let ss = ss.build();
 
@@ -188,6 +246,7 @@ impl Default for Markdown {
     fn default() -> Markdown {
         Markdown {
             highlight_code: false,
+            highlight_inline_code: false,
             highlight_theme: DEFAULT_HIGHLIGHT_THEME.to_owned(),
             highlight_themes_css: Vec::new(),
             render_emoji: false,
@@ -196,6 +255,10 @@ impl Default for Markdown {
             external_links_no_referrer: false,
             smart_punctuation: false,
             extra_syntaxes_and_themes: vec![],
+            raw_html: RawHtml::Pass,
+            raw_html_locked: false,
+            raw_html_allowed_tags: Vec::new(),
+            raw_html_allowed_attributes: Vec::new(),
             extra_syntax_set: None,
             extra_theme_set: Arc::new(None),
         }
@@ -0,0 +1,138 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use errors::{bail, Context, Result};
+use utils::site::resolve_internal_link;
+
+/// An entry of a menu, eg `[[menu.main]]`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MenuItem {
+    pub name: String,
+    /// Where the entry links to: a content path like `@/blog/_index.md`, a path of the site
+    /// like `/about/` or an external URL
+    pub url: String,
+    /// The entries are sorted by weight, the ones with the same weight staying in the order
+    /// of the config
+    pub weight: usize,
+    pub children: Vec<MenuItem>,
+    /// The full URL of `url`, set once the content is loaded
+    #[serde(skip_deserializing)]
+    pub permalink: String,
+}
+
+impl MenuItem {
+    fn resolve(
+        &mut self,
+        permalinks: &HashMap<String, String>,
+        make_permalink: &dyn Fn(&str) -> String,
+    ) -> Result<()> {
+        self.permalink = if self.url.starts_with("@/") {
+            resolve_internal_link(&self.url, permalinks)
+                .with_context(|| {
+                    format!("The menu entry `{}` links to a page that doesn't exist", self.name)
+                })?
+                .permalink
+        } else if self.url.starts_with('/') {
+            make_permalink(&self.url)
+        } else {
+            self.url.clone()
+        };
+
+        self.children.sort_by_key(|c| c.weight);
+        for child in &mut self.children {
+            child.resolve(permalinks, make_permalink)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks that every entry of the menus has a name and a URL
+pub fn validate(menus: &BTreeMap<String, Vec<MenuItem>>) -> Result<()> {
+    fn validate_items(menu: &str, items: &[MenuItem]) -> Result<()> {
+        for item in items {
+            if item.name.is_empty() {
+                bail!("An entry of the menu `{}` needs a `name`", menu);
+            }
+            if item.url.is_empty() {
+                bail!("The entry `{}` of the menu `{}` needs a `url`", item.name, menu);
+            }
+            validate_items(menu, &item.children)?;
+        }
+        Ok(())
+    }
+
+    for (menu, items) in menus {
+        validate_items(menu, items)?;
+    }
+    Ok(())
+}
+
+/// Sets the `permalink` of the entries of the menus and sorts them by weight, once the
+/// permalinks of the content are known
+pub fn resolve(
+    menus: &mut BTreeMap<String, Vec<MenuItem>>,
+    permalinks: &HashMap<String, String>,
+    make_permalink: &dyn Fn(&str) -> String,
+) -> Result<()> {
+    for (menu, items) in menus.iter_mut() {
+        items.sort_by_key(|i| i.weight);
+        for item in items {
+            item.resolve(permalinks, make_permalink)
+                .with_context(|| format!("Failed to resolve the menu `{}`", menu))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str, url: &str, weight: usize) -> MenuItem {
+        MenuItem { name: name.to_string(), url: url.to_string(), weight, ..Default::default() }
+    }
+
+    #[test]
+    fn can_resolve_menus() {
+        let mut about = item("About", "/about/", 2);
+        about.children = vec![item("Team", "@/team.md#people", 2), item("Jobs", "/jobs/", 1)];
+        let mut menus = BTreeMap::new();
+        menus.insert(
+            "main".to_string(),
+            vec![about, item("Blog", "@/blog/_index.md", 1), item("Code", "https://git.io", 3)],
+        );
+        let mut permalinks = HashMap::new();
+        permalinks.insert("blog/_index.md".to_string(), "https://a.com/blog/".to_string());
+        permalinks.insert("team.md".to_string(), "https://a.com/team/".to_string());
+
+        resolve(&mut menus, &permalinks, &|path| format!("https://a.com{}", path)).unwrap();
+        let main = &menus["main"];
+        let names: Vec<_> = main.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["Blog", "About", "Code"]);
+        let permalinks: Vec<_> = main.iter().map(|i| i.permalink.as_str()).collect();
+        assert_eq!(permalinks, ["https://a.com/blog/", "https://a.com/about/", "https://git.io"]);
+        assert_eq!(main[1].children[0].name, "Jobs");
+        assert_eq!(main[1].children[1].permalink, "https://a.com/team/#people");
+    }
+
+    #[test]
+    fn errors_on_missing_content() {
+        let mut menus = BTreeMap::new();
+        menus.insert("main".to_string(), vec![item("Blog", "@/blog/_index.md", 0)]);
+        let err = resolve(&mut menus, &HashMap::new(), &|path| path.to_string()).unwrap_err();
+        assert_eq!(err.to_string(), "Failed to resolve the menu `main`");
+        assert_eq!(err.root_cause().to_string(), "Relative link @/blog/_index.md not found.");
+    }
+
+    #[test]
+    fn errors_on_entry_without_url() {
+        let mut menus = BTreeMap::new();
+        let mut parent = item("Docs", "/docs/", 0);
+        parent.children = vec![item("API", "", 0)];
+        menus.insert("main".to_string(), vec![parent]);
+        let err = validate(&menus).unwrap_err();
+        assert_eq!(err.to_string(), "The entry `API` of the menu `main` needs a `url`");
+    }
+}
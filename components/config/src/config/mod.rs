@@ -1,19 +1,43 @@
+pub mod calendar;
+pub mod converters;
+pub mod deploy;
+pub mod drafts;
+mod env;
+pub mod favicons;
+pub mod gemini;
+pub mod humans_txt;
+pub mod images;
 pub mod languages;
 pub mod link_checker;
 pub mod markup;
+pub mod menus;
+pub mod netlify;
+pub mod newsletter;
+pub mod orphan_report;
+pub mod ping;
+pub mod plugins;
+pub mod podcast;
+pub mod related;
+pub mod render_cache;
+mod schema;
 pub mod search;
+pub mod security_txt;
+pub mod serve;
 pub mod slugify;
 pub mod taxonomies;
+pub mod templates;
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
 use libs::globset::{Glob, GlobSet, GlobSetBuilder};
+use libs::percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 use libs::toml::Value as Toml;
 use serde::{Deserialize, Serialize};
 
 use crate::theme::Theme;
-use errors::{anyhow, bail, Result};
+use errors::{anyhow, bail, Context, Result};
 use utils::fs::read_file;
 use utils::slugs::slugify_paths;
 
@@ -28,6 +52,18 @@ pub enum Mode {
     Check,
 }
 
+/// The files rendered for every page, next to each other in the directory of the page
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// `index.html`, rendered with the page template
+    Html,
+    /// `index.json`, the serialized page as given to the templates
+    Json,
+    /// `index.txt`, rendered with the `page.txt` template
+    Txt,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 #[serde(default)]
 pub struct Config {
@@ -43,10 +79,19 @@ pub struct Config {
 
     /// The language used in the site. Defaults to "en"
     pub default_language: String,
-    /// The list of supported languages outside of the default one
+    /// Whether the default language is written from right to left, like Arabic or Hebrew.
+    /// Defaults to `false`
+    pub rtl: bool,
+    /// The locale of the names of months and days in the dates of the default language,
+    /// eg `fr_FR`. English names are used if not set
+    pub locale: Option<String>,
+    /// The options of every language, the ones of the default language being taken from the
+    /// top level of the config if it's not in `[languages]`
+    /// Not taken from `Config::default()` when missing, which already has the default language
+    #[serde(default)]
     pub languages: HashMap<String, languages::LanguageOptions>,
     /// The translations strings for the default language
-    translations: HashMap<String, String>,
+    translations: BTreeMap<String, String>,
 
     /// Whether to generate a feed. Defaults to false.
     pub generate_feed: bool,
@@ -55,14 +100,42 @@ pub struct Config {
     /// The filename to use for feeds. Used to find the template, too.
     /// Defaults to "atom.xml", with "rss.xml" also having a template provided out of the box.
     pub feed_filename: String,
+    /// The pattern used for the path of pages when their section doesn't set a `permalink`,
+    /// eg `/:year/:month/:slug/`. Defaults to the path of the file in the content directory.
+    pub default_permalink: Option<String>,
+    /// The path used by paginated sections that don't set their own `paginate_path`.
+    /// An empty string puts the page number directly after the section path, eg `/blog/2/`
+    pub paginate_path: String,
+    /// The files rendered for every page that doesn't set its own `outputs`. Defaults to `["html"]`.
+    pub outputs: Vec<OutputFormat>,
+    /// Whether to generate yearly and monthly archive pages for the dated pages. Defaults to false.
+    pub generate_archive: bool,
+    /// Whether to write a `zola-manifest.json` listing the assets of the output directory.
+    /// Defaults to false.
+    pub generate_asset_manifest: bool,
     /// If set, files from static/ will be hardlinked instead of copied to the output dir.
     pub hard_link_static: bool,
+    /// The size in bytes from which the files of `static` are hard-linked when `hard_link_static`
+    /// is set, the smaller ones being copied. Defaults to 0, hard-linking all of them.
+    pub hard_link_static_min_size: u64,
+    /// Whether to follow the symlinks of the `content` and `static` directories. Defaults to true.
+    /// Symlinks are left out if not followed.
+    pub follow_symlinks: bool,
     pub taxonomies: Vec<taxonomies::TaxonomyConfig>,
 
     /// Whether to compile the `sass` directory and output the css files into the static folder
     pub compile_sass: bool,
     /// Whether to minify the html output
     pub minify_html: bool,
+    /// Whether to write gzip and brotli compressed copies of the HTML, CSS, JS and XML output
+    pub precompress: bool,
+    /// Whether `zola build` renders the templates of the sections one after the other instead of
+    /// all at once, to keep the memory usage of sites with tens of thousands of pages down. The
+    /// Markdown of every page is still rendered and kept when loading the site. Defaults to false.
+    pub low_memory: bool,
+    /// Whether the pages listed in sections, paginators, taxonomies and other pages are
+    /// serialized without their `content`, which is faster for large sites. Defaults to false.
+    pub listings_without_content: bool,
     /// Whether to build the search index for the content
     pub build_search_index: bool,
     /// A list of file glob patterns to ignore when processing the content folder. Defaults to none.
@@ -71,6 +144,16 @@ pub struct Config {
     pub ignored_content: Vec<String>,
     #[serde(skip_serializing, skip_deserializing)] // not a typo, 2 are needed
     pub ignored_content_globset: Option<GlobSet>,
+    /// A list of file glob patterns of the colocated assets not to copy to the output directory,
+    /// relative to the directory of their page or section, eg `*.psd`. Defaults to none.
+    pub ignored_assets: Vec<String>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub ignored_assets_globset: Option<GlobSet>,
+    /// A list of file glob patterns of the files of the `static` directories not to copy to the
+    /// output directory, relative to the `static` directory. Defaults to none.
+    pub ignored_static: Vec<String>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub ignored_static_globset: Option<GlobSet>,
 
     /// The mode Zola is currently being ran on. Some logging/feature can differ depending on the
     /// command being used.
@@ -84,10 +167,51 @@ pub struct Config {
     pub slugify: slugify::Slugify,
     /// The search config, telling what to include in the search index
     pub search: search::Search,
+    /// How to find the related pages of each page from their taxonomies
+    pub related_pages: related::RelatedPages,
+    /// The responsive images setup
+    pub images: images::Images,
+    /// The `_redirects` and `_headers` files to generate for Netlify
+    pub netlify: netlify::Netlify,
+    /// Where `zola deploy` uploads the site
+    pub deploy: deploy::Deploy,
+    /// How drafts are rendered when building with `--drafts`
+    pub drafts: drafts::Drafts,
+    /// The gemtext version of the site for Gemini
+    pub gemini: gemini::Gemini,
+    /// The email-friendly version of the dated pages
+    pub newsletter: newsletter::Newsletter,
+    /// The internal page listing the pages that are not in any section
+    pub orphan_report: orphan_report::OrphanReport,
+    /// Reusing the HTML of the unchanged pages between builds
+    pub render_cache: render_cache::RenderCache,
+    /// Where the templates are loaded from besides the `templates` directories of the site
+    /// and of its theme
+    pub templates: templates::Templates,
+    /// How the pages in other formats than Markdown are converted, by file extension
+    pub converters: HashMap<String, converters::Converter>,
+    /// The external programs transforming the pages during the build
+    pub plugins: Vec<plugins::Plugin>,
+    /// The navigation menus given to the templates by name, eg `[[menu.main]]`
+    pub menu: BTreeMap<String, Vec<menus::MenuItem>>,
+    /// The sections exported to the `events.ics` calendar
+    pub calendar: calendar::Calendar,
+    /// The iTunes fields of the podcast feeds
+    pub podcast: podcast::Podcast,
+    /// Who to notify of the changes with `zola ping`
+    pub ping: ping::Ping,
+    /// Options only used by `zola serve`
+    pub serve: serve::Serve,
+    /// The icons and web manifest generated from a single image
+    pub favicons: favicons::Favicons,
+    /// The content of the `humans.txt` file
+    pub humans_txt: humans_txt::HumansTxt,
+    /// The content of the `.well-known/security.txt` file
+    pub security_txt: security_txt::SecurityTxt,
     /// The config for the Markdown rendering: syntax highlighting and everything
     pub markdown: markup::Markdown,
     /// All user params set in `[extra]` in the config
-    pub extra: HashMap<String, Toml>,
+    pub extra: BTreeMap<String, Toml>,
 }
 
 #[derive(Serialize)]
@@ -96,13 +220,16 @@ pub struct SerializedConfig<'a> {
     mode: Mode,
     title: &'a Option<String>,
     description: &'a Option<String>,
-    languages: HashMap<&'a String, &'a languages::LanguageOptions>,
+    rtl: bool,
+    languages: BTreeMap<&'a String, &'a languages::LanguageOptions>,
     default_language: &'a str,
     generate_feed: bool,
     feed_filename: &'a str,
     taxonomies: &'a [taxonomies::TaxonomyConfig],
     build_search_index: bool,
-    extra: &'a HashMap<String, Toml>,
+    podcast: &'a podcast::Podcast,
+    menu: &'a BTreeMap<String, Vec<menus::MenuItem>>,
+    extra: &'a BTreeMap<String, Toml>,
 }
 
 impl Config {
@@ -110,40 +237,52 @@ impl Config {
     /// Parses a string containing TOML to our Config struct
     /// Any extra parameter will end up in the extra field
     pub fn parse(content: &str) -> Result<Config> {
-        let mut config: Config = match libs::toml::from_str(content) {
-            Ok(c) => c,
-            Err(e) => bail!(e),
+        // Environment variables are replaced in the parsed values rather than in the file
+        // so their value can't change the structure of the config
+        let mut config: Config = if content.contains("${") {
+            let mut value: Toml = match libs::toml::from_str(content) {
+                Ok(v) => v,
+                Err(e) => bail!(e),
+            };
+            env::interpolate_env_vars(&mut value)?;
+            match value.try_into() {
+                Ok(c) => c,
+                Err(e) => bail!(e),
+            }
+        } else {
+            match libs::toml::from_str(content) {
+                Ok(c) => c,
+                Err(e) => bail!(e),
+            }
         };
 
+        // The syntax is already known to be valid at this point
+        schema::check_unknown_keys(&libs::toml::from_str(content)?)?;
+
         if config.base_url.is_empty() || config.base_url == DEFAULT_BASE_URL {
             bail!("A base URL is required in config.toml with key `base_url`");
         }
 
         languages::validate_code(&config.default_language)?;
-        for code in config.languages.keys() {
+        languages::validate_locale(&config.default_language, config.locale.as_deref())?;
+        for (code, options) in &config.languages {
             languages::validate_code(code)?;
+            languages::validate_locale(code, options.locale.as_deref())?;
         }
 
+        config.markdown.validate()?;
+        config.security_txt.validate()?;
+        config.favicons.validate()?;
+        converters::validate(&config.converters)?;
+        plugins::validate(&config.plugins)?;
+        menus::validate(&config.menu)?;
+
         config.add_default_language();
         config.slugify_taxonomies();
 
-        if !config.ignored_content.is_empty() {
-            // Convert the file glob strings into a compiled glob set matcher. We want to do this once,
-            // at program initialization, rather than for every page, for example. We arrange for the
-            // globset matcher to always exist (even though it has to be an inside an Option at the
-            // moment because of the TOML serializer); if the glob set is empty the `is_match` function
-            // of the globber always returns false.
-            let mut glob_set_builder = GlobSetBuilder::new();
-            for pat in &config.ignored_content {
-                let glob = match Glob::new(pat) {
-                    Ok(g) => g,
-                    Err(e) => bail!("Invalid ignored_content glob pattern: {}, error = {}", pat, e),
-                };
-                glob_set_builder.add(glob);
-            }
-            config.ignored_content_globset =
-                Some(glob_set_builder.build().expect("Bad ignored_content in config file."));
-        }
+        config.ignored_content_globset = build_globset(&config.ignored_content, "ignored_content")?;
+        config.ignored_assets_globset = build_globset(&config.ignored_assets, "ignored_assets")?;
+        config.ignored_static_globset = build_globset(&config.ignored_static, "ignored_static")?;
 
         Ok(config)
     }
@@ -157,8 +296,31 @@ impl Config {
 
     /// Parses a config file from the given path
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Config> {
+        Config::from_file_in_env(path, None)
+    }
+
+    /// Parses a config file from the given path with the overlay of the environment merged in,
+    /// eg `config.staging.toml` for `config.toml` in `staging`
+    pub fn from_file_in_env<P: AsRef<Path>>(path: P, env: Option<&str>) -> Result<Config> {
         let path = path.as_ref();
-        let content = read_file(path)?;
+        let mut content = read_file(path)?;
+
+        if let Some(env) = env {
+            let overlay_path = env_overlay_path(path, env)?;
+            if !overlay_path.exists() {
+                bail!(
+                    "The `{}` environment was selected but {} doesn't exist",
+                    env,
+                    overlay_path.display()
+                );
+            }
+            let mut value: Toml = libs::toml::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            let overlay: Toml = libs::toml::from_str(&read_file(&overlay_path)?)
+                .with_context(|| format!("Failed to parse {}", overlay_path.display()))?;
+            merge_overlay(&mut value, overlay);
+            content = libs::toml::to_string(&value)?;
+        }
 
         let mut config = Config::parse(&content)?;
         let config_dir = path
@@ -179,8 +341,15 @@ impl Config {
         }
     }
 
-    /// Makes a url, taking into account that the base url might have a trailing slash
+    /// Makes a url, taking into account that the base url might have a trailing slash.
+    /// The characters that can't be in a URL, like non-ASCII ones, are percent-encoded and the
+    /// query and fragment of the path, if any, are kept after the trailing slash
     pub fn make_permalink(&self, path: &str) -> String {
+        let (path, suffix) = match path.find(['?', '#']) {
+            Some(index) => path.split_at(index),
+            None => (path, ""),
+        };
+        let path = collapse_slashes(path.trim_start_matches('/'));
         let trailing_bit =
             if path.ends_with('/') || path.ends_with(&self.feed_filename) || path.is_empty() {
                 ""
@@ -188,19 +357,22 @@ impl Config {
                 "/"
             };
 
-        // Index section with a base url that has a trailing slash
-        if self.base_url.ends_with('/') && path == "/" {
-            self.base_url.clone()
-        } else if path == "/" {
-            // index section with a base url that doesn't have a trailing slash
-            format!("{}/", self.base_url)
-        } else if self.base_url.ends_with('/') && path.starts_with('/') {
-            format!("{}{}{}", self.base_url, &path[1..], trailing_bit)
-        } else if self.base_url.ends_with('/') || path.starts_with('/') {
-            format!("{}{}{}", self.base_url, path, trailing_bit)
-        } else {
-            format!("{}/{}{}", self.base_url, path, trailing_bit)
-        }
+        format!(
+            "{}/{}{}{}",
+            self.base_url.trim_end_matches('/'),
+            utf8_percent_encode(&path, PATH_ENCODE_SET),
+            trailing_bit,
+            utf8_percent_encode(suffix, FRAGMENT_ENCODE_SET)
+        )
+    }
+
+    /// Sets the permalinks of the entries of the menus once the permalinks of the content are
+    /// known
+    pub fn resolve_menus(&mut self, permalinks: &HashMap<String, String>) -> Result<()> {
+        let mut menu = std::mem::take(&mut self.menu);
+        let res = menus::resolve(&mut menu, permalinks, &|path| self.make_permalink(path));
+        self.menu = menu;
+        res
     }
 
     /// Adds the default language to the list of languages if not present
@@ -214,6 +386,8 @@ impl Config {
                 languages::LanguageOptions {
                     title: self.title.clone(),
                     description: self.description.clone(),
+                    rtl: self.rtl,
+                    locale: self.locale.clone(),
                     generate_feed: self.generate_feed,
                     feed_filename: self.feed_filename.clone(),
                     build_search_index: self.build_search_index,
@@ -246,8 +420,8 @@ impl Config {
     }
 
     /// Returns all the languages settings for languages other than the default one
-    pub fn other_languages(&self) -> HashMap<&str, &languages::LanguageOptions> {
-        let mut others = HashMap::new();
+    pub fn other_languages(&self) -> BTreeMap<&str, &languages::LanguageOptions> {
+        let mut others = BTreeMap::new();
         for (k, v) in &self.languages {
             if k == &self.default_language {
                 continue;
@@ -302,7 +476,7 @@ impl Config {
         }
     }
 
-    pub fn serialize(&self, lang: &str) -> SerializedConfig {
+    pub fn serialize(&self, lang: &str) -> SerializedConfig<'_> {
         let options = &self.languages[lang];
 
         SerializedConfig {
@@ -310,17 +484,96 @@ impl Config {
             mode: self.mode,
             title: &options.title,
             description: &options.description,
+            rtl: options.rtl,
             languages: self.languages.iter().filter(|(k, _)| k.as_str() != lang).collect(),
             default_language: &self.default_language,
             generate_feed: options.generate_feed,
             feed_filename: &options.feed_filename,
             taxonomies: &options.taxonomies,
             build_search_index: options.build_search_index,
+            podcast: &self.podcast,
+            menu: &self.menu,
             extra: &self.extra,
         }
     }
 }
 
+/// The characters encoded in the path of permalinks, on top of the non-ASCII ones.
+/// `%` is not encoded so paths that are already encoded aren't encoded twice
+const PATH_ENCODE_SET: &AsciiSet =
+    &CONTROLS.add(b' ').add(b'"').add(b'<').add(b'>').add(b'`').add(b'{').add(b'}').add(b'|');
+/// The characters encoded in the query and fragment of permalinks, on top of the non-ASCII ones
+const FRAGMENT_ENCODE_SET: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'<').add(b'>').add(b'`');
+
+/// Replaces the consecutive slashes of a path by a single one
+fn collapse_slashes(path: &str) -> Cow<'_, str> {
+    if !path.contains("//") {
+        return Cow::Borrowed(path);
+    }
+    let mut collapsed = String::with_capacity(path.len());
+    for c in path.chars() {
+        if c != '/' || !collapsed.ends_with('/') {
+            collapsed.push(c);
+        }
+    }
+    Cow::Owned(collapsed)
+}
+
+/// Convert the file glob strings into a compiled glob set matcher. We want to do this once,
+/// at program initialization, rather than for every page, for example.
+/// Returns `None` if there are no patterns.
+fn build_globset(patterns: &[String], name: &str) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut glob_set_builder = GlobSetBuilder::new();
+    for pat in patterns {
+        let glob = match Glob::new(pat) {
+            Ok(g) => g,
+            Err(e) => bail!("Invalid {} glob pattern: {}, error = {}", name, pat, e),
+        };
+        glob_set_builder.add(glob);
+    }
+    let glob_set =
+        glob_set_builder.build().map_err(|e| anyhow!("Invalid {} glob patterns: {}", name, e))?;
+    Ok(Some(glob_set))
+}
+
+/// The path of the overlay of the config file for the given environment:
+/// `config.staging.toml` for `config.toml` and `staging`
+pub fn env_overlay_path(config_path: &Path, env: &str) -> Result<PathBuf> {
+    if env.is_empty() || !env.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        bail!(
+            "Invalid environment `{}`: only letters, digits, `-` and `_` can be used in its name",
+            env
+        );
+    }
+    let stem = config_path.file_stem().and_then(|s| s.to_str()).unwrap_or("config");
+    let file_name = match config_path.extension().and_then(|e| e.to_str()) {
+        Some(extension) => format!("{}.{}.{}", stem, env, extension),
+        None => format!("{}.{}", stem, env),
+    };
+    Ok(config_path.with_file_name(file_name))
+}
+
+/// Merges the overlay of an environment into the config: tables are merged recursively and
+/// any other value of the overlay, arrays included, replaces the one of the config
+fn merge_overlay(into: &mut Toml, overlay: Toml) {
+    match (into, overlay) {
+        (Toml::Table(into_table), Toml::Table(overlay_table)) => {
+            for (key, val) in overlay_table {
+                match into_table.get_mut(&key) {
+                    Some(existing) => merge_overlay(existing, val),
+                    None => {
+                        into_table.insert(key, val);
+                    }
+                }
+            }
+        }
+        (into, overlay) => *into = overlay,
+    }
+}
+
 // merge TOML data that can be a table, or anything else
 pub fn merge(into: &mut Toml, from: &Toml) -> Result<()> {
     match (from.is_table(), into.is_table()) {
@@ -351,32 +604,71 @@ pub fn merge(into: &mut Toml, from: &Toml) -> Result<()> {
 
 impl Default for Config {
     fn default() -> Config {
-        Config {
+        let mut config = Config {
             base_url: DEFAULT_BASE_URL.to_string(),
             title: None,
             description: None,
             theme: None,
             default_language: "en".to_string(),
+            rtl: false,
+            locale: None,
             languages: HashMap::new(),
             generate_feed: false,
             feed_limit: None,
             feed_filename: "atom.xml".to_string(),
+            default_permalink: None,
+            paginate_path: "page".to_string(),
+            outputs: vec![OutputFormat::Html],
+            generate_archive: false,
+            generate_asset_manifest: false,
             hard_link_static: false,
+            hard_link_static_min_size: 0,
+            follow_symlinks: true,
             taxonomies: Vec::new(),
             compile_sass: false,
             minify_html: false,
+            precompress: false,
+            low_memory: false,
+            listings_without_content: false,
             mode: Mode::Build,
             build_search_index: false,
             ignored_content: Vec::new(),
             ignored_content_globset: None,
-            translations: HashMap::new(),
+            ignored_assets: Vec::new(),
+            ignored_assets_globset: None,
+            ignored_static: Vec::new(),
+            ignored_static_globset: None,
+            translations: BTreeMap::new(),
             output_dir: "public".to_string(),
             link_checker: link_checker::LinkChecker::default(),
             slugify: slugify::Slugify::default(),
             search: search::Search::default(),
+            related_pages: related::RelatedPages::default(),
+            images: images::Images::default(),
+            netlify: netlify::Netlify::default(),
+            deploy: deploy::Deploy::default(),
+            drafts: drafts::Drafts::default(),
+            gemini: gemini::Gemini::default(),
+            newsletter: newsletter::Newsletter::default(),
+            orphan_report: orphan_report::OrphanReport::default(),
+            render_cache: render_cache::RenderCache::default(),
+            templates: templates::Templates::default(),
+            converters: HashMap::new(),
+            plugins: Vec::new(),
+            menu: BTreeMap::new(),
+            calendar: calendar::Calendar::default(),
+            podcast: podcast::Podcast::default(),
+            ping: ping::Ping::default(),
+            serve: serve::Serve::default(),
+            favicons: favicons::Favicons::default(),
+            humans_txt: humans_txt::HumansTxt::default(),
+            security_txt: security_txt::SecurityTxt::default(),
             markdown: markup::Markdown::default(),
-            extra: HashMap::new(),
-        }
+            extra: BTreeMap::new(),
+        };
+        // So the options of the default language can always be found
+        config.add_default_language();
+        config
     }
 }
 
@@ -407,6 +699,120 @@ base_url = "https://replace-this-with-your-url.com"
         assert!(config.is_err());
     }
 
+    #[test]
+    fn default_config_has_options_of_default_language() {
+        let config = Config::default();
+        assert!(config.languages.contains_key("en"));
+        assert_eq!(config.serialize("en").default_language, "en");
+    }
+
+    #[test]
+    fn only_adds_configured_default_language() {
+        let config = Config::parse(
+            r#"
+base_url = "https://example.com"
+default_language = "fr"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(config.languages.keys().collect::<Vec<_>>(), vec!["fr"]);
+    }
+
+    #[test]
+    fn can_set_locale_of_languages() {
+        let config = Config::parse(
+            r#"
+base_url = "https://example.com"
+locale = "fr_FR"
+
+[languages.de]
+locale = "de_DE"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(config.languages["en"].locale.as_deref(), Some("fr_FR"));
+        assert_eq!(config.languages["de"].locale.as_deref(), Some("de_DE"));
+
+        let err = Config::parse(
+            r#"
+base_url = "https://example.com"
+
+[languages.tlh]
+locale = "tlh_KLI"
+        "#,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("The locale `tlh_KLI` of the language `tlh` is not supported"));
+    }
+
+    #[test]
+    fn can_find_env_overlay_path() {
+        assert_eq!(
+            env_overlay_path(Path::new("site/config.toml"), "staging").unwrap(),
+            PathBuf::from("site/config.staging.toml")
+        );
+        assert_eq!(
+            env_overlay_path(Path::new("zola.toml"), "prod-eu").unwrap(),
+            PathBuf::from("zola.prod-eu.toml")
+        );
+        assert!(env_overlay_path(Path::new("config.toml"), "../secrets").is_err());
+    }
+
+    #[test]
+    fn can_merge_env_overlay() {
+        let mut config: Toml = libs::toml::from_str(
+            r#"
+base_url = "https://example.com"
+taxonomies = [{name = "tags"}, {name = "categories"}]
+[extra]
+author = "Vincent"
+social = { twitter = "vincent", github = "Keats" }
+        "#,
+        )
+        .unwrap();
+        let overlay: Toml = libs::toml::from_str(
+            r#"
+base_url = "https://staging.example.com"
+taxonomies = [{name = "tags"}]
+[extra.social]
+twitter = "staging"
+        "#,
+        )
+        .unwrap();
+        merge_overlay(&mut config, overlay);
+
+        assert_eq!(config["base_url"].as_str(), Some("https://staging.example.com"));
+        assert_eq!(config["taxonomies"].as_array().unwrap().len(), 1);
+        assert_eq!(config["extra"]["author"].as_str(), Some("Vincent"));
+        assert_eq!(config["extra"]["social"]["twitter"].as_str(), Some("staging"));
+        assert_eq!(config["extra"]["social"]["github"].as_str(), Some("Keats"));
+    }
+
+    #[test]
+    fn can_use_env_vars_with_defaults() {
+        let config = r#"
+base_url = "${ZOLA_TEST_UNSET_BASE_URL:-https://example.com}"
+[extra]
+api_key = "${ZOLA_TEST_UNSET_API_KEY:-dev}"
+        "#;
+
+        let config = Config::parse(config).unwrap();
+        assert_eq!(config.base_url, "https://example.com");
+        assert_eq!(config.extra["api_key"].as_str(), Some("dev"));
+    }
+
+    #[test]
+    fn errors_when_env_var_is_not_set() {
+        let config = r#"
+base_url = "${ZOLA_TEST_UNSET_BASE_URL}"
+        "#;
+
+        let err = Config::parse(config).unwrap_err();
+        assert!(err.to_string().contains("`ZOLA_TEST_UNSET_BASE_URL`"));
+    }
+
     #[test]
     fn errors_when_missing_required_field() {
         // base_url is required
@@ -470,6 +876,53 @@ hello = "world"
         assert_eq!(config.make_permalink("atom.xml"), "http://vincent.is/atom.xml");
     }
 
+    #[test]
+    fn can_make_url_with_trailing_slash_base_url_and_index() {
+        let config = Config { base_url: "http://vincent.is/".to_string(), ..Default::default() };
+        assert_eq!(config.make_permalink("/"), "http://vincent.is/");
+        assert_eq!(config.make_permalink("/hello/"), "http://vincent.is/hello/");
+    }
+
+    #[test]
+    fn make_url_percent_encodes_path() {
+        let config = Config { base_url: "http://vincent.is".to_string(), ..Default::default() };
+        let tests = vec![
+            ("日本", "http://vincent.is/%E6%97%A5%E6%9C%AC/"),
+            ("/tags/c++ & rust/", "http://vincent.is/tags/c++%20&%20rust/"),
+            ("/tags/%E6%97%A5/", "http://vincent.is/tags/%E6%97%A5/"),
+            ("blog/café", "http://vincent.is/blog/caf%C3%A9/"),
+        ];
+        for (path, expected) in tests {
+            assert_eq!(config.make_permalink(path), expected);
+        }
+    }
+
+    #[test]
+    fn make_url_keeps_query_and_fragment() {
+        let config = Config { base_url: "http://vincent.is".to_string(), ..Default::default() };
+        let tests = vec![
+            ("/about#team", "http://vincent.is/about/#team"),
+            ("/about/#team", "http://vincent.is/about/#team"),
+            ("search?q=zola", "http://vincent.is/search/?q=zola"),
+            ("/atom.xml?v=2#top", "http://vincent.is/atom.xml?v=2#top"),
+            ("#équipe", "http://vincent.is/#%C3%A9quipe"),
+        ];
+        for (path, expected) in tests {
+            assert_eq!(config.make_permalink(path), expected);
+        }
+    }
+
+    #[test]
+    fn make_url_avoids_double_slashes() {
+        for base_url in ["http://vincent.is", "http://vincent.is/", "http://vincent.is//"] {
+            let config = Config { base_url: base_url.to_string(), ..Default::default() };
+            assert_eq!(config.make_permalink("//blog//hello/"), "http://vincent.is/blog/hello/");
+            assert_eq!(config.make_permalink("//"), "http://vincent.is/");
+        }
+        let config = Config { base_url: "/".to_string(), ..Default::default() };
+        assert_eq!(config.make_permalink("/blog/"), "/blog/");
+    }
+
     #[test]
     fn can_merge_with_theme_data_and_preserve_config_value() {
         let config_str = r#"
@@ -604,6 +1057,43 @@ ignored_content = ["*.{graphml,iso}", "*.py?", "**/{target,temp_folder}"]
         assert!(g.is_match("content/poetry/zen.py2"));
     }
 
+    #[test]
+    fn can_parse_ignored_assets() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+ignored_assets = ["*.psd", "sources/**"]
+        "#;
+
+        let config = Config::parse(config_str).unwrap();
+        assert!(config.ignored_content_globset.is_none());
+        let g = config.ignored_assets_globset.unwrap();
+        assert!(g.is_match("cover.psd"));
+        assert!(g.is_match("sources/cover.png"));
+        assert!(!g.is_match("cover.png"));
+
+        let config_str = r#"
+base_url = "example.com"
+ignored_assets = ["[*.psd"]
+        "#;
+        assert!(Config::parse(config_str).is_err());
+    }
+
+    #[test]
+    fn can_parse_ignored_static() {
+        let config_str = r#"
+base_url = "example.com"
+ignored_static = ["**/*.map", "drafts"]
+        "#;
+
+        let config = Config::parse(config_str).unwrap();
+        assert!(config.ignored_assets_globset.is_none());
+        let g = config.ignored_static_globset.unwrap();
+        assert!(g.is_match("js/app.js.map"));
+        assert!(g.is_match("drafts"));
+        assert!(!g.is_match("js/app.js"));
+    }
+
     #[test]
     fn link_checker_skip_anchor_prefixes() {
         let config_str = r#"
@@ -662,6 +1152,31 @@ anchors = "off"
         assert_eq!(config.slugify.anchors, SlugifyStrategy::Off);
     }
 
+    #[test]
+    fn can_configure_related_pages() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+
+[related_pages]
+limit = 3
+weights = { categories = 2, authors = 0 }
+        "#;
+
+        let config = Config::parse(config_str).unwrap();
+        assert!(config.related_pages.is_enabled());
+        assert_eq!(config.related_pages.limit, 3);
+        assert_eq!(config.related_pages.weight("categories"), 2);
+        assert_eq!(config.related_pages.weight("authors"), 0);
+        assert_eq!(config.related_pages.weight("tags"), 1);
+    }
+
+    #[test]
+    fn related_pages_are_disabled_by_default() {
+        let config = Config::default();
+        assert!(!config.related_pages.is_enabled());
+    }
+
     #[test]
     fn cannot_overwrite_theme_mapping_with_invalid_type() {
         let config_str = r#"
@@ -744,4 +1259,102 @@ title = "Zola"
         let serialised = config.serialize(&config.default_language);
         assert_eq!(serialised.title, &config.title);
     }
+
+    #[test]
+    fn can_parse_outputs() {
+        let config = Config::parse(r#"base_url = "https://example.com""#).unwrap();
+        assert_eq!(config.outputs, vec![OutputFormat::Html]);
+
+        let config = r#"
+base_url = "https://example.com"
+outputs = ["html", "json"]
+    "#;
+        let config = Config::parse(config).unwrap();
+        assert_eq!(config.outputs, vec![OutputFormat::Html, OutputFormat::Json]);
+
+        let config = r#"
+base_url = "https://example.com"
+outputs = ["pdf"]
+    "#;
+        assert!(Config::parse(config).is_err());
+    }
+
+    #[test]
+    fn security_txt_requires_contact_and_expires() {
+        let config = r#"
+base_url = "https://example.com"
+
+[security_txt]
+generate = true
+contact = ["mailto:security@example.com"]
+    "#;
+        let err = Config::parse(config).unwrap_err();
+        assert!(err.to_string().contains("security_txt.expires"));
+
+        let config = r#"
+base_url = "https://example.com"
+
+[security_txt]
+generate = true
+expires = "2030-01-01T00:00:00Z"
+    "#;
+        let err = Config::parse(config).unwrap_err();
+        assert!(err.to_string().contains("security_txt.contact"));
+
+        // nothing is required if it's not generated
+        let config = r#"
+base_url = "https://example.com"
+
+[security_txt]
+contact = ["mailto:security@example.com"]
+    "#;
+        assert!(Config::parse(config).is_ok());
+    }
+
+    #[test]
+    fn can_configure_converters() {
+        let config = r#"
+base_url = "https://example.com"
+
+[converters.org]
+[converters.adoc]
+command = ["asciidoctor", "-o", "-", "-"]
+output = "html"
+    "#;
+        let config = Config::parse(config).unwrap();
+        assert!(config.converters["org"].command.is_empty());
+        assert_eq!(config.converters["org"].output, converters::ConverterOutput::Markdown);
+        assert_eq!(config.converters["adoc"].command[0], "asciidoctor");
+        assert_eq!(config.converters["adoc"].output, converters::ConverterOutput::Html);
+
+        // Only org and rst have a built-in converter
+        let config = "base_url = \"https://example.com\"\n[converters.adoc]\n";
+        let err = Config::parse(config).unwrap_err();
+        assert!(err.to_string().contains("`converters.adoc` needs a `command`"));
+        let config = "base_url = \"https://example.com\"\n[converters.org]\noutput = \"html\"\n";
+        let err = Config::parse(config).unwrap_err();
+        assert!(err.to_string().contains("`converters.org` needs a `command` to output HTML"));
+    }
+
+    #[test]
+    fn can_configure_plugins() {
+        let config = r#"
+base_url = "https://example.com"
+
+[[plugins]]
+name = "abbreviations"
+command = ["python3", "plugins/abbr.py"]
+hooks = ["parse_markdown", "post_build"]
+    "#;
+        let config = Config::parse(config).unwrap();
+        assert_eq!(config.plugins.len(), 1);
+        assert!(config.plugins[0].runs_at(plugins::PluginHook::ParseMarkdown));
+        assert!(!config.plugins[0].runs_at(plugins::PluginHook::PreRenderPage));
+
+        let config = "base_url = \"https://example.com\"\n[[plugins]]\ncommand = [\"cat\"]\n";
+        let err = Config::parse(config).unwrap_err();
+        assert!(err.to_string().contains("The plugin `#1` needs at least one"));
+        let config = "base_url = \"https://example.com\"\n[[plugins]]\nhooks = [\"post_render\"]\n";
+        assert!(Config::parse(config).is_err());
+    }
 }
@@ -0,0 +1,39 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+fn default_redirect_status() -> u16 {
+    301
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Redirect {
+    /// The path being redirected, eg `/old-blog/*`
+    pub from: String,
+    /// Where to redirect to, eg `/blog/:splat`
+    pub to: String,
+    /// The HTTP status of the redirect. Defaults to 301
+    #[serde(default = "default_redirect_status")]
+    pub status: u16,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Headers {
+    /// The path the headers apply to, eg `/*`
+    #[serde(rename = "for")]
+    pub path: String,
+    /// The headers names and values
+    pub values: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Netlify {
+    /// Whether to write a `_redirects` file with the aliases of the pages and sections
+    /// followed by the `redirects` below. Defaults to false
+    pub generate_redirects: bool,
+    /// Redirects to add to the `_redirects` file, in that order
+    pub redirects: Vec<Redirect>,
+    /// The headers to write in a `_headers` file, which is only written if there are some
+    pub headers: Vec<Headers>,
+}
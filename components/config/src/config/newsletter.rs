@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Newsletter {
+    /// Whether to render the dated pages with the `newsletter.html` template. Defaults to false
+    pub generate: bool,
+    /// Where the newsletter files are written, relative to the site directory
+    pub output_dir: String,
+}
+
+impl Default for Newsletter {
+    fn default() -> Self {
+        Newsletter { generate: false, output_dir: "public-newsletter".to_string() }
+    }
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OrphanReport {
+    /// Whether to render the list of the orphan pages with the `orphans.html` template.
+    /// Defaults to false
+    pub generate: bool,
+    /// Where the report is rendered in the output directory
+    pub path: String,
+}
+
+impl Default for OrphanReport {
+    fn default() -> Self {
+        OrphanReport { generate: false, path: "orphans".to_string() }
+    }
+}
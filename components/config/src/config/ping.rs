@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Ping {
+    /// The WebSub hubs to notify of the feeds update, eg `https://pubsubhubbub.appspot.com/`
+    pub websub_hubs: Vec<String>,
+    /// The endpoints to send the sitemap URL to, as a `sitemap` query parameter
+    pub sitemap_endpoints: Vec<String>,
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+use errors::{bail, Result};
+
+/// When a plugin is run during a build
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginHook {
+    /// Before the Markdown of the pages is rendered, to change their Markdown
+    ParseMarkdown,
+    /// Once the Markdown of the pages is rendered and before their templates are, to change
+    /// their HTML content, title and description
+    PreRenderPage,
+    /// Once the whole site is written in the output directory
+    PostBuild,
+}
+
+impl PluginHook {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PluginHook::ParseMarkdown => "parse_markdown",
+            PluginHook::PreRenderPage => "pre_render_page",
+            PluginHook::PostBuild => "post_build",
+        }
+    }
+}
+
+/// A program run at some steps of the build to transform the pages, given the data of the
+/// pages as JSON on its standard input and writing the changed pages as JSON on its standard
+/// output
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Plugin {
+    /// Only used in the error messages
+    pub name: String,
+    /// The program and its arguments, run from the root of the site
+    pub command: Vec<String>,
+    /// The steps of the build the plugin is run at, in the order of the `plugins` list
+    pub hooks: Vec<PluginHook>,
+}
+
+impl Plugin {
+    pub fn runs_at(&self, hook: PluginHook) -> bool {
+        self.hooks.contains(&hook)
+    }
+}
+
+/// Checks the plugins of the config
+pub fn validate(plugins: &[Plugin]) -> Result<()> {
+    for (i, plugin) in plugins.iter().enumerate() {
+        let name = if plugin.name.is_empty() { format!("#{}", i + 1) } else { plugin.name.clone() };
+        if plugin.command.is_empty() {
+            bail!("The plugin `{}` needs a `command`", name);
+        }
+        if plugin.hooks.is_empty() {
+            bail!(
+                "The plugin `{}` needs at least one of the `parse_markdown`, `pre_render_page` \
+                 and `post_build` hooks",
+                name
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Podcast {
+    /// The author of the podcast, `itunes:author`
+    pub author: Option<String>,
+    /// The name of the owner of the podcast, who podcast directories contact
+    pub owner_name: Option<String>,
+    /// The email of the owner of the podcast
+    pub owner_email: Option<String>,
+    /// The cover art of the podcast, a path from the root of the site or a URL.
+    /// Podcast directories want a square image of at least 1400x1400 pixels
+    pub image: Option<String>,
+    /// The iTunes categories of the podcast, with a `/` before the subcategory,
+    /// eg `["Technology", "Society & Culture/Documentary"]`
+    pub categories: Vec<String>,
+    /// Whether the podcast contains explicit content. Defaults to false
+    pub explicit: bool,
+}
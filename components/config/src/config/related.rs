@@ -0,0 +1,23 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelatedPages {
+    /// How many related pages to find for each page. `0` (the default) disables the feature.
+    pub limit: usize,
+    /// How much a term shared with another page counts, by taxonomy name.
+    /// Taxonomies not listed there have a weight of `1`, set it to `0` to ignore a taxonomy.
+    pub weights: HashMap<String, usize>,
+}
+
+impl RelatedPages {
+    pub fn is_enabled(&self) -> bool {
+        self.limit > 0
+    }
+
+    pub fn weight(&self, taxonomy: &str) -> usize {
+        self.weights.get(taxonomy).copied().unwrap_or(1)
+    }
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderCache {
+    /// Whether to keep the HTML of the pages between builds and reuse it for the pages whose
+    /// templates and template context didn't change. Defaults to false
+    pub enabled: bool,
+    /// The directory the rendered pages are kept in, relative to the root of the site
+    pub path: String,
+}
+
+impl Default for RenderCache {
+    fn default() -> Self {
+        RenderCache { enabled: false, path: ".zola-cache".to_string() }
+    }
+}
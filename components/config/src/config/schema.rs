@@ -0,0 +1,307 @@
+use std::fmt;
+
+use libs::toml::Value as Toml;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+
+use super::{
+    calendar, converters, deploy, drafts, favicons, gemini, humans_txt, images, languages,
+    link_checker, markup, menus, netlify, newsletter, orphan_report, ping, plugins, podcast,
+    related, render_cache, search, security_txt, serve, slugify, taxonomies, templates, Config,
+};
+use errors::{bail, Result};
+use utils::suggestions::closest_matches;
+
+/// Keys that were renamed, with their new name
+const RENAMED_KEYS: &[(&str, &str)] =
+    &[("generate_rss", "generate_feed"), ("rss_limit", "feed_limit")];
+
+/// Only used to get the fields a struct deriving `Deserialize` expects
+struct FieldsDeserializer<'a>(&'a mut &'static [&'static str]);
+
+#[derive(Debug)]
+struct FieldsFound;
+
+impl fmt::Display for FieldsFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fields found")
+    }
+}
+
+impl std::error::Error for FieldsFound {}
+
+impl de::Error for FieldsFound {
+    fn custom<T: fmt::Display>(_: T) -> Self {
+        FieldsFound
+    }
+}
+
+impl<'de, 'a> Deserializer<'de> for FieldsDeserializer<'a> {
+    type Error = FieldsFound;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> std::result::Result<V::Value, FieldsFound> {
+        Err(FieldsFound)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        _: V,
+    ) -> std::result::Result<V::Value, FieldsFound> {
+        *self.0 = fields;
+        Err(FieldsFound)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
+
+/// The keys of the table a struct is deserialized from
+fn fields<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldsDeserializer(&mut fields));
+    fields
+}
+
+/// The keys of the sections of the config, the other ones like `extra` being free-form
+fn section_fields(section: &str) -> Option<&'static [&'static str]> {
+    let fields = match section {
+        "link_checker" => fields::<link_checker::LinkChecker>(),
+        "slugify" => fields::<slugify::Slugify>(),
+        "search" => fields::<search::Search>(),
+        "related_pages" => fields::<related::RelatedPages>(),
+        "images" => fields::<images::Images>(),
+        "netlify" => fields::<netlify::Netlify>(),
+        "deploy" => fields::<deploy::Deploy>(),
+        "drafts" => fields::<drafts::Drafts>(),
+        "gemini" => fields::<gemini::Gemini>(),
+        "newsletter" => fields::<newsletter::Newsletter>(),
+        "orphan_report" => fields::<orphan_report::OrphanReport>(),
+        "render_cache" => fields::<render_cache::RenderCache>(),
+        "calendar" => fields::<calendar::Calendar>(),
+        "podcast" => fields::<podcast::Podcast>(),
+        "ping" => fields::<ping::Ping>(),
+        "serve" => fields::<serve::Serve>(),
+        "favicons" => fields::<favicons::Favicons>(),
+        "humans_txt" => fields::<humans_txt::HumansTxt>(),
+        "security_txt" => fields::<security_txt::SecurityTxt>(),
+        "markdown" => fields::<markup::Markdown>(),
+        "templates" => fields::<templates::Templates>(),
+        _ => return None,
+    };
+    Some(fields)
+}
+
+/// The error for an unknown key, with the keys it could be a typo of
+fn unknown_key(key: &str, path: &str, known: &[&str]) -> String {
+    let full_key = if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+    let mut message = format!("Unknown key `{}`", full_key);
+
+    if path.is_empty() {
+        if let Some((_, new)) = RENAMED_KEYS.iter().find(|(old, _)| *old == key) {
+            message.push_str(&format!(", it was renamed to `{}`", new));
+            return message;
+        }
+        // Keys of the sections are sometimes put at the top level, or were there before
+        let sections = known.iter().filter_map(|s| section_fields(s).map(|f| (s, f)));
+        for (section, fields) in sections {
+            if fields.contains(&key) {
+                message.push_str(&format!(", it belongs in the `[{}]` section", section));
+                return message;
+            }
+        }
+    }
+
+    let suggestions = closest_matches(key, known.iter().copied());
+    if !suggestions.is_empty() {
+        let suggestions: Vec<_> = suggestions.iter().map(|s| format!("`{}`", s)).collect();
+        message.push_str(&format!(", did you mean {}?", suggestions.join(" or ")));
+    }
+    message
+}
+
+fn check_table(value: &Toml, path: &str, known: &[&str], errors: &mut Vec<String>) {
+    if let Some(table) = value.as_table() {
+        for key in table.keys() {
+            if !known.contains(&key.as_str()) {
+                errors.push(unknown_key(key, path, known));
+            }
+        }
+    }
+}
+
+/// Rejects the keys of the config that Zola doesn't know about: they would be ignored
+/// otherwise and a typo would look like a bug
+pub(crate) fn check_unknown_keys(value: &Toml) -> Result<()> {
+    let mut errors = Vec::new();
+    let known = fields::<Config>();
+    check_table(value, "", known, &mut errors);
+
+    if let Some(table) = value.as_table() {
+        for (key, section) in table {
+            if let Some(fields) = section_fields(key) {
+                check_table(section, key, fields, &mut errors);
+            }
+        }
+
+        let taxonomy_fields = fields::<taxonomies::TaxonomyConfig>();
+        let check_taxonomies = |taxonomies: &Toml, path: &str, errors: &mut Vec<String>| {
+            for (i, taxonomy) in taxonomies.as_array().into_iter().flatten().enumerate() {
+                check_table(taxonomy, &format!("{}[{}]", path, i), taxonomy_fields, errors);
+            }
+        };
+        if let Some(taxonomies) = table.get("taxonomies") {
+            check_taxonomies(taxonomies, "taxonomies", &mut errors);
+        }
+
+        let menu_item_fields = fields::<menus::MenuItem>();
+        fn check_menu_items(items: &Toml, path: &str, known: &[&str], errors: &mut Vec<String>) {
+            for (i, item) in items.as_array().into_iter().flatten().enumerate() {
+                let path = format!("{}[{}]", path, i);
+                check_table(item, &path, known, errors);
+                if let Some(children) = item.get("children") {
+                    check_menu_items(children, &format!("{}.children", path), known, errors);
+                }
+            }
+        }
+        for (name, items) in table.get("menu").and_then(|m| m.as_table()).into_iter().flatten() {
+            check_menu_items(items, &format!("menu.{}", name), menu_item_fields, &mut errors);
+        }
+
+        let converter_fields = fields::<converters::Converter>();
+        for (extension, converter) in
+            table.get("converters").and_then(|c| c.as_table()).into_iter().flatten()
+        {
+            check_table(
+                converter,
+                &format!("converters.{}", extension),
+                converter_fields,
+                &mut errors,
+            );
+        }
+
+        let plugin_fields = fields::<plugins::Plugin>();
+        for (i, plugin) in
+            table.get("plugins").and_then(|p| p.as_array()).into_iter().flatten().enumerate()
+        {
+            check_table(plugin, &format!("plugins[{}]", i), plugin_fields, &mut errors);
+        }
+
+        let language_fields = fields::<languages::LanguageOptions>();
+        for (code, options) in
+            table.get("languages").and_then(|l| l.as_table()).into_iter().flatten()
+        {
+            let path = format!("languages.{}", code);
+            check_table(options, &path, language_fields, &mut errors);
+            if let Some(taxonomies) = options.get("taxonomies") {
+                check_taxonomies(taxonomies, &format!("{}.taxonomies", path), &mut errors);
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        bail!("Invalid config:\n- {}", errors.join("\n- "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(config: &str) -> Result<()> {
+        check_unknown_keys(&libs::toml::from_str(config).unwrap())
+    }
+
+    #[test]
+    fn can_find_fields_of_structs() {
+        let config_fields = fields::<Config>();
+        assert!(config_fields.contains(&"base_url"));
+        assert!(config_fields.contains(&"markdown"));
+        // Not deserialized
+        assert!(!config_fields.contains(&"ignored_content_globset"));
+        assert!(fields::<markup::Markdown>().contains(&"highlight_code"));
+    }
+
+    #[test]
+    fn accepts_known_keys() {
+        let config = r#"
+base_url = "https://example.com"
+taxonomies = [{name = "tags", feed = true}]
+[markdown]
+highlight_code = true
+[extra]
+anything = "goes"
+[languages.fr]
+title = "Mon site"
+taxonomies = [{name = "auteurs"}]
+[translations]
+hello = "Hello"
+[converters.org]
+[converters.rst]
+command = ["pandoc", "-f", "rst", "-t", "html"]
+output = "html"
+[[plugins]]
+name = "shout"
+command = ["./shout.sh"]
+hooks = ["parse_markdown"]
+[[menu.main]]
+name = "Docs"
+url = "@/docs/_index.md"
+children = [{name = "API", url = "/api/", weight = 1}]
+"#;
+        assert!(check(config).is_ok());
+    }
+
+    #[test]
+    fn suggests_close_keys() {
+        let err = check("base_url = \"https://example.com\"\ncompile_sas = true").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid config:\n- Unknown key `compile_sas`, did you mean `compile_sass`?"
+        );
+    }
+
+    #[test]
+    fn points_to_renamed_and_moved_keys() {
+        let err = check("generate_rss = true\nhighlight_code = true").unwrap_err().to_string();
+        assert!(err.contains("Unknown key `generate_rss`, it was renamed to `generate_feed`"));
+        assert!(
+            err.contains("Unknown key `highlight_code`, it belongs in the `[markdown]` section")
+        );
+    }
+
+    #[test]
+    fn checks_keys_of_sections_languages_and_taxonomies() {
+        let config = r#"
+taxonomies = [{name = "tags", feeds = true}]
+[markdown]
+highlight_them = "base16-ocean-dark"
+[templates]
+loads = ["shared"]
+[languages.fr]
+titel = "Mon site"
+[converters.rst]
+comand = ["pandoc"]
+[[plugins]]
+command = ["./shout.sh"]
+hook = ["parse_markdown"]
+[[menu.main]]
+name = "Docs"
+children = [{name = "API", link = "/api/"}]
+"#;
+        let err = check(config).unwrap_err().to_string();
+        assert!(err.contains("Unknown key `taxonomies[0].feeds`, did you mean `feed`?"));
+        assert!(
+            err.contains("Unknown key `markdown.highlight_them`, did you mean `highlight_theme`")
+        );
+        assert!(err.contains("Unknown key `languages.fr.titel`, did you mean `title`?"));
+        assert!(err.contains("Unknown key `templates.loads`, did you mean `load`?"));
+        assert!(err.contains("Unknown key `menu.main[0].children[0].link`"));
+        assert!(err.contains("Unknown key `converters.rst.comand`, did you mean `command`?"));
+        assert!(err.contains("Unknown key `plugins[0].hook`, did you mean `hooks`?"));
+    }
+}
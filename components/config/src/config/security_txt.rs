@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+use errors::{bail, Result};
+
+/// The fields of a `security.txt` file, see <https://www.rfc-editor.org/rfc/rfc9116>
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecurityTxt {
+    /// Whether to render `/.well-known/security.txt`. Defaults to false
+    pub generate: bool,
+    /// Where to report security issues, eg `mailto:security@example.com`. At least one is required
+    pub contact: Vec<String>,
+    /// When the file should be considered stale, as a RFC 3339 datetime. Required
+    pub expires: Option<String>,
+    /// Links to the keys to use to encrypt the reports
+    pub encryption: Vec<String>,
+    /// A link to the page thanking the reporters
+    pub acknowledgments: Option<String>,
+    /// The languages the reports can be written in, eg `["en", "fr"]`
+    pub preferred_languages: Vec<String>,
+    /// A link to the security policy
+    pub policy: Option<String>,
+    /// A link to the security related job positions
+    pub hiring: Option<String>,
+}
+
+impl SecurityTxt {
+    pub fn validate(&self) -> Result<()> {
+        if !self.generate {
+            return Ok(());
+        }
+        if self.contact.is_empty() {
+            bail!("`security_txt.contact` needs at least one value to generate a security.txt");
+        }
+        if self.expires.is_none() {
+            bail!("`security_txt.expires` is required to generate a security.txt");
+        }
+        Ok(())
+    }
+}
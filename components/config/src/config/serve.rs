@@ -0,0 +1,10 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Serve {
+    /// Headers added to every response of `zola serve`, eg `Cross-Origin-Opener-Policy`
+    pub headers: BTreeMap<String, String>,
+}
@@ -7,14 +7,19 @@ pub struct TaxonomyConfig {
     pub name: String,
     /// The slug according to the config slugification strategy
     pub slug: String,
-    /// If this is set, the list of individual taxonomy term page will be paginated
-    /// by this much
-    pub paginate_by: Option<usize>,
-    pub paginate_path: Option<String>,
+    /// If above 0, the list of individual taxonomy term page will be paginated
+    /// by this much. Defaults to 0
+    pub paginate_by: usize,
+    /// The path of the paginated term pages, the page number being appended to it.
+    /// Defaults to `page`
+    pub paginate_path: String,
     /// Whether the taxonomy will be rendered, defaults to `true`
     pub render: bool,
     /// Whether to generate a feed only for each taxonomy term, defaults to `false`
     pub feed: bool,
+    /// The template of the term feeds, instead of the one named after `feed_filename`.
+    /// The feeds are still written to `feed_filename`
+    pub feed_template: Option<String>,
 }
 
 impl Default for TaxonomyConfig {
@@ -22,28 +27,17 @@ impl Default for TaxonomyConfig {
         Self {
             name: String::new(),
             slug: String::new(),
-            paginate_by: None,
-            paginate_path: None,
+            paginate_by: 0,
+            paginate_path: "page".to_string(),
             render: true,
             feed: false,
+            feed_template: None,
         }
     }
 }
 
 impl TaxonomyConfig {
     pub fn is_paginated(&self) -> bool {
-        if let Some(paginate_by) = self.paginate_by {
-            paginate_by > 0
-        } else {
-            false
-        }
-    }
-
-    pub fn paginate_path(&self) -> &str {
-        if let Some(ref path) = self.paginate_path {
-            path
-        } else {
-            "page"
-        }
+        self.paginate_by > 0
     }
 }
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Templates {
+    /// Other directories of templates, relative to the root of the site, eg shared between
+    /// several sites. A template of the `templates` directory is used instead of one with the
+    /// same name in these directories, which are used in order and before the templates of
+    /// the theme.
+    pub load: Vec<String>,
+}
@@ -5,13 +5,30 @@ mod theme;
 use std::path::Path;
 
 pub use crate::config::{
-    languages::LanguageOptions, link_checker::LinkChecker, link_checker::LinkCheckerLevel,
-    search::Search, slugify::Slugify, taxonomies::TaxonomyConfig, Config,
+    converters::{Converter, ConverterOutput},
+    deploy::{Deploy, GithubPages, Rsync, S3},
+    env_overlay_path,
+    favicons::Favicons,
+    humans_txt::HumansTxt,
+    images::Images,
+    languages::LanguageOptions,
+    link_checker::LinkChecker,
+    link_checker::LinkCheckerLevel,
+    markup::Markdown,
+    menus::MenuItem,
+    netlify::Netlify,
+    plugins::{Plugin, PluginHook},
+    related::RelatedPages,
+    search::Search,
+    security_txt::SecurityTxt,
+    slugify::Slugify,
+    taxonomies::TaxonomyConfig,
+    Config, OutputFormat,
 };
 use errors::Result;
 
-/// Get and parse the config.
+/// Get and parse the config, with the overlay of the environment if one is selected.
 /// If it doesn't succeed, exit
-pub fn get_config(filename: &Path) -> Result<Config> {
-    Config::from_file(filename)
+pub fn get_config(filename: &Path, env: Option<&str>) -> Result<Config> {
+    Config::from_file_in_env(filename, env)
 }
@@ -1,9 +1,12 @@
 use std::env;
 use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use libs::atty;
-use libs::once_cell::sync::Lazy;
+use libs::log::{self, Level, LevelFilter, Log, Metadata, Record};
+use libs::once_cell::sync::{Lazy, OnceCell};
 use libs::termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+use libs::time::{OffsetDateTime, UtcOffset};
 
 /// Termcolor color choice.
 /// We do not rely on ColorChoice::Auto behavior
@@ -11,24 +14,118 @@ use libs::termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor}
 static COLOR_CHOICE: Lazy<ColorChoice> =
     Lazy::new(|| if has_color() { ColorChoice::Always } else { ColorChoice::Never });
 
+/// The most verbose level printed, as a `LevelFilter` index
+static LEVEL: AtomicUsize = AtomicUsize::new(LevelFilter::Info as usize);
+
+/// Set in `zola serve` to start every line with the time
+static TIMESTAMP_OFFSET: OnceCell<UtcOffset> = OnceCell::new();
+
+/// The subsystems logging what they do with `debug!` and `trace!`:
+/// - `render`: the steps of a build and how long they took, the files rendered
+/// - `watch`: the file events seen by `zola serve` and why they were ignored
+/// - `copy`: the static files and assets copied or skipped
+const TARGETS: [&str; 3] = ["render", "watch", "copy"];
+
+struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= level() && TARGETS.contains(&metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+        match record.level() {
+            Level::Error => error(&message),
+            Level::Warn => warn(&message),
+            Level::Info => info(&message),
+            Level::Debug | Level::Trace => {
+                let mut color = ColorSpec::new();
+                color.set_dimmed(record.level() == Level::Trace);
+                colorize(
+                    &format!("[{}] {}", record.target(), message),
+                    &color,
+                    StandardStream::stdout(*COLOR_CHOICE),
+                );
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: Logger = Logger;
+
+fn level() -> LevelFilter {
+    match LEVEL.load(Ordering::Relaxed) {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Sets what is printed: only the warnings and errors if `quiet` is set, the `debug!` logs of
+/// the subsystems with a verbosity of 1 (`-v`) and their `trace!` logs as well with 2 (`-vv`)
+pub fn init(verbosity: u8, quiet: bool) {
+    let level = match (quiet, verbosity) {
+        (true, _) => LevelFilter::Warn,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    LEVEL.store(level as usize, Ordering::Relaxed);
+    // Only fails if it was already set, which is fine
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(level);
+}
+
+/// Starts every line with the time in the given offset, for `zola serve`
+pub fn enable_timestamps(offset: UtcOffset) {
+    let _ = TIMESTAMP_OFFSET.set(offset);
+}
+
+/// Whether `--quiet` is set, in which case only the warnings and errors are printed
+pub fn is_quiet() -> bool {
+    level() < LevelFilter::Info
+}
+
+/// Prints a message without formatting, unless `--quiet` is set
+pub fn plain(message: &str) {
+    if level() >= LevelFilter::Info {
+        colorize(message, &ColorSpec::new(), StandardStream::stdout(*COLOR_CHOICE));
+    }
+}
+
 pub fn info(message: &str) {
-    colorize(message, ColorSpec::new().set_bold(true), StandardStream::stdout(*COLOR_CHOICE));
+    if level() >= LevelFilter::Info {
+        colorize(message, ColorSpec::new().set_bold(true), StandardStream::stdout(*COLOR_CHOICE));
+    }
 }
 
 pub fn warn(message: &str) {
-    colorize(
-        &format!("{}{}", "Warning: ", message),
-        ColorSpec::new().set_bold(true).set_fg(Some(Color::Yellow)),
-        StandardStream::stdout(*COLOR_CHOICE),
-    );
+    if level() >= LevelFilter::Warn {
+        colorize(
+            &format!("{}{}", "Warning: ", message),
+            ColorSpec::new().set_bold(true).set_fg(Some(Color::Yellow)),
+            StandardStream::stdout(*COLOR_CHOICE),
+        );
+    }
 }
 
 pub fn success(message: &str) {
-    colorize(
-        message,
-        ColorSpec::new().set_bold(true).set_fg(Some(Color::Green)),
-        StandardStream::stdout(*COLOR_CHOICE),
-    );
+    if level() >= LevelFilter::Info {
+        colorize(
+            message,
+            ColorSpec::new().set_bold(true).set_fg(Some(Color::Green)),
+            StandardStream::stdout(*COLOR_CHOICE),
+        );
+    }
 }
 
 pub fn error(message: &str) {
@@ -39,8 +136,60 @@ pub fn error(message: &str) {
     );
 }
 
+/// Prints the lines an error comes from to stderr, like rustc does:
+///
+/// ```text
+///   --> content/blog/hello.md:4:8
+///    |
+///  3 | description = "Hey"
+///  4 | date = 2022-13-01
+///    |        ^
+/// ```
+pub fn source_snippet(location: &str, lines: &[(usize, String)], column: Option<usize>) {
+    let mut stream = StandardStream::stderr(*COLOR_CHOICE);
+    let mut gutter_color = ColorSpec::new();
+    gutter_color.set_bold(true).set_fg(Some(Color::Blue));
+    let width = lines.last().map_or(1, |(number, _)| number.to_string().len());
+    let gutter = |stream: &mut StandardStream, number: &str| {
+        stream.set_color(&gutter_color).unwrap();
+        write!(stream, "{:>width$} |", number, width = width).unwrap();
+        stream.set_color(&ColorSpec::new()).unwrap();
+    };
+
+    stream.set_color(&gutter_color).unwrap();
+    write!(stream, "{:width$}--> ", "", width = width).unwrap();
+    stream.set_color(&ColorSpec::new()).unwrap();
+    writeln!(stream, "{}", location).unwrap();
+    gutter(&mut stream, "");
+    writeln!(stream).unwrap();
+    for (number, line) in lines {
+        gutter(&mut stream, &number.to_string());
+        writeln!(stream, " {}", line).unwrap();
+    }
+    if let Some(column) = column {
+        gutter(&mut stream, "");
+        stream.set_color(ColorSpec::new().set_bold(true).set_fg(Some(Color::Red))).unwrap();
+        writeln!(stream, " {:>column$}", "^", column = column).unwrap();
+        stream.set_color(&ColorSpec::new()).unwrap();
+    }
+}
+
+/// The time prefixing the lines in `zola serve`, eg `[14:03:27] `
+fn timestamp() -> String {
+    match TIMESTAMP_OFFSET.get() {
+        Some(offset) => {
+            let now = OffsetDateTime::now_utc().to_offset(*offset);
+            format!("[{:02}:{:02}:{:02}] ", now.hour(), now.minute(), now.second())
+        }
+        None => String::new(),
+    }
+}
+
 /// Print a colorized message to stdout
 fn colorize(message: &str, color: &ColorSpec, mut stream: StandardStream) {
+    // Blank lines are kept blank
+    let timestamp = if message.trim().is_empty() { String::new() } else { timestamp() };
+    write!(stream, "{}", timestamp).unwrap();
     stream.set_color(color).unwrap();
     write!(stream, "{}", message).unwrap();
     stream.set_color(&ColorSpec::new()).unwrap();
@@ -55,3 +204,24 @@ fn has_color() -> bool {
 
     force_colors || use_colors && atty::is(atty::Stream::Stdout)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_set_level_from_flags() {
+        for (verbosity, quiet, expected) in [
+            (0, false, LevelFilter::Info),
+            (1, false, LevelFilter::Debug),
+            (3, false, LevelFilter::Trace),
+            (2, true, LevelFilter::Warn),
+        ] {
+            init(verbosity, quiet);
+            assert_eq!(level(), expected);
+            assert_eq!(log::max_level(), expected);
+        }
+        assert!(LOGGER.enabled(&Metadata::builder().target("watch").level(Level::Warn).build()));
+        assert!(!LOGGER.enabled(&Metadata::builder().target("hyper").level(Level::Warn).build()));
+    }
+}
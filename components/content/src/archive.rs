@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use config::Config;
+use errors::{Context as ErrorContext, Result};
+use libs::tera::{Context, Tera};
+use utils::templates::render_template;
+
+use crate::library::Library;
+use crate::ser::{PageSerMode, SerializingPage};
+use crate::sorting::sort_pages;
+use crate::{Page, SortBy};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SerializedArchive<'a> {
+    year: i32,
+    month: Option<u8>,
+    lang: &'a str,
+    path: &'a str,
+    permalink: &'a str,
+    pages: Vec<SerializingPage<'a>>,
+}
+
+impl<'a> SerializedArchive<'a> {
+    pub fn from_archive(archive: &'a Archive, library: &'a Library) -> Self {
+        let pages = archive
+            .pages
+            .iter()
+            .map(|p| SerializingPage::new(&library.pages[p], Some(library), PageSerMode::Listing))
+            .collect();
+
+        SerializedArchive {
+            year: archive.year,
+            month: archive.month,
+            lang: &archive.lang,
+            path: &archive.path,
+            permalink: &archive.permalink,
+            pages,
+        }
+    }
+}
+
+/// All the dated pages of a given year, or of a given month of a year if `month` is set
+#[derive(Debug, Clone, PartialEq)]
+pub struct Archive {
+    pub year: i32,
+    /// 1-indexed
+    pub month: Option<u8>,
+    pub lang: String,
+    pub path: String,
+    pub permalink: String,
+    /// The pages of that period, most recent first
+    pub pages: Vec<PathBuf>,
+}
+
+impl Archive {
+    pub(crate) fn new(
+        year: i32,
+        month: Option<u8>,
+        lang: &str,
+        archive_pages: &[&Page],
+        config: &Config,
+    ) -> Self {
+        let mut path = if lang != config.default_language {
+            format!("/{}/{}/", lang, year)
+        } else {
+            format!("/{}/", year)
+        };
+        if let Some(m) = month {
+            path.push_str(&format!("{:02}/", m));
+        }
+        let permalink = config.make_permalink(&path);
+        // All those pages have a date so nothing can be ignored
+        let (pages, _) = sort_pages(archive_pages, SortBy::Date);
+
+        Archive { year, month, lang: lang.to_string(), path, permalink, pages }
+    }
+
+    pub fn render_html(&self, tera: &Tera, config: &Config, library: &Library) -> Result<String> {
+        let mut context = Context::new();
+        context.insert("config", &config.serialize(&self.lang));
+        context.insert("lang", &self.lang);
+        context.insert("archive", &self.serialize(library));
+        context.insert("current_url", &self.permalink);
+        context.insert("current_path", &self.path);
+
+        render_template("archive.html", tera, context, &config.theme)
+            .with_context(|| format!("Failed to render archive page '{}'", self.path))
+    }
+
+    pub fn serialize<'a>(&'a self, library: &'a Library) -> SerializedArchive<'a> {
+        SerializedArchive::from_archive(self, library)
+    }
+}
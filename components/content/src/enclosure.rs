@@ -0,0 +1,214 @@
+use std::path::{Path, PathBuf};
+
+use libs::mime_guess;
+use serde::Serialize;
+
+use config::Config;
+use errors::{bail, Result};
+
+use crate::front_matter::EnclosureFrontMatter;
+
+/// The media file of a page with everything needed by the `<enclosure>` of a feed
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Enclosure {
+    pub url: String,
+    /// The size of the file in bytes
+    pub length: u64,
+    pub mime_type: String,
+}
+
+fn is_external(path: &str) -> bool {
+    path.contains("://")
+}
+
+/// The full URL of the enclosure: relative paths are relative to the page and paths
+/// starting with a slash are relative to the root of the site
+pub fn enclosure_url(path: &str, permalink: &str, config: &Config) -> String {
+    if is_external(path) {
+        path.to_string()
+    } else if let Some(path) = path.strip_prefix('/') {
+        format!("{}/{}", config.base_url.trim_end_matches('/'), path)
+    } else {
+        let separator = if permalink.ends_with('/') { "" } else { "/" };
+        format!("{}{}{}", permalink, separator, path.trim_start_matches("./"))
+    }
+}
+
+/// Where the file of the enclosure is on disk, `None` if it is hosted elsewhere or doesn't exist
+fn find_file(path: &str, page_dir: Option<&Path>, base_path: &Path) -> Result<Option<PathBuf>> {
+    if is_external(path) {
+        return Ok(None);
+    }
+    if let Some(path) = path.strip_prefix('/') {
+        return Ok(["static", "content"]
+            .iter()
+            .map(|dir| base_path.join(dir).join(path))
+            .find(|p| p.is_file()));
+    }
+    match page_dir {
+        Some(dir) => {
+            let file = dir.join(path);
+            Ok(if file.is_file() { Some(file) } else { None })
+        }
+        None => bail!(
+            "The relative path `{}` can only be used by pages with colocated assets (`index.md`), \
+             use a path starting with `/` instead",
+            path
+        ),
+    }
+}
+
+impl Enclosure {
+    /// Finds the URL, length and MIME type of the enclosure from the front matter.
+    /// `page_dir` is the directory of the page if it is an `index.md` with colocated assets.
+    pub fn resolve(
+        meta: &EnclosureFrontMatter,
+        permalink: &str,
+        page_dir: Option<&Path>,
+        config: &Config,
+        base_path: &Path,
+    ) -> Result<Enclosure> {
+        let file = find_file(&meta.path, page_dir, base_path)?;
+
+        let length = match (meta.length, &file) {
+            (Some(length), _) => length,
+            (None, Some(file)) => file.metadata()?.len(),
+            (None, None) if is_external(&meta.path) => {
+                bail!("The `length` of the file hosted at `{}` needs to be set", meta.path)
+            }
+            (None, None) => bail!("The file `{}` doesn't exist", meta.path),
+        };
+
+        let mime_type = match meta.mime_type {
+            Some(ref mime_type) => mime_type.clone(),
+            None => {
+                // Only the extension matters, not the query string of URLs
+                let path = meta.path.split(['?', '#']).next().unwrap_or_default();
+                match mime_guess::from_path(path).first() {
+                    Some(mime) => mime.essence_str().to_string(),
+                    None => bail!(
+                        "The MIME type of `{}` couldn't be guessed, it needs to be set with `mime_type`",
+                        meta.path
+                    ),
+                }
+            }
+        };
+
+        Ok(Enclosure { url: enclosure_url(&meta.path, permalink, config), length, mime_type })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir, File};
+    use std::io::Write;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn front_matter(path: &str) -> EnclosureFrontMatter {
+        EnclosureFrontMatter { path: path.to_string(), length: None, mime_type: None }
+    }
+
+    #[test]
+    fn can_make_enclosure_urls() {
+        let config = Config::default_for_test();
+        let permalink = "http://a-website.com/podcast/episode-1/";
+        assert_eq!(
+            enclosure_url("episode.mp3", permalink, &config),
+            "http://a-website.com/podcast/episode-1/episode.mp3"
+        );
+        assert_eq!(
+            enclosure_url("/audio/episode.mp3", permalink, &config),
+            "http://a-website.com/audio/episode.mp3"
+        );
+        assert_eq!(
+            enclosure_url("https://cdn.example.com/episode.mp3", permalink, &config),
+            "https://cdn.example.com/episode.mp3"
+        );
+    }
+
+    #[test]
+    fn can_resolve_local_files() {
+        let tmp_dir = tempdir().unwrap();
+        let page_dir = tmp_dir.path().join("content").join("episode-1");
+        create_dir(tmp_dir.path().join("content")).unwrap();
+        create_dir(&page_dir).unwrap();
+        File::create(page_dir.join("episode.mp3")).unwrap().write_all(b"not really audio").unwrap();
+        let config = Config::default_for_test();
+        let permalink = "http://a-website.com/episode-1/";
+
+        let enclosure = Enclosure::resolve(
+            &front_matter("episode.mp3"),
+            permalink,
+            Some(&page_dir),
+            &config,
+            tmp_dir.path(),
+        )
+        .unwrap();
+        assert_eq!(
+            enclosure,
+            Enclosure {
+                url: "http://a-website.com/episode-1/episode.mp3".to_string(),
+                length: 16,
+                mime_type: "audio/mpeg".to_string(),
+            }
+        );
+
+        let enclosure = Enclosure::resolve(
+            &front_matter("/episode-1/episode.mp3"),
+            permalink,
+            None,
+            &config,
+            tmp_dir.path(),
+        )
+        .unwrap();
+        assert_eq!(enclosure.length, 16);
+
+        let res = Enclosure::resolve(
+            &front_matter("episode.mp3"),
+            permalink,
+            None,
+            &config,
+            tmp_dir.path(),
+        );
+        assert!(res.is_err());
+        let res = Enclosure::resolve(
+            &front_matter("missing.mp3"),
+            permalink,
+            Some(&page_dir),
+            &config,
+            tmp_dir.path(),
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn needs_the_length_and_type_of_external_files() {
+        let config = Config::default_for_test();
+        let permalink = "http://a-website.com/episode-1/";
+        let base_path = Path::new("");
+
+        let res = Enclosure::resolve(
+            &front_matter("https://cdn.example.com/episode.mp3"),
+            permalink,
+            None,
+            &config,
+            base_path,
+        );
+        assert!(res.is_err());
+
+        let mut meta = front_matter("https://cdn.example.com/episode.m4a?id=1");
+        meta.length = Some(1234);
+        let enclosure = Enclosure::resolve(&meta, permalink, None, &config, base_path).unwrap();
+        assert_eq!(enclosure.mime_type, "audio/m4a");
+
+        let mut meta = front_matter("https://cdn.example.com/episode");
+        meta.length = Some(1234);
+        assert!(Enclosure::resolve(&meta, permalink, None, &config, base_path).is_err());
+        meta.mime_type = Some("audio/ogg".to_string());
+        let enclosure = Enclosure::resolve(&meta, permalink, None, &config, base_path).unwrap();
+        assert_eq!(enclosure.mime_type, "audio/ogg");
+    }
+}
@@ -57,7 +57,7 @@ impl FileInfo {
         let name = path.file_stem().unwrap().to_string_lossy().to_string();
         let canonical = parent.join(&name);
         let mut components =
-            find_content_components(&file_path.strip_prefix(base_path).unwrap_or(&file_path));
+            find_content_components(file_path.strip_prefix(base_path).unwrap_or(&file_path));
         let relative = if !components.is_empty() {
             format!("{}/{}.md", components.join("/"), name)
         } else {
@@ -91,7 +91,7 @@ impl FileInfo {
         let parent = path.parent().expect("Get parent of section").to_path_buf();
         let name = path.file_stem().unwrap().to_string_lossy().to_string();
         let components =
-            find_content_components(&file_path.strip_prefix(base_path).unwrap_or(&file_path));
+            find_content_components(file_path.strip_prefix(base_path).unwrap_or(&file_path));
         let relative = if !components.is_empty() {
             format!("{}/{}.md", components.join("/"), name)
         } else {
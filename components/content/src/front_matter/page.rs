@@ -6,11 +6,25 @@ use time::format_description::well_known::Rfc3339;
 use time::macros::{format_description, time};
 use time::{Date, OffsetDateTime, PrimitiveDateTime};
 
+use config::OutputFormat;
 use errors::{bail, Result};
 use utils::de::{fix_toml_dates, from_toml_datetime};
 
 use crate::front_matter::split::RawFrontMatter;
 
+/// A media file attached to a page, like the audio of a podcast episode
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnclosureFrontMatter {
+    /// A path relative to the page for colocated assets, a path from the root of the site
+    /// for files in `static` or `content`, or the URL of a file hosted elsewhere
+    pub path: String,
+    /// The size of the file in bytes, read from the file if not set
+    pub length: Option<u64>,
+    /// The MIME type of the file, guessed from its extension if not set
+    pub mime_type: Option<String>,
+}
+
 /// The front matter of every page
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 #[serde(default)]
@@ -47,6 +61,8 @@ pub struct PageFrontMatter {
     /// Can't be an empty string if present
     pub path: Option<String>,
     pub taxonomies: HashMap<String, Vec<String>>,
+    /// The name of the series this page is part of, if any
+    pub series: Option<String>,
     /// Integer to use to order content. Highest is at the bottom, lowest first
     pub weight: Option<usize>,
     /// All aliases for that page. Zola will create HTML templates that will
@@ -56,10 +72,23 @@ pub struct PageFrontMatter {
     /// Specify a template different from `page.html` to use for that page
     #[serde(skip_serializing)]
     pub template: Option<String>,
+    /// Renders the page as a redirect to that internal path or URL instead of its content.
+    /// Useful for stub pages pointing elsewhere
+    #[serde(skip_serializing)]
+    pub redirect_to: Option<String>,
+    /// The files to render for this page, `outputs` of the config if not set
+    #[serde(skip_serializing)]
+    pub outputs: Option<Vec<OutputFormat>>,
+    /// Encrypts the rendered page with that password, to be decrypted in the browser
+    #[serde(skip_serializing)]
+    pub password: Option<String>,
     /// Whether the page is included in the search index
     /// Defaults to `true` but is only used if search if explicitly enabled in the config.
     #[serde(skip_serializing)]
     pub in_search_index: bool,
+    /// A media file added to the entry of the page in the feeds
+    #[serde(skip_serializing)]
+    pub enclosure: Option<EnclosureFrontMatter>,
     /// Any extra parameter present in the front matter
     pub extra: Map<String, Value>,
 }
@@ -69,8 +98,9 @@ pub struct PageFrontMatter {
 /// 1. an offset datetime (plain RFC3339)
 /// 2. a local datetime (RFC3339 with timezone omitted)
 /// 3. a local date (YYYY-MM-DD).
+///
 /// This tries each in order.
-fn parse_datetime(d: &str) -> Option<OffsetDateTime> {
+pub fn parse_datetime(d: &str) -> Option<OffsetDateTime> {
     OffsetDateTime::parse(d, &Rfc3339)
         .or_else(|_| OffsetDateTime::parse(format!("{}Z", d).as_ref(), &Rfc3339))
         .or_else(|_| match Date::parse(d, &format_description!("[year]-[month]-[day]")) {
@@ -96,6 +126,12 @@ impl PageFrontMatter {
             }
         }
 
+        if let Some(ref series) = f.series {
+            if series.trim().is_empty() {
+                bail!("`series` can't be empty if present")
+            }
+        }
+
         f.extra = match fix_toml_dates(f.extra) {
             Value::Object(o) => o,
             _ => unreachable!("Got something other than a table in page extra"),
@@ -144,9 +180,14 @@ impl Default for PageFrontMatter {
             slug: None,
             path: None,
             taxonomies: HashMap::new(),
+            series: None,
             weight: None,
             aliases: Vec::new(),
             template: None,
+            redirect_to: None,
+            outputs: None,
+            password: None,
+            enclosure: None,
             extra: Map::new(),
         }
     }
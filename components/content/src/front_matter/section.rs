@@ -3,13 +3,11 @@ use serde::{Deserialize, Serialize};
 
 use errors::Result;
 use utils::de::fix_toml_dates;
-use utils::types::InsertAnchor;
+use utils::types::{InsertAnchor, RawHtml};
 
 use crate::front_matter::split::RawFrontMatter;
 use crate::SortBy;
 
-static DEFAULT_PAGINATE_PATH: &str = "page";
-
 /// The front matter of every section
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default)]
@@ -36,12 +34,32 @@ pub struct SectionFrontMatter {
     /// Whether to reverse the order of the pages before segmenting into pagers
     #[serde(skip_serializing)]
     pub paginate_reversed: bool,
-    /// Path to be used by pagination: the page number will be appended after it. Defaults to `page`.
+    /// Path to be used by pagination: the page number will be appended after it.
+    /// An empty string puts the page number directly after the section path.
+    /// Defaults to the `paginate_path` of the config, which is `page` unless set.
+    #[serde(skip_serializing)]
+    pub paginate_path: Option<String>,
+    /// How many of the first pages to leave out of the pagination, eg to feature them separately.
+    /// Defaults to `0`.
+    #[serde(skip_serializing)]
+    pub paginate_offset: usize,
+    /// The maximum number of pages to paginate after the offset. All of them if not set.
+    #[serde(skip_serializing)]
+    pub paginate_limit: Option<usize>,
+    /// Whether to paginate all the pages of the site in the language of that section instead of
+    /// only its own pages. Defaults to `false`.
     #[serde(skip_serializing)]
-    pub paginate_path: String,
+    pub paginate_all_pages: bool,
+    /// Sections whose pages, including the pages of their subsections, are paginated instead of
+    /// the own pages of that section, eg `["blog", "notes"]`. Relative to the `content` directory.
+    #[serde(skip_serializing)]
+    pub paginate_sections: Vec<String>,
     /// Whether to insert a link for each header like the ones you can see in this site if you hover one
     /// The default template can be overridden by creating a `anchor-link.html` in the `templates` directory
     pub insert_anchor_links: InsertAnchor,
+    /// What to do with the raw HTML of the Markdown of that section and of its pages and
+    /// subsections, unless a subsection sets its own. Defaults to `markdown.raw_html` of the config
+    pub raw_html: Option<RawHtml>,
     /// Whether to render that section or not. Defaults to `true`.
     /// Useful when the section is only there to organize things but is not meant
     /// to be used directly, like a posts section in a personal site
@@ -60,6 +78,10 @@ pub struct SectionFrontMatter {
     /// Useful when the section shouldn't split up the parent section, like
     /// sections for each year under a posts section.
     pub transparent: bool,
+    /// Optional pattern for the path of the pages of this section, eg `/:year/:month/:slug/`.
+    /// Overrides the `default_permalink` of the config.
+    #[serde(skip_serializing)]
+    pub permalink: Option<String>,
     /// Optional template for all pages in this section (including the pages of children section)
     #[serde(skip_serializing)]
     pub page_template: Option<String>,
@@ -67,9 +89,18 @@ pub struct SectionFrontMatter {
     /// redirect to this
     #[serde(skip_serializing)]
     pub aliases: Vec<String>,
+    /// Whether to also render an AMP version of the pages of this section and of its subsections
+    /// with the `amp.html` template, in an `amp/` directory next to their `index.html`.
+    /// Defaults to `false`.
+    #[serde(skip_serializing)]
+    pub amp: bool,
     /// Whether to generate a feed for the current section
     #[serde(skip_serializing)]
     pub generate_feed: bool,
+    /// The template of the feed of the section, instead of the one named after `feed_filename`.
+    /// The feed is still written to `feed_filename`
+    #[serde(skip_serializing)]
+    pub feed_template: Option<String>,
     /// Any extra parameter present in the front matter
     pub extra: Map<String, Value>,
 }
@@ -105,15 +136,23 @@ impl Default for SectionFrontMatter {
             template: None,
             paginate_by: None,
             paginate_reversed: false,
-            paginate_path: DEFAULT_PAGINATE_PATH.to_string(),
+            paginate_path: None,
+            paginate_offset: 0,
+            paginate_limit: None,
+            paginate_all_pages: false,
+            paginate_sections: Vec::new(),
             render: true,
             redirect_to: None,
             insert_anchor_links: InsertAnchor::None,
+            raw_html: None,
             in_search_index: true,
             transparent: false,
+            permalink: None,
             page_template: None,
             aliases: Vec::new(),
+            amp: false,
             generate_feed: false,
+            feed_template: None,
             extra: Map::new(),
             draft: false,
         }
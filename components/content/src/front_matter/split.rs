@@ -1,6 +1,7 @@
+use std::borrow::Cow;
 use std::path::Path;
 
-use errors::{bail, Context, Result};
+use errors::{bail, Context, Error, Result, SourceError};
 use libs::once_cell::sync::Lazy;
 use libs::regex::Regex;
 use libs::{serde_yaml, toml};
@@ -26,24 +27,98 @@ pub enum RawFrontMatter<'a> {
 }
 
 impl RawFrontMatter<'_> {
+    fn as_str(&self) -> &str {
+        match self {
+            RawFrontMatter::Toml(s) | RawFrontMatter::Yaml(s) => s,
+        }
+    }
+
     pub(crate) fn deserialize<T>(&self) -> Result<T>
     where
         T: serde::de::DeserializeOwned,
     {
         let f: T = match self {
             RawFrontMatter::Toml(s) => toml::from_str(s)?,
-            RawFrontMatter::Yaml(s) => match serde_yaml::from_str(s) {
-                Ok(d) => d,
-                Err(e) => bail!("YAML deserialize error: {:?}", e),
-            },
+            RawFrontMatter::Yaml(s) => {
+                serde_yaml::from_str(s).context("Failed to deserialize the YAML front matter")?
+            }
         };
         Ok(f)
     }
 }
 
+/// Removes the UTF-8 BOM some Windows editors add at the start of files and turns the `\r\n`
+/// and `\r` line endings into `\n`, so the front matter is found and the content is the same
+/// whatever editor saved the file
+pub fn normalize_content(content: &str) -> Cow<'_, str> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    if content.contains('\r') {
+        Cow::Owned(content.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        Cow::Borrowed(content)
+    }
+}
+
+/// The position TOML and YAML errors add to their message
+static POSITION_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r" at line \d+ column \d+").unwrap());
+/// The key TOML errors without a position, like an invalid date, are about
+static KEY_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"for key `(?:[^`]*\.)?([^`.]+)`").unwrap());
+
+/// Finds the line of the front matter setting `key`, 1-indexed
+fn find_key_line(front_matter: &str, key: &str) -> Option<usize> {
+    front_matter
+        .lines()
+        .position(|line| {
+            line.trim_start()
+                .strip_prefix(key)
+                .map_or(false, |rest| rest.trim_start().starts_with(['=', ':']))
+        })
+        .map(|index| index + 1)
+}
+
+/// Points a TOML or YAML error of the front matter at the line of the file it comes from,
+/// `line_offset` being the number of lines of the file before the front matter.
+/// Other errors, like an empty `slug`, are returned as is.
+fn locate_front_matter_error(
+    error: Error,
+    file_path: &Path,
+    file_content: &str,
+    front_matter: &RawFrontMatter,
+    line_offset: usize,
+) -> Error {
+    let message = error.root_cause().to_string();
+    let position = if let Some(e) = error.downcast_ref::<toml::de::Error>() {
+        e.line_col().map(|(line, column)| (line + 1, Some(column + 1)))
+    } else if let Some(e) = error.downcast_ref::<serde_yaml::Error>() {
+        e.location().map(|l| (l.line(), Some(l.column())))
+    } else {
+        return error;
+    };
+    // The first line is what follows the `+++`, errors without a position point there
+    let position = position.filter(|(line, _)| *line > 1).or_else(|| {
+        let key = KEY_RE.captures(&message)?;
+        find_key_line(front_matter.as_str(), &key[1]).map(|line| (line, None))
+    });
+    let (line, column) = match position {
+        Some(p) => p,
+        None => return error,
+    };
+
+    // The position is shown with the lines and would be wrong as it doesn't count the lines
+    // before the front matter
+    let message = POSITION_RE.replace_all(&message, "");
+    let mut source_error = SourceError::new(message, file_content, line + line_offset, column);
+    source_error.path = Some(file_path.to_path_buf());
+    Error::new(source_error)
+}
+
 /// Split a file between the front matter and its content
-/// Will return an error if the front matter wasn't found
-fn split_content<'c>(file_path: &Path, content: &'c str) -> Result<(RawFrontMatter<'c>, &'c str)> {
+/// Will return an error if the front matter wasn't found.
+/// Also returns the number of lines before the front matter.
+fn split_content<'c>(
+    file_path: &Path,
+    content: &'c str,
+) -> Result<(RawFrontMatter<'c>, usize, &'c str)> {
     let (re, is_toml) = if TOML_RE.is_match(content) {
         (&TOML_RE as &Regex, true)
     } else if YAML_RE.is_match(content) {
@@ -60,13 +135,15 @@ fn split_content<'c>(file_path: &Path, content: &'c str) -> Result<(RawFrontMatt
     // caps[0] is the full match
     // caps[1] => front matter
     // caps[2] => content
-    let front_matter = caps.get(1).unwrap().as_str();
+    let front_matter = caps.get(1).unwrap();
+    let line_offset = content[..front_matter.start()].matches('\n').count();
+    let front_matter = front_matter.as_str();
     let content = caps.get(2).map_or("", |m| m.as_str());
 
     if is_toml {
-        Ok((RawFrontMatter::Toml(front_matter), content))
+        Ok((RawFrontMatter::Toml(front_matter), line_offset, content))
     } else {
-        Ok((RawFrontMatter::Yaml(front_matter), content))
+        Ok((RawFrontMatter::Yaml(front_matter), line_offset, content))
     }
 }
 
@@ -76,12 +153,14 @@ pub fn split_section_content<'c>(
     file_path: &Path,
     content: &'c str,
 ) -> Result<(SectionFrontMatter, &'c str)> {
-    let (front_matter, content) = split_content(file_path, content)?;
-    let meta = SectionFrontMatter::parse(&front_matter).with_context(|| {
-        format!("Error when parsing front matter of section `{}`", file_path.to_string_lossy())
-    })?;
+    let (front_matter, line_offset, body) = split_content(file_path, content)?;
+    let meta = SectionFrontMatter::parse(&front_matter)
+        .map_err(|e| locate_front_matter_error(e, file_path, content, &front_matter, line_offset))
+        .with_context(|| {
+            format!("Error when parsing front matter of section `{}`", file_path.to_string_lossy())
+        })?;
 
-    Ok((meta, content))
+    Ok((meta, body))
 }
 
 /// Split a file between the front matter and its content
@@ -90,11 +169,13 @@ pub fn split_page_content<'c>(
     file_path: &Path,
     content: &'c str,
 ) -> Result<(PageFrontMatter, &'c str)> {
-    let (front_matter, content) = split_content(file_path, content)?;
-    let meta = PageFrontMatter::parse(&front_matter).with_context(|| {
-        format!("Error when parsing front matter of section `{}`", file_path.to_string_lossy())
-    })?;
-    Ok((meta, content))
+    let (front_matter, line_offset, body) = split_content(file_path, content)?;
+    let meta = PageFrontMatter::parse(&front_matter)
+        .map_err(|e| locate_front_matter_error(e, file_path, content, &front_matter, line_offset))
+        .with_context(|| {
+            format!("Error when parsing front matter of page `{}`", file_path.to_string_lossy())
+        })?;
+    Ok((meta, body))
 }
 
 #[cfg(test)]
@@ -102,7 +183,8 @@ mod tests {
     use std::path::Path;
     use test_case::test_case;
 
-    use super::{split_page_content, split_section_content};
+    use super::{normalize_content, split_page_content, split_section_content};
+    use errors::SourceError;
 
     #[test_case(r#"
 +++
@@ -248,4 +330,39 @@ date: 2002-10-12
         let res = split_page_content(Path::new(""), content);
         assert!(res.is_err());
     }
+
+    #[test_case("\u{feff}+++\ntitle = \"Title\"\n+++\nHello\n"; "bom")]
+    #[test_case("+++\r\ntitle = \"Title\"\r\n+++\r\nHello\r\n"; "crlf")]
+    #[test_case("\u{feff}---\r\ntitle: Title\r\n---\r\nHello\r\n"; "yaml bom and crlf")]
+    #[test_case("+++\rtitle = \"Title\"\r+++\rHello\r"; "cr")]
+    fn can_split_normalized_content(content: &str) {
+        let content = normalize_content(content);
+        let (front_matter, content) = split_page_content(Path::new(""), &content).unwrap();
+        assert_eq!(content, "Hello\n");
+        assert_eq!(front_matter.title.unwrap(), "Title");
+    }
+
+    #[test_case("\n+++\ntitle = \"Title\"\ndescription \"hey\"\n+++\nHello", 4, Some(13), "expected an equals, found a string"; "toml syntax")]
+    #[test_case("+++\ntitle = \"Title\"\ndate = 2002-13-12\n+++\nHello", 3, None, "data did not match any variant of untagged enum MaybeDatetime for key `date`"; "toml invalid date")]
+    #[test_case("---\ntitle: Title\ntags: [a\n---\nHello", 4, Some(1), "did not find expected ',' or ']', while parsing a flow sequence"; "yaml syntax")]
+    fn can_locate_front_matter_errors(
+        content: &str,
+        line: usize,
+        column: Option<usize>,
+        message: &str,
+    ) {
+        let err = split_page_content(Path::new("content/a.md"), content).unwrap_err();
+        let source_error = err.downcast_ref::<SourceError>().unwrap();
+        assert_eq!(source_error.message, message);
+        assert_eq!(source_error.line, line);
+        assert_eq!(source_error.column, column);
+        assert_eq!(source_error.lines.last().unwrap().0, line);
+        assert_eq!(source_error.path.as_deref(), Some(Path::new("content/a.md")));
+    }
+
+    #[test]
+    fn leaves_front_matter_errors_without_position_as_is() {
+        let err = split_page_content(Path::new(""), "+++\nslug = \"\"\n+++\n").unwrap_err();
+        assert!(err.downcast_ref::<SourceError>().is_none());
+    }
 }
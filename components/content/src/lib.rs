@@ -1,21 +1,27 @@
 mod front_matter;
 
+mod archive;
+mod enclosure;
 mod file_info;
 mod library;
 mod page;
 mod pagination;
 mod section;
 mod ser;
+mod series;
 mod sorting;
 mod taxonomies;
 mod types;
 mod utils;
 
+pub use archive::Archive;
+pub use enclosure::Enclosure;
 pub use file_info::FileInfo;
-pub use front_matter::{PageFrontMatter, SectionFrontMatter};
+pub use front_matter::{parse_datetime, EnclosureFrontMatter, PageFrontMatter, SectionFrontMatter};
 pub use library::Library;
-pub use page::Page;
+pub use page::{Page, TermSiblings};
 pub use pagination::Paginator;
 pub use section::Section;
+pub use series::Series;
 pub use taxonomies::{Taxonomy, TaxonomyTerm};
 pub use types::*;
@@ -3,10 +3,13 @@ use std::path::{Path, PathBuf};
 use config::Config;
 use libs::ahash::{AHashMap, AHashSet};
 
+use crate::archive::Archive;
 use crate::ser::TranslatedContent;
+use crate::series::Series;
 use crate::sorting::sort_pages;
 use crate::taxonomies::{Taxonomy, TaxonomyFound};
-use crate::{Page, Section, SortBy};
+use crate::{Page, Section, SortBy, TermSiblings};
+use utils::slugs::slugify_paths;
 
 macro_rules! set {
     ($($key:expr,)+) => (set!($($key),+));
@@ -36,11 +39,17 @@ pub struct Library {
     // So we don't need to pass the Config when adding a page to know how to slugify and we only
     // slugify once
     taxo_name_to_slug: AHashMap<String, String>,
+    /// Incremented when a page or section is inserted and when the sections are populated,
+    /// so what is derived from the library can be cached until it changes
+    version: usize,
+    /// Set by `listings_without_content` in the config
+    listings_without_content: bool,
 }
 
 impl Library {
     pub fn new(config: &Config) -> Self {
-        let mut lib = Self::default();
+        let mut lib =
+            Self { listings_without_content: config.listings_without_content, ..Self::default() };
 
         for (lang, options) in &config.languages {
             let mut taxas = AHashMap::new();
@@ -86,7 +95,9 @@ impl Library {
         entries.extend(page.meta.aliases.to_vec());
         self.insert_reverse_aliases(&file_path, entries);
 
-        for (taxa_name, terms) in &page.meta.taxonomies {
+        // Draft previews are not listed in taxonomies
+        let taxonomies = if page.is_draft_preview() { None } else { Some(&page.meta.taxonomies) };
+        for (taxa_name, terms) in taxonomies.into_iter().flatten() {
             for term in terms {
                 // Safe unwraps as we create all lang/taxa and we validated that they are correct
                 // before getting there
@@ -105,6 +116,7 @@ impl Library {
         }
 
         self.pages.insert(file_path, page);
+        self.version += 1;
     }
 
     pub fn insert_section(&mut self, section: Section) {
@@ -115,6 +127,16 @@ impl Library {
             self.insert_reverse_aliases(&file_path, entries);
         }
         self.sections.insert(file_path, section);
+        self.version += 1;
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    /// Whether the pages listed in other pages are serialized without their `content`
+    pub fn listings_without_content(&self) -> bool {
+        self.listings_without_content
     }
 
     /// Fills a map of target -> {content mentioning it}
@@ -166,9 +188,176 @@ impl Library {
             }
         }
 
+        // In the order of the config, which the map doesn't keep
+        taxonomies.sort_by_cached_key(|t| {
+            let position = config.languages[&t.lang]
+                .taxonomies
+                .iter()
+                .position(|c| c.slug == t.slug)
+                .unwrap_or_default();
+            (t.lang.clone(), position)
+        });
         taxonomies
     }
 
+    /// Fills the `taxonomy_siblings` of every page from the order of the pages of each term,
+    /// which is the order they are listed in on the term page
+    pub fn fill_taxonomy_siblings(&mut self, taxonomies: &[Taxonomy]) {
+        for page in self.pages.values_mut() {
+            page.taxonomy_siblings.clear();
+        }
+        for taxonomy in taxonomies {
+            for term in &taxonomy.items {
+                for (i, page_path) in term.pages.iter().enumerate() {
+                    let siblings = TermSiblings {
+                        prev: i.checked_sub(1).map(|j| term.pages[j].clone()),
+                        next: term.pages.get(i + 1).cloned(),
+                    };
+                    self.pages
+                        .get_mut(page_path)
+                        .unwrap()
+                        .taxonomy_siblings
+                        .entry(taxonomy.kind.name.clone())
+                        .or_default()
+                        .insert(term.name.clone(), siblings);
+                }
+            }
+        }
+    }
+
+    /// Groups the dated pages by year and by month if `generate_archive` is enabled in the config
+    pub fn find_archives(&self, config: &Config) -> Vec<Archive> {
+        if !config.generate_archive {
+            return Vec::new();
+        }
+
+        let mut years: AHashMap<(&str, i32), Vec<&Page>> = AHashMap::new();
+        let mut months: AHashMap<(&str, i32, u8), Vec<&Page>> = AHashMap::new();
+        for page in self.pages.values().filter(|p| !p.is_draft_preview()) {
+            if let Some((year, month, _)) = page.meta.datetime_tuple {
+                years.entry((&page.lang, year)).or_default().push(page);
+                months.entry((&page.lang, year, month)).or_default().push(page);
+            }
+        }
+
+        let mut archives: Vec<_> = years
+            .into_iter()
+            .map(|((lang, year), pages)| Archive::new(year, None, lang, &pages, config))
+            .chain(months.into_iter().map(|((lang, year, month), pages)| {
+                Archive::new(year, Some(month), lang, &pages, config)
+            }))
+            .collect();
+        archives.sort_by(|a, b| a.path.cmp(&b.path));
+        archives
+    }
+
+    /// Groups the pages by the `series` set in their front matter and fills the
+    /// `series_prev`/`series_next` of each of those pages
+    pub fn find_series(&mut self, config: &Config) -> Vec<Series> {
+        let mut grouped: AHashMap<(&str, String), Vec<&Page>> = AHashMap::new();
+        for page in self.pages.values().filter(|p| !p.is_draft_preview()) {
+            if let Some(ref name) = page.meta.series {
+                grouped
+                    .entry((&page.lang, slugify_paths(name, config.slugify.paths)))
+                    .or_default()
+                    .push(page);
+            }
+        }
+
+        let mut all_series: Vec<_> = grouped
+            .into_iter()
+            .map(|((lang, _), mut pages)| {
+                // Different spellings of a name can end up in the same series, pick one consistently
+                pages.sort_by(|a, b| a.file.path.cmp(&b.file.path));
+                let name = pages[0].meta.series.as_ref().unwrap();
+                Series::new(name, lang, &pages, config)
+            })
+            .collect();
+        all_series.sort_by(|a, b| a.lang.cmp(&b.lang).then_with(|| a.slug.cmp(&b.slug)));
+
+        for page in self.pages.values_mut() {
+            page.series_prev = None;
+            page.series_next = None;
+        }
+        for series in &all_series {
+            for (i, page_path) in series.pages.iter().enumerate() {
+                let page = self.pages.get_mut(page_path).unwrap();
+                if i > 0 {
+                    page.series_prev = Some(series.pages[i - 1].clone());
+                }
+                if i < series.pages.len() - 1 {
+                    page.series_next = Some(series.pages[i + 1].clone());
+                }
+            }
+        }
+
+        all_series
+    }
+
+    /// Finds the pages sharing the most taxonomy terms with each page.
+    /// Every shared term adds the weight of its taxonomy to the score of a candidate, ties
+    /// are broken by date (newest first) and then by path so the order is stable across builds.
+    pub fn fill_related_pages(&mut self, config: &Config) {
+        for page in self.pages.values_mut() {
+            page.related.clear();
+        }
+
+        let related_config = &config.related_pages;
+        if !related_config.is_enabled() {
+            return;
+        }
+
+        let weights: AHashMap<&str, usize> = self
+            .taxo_name_to_slug
+            .iter()
+            .map(|(name, slug)| (slug.as_str(), related_config.weight(name)))
+            .collect();
+
+        // {page -> {candidate -> score}}
+        let mut scores: AHashMap<&PathBuf, AHashMap<&PathBuf, usize>> = AHashMap::new();
+        for taxonomies in self.taxonomies_def.values() {
+            for (taxa_slug, terms) in taxonomies {
+                let weight = weights.get(taxa_slug.as_str()).copied().unwrap_or(1);
+                if weight == 0 {
+                    continue;
+                }
+
+                for term_pages in terms.values() {
+                    // A term can be listed several times in the same page
+                    let term_pages: AHashSet<&PathBuf> =
+                        term_pages.iter().filter(|p| self.pages.contains_key(*p)).collect();
+                    for page in &term_pages {
+                        for other in &term_pages {
+                            if page != other {
+                                *scores.entry(page).or_default().entry(other).or_default() +=
+                                    weight;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut related = AHashMap::with_capacity(scores.len());
+        for (path, candidates) in scores {
+            let mut candidates: Vec<_> = candidates.into_iter().collect();
+            candidates.sort_by(|(a, a_score), (b, b_score)| {
+                b_score
+                    .cmp(a_score)
+                    .then_with(|| self.pages[*b].meta.datetime.cmp(&self.pages[*a].meta.datetime))
+                    .then_with(|| a.cmp(b))
+            });
+            candidates.truncate(related_config.limit);
+            related.insert(path.clone(), candidates.into_iter().map(|(p, _)| p.clone()).collect());
+        }
+
+        for (path, related_pages) in related {
+            if let Some(page) = self.pages.get_mut(&path) {
+                page.related = related_pages;
+            }
+        }
+    }
+
     /// Sort all sections pages according to sorting method given
     /// Pages that cannot be sorted are set to the section.ignored_pages instead
     pub fn sort_section_pages(&mut self) {
@@ -188,7 +377,7 @@ impl Library {
             if !self.sections[&path].meta.transparent {
                 // Fill siblings
                 for (i, page_path) in sorted.iter().enumerate() {
-                    let mut p = self.pages.get_mut(page_path).unwrap();
+                    let p = self.pages.get_mut(page_path).unwrap();
                     if i > 0 {
                         // lighter / later / title_prev
                         p.lower = Some(sorted[i - 1].clone());
@@ -269,14 +458,15 @@ impl Library {
             section.subsections.clear();
             section.pages.clear();
             section.ignored_pages.clear();
+            section.paginated_pages = None;
             section.ancestors.clear();
 
-            if let Some(children) = subsections.get(&*path) {
+            if let Some(children) = subsections.get(path) {
                 let mut children: Vec<_> = children.clone();
-                children.sort_by(|a, b| sections_weight[a].cmp(&sections_weight[b]));
+                children.sort_by(|a, b| sections_weight[a].cmp(&sections_weight[b]).then(a.cmp(b)));
                 section.subsections = children;
             }
-            if let Some(parents) = ancestors.get(&*path) {
+            if let Some(parents) = ancestors.get(path) {
                 section.ancestors = parents.clone();
             }
         }
@@ -295,11 +485,14 @@ impl Library {
         for (path, page) in self.pages.iter_mut() {
             let parent_filename = &index_filename_by_lang[&page.lang];
             add_translation(&page.file.canonical, path);
-            let mut parent_section_path = page.file.parent.join(&parent_filename);
+            let mut parent_section_path = page.file.parent.join(parent_filename);
 
             while let Some(parent_section) = self.sections.get_mut(&parent_section_path) {
                 let is_transparent = parent_section.meta.transparent;
-                parent_section.pages.push(path.clone());
+                // Draft previews keep their ancestors but are not listed in their sections
+                if !page.is_draft_preview() {
+                    parent_section.pages.push(path.clone());
+                }
                 page.ancestors = ancestors.get(&parent_section_path).cloned().unwrap_or_default();
                 // Don't forget to push the actual parent
                 page.ancestors.push(parent_section.file.relative.clone());
@@ -317,20 +510,133 @@ impl Library {
                     }
                 }
 
+                // Redirects have nothing to render and protected pages would be readable
+                let has_amp =
+                    page.ancestors.iter().any(|a| self.sections[&content_path.join(a)].meta.amp);
+                if has_amp && page.meta.redirect_to.is_none() && page.meta.password.is_none() {
+                    let separator = if page.permalink.ends_with('/') { "" } else { "/" };
+                    page.amp_permalink = Some(format!("{}{}amp/", page.permalink, separator));
+                }
+
                 if !is_transparent {
                     break;
                 }
 
                 // We've added `_index(.{LANG})?.md` so if we are here so we need to go up twice
                 match parent_section_path.clone().parent().unwrap().parent() {
-                    Some(parent) => parent_section_path = parent.join(&parent_filename),
+                    Some(parent) => parent_section_path = parent.join(parent_filename),
                     None => break,
                 }
             }
         }
 
+        // The pages were added in whatever order the map gave them, start from the same order in
+        // every build so sections that aren't sorted list them in the same order
+        for section in self.sections.values_mut() {
+            section.pages.sort();
+        }
+
         // And once we have all the pages assigned to their section, we sort them
         self.sort_section_pages();
+        self.fill_paginated_pages();
+        self.fill_book_siblings();
+        self.version += 1;
+    }
+
+    /// Fills the `book_prev`/`book_next` of every page with the pages around it when reading the
+    /// site from start to end: the pages of a section in its sort order, then each of its
+    /// subsections by weight. Every language is its own book.
+    fn fill_book_siblings(&mut self) {
+        let mut roots: Vec<_> = self.sections.values().filter(|s| s.is_index()).collect();
+        roots.sort_by(|a, b| a.lang.cmp(&b.lang));
+
+        let mut books = Vec::with_capacity(roots.len());
+        for root in roots {
+            let mut pages = Vec::new();
+            // Pages of transparent sections are also in the pages of their parent
+            let mut seen = AHashSet::new();
+            self.walk_book(root, &mut pages, &mut seen);
+            books.push(pages);
+        }
+
+        for page in self.pages.values_mut() {
+            page.book_prev = None;
+            page.book_next = None;
+        }
+        for pages in books {
+            for (i, page_path) in pages.iter().enumerate() {
+                let page = self.pages.get_mut(page_path).unwrap();
+                if i > 0 {
+                    page.book_prev = Some(pages[i - 1].clone());
+                }
+                if i < pages.len() - 1 {
+                    page.book_next = Some(pages[i + 1].clone());
+                }
+            }
+        }
+    }
+
+    fn walk_book<'a>(
+        &'a self,
+        section: &'a Section,
+        pages: &mut Vec<PathBuf>,
+        seen: &mut AHashSet<&'a PathBuf>,
+    ) {
+        for page in &section.pages {
+            if seen.insert(page) {
+                pages.push(page.clone());
+            }
+        }
+        for subsection in &section.subsections {
+            if let Some(s) = self.sections.get(subsection) {
+                self.walk_book(s, pages, seen);
+            }
+        }
+    }
+
+    /// Finds the pages to paginate for the sections paginating more than their own pages,
+    /// sorted like the pages of the section itself
+    fn fill_paginated_pages(&mut self) {
+        let mut updates = AHashMap::new();
+        for (path, section) in &self.sections {
+            if !section.meta.paginate_all_pages && section.meta.paginate_sections.is_empty() {
+                continue;
+            }
+
+            let prefixes: Vec<Vec<&str>> = section
+                .meta
+                .paginate_sections
+                .iter()
+                .map(|s| s.split('/').filter(|c| !c.is_empty()).collect())
+                .collect();
+            let mut pages: Vec<_> = self
+                .pages
+                .values()
+                .filter(|p| p.lang == section.lang)
+                .filter(|p| {
+                    section.meta.paginate_all_pages
+                        || prefixes.iter().any(|prefix| {
+                            p.file.components.len() >= prefix.len()
+                                && prefix.iter().zip(&p.file.components).all(|(a, b)| a == b)
+                        })
+                })
+                .collect();
+
+            let paginated_pages = match section.meta.sort_by {
+                SortBy::None => {
+                    pages.sort_by(|a, b| a.file.path.cmp(&b.file.path));
+                    pages.into_iter().map(|p| p.file.path.clone()).collect()
+                }
+                _ => sort_pages(&pages, section.meta.sort_by).0,
+            };
+            updates.insert(path.clone(), paginated_pages);
+        }
+
+        for (path, paginated_pages) in updates {
+            if let Some(s) = self.sections.get_mut(&path) {
+                s.paginated_pages = Some(paginated_pages);
+            }
+        }
     }
 
     /// Find all the orphan pages: pages that are in a folder without an `_index.md`
@@ -357,6 +663,7 @@ impl Library {
                 translations.push(TranslatedContent { lang, permalink, title, path });
             }
         }
+        translations.sort_by(|a, b| a.lang.cmp(b.lang).then(a.path.cmp(b.path)));
 
         translations
     }
@@ -429,9 +736,11 @@ mod tests {
     }
 
     fn create_page(file_path: &str, lang: &str, page_sort: PageSort) -> Page {
-        let mut page = Page::default();
-        page.lang = lang.to_owned();
-        page.file = FileInfo::new_page(Path::new(file_path), &PathBuf::new());
+        let mut page = Page {
+            lang: lang.to_owned(),
+            file: FileInfo::new_page(Path::new(file_path), &PathBuf::new()),
+            ..Default::default()
+        };
         match page_sort {
             PageSort::None => (),
             PageSort::Date(date) => {
@@ -456,9 +765,11 @@ mod tests {
         transparent: bool,
         sort_by: SortBy,
     ) -> Section {
-        let mut section = Section::default();
-        section.lang = lang.to_owned();
-        section.file = FileInfo::new_section(Path::new(file_path), &PathBuf::new());
+        let mut section = Section {
+            lang: lang.to_owned(),
+            file: FileInfo::new_section(Path::new(file_path), &PathBuf::new()),
+            ..Default::default()
+        };
         section.meta.weight = weight;
         section.meta.transparent = transparent;
         section.meta.sort_by = sort_by;
@@ -630,6 +941,99 @@ mod tests {
         page
     }
 
+    #[test]
+    fn can_fill_book_siblings_across_sections() {
+        let mut config = Config::default_for_test();
+        config.languages.insert("fr".to_owned(), LanguageOptions::default());
+        let mut library = Library::default();
+        let sections = vec![
+            ("content/_index.md", "en", 0, false, SortBy::Weight),
+            ("content/_index.fr.md", "fr", 0, false, SortBy::Weight),
+            ("content/docs/_index.md", "en", 0, false, SortBy::Weight),
+            ("content/docs/advanced/_index.md", "en", 2, false, SortBy::Weight),
+            ("content/docs/basics/_index.md", "en", 1, false, SortBy::Weight),
+            ("content/docs/basics/install/_index.md", "en", 0, true, SortBy::Weight),
+        ];
+        for (p, l, w, t, s) in sections {
+            library.insert_section(create_section(p, l, w, t, s));
+        }
+        let pages = vec![
+            ("content/about.md", "en", PageSort::Weight(1)),
+            ("content/about.fr.md", "fr", PageSort::Weight(1)),
+            ("content/docs/intro.md", "en", PageSort::Weight(1)),
+            ("content/docs/advanced/plugins.md", "en", PageSort::Weight(1)),
+            ("content/docs/basics/usage.md", "en", PageSort::Weight(2)),
+            ("content/docs/basics/install/linux.md", "en", PageSort::Weight(1)),
+        ];
+        for (p, l, s) in pages {
+            library.insert_page(create_page(p, l, s));
+        }
+        library.populate_sections(&config, Path::new("content"));
+
+        let book: Vec<_> = [
+            "content/about.md",
+            "content/docs/intro.md",
+            "content/docs/basics/install/linux.md",
+            "content/docs/basics/usage.md",
+            "content/docs/advanced/plugins.md",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        for (i, path) in book.iter().enumerate() {
+            let page = &library.pages[path];
+            assert_eq!(page.book_prev.as_ref(), i.checked_sub(1).map(|i| &book[i]));
+            assert_eq!(page.book_next.as_ref(), book.get(i + 1));
+        }
+        // Every language is read separately
+        let about_fr = &library.pages[&PathBuf::from("content/about.fr.md")];
+        assert!(about_fr.book_prev.is_none());
+        assert!(about_fr.book_next.is_none());
+    }
+
+    #[test]
+    fn can_find_amp_pages() {
+        let config = Config::default_for_test();
+        let mut library = Library::default();
+        for (path, amp) in [
+            ("content/_index.md", false),
+            ("content/blog/_index.md", true),
+            ("content/blog/2022/_index.md", false),
+            ("content/notes/_index.md", false),
+        ] {
+            let mut section = create_section(path, "en", 0, false, SortBy::None);
+            section.meta.amp = amp;
+            library.insert_section(section);
+        }
+        for path in [
+            "content/blog/rust.md",
+            "content/blog/2022/python.md",
+            "content/blog/secret.md",
+            "content/notes/hello.md",
+        ] {
+            let mut page = create_page(path, "en", PageSort::None);
+            page.permalink = format!("https://example.com/{}/", page.file.name);
+            if page.file.name == "secret" {
+                page.meta.password = Some("hunter2".to_owned());
+            }
+            library.insert_page(page);
+        }
+        library.populate_sections(&config, Path::new("content"));
+
+        let amp_permalink = |path: &str| library.pages[&PathBuf::from(path)].amp_permalink.clone();
+        assert_eq!(
+            amp_permalink("content/blog/rust.md"),
+            Some("https://example.com/rust/amp/".to_owned())
+        );
+        // Subsections get the AMP version as well
+        assert_eq!(
+            amp_permalink("content/blog/2022/python.md"),
+            Some("https://example.com/python/amp/".to_owned())
+        );
+        assert_eq!(amp_permalink("content/blog/secret.md"), None);
+        assert_eq!(amp_permalink("content/notes/hello.md"), None);
+    }
+
     #[test]
     fn can_make_taxonomies() {
         let mut config = Config::default_for_test();
@@ -708,7 +1112,10 @@ mod tests {
         assert_eq!(tags.items[0].permalink, "http://a-website.com/tags/rust/");
         let fr_categories = taxonomies.iter().find(|t| t.kind.name == "catégories").unwrap();
         assert_eq!(fr_categories.len(), 1);
-        assert_eq!(fr_categories.items[0].permalink, "http://a-website.com/fr/catégories/rust/");
+        assert_eq!(
+            fr_categories.items[0].permalink,
+            "http://a-website.com/fr/cat%C3%A9gories/rust/"
+        );
     }
 
     #[test]
@@ -756,6 +1163,254 @@ mod tests {
         assert_eq!(tax.items.len(), 4);
     }
 
+    #[test]
+    fn can_paginate_pages_of_other_sections() {
+        let mut config = Config::default_for_test();
+        config.languages.insert("fr".to_owned(), LanguageOptions::default());
+        let mut library = Library::default();
+        let mut index = create_section("content/_index.md", "en", 0, false, SortBy::Date);
+        index.meta.paginate_all_pages = true;
+        library.insert_section(index);
+        let mut notes = create_section("content/notes/_index.md", "en", 0, false, SortBy::Date);
+        notes.meta.paginate_sections = vec!["blog/rust".to_owned(), "/wiki/".to_owned()];
+        library.insert_section(notes);
+        for path in
+            ["content/blog/_index.md", "content/blog/rust/_index.md", "content/wiki/_index.md"]
+        {
+            library.insert_section(create_section(path, "en", 0, false, SortBy::Date));
+        }
+        let pages = vec![
+            ("content/blog/python.md", "en", PageSort::Date("2022-03-03")),
+            ("content/blog/rust/borrowck.md", "en", PageSort::Date("2022-01-01")),
+            ("content/blog/rust/traits.md", "en", PageSort::Date("2022-04-04")),
+            ("content/wiki/zola.md", "en", PageSort::Date("2022-02-02")),
+            ("content/wiki/undated.md", "en", PageSort::None),
+            ("content/wiki/zola.fr.md", "fr", PageSort::Date("2022-02-02")),
+        ];
+        for (p, l, s) in pages {
+            library.insert_page(create_page(p, l, s));
+        }
+        library.populate_sections(&config, Path::new("content"));
+
+        let index = &library.sections[&PathBuf::from("content/_index.md")];
+        assert!(index.pages.is_empty());
+        assert_eq!(
+            index.paginated_pages,
+            Some(vec![
+                PathBuf::from("content/blog/rust/traits.md"),
+                PathBuf::from("content/blog/python.md"),
+                PathBuf::from("content/wiki/zola.md"),
+                PathBuf::from("content/blog/rust/borrowck.md"),
+            ])
+        );
+        let notes = &library.sections[&PathBuf::from("content/notes/_index.md")];
+        assert_eq!(
+            notes.paginated_pages,
+            Some(vec![
+                PathBuf::from("content/blog/rust/traits.md"),
+                PathBuf::from("content/wiki/zola.md"),
+                PathBuf::from("content/blog/rust/borrowck.md"),
+            ])
+        );
+        let blog = &library.sections[&PathBuf::from("content/blog/_index.md")];
+        assert_eq!(blog.paginated_pages, None);
+    }
+
+    #[test]
+    fn can_find_archives() {
+        let mut config = Config::default_for_test();
+        config.languages.insert("fr".to_owned(), LanguageOptions::default());
+        let mut library = Library::new(&config);
+        let pages = vec![
+            ("content/blog/a.md", "en", PageSort::Date("2021-05-01")),
+            ("content/blog/b.md", "en", PageSort::Date("2021-05-20")),
+            ("content/blog/c.md", "en", PageSort::Date("2021-11-03")),
+            ("content/news/d.md", "en", PageSort::Date("2022-01-01")),
+            ("content/blog/a.fr.md", "fr", PageSort::Date("2021-05-01")),
+            ("content/about.md", "en", PageSort::None),
+        ];
+        for (path, lang, sort) in pages {
+            library.insert_page(create_page(path, lang, sort));
+        }
+
+        assert!(library.find_archives(&config).is_empty());
+
+        config.generate_archive = true;
+        let archives = library.find_archives(&config);
+        let paths: Vec<_> = archives.iter().map(|a| a.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/2021/",
+                "/2021/05/",
+                "/2021/11/",
+                "/2022/",
+                "/2022/01/",
+                "/fr/2021/",
+                "/fr/2021/05/"
+            ]
+        );
+        assert_eq!(archives[0].year, 2021);
+        assert_eq!(archives[0].month, None);
+        assert_eq!(archives[0].permalink, "http://a-website.com/2021/");
+        // most recent first
+        assert_eq!(
+            archives[0].pages,
+            vec![
+                PathBuf::from("content/blog/c.md"),
+                PathBuf::from("content/blog/b.md"),
+                PathBuf::from("content/blog/a.md"),
+            ]
+        );
+        assert_eq!(archives[1].month, Some(5));
+        assert_eq!(
+            archives[1].pages,
+            vec![PathBuf::from("content/blog/b.md"), PathBuf::from("content/blog/a.md")]
+        );
+        assert_eq!(archives[5].lang, "fr");
+        assert_eq!(archives[5].pages, vec![PathBuf::from("content/blog/a.fr.md")]);
+    }
+
+    #[test]
+    fn can_find_series() {
+        let config = Config::default_for_test();
+        let mut library = Library::new(&config);
+        let pages = vec![
+            ("content/blog/part-2.md", Some("Rust from scratch"), PageSort::Date("2022-02-01")),
+            ("content/blog/part-1.md", Some("Rust from scratch"), PageSort::Date("2022-01-01")),
+            ("content/docs/appendix.md", Some("rust from Scratch"), PageSort::None),
+            ("content/docs/part-3.md", Some("Rust from scratch"), PageSort::Date("2022-03-01")),
+            ("content/blog/other.md", Some("Another series"), PageSort::None),
+            ("content/blog/alone.md", None, PageSort::Date("2022-01-15")),
+        ];
+        for (path, series, sort) in pages {
+            let mut page = create_page(path, "en", sort);
+            page.meta.series = series.map(|s| s.to_owned());
+            library.insert_page(page);
+        }
+
+        let series = library.find_series(&config);
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].slug, "another-series");
+        assert_eq!(series[0].pages, vec![PathBuf::from("content/blog/other.md")]);
+        assert_eq!(series[1].name, "Rust from scratch");
+        assert_eq!(series[1].slug, "rust-from-scratch");
+        assert_eq!(series[1].permalink, "http://a-website.com/series/rust-from-scratch/");
+        assert_eq!(
+            series[1].pages,
+            vec![
+                PathBuf::from("content/blog/part-1.md"),
+                PathBuf::from("content/blog/part-2.md"),
+                PathBuf::from("content/docs/part-3.md"),
+                PathBuf::from("content/docs/appendix.md"),
+            ]
+        );
+
+        let part_1 = &library.pages[&PathBuf::from("content/blog/part-1.md")];
+        assert!(part_1.series_prev.is_none());
+        assert_eq!(part_1.series_next, Some(PathBuf::from("content/blog/part-2.md")));
+        let part_3 = &library.pages[&PathBuf::from("content/docs/part-3.md")];
+        assert_eq!(part_3.series_prev, Some(PathBuf::from("content/blog/part-2.md")));
+        assert_eq!(part_3.series_next, Some(PathBuf::from("content/docs/appendix.md")));
+        let other = &library.pages[&PathBuf::from("content/blog/other.md")];
+        assert!(other.series_prev.is_none());
+        assert!(other.series_next.is_none());
+        let alone = &library.pages[&PathBuf::from("content/blog/alone.md")];
+        assert!(alone.series_prev.is_none());
+        assert!(alone.series_next.is_none());
+    }
+
+    #[test]
+    fn can_fill_taxonomy_siblings() {
+        let mut config = Config::default_for_test();
+        config.languages.get_mut("en").unwrap().taxonomies =
+            vec![TaxonomyConfig { name: "tags".to_string(), ..TaxonomyConfig::default() }];
+        config.slugify_taxonomies();
+        let mut library = Library::new(&config);
+        let pages = vec![
+            ("content/a.md", vec!["rust", "db"], "2022-01-01"),
+            ("content/b.md", vec!["rust"], "2022-02-01"),
+            ("content/c.md", vec!["rust", "db"], "2022-03-01"),
+            ("content/d.md", vec!["js"], "2022-04-01"),
+        ];
+        for (path, tags, date) in pages {
+            let mut page = create_page(path, "en", PageSort::Date(date));
+            page.meta
+                .taxonomies
+                .insert("tags".to_string(), tags.iter().map(|t| t.to_string()).collect());
+            library.insert_page(page);
+        }
+
+        let taxonomies = library.find_taxonomies(&config);
+        library.fill_taxonomy_siblings(&taxonomies);
+
+        // Siblings follow the order of the term page, newest first
+        let b = &library.pages[&PathBuf::from("content/b.md")].taxonomy_siblings["tags"];
+        assert_eq!(
+            b["rust"],
+            TermSiblings {
+                prev: Some(PathBuf::from("content/c.md")),
+                next: Some(PathBuf::from("content/a.md")),
+            }
+        );
+        assert!(!b.contains_key("db"));
+        let a = &library.pages[&PathBuf::from("content/a.md")].taxonomy_siblings["tags"];
+        assert_eq!(a["rust"].prev, Some(PathBuf::from("content/b.md")));
+        assert!(a["rust"].next.is_none());
+        assert_eq!(a["db"].prev, Some(PathBuf::from("content/c.md")));
+        assert!(a["db"].next.is_none());
+        let d = &library.pages[&PathBuf::from("content/d.md")].taxonomy_siblings["tags"];
+        assert_eq!(d["js"], TermSiblings::default());
+    }
+
+    #[test]
+    fn can_fill_related_pages() {
+        let mut config = Config::default_for_test();
+        config.languages.get_mut("en").unwrap().taxonomies = vec![
+            TaxonomyConfig { name: "categories".to_string(), ..TaxonomyConfig::default() },
+            TaxonomyConfig { name: "tags".to_string(), ..TaxonomyConfig::default() },
+        ];
+        config.slugify_taxonomies();
+        config.related_pages.limit = 2;
+        config.related_pages.weights.insert("categories".to_string(), 3);
+
+        let mut library = Library::new(&config);
+        library.insert_page(create_page_w_taxa(
+            "a.md",
+            "en",
+            vec![("tags", vec!["rust", "db"]), ("categories", vec!["tutorials"])],
+        ));
+        library.insert_page(create_page_w_taxa(
+            "b.md",
+            "en",
+            vec![("tags", vec!["rust", "db"]), ("categories", vec!["others"])],
+        ));
+        library.insert_page(create_page_w_taxa(
+            "c.md",
+            "en",
+            vec![("tags", vec!["js"]), ("categories", vec!["tutorials"])],
+        ));
+        library.insert_page(create_page_w_taxa("d.md", "en", vec![("tags", vec!["db"])]));
+        library.insert_page(create_page_w_taxa("e.md", "en", vec![("tags", vec!["python"])]));
+        library.fill_related_pages(&config);
+
+        // c.md shares a category (3) while b.md shares two tags (2), d.md is cut by the limit
+        assert_eq!(
+            library.pages[&PathBuf::from("a.md")].related,
+            vec![PathBuf::from("c.md"), PathBuf::from("b.md")]
+        );
+        // a.md and d.md share the same score so they are ordered by path
+        assert_eq!(
+            library.pages[&PathBuf::from("b.md")].related,
+            vec![PathBuf::from("a.md"), PathBuf::from("d.md")]
+        );
+        assert!(library.pages[&PathBuf::from("e.md")].related.is_empty());
+
+        config.related_pages.limit = 0;
+        library.fill_related_pages(&config);
+        assert!(library.pages[&PathBuf::from("a.md")].related.is_empty());
+    }
+
     #[test]
     fn can_fill_backlinks() {
         let mut page1 = create_page("page1.md", "en", PageSort::None);
@@ -6,18 +6,19 @@ use libs::once_cell::sync::Lazy;
 use libs::regex::Regex;
 use libs::tera::{Context as TeraContext, Tera};
 
-use config::Config;
-use errors::{Context, Result};
-use markdown::{render_content, RenderContext};
+use config::{Config, OutputFormat};
+use errors::{bail, set_source_error_file, Context, Result};
+use markdown::{render_content, render_gemtext, RenderContext};
 use utils::slugs::slugify_paths;
 use utils::table_of_contents::Heading;
 use utils::templates::{render_template, ShortcodeDefinition};
-use utils::types::InsertAnchor;
+use utils::types::{InsertAnchor, RawHtml};
 
+use crate::enclosure::{enclosure_url, Enclosure};
 use crate::file_info::FileInfo;
-use crate::front_matter::{split_page_content, PageFrontMatter};
+use crate::front_matter::{normalize_content, split_page_content, PageFrontMatter};
 use crate::library::Library;
-use crate::ser::SerializingPage;
+use crate::ser::{PageSerMode, SerializingPage};
 use crate::utils::get_reading_analytics;
 use crate::utils::{find_related_assets, has_anchor};
 use utils::anchors::has_anchor_id;
@@ -31,8 +32,18 @@ static RFC3339_DATE: Lazy<Regex> = Lazy::new(|| {
     ).unwrap()
 });
 
+/// Where the summary of a page ends
+const SUMMARY_SEPARATOR: &str = "<!-- more -->";
+
 static FOOTNOTES_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<sup\s*.*?>\s*.*?</sup>").unwrap());
 
+/// The previous and next pages of a page in the list of pages of a taxonomy term
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TermSiblings {
+    pub prev: Option<PathBuf>,
+    pub next: Option<PathBuf>,
+}
+
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Page {
     /// All info about the actual file
@@ -43,6 +54,12 @@ pub struct Page {
     pub ancestors: Vec<String>,
     /// The actual content of the page, in markdown
     pub raw_content: String,
+    /// Whether the raw content is the HTML output by the converter of the file, used as it is
+    /// instead of being rendered as Markdown
+    pub raw_content_is_html: bool,
+    /// The number of lines of the file before the content, to point errors of the content at the
+    /// right line of the file
+    pub content_line_offset: usize,
     /// All the non-md files we found next to the .md file
     pub assets: Vec<PathBuf>,
     /// All the non-md files we found next to the .md file
@@ -66,6 +83,19 @@ pub struct Page {
     pub lower: Option<PathBuf>,
     /// The next page when sorting: later/later_updated/heavier/next
     pub higher: Option<PathBuf>,
+    /// The previous page of the series this page is part of, if any
+    pub series_prev: Option<PathBuf>,
+    /// The next page of the series this page is part of, if any
+    pub series_next: Option<PathBuf>,
+    /// The previous page when reading the whole site like a book, across sections
+    pub book_prev: Option<PathBuf>,
+    /// The next page when reading the whole site like a book, across sections
+    pub book_next: Option<PathBuf>,
+    /// The previous/next pages in each taxonomy term of this page, by taxonomy name then term name
+    pub taxonomy_siblings: HashMap<String, HashMap<String, TermSiblings>>,
+    /// The pages sharing the most taxonomy terms with this one, best match first.
+    /// Only filled if `related_pages` is enabled in the config
+    pub related: Vec<PathBuf>,
     /// Toc made from the headings of the markdown file
     pub toc: Vec<Heading>,
     /// How many words in the raw content
@@ -84,6 +114,12 @@ pub struct Page {
     pub internal_links: Vec<(String, Option<String>)>,
     /// The list of all links to external webpages. They can be validated by the `link_checker`.
     pub external_links: Vec<String>,
+    /// The token prefixing the path of drafts when `drafts.preview_tokens` is enabled
+    pub preview_token: Option<String>,
+    /// The permalink of the AMP version of the page, if one of its sections has `amp` set
+    pub amp_permalink: Option<String>,
+    /// The media file of the page from its `enclosure` front matter, for the feeds
+    pub enclosure: Option<Enclosure>,
 }
 
 impl Page {
@@ -102,13 +138,18 @@ impl Page {
         config: &Config,
         base_path: &Path,
     ) -> Result<Page> {
-        let (meta, content) = split_page_content(file_path, content)?;
+        let content = normalize_content(content);
+        let file_content = content;
+        let (meta, content) = split_page_content(file_path, &file_content)?;
         let mut page = Page::new(file_path, meta, base_path);
 
         page.lang =
             page.file.find_language(&config.default_language, &config.other_languages_codes())?;
 
         page.raw_content = content.to_string();
+        // The content is what's left of the file after the front matter
+        page.content_line_offset =
+            file_content[..file_content.len() - content.len()].matches('\n').count();
         let (word_count, reading_time) = get_reading_analytics(&page.raw_content);
         page.word_count = Some(word_count);
         page.reading_time = Some(reading_time);
@@ -164,20 +205,93 @@ impl Page {
 
             format!("/{}", path)
         };
+        page.set_path(page.path.clone(), config);
 
-        if !page.path.ends_with('/') {
-            page.path = format!("{}/", page.path);
-        }
+        Ok(page)
+    }
 
-        page.components = page
+    fn set_path(&mut self, path: String, config: &Config) {
+        self.path = if path.ends_with('/') { path } else { format!("{}/", path) };
+        self.components = self
             .path
             .split('/')
             .map(|p| p.to_string())
             .filter(|p| !p.is_empty())
             .collect::<Vec<_>>();
-        page.permalink = config.make_permalink(&page.path);
+        self.permalink = config.make_permalink(&self.path);
+        // The enclosure can be relative to the page
+        if let (Some(enclosure), Some(meta)) = (&mut self.enclosure, &self.meta.enclosure) {
+            enclosure.url = enclosure_url(&meta.path, &self.permalink, config);
+        }
+    }
 
-        Ok(page)
+    /// Moves a draft under an unguessable `/<token>/` prefix so it can be shared for preview
+    /// without being listed anywhere
+    pub fn apply_preview_token(&mut self, token: String, config: &Config) {
+        let path = format!("/{}{}", token, self.path);
+        self.preview_token = Some(token);
+        self.set_path(path, config);
+    }
+
+    /// Whether the page is in the feeds: only dated pages are. Protected pages would give away
+    /// their content.
+    pub fn is_in_feed(&self) -> bool {
+        self.meta.date.is_some() && self.meta.password.is_none()
+    }
+
+    /// Whether this is a draft only reachable through its preview token
+    pub fn is_draft_preview(&self) -> bool {
+        self.preview_token.is_some()
+    }
+
+    /// Whether the page is rendered to gemtext when `[gemini]` is enabled: redirecting and
+    /// protected pages are left out
+    pub fn has_gemtext(&self) -> bool {
+        self.meta.redirect_to.is_none() && self.meta.password.is_none()
+    }
+
+    /// Whether the page is written as an HTML page of its own, ie it has an HTML output
+    /// and doesn't redirect elsewhere
+    pub fn has_html_page(&self, config: &Config) -> bool {
+        self.meta.redirect_to.is_none()
+            && self.meta.outputs.as_ref().unwrap_or(&config.outputs).contains(&OutputFormat::Html)
+    }
+
+    /// Replaces the path computed from the file location with one built from a permalink
+    /// pattern such as `/:year/:month/:slug/`.
+    /// Available placeholders are `:year`, `:month`, `:day`, `:slug` and `:section`.
+    /// Does nothing if the page sets its own `path` in its front matter.
+    pub fn apply_permalink_pattern(&mut self, pattern: &str, config: &Config) -> Result<()> {
+        if self.meta.path.is_some() {
+            return Ok(());
+        }
+
+        let mut path = pattern.trim().to_string();
+        if path.contains(":year") || path.contains(":month") || path.contains(":day") {
+            let (year, month, day) = match self.meta.datetime_tuple {
+                Some(t) => t,
+                None => bail!(
+                    "Page `{}` has no date but its permalink pattern `{}` requires one",
+                    self.file.path.display(),
+                    pattern
+                ),
+            };
+            path = path
+                .replace(":year", &year.to_string())
+                .replace(":month", &format!("{:02}", month))
+                .replace(":day", &format!("{:02}", day));
+        }
+        path =
+            path.replace(":section", &self.file.components.join("/")).replace(":slug", &self.slug);
+
+        // Placeholders can be empty, eg `:section` for a page at the root
+        let mut path = path.split('/').filter(|p| !p.is_empty()).collect::<Vec<_>>().join("/");
+        if self.lang != config.default_language {
+            path = format!("{}/{}", self.lang, path);
+        }
+        self.set_path(format!("/{}", path), config);
+
+        Ok(())
     }
 
     pub fn find_language(&mut self) {}
@@ -186,9 +300,21 @@ impl Page {
     pub fn from_file<P: AsRef<Path>>(path: P, config: &Config, base_path: &Path) -> Result<Page> {
         let path = path.as_ref();
         let content = read_file(path)?;
-        let mut page = Page::parse(path, &content, config, base_path)?;
+        Page::from_content(path, &content, config, base_path, true)
+    }
 
-        if page.file.name == "index" {
+    /// Parse the content of a .md file into a Page struct. The assets of the page and the
+    /// file of its enclosure are only looked for next to it if the file is on disk.
+    pub fn from_content(
+        path: &Path,
+        content: &str,
+        config: &Config,
+        base_path: &Path,
+        on_disk: bool,
+    ) -> Result<Page> {
+        let mut page = Page::parse(path, content, config, base_path)?;
+
+        if page.file.name == "index" && on_disk {
             let parent_dir = path.parent().unwrap();
             page.assets = find_related_assets(parent_dir, config, true);
             page.serialized_assets = page.serialize_assets(base_path);
@@ -196,6 +322,14 @@ impl Page {
             page.assets = vec![];
         }
 
+        if let Some(ref enclosure) = page.meta.enclosure {
+            let page_dir = if page.file.name == "index" && on_disk { path.parent() } else { None };
+            let enclosure =
+                Enclosure::resolve(enclosure, &page.permalink, page_dir, config, base_path)
+                    .with_context(|| format!("Invalid `enclosure` in {}", path.display()))?;
+            page.enclosure = Some(enclosure);
+        }
+
         Ok(page)
     }
 
@@ -207,8 +341,18 @@ impl Page {
         tera: &Tera,
         config: &Config,
         anchor_insert: InsertAnchor,
+        raw_html: RawHtml,
         shortcode_definitions: &HashMap<String, ShortcodeDefinition>,
     ) -> Result<()> {
+        if self.raw_content_is_html {
+            self.summary = self
+                .raw_content
+                .find(SUMMARY_SEPARATOR)
+                .map(|i| FOOTNOTES_RE.replace(self.raw_content[..i].trim_end(), "").into_owned());
+            self.content = self.raw_content.clone();
+            return Ok(());
+        }
+
         let mut context = RenderContext::new(
             tera,
             config,
@@ -217,11 +361,15 @@ impl Page {
             permalinks,
             anchor_insert,
         );
+        context.raw_html = raw_html;
         context.set_shortcode_definitions(shortcode_definitions);
         context.set_current_page_path(&self.file.relative);
-        context.tera_context.insert("page", &SerializingPage::new(self, None, false));
+        context
+            .tera_context
+            .insert("page", &SerializingPage::new(self, None, PageSerMode::WithoutSiblings));
 
         let res = render_content(&self.raw_content, &context)
+            .map_err(|e| set_source_error_file(e, &self.file.path, self.content_line_offset))
             .with_context(|| format!("Failed to render content of {}", self.file.path.display()))?;
 
         self.summary = res
@@ -238,27 +386,106 @@ impl Page {
 
     /// Renders the page using the default layout, unless specified in front-matter
     pub fn render_html(&self, tera: &Tera, config: &Config, library: &Library) -> Result<String> {
-        let tpl_name = match self.meta.template {
-            Some(ref l) => l,
-            None => "page.html",
-        };
+        self.render_html_with_context(tera, config, self.render_context(config, library))
+    }
+
+    /// Same as `render_html` with a context already made by `render_context`
+    pub fn render_html_with_context(
+        &self,
+        tera: &Tera,
+        config: &Config,
+        context: TeraContext,
+    ) -> Result<String> {
+        render_template(self.template_name(), tera, context, &config.theme)
+            .with_context(|| format!("Failed to render page '{}'", self.file.path.display()))
+    }
+
+    /// The template the page is rendered with
+    pub fn template_name(&self) -> &str {
+        self.meta.template.as_deref().unwrap_or("page.html")
+    }
 
+    /// Renders the AMP version of the page with the `amp.html` template
+    pub fn render_amp(&self, tera: &Tera, config: &Config, library: &Library) -> Result<String> {
+        render_template("amp.html", tera, self.render_context(config, library), &config.theme)
+            .with_context(|| {
+                format!("Failed to render the AMP version of page '{}'", self.file.path.display())
+            })
+    }
+
+    /// Renders the email version of the page with the `newsletter.html` template
+    pub fn render_newsletter(
+        &self,
+        tera: &Tera,
+        config: &Config,
+        library: &Library,
+    ) -> Result<String> {
+        render_template(
+            "newsletter.html",
+            tera,
+            self.render_context(config, library),
+            &config.theme,
+        )
+        .with_context(|| {
+            format!("Failed to render the newsletter of page '{}'", self.file.path.display())
+        })
+    }
+
+    /// Renders the plain text version of the page with the `page.txt` template
+    pub fn render_txt(&self, tera: &Tera, config: &Config, library: &Library) -> Result<String> {
+        render_template("page.txt", tera, self.render_context(config, library), &config.theme)
+            .with_context(|| {
+                format!("Failed to render the text of page '{}'", self.file.path.display())
+            })
+    }
+
+    /// Renders the gemtext version of the page with the `page.gmi` template
+    pub fn render_gemini(
+        &self,
+        tera: &Tera,
+        config: &Config,
+        library: &Library,
+        permalinks: &HashMap<String, String>,
+        shortcode_definitions: &HashMap<String, ShortcodeDefinition>,
+    ) -> Result<String> {
+        let mut render_context = RenderContext::new(
+            tera,
+            config,
+            &self.lang,
+            &self.permalink,
+            permalinks,
+            InsertAnchor::None,
+        );
+        render_context.set_shortcode_definitions(shortcode_definitions);
+        render_context.set_current_page_path(&self.file.relative);
+        render_context
+            .tera_context
+            .insert("page", &SerializingPage::new(self, None, PageSerMode::WithoutSiblings));
+        let gemtext = render_gemtext(&self.raw_content, &render_context)?;
+
+        let mut context = self.render_context(config, library);
+        context.insert("gemtext", &gemtext);
+        render_template("page.gmi", tera, context, &config.theme).with_context(|| {
+            format!("Failed to render the gemtext of page '{}'", self.file.path.display())
+        })
+    }
+
+    /// The variables available in the templates rendering the page
+    pub fn render_context(&self, config: &Config, library: &Library) -> TeraContext {
         let mut context = TeraContext::new();
         context.insert("config", &config.serialize(&self.lang));
         context.insert("current_url", &self.permalink);
         context.insert("current_path", &self.path);
-        context.insert("page", &self.serialize(library));
+        context.insert("page", &self.serialize(library).with_protected_content(self));
         context.insert("lang", &self.lang);
-
-        render_template(tpl_name, tera, context, &config.theme)
-            .with_context(|| format!("Failed to render page '{}'", self.file.path.display()))
+        context
     }
 
     /// Creates a vectors of asset URLs.
     fn serialize_assets(&self, base_path: &Path) -> Vec<String> {
         self.assets
             .iter()
-            .filter_map(|asset| asset.strip_prefix(&self.file.path.parent().unwrap()).ok())
+            .filter_map(|asset| asset.strip_prefix(self.file.path.parent().unwrap()).ok())
             .filter_map(|filename| filename.to_str())
             .map(|filename| {
                 let mut path = self.file.path.clone();
@@ -267,7 +494,7 @@ impl Page {
                 path.pop();
                 path.push(filename);
                 path = path
-                    .strip_prefix(&base_path.join("content"))
+                    .strip_prefix(base_path.join("content"))
                     .expect("Should be able to stripe prefix")
                     .to_path_buf();
                 path
@@ -285,11 +512,17 @@ impl Page {
     }
 
     pub fn serialize<'a>(&'a self, library: &'a Library) -> SerializingPage<'a> {
-        SerializingPage::new(self, Some(library), true)
+        SerializingPage::new(self, Some(library), PageSerMode::Full)
     }
 
     pub fn serialize_without_siblings<'a>(&'a self, library: &'a Library) -> SerializingPage<'a> {
-        SerializingPage::new(self, Some(library), false)
+        SerializingPage::new(self, Some(library), PageSerMode::WithoutSiblings)
+    }
+
+    /// Without the siblings, and without the content when `listings_without_content` is set,
+    /// for the pages listed in other pages
+    pub fn serialize_for_listing<'a>(&'a self, library: &'a Library) -> SerializingPage<'a> {
+        SerializingPage::new(self, Some(library), PageSerMode::Listing)
     }
 }
 
@@ -304,10 +537,74 @@ mod tests {
     use libs::tera::Tera;
     use tempfile::tempdir;
 
-    use crate::Page;
+    use crate::{Library, Page};
     use config::{Config, LanguageOptions};
     use utils::slugs::SlugifyStrategy;
-    use utils::types::InsertAnchor;
+    use utils::types::{InsertAnchor, RawHtml};
+
+    #[test]
+    fn can_apply_permalink_pattern() {
+        let mut config = Config::default_for_test();
+        config.languages.insert("fr".to_owned(), LanguageOptions::default());
+        let content = r#"
++++
+date = 2021-05-03
++++
+Hello world"#;
+        let mut page = Page::parse(
+            Path::new("content/posts/2021-05-03_hello.md"),
+            content,
+            &config,
+            &PathBuf::new(),
+        )
+        .unwrap();
+        assert_eq!(page.path, "/posts/hello/");
+
+        page.apply_permalink_pattern("/:year/:month/:day/:slug", &config).unwrap();
+        assert_eq!(page.path, "/2021/05/03/hello/");
+        assert_eq!(page.components, vec!["2021", "05", "03", "hello"]);
+        assert_eq!(page.permalink, "http://a-website.com/2021/05/03/hello/");
+
+        page.apply_permalink_pattern("/:section/:year/:slug/", &config).unwrap();
+        assert_eq!(page.path, "/posts/2021/hello/");
+
+        let mut page =
+            Page::parse(Path::new("content/posts/hello.fr.md"), content, &config, &PathBuf::new())
+                .unwrap();
+        page.apply_permalink_pattern("/:year/:slug/", &config).unwrap();
+        assert_eq!(page.path, "/fr/2021/hello/");
+    }
+
+    #[test]
+    fn errors_on_permalink_pattern_with_date_for_undated_page() {
+        let config = Config::default_for_test();
+        let mut page = Page::parse(
+            Path::new("content/posts/hello.md"),
+            "+++\n+++\nHello",
+            &config,
+            &PathBuf::new(),
+        )
+        .unwrap();
+        assert!(page.apply_permalink_pattern("/:year/:slug/", &config).is_err());
+        assert!(page.apply_permalink_pattern("/articles/:slug/", &config).is_ok());
+        assert_eq!(page.path, "/articles/hello/");
+    }
+
+    #[test]
+    fn page_path_overrides_permalink_pattern() {
+        let config = Config::default_for_test();
+        let content = r#"
++++
+path = "hello-world"
+date = 2021-05-03
++++
+Hello world"#;
+        let mut page =
+            Page::parse(Path::new("content/posts/hello.md"), content, &config, &PathBuf::new())
+                .unwrap();
+        page.apply_permalink_pattern("/:year/:slug/", &config).unwrap();
+        assert_eq!(page.path, "/hello-world/");
+    }
 
     #[test]
     fn can_parse_a_valid_page() {
@@ -327,6 +624,7 @@ Hello world"#;
             &Tera::default(),
             &config,
             InsertAnchor::None,
+            RawHtml::Pass,
             &HashMap::new(),
         )
         .unwrap();
@@ -337,6 +635,25 @@ Hello world"#;
         assert_eq!(page.content, "<p>Hello world</p>\n".to_string());
     }
 
+    #[test]
+    fn can_parse_a_page_saved_with_a_bom_and_crlf() {
+        let config = Config::default_for_test();
+        let content = "\u{feff}+++\r\ntitle = \"Hello\"\r\n+++\r\nHello\r\nworld\r\n";
+        let page = Page::parse(Path::new("post.md"), content, &config, &PathBuf::new()).unwrap();
+        assert_eq!(page.meta.title.unwrap(), "Hello");
+        assert_eq!(page.raw_content, "Hello\nworld\n");
+    }
+
+    #[test]
+    fn can_find_the_line_the_content_starts_at() {
+        let config = Config::default_for_test();
+        let content = "\n+++\ntitle = \"Hello\"\n+++\n\nHello\n";
+        let page = Page::parse(Path::new("post.md"), content, &config, &PathBuf::new()).unwrap();
+        // The blank lines after the front matter are not part of the content
+        assert_eq!(page.raw_content, "Hello\n");
+        assert_eq!(page.content_line_offset, 5);
+    }
+
     #[test]
     fn test_can_make_url_from_sections_and_slug() {
         let content = r#"
@@ -499,12 +816,75 @@ Hello world
             &Tera::default(),
             &config,
             InsertAnchor::None,
+            RawHtml::Pass,
             &HashMap::new(),
         )
         .unwrap();
         assert_eq!(page.summary, Some("<p>Hello world</p>\n".to_string()));
     }
 
+    #[test]
+    fn can_serialize_page_without_its_content_for_listings() {
+        let mut config = Config::default_for_test();
+        let content = "+++\n+++\nHello world\n<!-- more -->\nAnd the rest";
+        let mut page =
+            Page::parse(Path::new("hello.md"), content, &config, &PathBuf::new()).unwrap();
+        page.render_markdown(
+            &HashMap::default(),
+            &Tera::default(),
+            &config,
+            InsertAnchor::None,
+            RawHtml::Pass,
+            &HashMap::new(),
+        )
+        .unwrap();
+        let library = Library::new(&config);
+        let listing = libs::tera::to_value(page.serialize_for_listing(&library)).unwrap();
+        assert!(listing["content"].as_str().unwrap().contains("And the rest"));
+
+        config.listings_without_content = true;
+        let library = Library::new(&config);
+        let full = libs::tera::to_value(page.serialize(&library)).unwrap();
+        assert!(full["content"].as_str().unwrap().contains("And the rest"));
+        let listing = libs::tera::to_value(page.serialize_for_listing(&library)).unwrap();
+        assert!(listing.get("content").is_none());
+        assert_eq!(listing["summary"], "<p>Hello world</p>\n");
+        assert_eq!(listing["permalink"], full["permalink"]);
+    }
+
+    #[test]
+    fn can_serialize_protected_page_without_its_content() {
+        let config = Config::default_for_test();
+        let content = "+++\npassword = \"hunter2\"\n+++\n# Secret\n<!-- more -->\nAnd the rest";
+        let mut page =
+            Page::parse(Path::new("hello.md"), content, &config, &PathBuf::new()).unwrap();
+        page.render_markdown(
+            &HashMap::default(),
+            &Tera::default(),
+            &config,
+            InsertAnchor::None,
+            RawHtml::Pass,
+            &HashMap::new(),
+        )
+        .unwrap();
+        let library = Library::new(&config);
+
+        for serialized in [
+            page.serialize(&library),
+            page.serialize_without_siblings(&library),
+            page.serialize_for_listing(&library),
+        ] {
+            let serialized = libs::tera::to_value(serialized).unwrap();
+            assert!(!serialized.to_string().contains("Secret"));
+            assert!(serialized["summary"].is_null());
+            assert_eq!(serialized["toc"], libs::tera::Value::Array(vec![]));
+        }
+        // Only the page itself gets its content, which is encrypted
+        let context = page.render_context(&config, &library).into_json();
+        assert!(context["page"]["content"].as_str().unwrap().contains("And the rest"));
+        assert!(context["page"]["summary"].as_str().unwrap().contains("Secret"));
+    }
+
     #[test]
     fn strips_footnotes_in_summary() {
         let config = Config::default_for_test();
@@ -529,6 +909,7 @@ And here's another. [^2]
             &Tera::default(),
             &config,
             InsertAnchor::None,
+            RawHtml::Pass,
             &HashMap::new(),
         )
         .unwrap();
@@ -542,8 +923,8 @@ And here's another. [^2]
     fn page_with_assets_gets_right_info() {
         let tmp_dir = tempdir().expect("create temp dir");
         let path = tmp_dir.path();
-        create_dir(&path.join("content")).expect("create content temp dir");
-        create_dir(&path.join("content").join("posts")).expect("create posts temp dir");
+        create_dir(path.join("content")).expect("create content temp dir");
+        create_dir(path.join("content").join("posts")).expect("create posts temp dir");
         let nested_path = path.join("content").join("posts").join("with-assets");
         create_dir(&nested_path).expect("create nested temp dir");
         let mut f = File::create(nested_path.join("index.md")).unwrap();
@@ -566,8 +947,8 @@ And here's another. [^2]
     fn page_with_assets_and_slug_overrides_path() {
         let tmp_dir = tempdir().expect("create temp dir");
         let path = tmp_dir.path();
-        create_dir(&path.join("content")).expect("create content temp dir");
-        create_dir(&path.join("content").join("posts")).expect("create posts temp dir");
+        create_dir(path.join("content")).expect("create content temp dir");
+        create_dir(path.join("content").join("posts")).expect("create posts temp dir");
         let nested_path = path.join("content").join("posts").join("with-assets");
         create_dir(&nested_path).expect("create nested temp dir");
         let mut f = File::create(nested_path.join("index.md")).unwrap();
@@ -590,8 +971,8 @@ And here's another. [^2]
     fn page_with_assets_uses_filepath_for_assets() {
         let tmp_dir = tempdir().expect("create temp dir");
         let path = tmp_dir.path();
-        create_dir(&path.join("content")).expect("create content temp dir");
-        create_dir(&path.join("content").join("posts")).expect("create posts temp dir");
+        create_dir(path.join("content")).expect("create content temp dir");
+        create_dir(path.join("content").join("posts")).expect("create posts temp dir");
         let nested_path = path.join("content").join("posts").join("with_assets");
         create_dir(&nested_path).expect("create nested temp dir");
         let mut f = File::create(nested_path.join("index.md")).unwrap();
@@ -616,8 +997,8 @@ And here's another. [^2]
     fn page_with_assets_and_date_in_folder_name() {
         let tmp_dir = tempdir().expect("create temp dir");
         let path = tmp_dir.path();
-        create_dir(&path.join("content")).expect("create content temp dir");
-        create_dir(&path.join("content").join("posts")).expect("create posts temp dir");
+        create_dir(path.join("content")).expect("create content temp dir");
+        create_dir(path.join("content").join("posts")).expect("create posts temp dir");
         let nested_path = path.join("content").join("posts").join("2013-06-02_with-assets");
         create_dir(&nested_path).expect("create nested temp dir");
         let mut f = File::create(nested_path.join("index.md")).unwrap();
@@ -640,8 +1021,8 @@ And here's another. [^2]
     fn page_with_ignored_assets_filters_out_correct_files() {
         let tmp_dir = tempdir().expect("create temp dir");
         let path = tmp_dir.path();
-        create_dir(&path.join("content")).expect("create content temp dir");
-        create_dir(&path.join("content").join("posts")).expect("create posts temp dir");
+        create_dir(path.join("content")).expect("create content temp dir");
+        create_dir(path.join("content").join("posts")).expect("create posts temp dir");
         let nested_path = path.join("content").join("posts").join("with-assets");
         create_dir(&nested_path).expect("create nested temp dir");
         let mut f = File::create(nested_path.join("index.md")).unwrap();
@@ -663,6 +1044,30 @@ And here's another. [^2]
         assert_eq!(page.assets[0].file_name().unwrap().to_str(), Some("graph.jpg"));
     }
 
+    #[test]
+    fn can_find_enclosure_of_page() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let path = tmp_dir.path();
+        create_dir(path.join("content")).expect("create content temp dir");
+        let nested_path = path.join("content").join("episode-1");
+        create_dir(&nested_path).expect("create nested temp dir");
+        let mut f = File::create(nested_path.join("index.md")).unwrap();
+        f.write_all(b"+++\ndate = 2022-01-01\n[enclosure]\npath = \"episode.mp3\"\n+++\n").unwrap();
+        let mut f = File::create(nested_path.join("episode.mp3")).unwrap();
+        f.write_all(b"ID3").unwrap();
+        let config = Config::default_for_test();
+
+        let mut page = Page::from_file(nested_path.join("index.md"), &config, path).unwrap();
+        let enclosure = page.enclosure.clone().unwrap();
+        assert_eq!(enclosure.url, "http://a-website.com/episode-1/episode.mp3");
+        assert_eq!(enclosure.length, 3);
+        assert_eq!(enclosure.mime_type, "audio/mpeg");
+
+        // The URL follows the page
+        page.apply_permalink_pattern("/:year/:slug/", &config).unwrap();
+        assert_eq!(page.enclosure.unwrap().url, "http://a-website.com/2022/episode-1/episode.mp3");
+    }
+
     #[test]
     fn can_get_date_from_short_date_in_filename() {
         let config = Config::default();
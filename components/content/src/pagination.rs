@@ -2,14 +2,14 @@ use config::Config;
 use serde::Serialize;
 use std::borrow::Cow;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use errors::{Context as ErrorContext, Result};
 use libs::tera::{to_value, Context, Tera, Value};
 use utils::templates::{check_template_fallbacks, render_template};
 
 use crate::library::Library;
-use crate::ser::{SectionSerMode, SerializingPage, SerializingSection};
+use crate::ser::{PageSerMode, SectionSerMode, SerializingPage, SerializingSection};
 use crate::taxonomies::{Taxonomy, TaxonomyTerm};
 use crate::Section;
 
@@ -43,6 +43,14 @@ impl<'a> Pager<'a> {
     }
 }
 
+/// A link to a pager, for the list of all the pagers in the paginator context
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct PagerLink<'a> {
+    index: usize,
+    permalink: &'a str,
+    path: &'a str,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Paginator<'a> {
     /// All pages in the section/taxonomy
@@ -51,6 +59,8 @@ pub struct Paginator<'a> {
     pub pagers: Vec<Pager<'a>>,
     /// How many content pages on a paginated page at max
     paginate_by: usize,
+    /// How many pages of the section were left out before the first pager
+    paginate_offset: usize,
     /// whether to reverse before grouping
     paginate_reversed: bool,
     /// The thing we are creating the paginator for: section or taxonomy
@@ -69,15 +79,17 @@ impl<'a> Paginator<'a> {
     /// It will always at least create one pager (the first) even if there are not enough pages to paginate
     pub fn from_section(section: &'a Section, library: &'a Library) -> Paginator<'a> {
         let paginate_by = section.meta.paginate_by.unwrap();
+        let pages = section.pages_to_paginate();
         let mut paginator = Paginator {
-            all_pages: Cow::from(&section.pages[..]),
-            pagers: Vec::with_capacity(section.pages.len() / paginate_by),
+            all_pages: Cow::from(pages),
+            pagers: Vec::with_capacity(pages.len() / paginate_by),
             paginate_by,
+            paginate_offset: section.meta.paginate_offset,
             paginate_reversed: section.meta.paginate_reversed,
             root: PaginationRoot::Section(section),
             permalink: section.permalink.clone(),
             path: section.path.clone(),
-            paginate_path: section.meta.paginate_path.clone(),
+            paginate_path: section.paginate_path().to_string(),
             is_index: section.is_index(),
             template: section.get_template_name().to_string(),
         };
@@ -95,24 +107,21 @@ impl<'a> Paginator<'a> {
         tera: &Tera,
         theme: &Option<String>,
     ) -> Paginator<'a> {
-        let paginate_by = taxonomy.kind.paginate_by.unwrap();
+        let paginate_by = taxonomy.kind.paginate_by;
         // Check for taxon-specific template, or use generic as fallback.
         let specific_template = format!("{}/single.html", taxonomy.kind.name);
         let template = check_template_fallbacks(&specific_template, tera, theme)
             .unwrap_or("taxonomy_single.html");
         let mut paginator = Paginator {
             all_pages: Cow::Borrowed(&item.pages),
-            pagers: Vec::with_capacity(item.pages.len() / paginate_by),
+            pagers: Vec::with_capacity(item.pages.len() / paginate_by.max(1)),
             paginate_by,
+            paginate_offset: 0,
             paginate_reversed: false,
             root: PaginationRoot::Taxonomy(taxonomy, item),
             permalink: item.permalink.clone(),
-            path: format!("/{}/{}/", taxonomy.slug, item.slug),
-            paginate_path: taxonomy
-                .kind
-                .paginate_path
-                .clone()
-                .unwrap_or_else(|| "page".to_string()),
+            path: item.path.clone(),
+            paginate_path: taxonomy.kind.paginate_path.clone(),
             is_index: false,
             template: template.to_string(),
         };
@@ -134,7 +143,7 @@ impl<'a> Paginator<'a> {
 
         for p in &*self.all_pages {
             let page = &library.pages[p];
-            current_page.push(SerializingPage::new(page, Some(library), false));
+            current_page.push(SerializingPage::new(page, Some(library), PageSerMode::Listing));
 
             if current_page.len() == self.paginate_by {
                 pages.push(current_page);
@@ -180,6 +189,14 @@ impl<'a> Paginator<'a> {
         self.pagers = pagers;
     }
 
+    /// The content file of the section being paginated, taxonomies don't have one
+    pub fn source(&self) -> Option<&Path> {
+        match self.root {
+            PaginationRoot::Section(section) => Some(&section.file.path),
+            PaginationRoot::Taxonomy(_, _) => None,
+        }
+    }
+
     pub fn build_paginator_context(&self, current_pager: &Pager) -> HashMap<&str, Value> {
         let mut paginator = HashMap::new();
         // the pager index is 1-indexed so we want a 0-indexed one for indexing there
@@ -187,11 +204,13 @@ impl<'a> Paginator<'a> {
 
         // Global variables
         paginator.insert("paginate_by", to_value(self.paginate_by).unwrap());
+        paginator.insert("paginate_offset", to_value(self.paginate_offset).unwrap());
         paginator.insert("first", to_value(&self.permalink).unwrap());
         let last_pager = &self.pagers[self.pagers.len() - 1];
         paginator.insert("last", to_value(&last_pager.permalink).unwrap());
 
         // Variables for this specific page
+        paginator.insert("canonical", to_value(&current_pager.permalink).unwrap());
         if pager_index > 0 {
             let prev_pager = &self.pagers[pager_index - 1];
             paginator.insert("previous", to_value(&prev_pager.permalink).unwrap());
@@ -205,7 +224,13 @@ impl<'a> Paginator<'a> {
         } else {
             paginator.insert("next", Value::Null);
         }
-        paginator.insert("number_pagers", to_value(&self.pagers.len()).unwrap());
+        paginator.insert("number_pagers", to_value(self.pagers.len()).unwrap());
+        let pagers: Vec<_> = self
+            .pagers
+            .iter()
+            .map(|p| PagerLink { index: p.index, permalink: &p.permalink, path: &p.path })
+            .collect();
+        paginator.insert("pagers", to_value(&pagers).unwrap());
         let base_url = if self.paginate_path.is_empty() {
             self.permalink.to_string()
         } else {
@@ -261,7 +286,7 @@ mod tests {
     fn create_section(is_index: bool, paginate_reversed: bool) -> Section {
         let f = SectionFrontMatter {
             paginate_by: Some(2),
-            paginate_path: "page".to_string(),
+            paginate_path: Some("page".to_string()),
             paginate_reversed,
             ..Default::default()
         };
@@ -321,6 +346,20 @@ mod tests {
         assert_eq!(paginator.pagers[1].path, "/posts/page/2/");
     }
 
+    #[test]
+    fn test_can_create_section_paginator_with_paginated_pages() {
+        let (mut section, library) = create_library(true, 5, false);
+        section.pages = vec![PathBuf::from("1.md")];
+        section.paginated_pages =
+            Some(vec![PathBuf::from("5.md"), PathBuf::from("3.md"), PathBuf::from("1.md")]);
+        let paginator = Paginator::from_section(&section, &library);
+        assert_eq!(paginator.pagers.len(), 2);
+        assert_eq!(paginator.pagers[0].pages[0].title.clone().unwrap(), "5");
+        assert_eq!(paginator.pagers[0].pages[1].title.clone().unwrap(), "3");
+        assert_eq!(paginator.pagers[1].pages[0].title.clone().unwrap(), "1");
+        assert_eq!(paginator.pagers[1].path, "/page/2/");
+    }
+
     #[test]
     fn test_can_create_reversed_section_paginator() {
         let (section, library) = create_library(false, 3, true);
@@ -368,6 +407,7 @@ mod tests {
         assert_eq!(context["paginate_by"], to_value(2).unwrap());
         assert_eq!(context["first"], to_value("https://vincent.is/posts/").unwrap());
         assert_eq!(context["last"], to_value("https://vincent.is/posts/page/2/").unwrap());
+        assert_eq!(context["canonical"], to_value("https://vincent.is/posts/").unwrap());
         assert_eq!(context["previous"], to_value::<Option<()>>(None).unwrap());
         assert_eq!(context["next"], to_value("https://vincent.is/posts/page/2/").unwrap());
         assert_eq!(context["current_index"], to_value(1).unwrap());
@@ -377,6 +417,7 @@ mod tests {
         assert_eq!(context["paginate_by"], to_value(2).unwrap());
         assert_eq!(context["first"], to_value("https://vincent.is/posts/").unwrap());
         assert_eq!(context["last"], to_value("https://vincent.is/posts/page/2/").unwrap());
+        assert_eq!(context["canonical"], to_value("https://vincent.is/posts/page/2/").unwrap());
         assert_eq!(context["next"], to_value::<Option<()>>(None).unwrap());
         assert_eq!(context["previous"], to_value("https://vincent.is/posts/").unwrap());
         assert_eq!(context["current_index"], to_value(2).unwrap());
@@ -385,20 +426,46 @@ mod tests {
     }
 
     #[test]
-    fn test_can_create_paginator_for_taxonomy() {
-        let (_, library) = create_library(false, 3, false);
-        let tera = Tera::default();
+    fn test_can_offset_and_limit_section_paginator() {
+        let (mut section, library) = create_library(false, 9, false);
+        section.meta.paginate_offset = 3;
+        let paginator = Paginator::from_section(&section, &library);
+        assert_eq!(paginator.pagers.len(), 3);
+        assert_eq!(paginator.pagers[0].pages[0].title.clone().unwrap(), "4");
+        assert_eq!(paginator.pagers[2].pages.len(), 2);
+        assert_eq!(paginator.pagers[2].pages[1].title.clone().unwrap(), "9");
+        let context = paginator.build_paginator_context(&paginator.pagers[0]);
+        assert_eq!(context["paginate_offset"], to_value(3).unwrap());
+        assert_eq!(context["total_pages"], to_value(6).unwrap());
+        assert_eq!(context["number_pagers"], to_value(3).unwrap());
+
+        section.meta.paginate_limit = Some(3);
+        let paginator = Paginator::from_section(&section, &library);
+        assert_eq!(paginator.pagers.len(), 2);
+        assert_eq!(paginator.pagers[1].pages.len(), 1);
+        assert_eq!(paginator.pagers[1].pages[0].title.clone().unwrap(), "6");
+
+        // An offset past the end gives an empty first pager
+        section.meta.paginate_offset = 20;
+        let paginator = Paginator::from_section(&section, &library);
+        assert_eq!(paginator.pagers.len(), 1);
+        assert!(paginator.pagers[0].pages.is_empty());
+    }
+
+    fn create_taxonomy(library: &Library, term_path: &str) -> (Taxonomy, TaxonomyTerm) {
         let taxonomy_def = TaxonomyConfig {
             name: "some tags".to_string(),
-            paginate_by: Some(2),
+            paginate_by: 2,
             ..TaxonomyConfig::default()
         };
+        let mut pages: Vec<_> = library.pages.keys().cloned().collect();
+        pages.sort();
         let taxonomy_item = TaxonomyTerm {
             name: "Something".to_string(),
             slug: "something".to_string(),
-            path: "/some-tags/something/".to_string(),
-            permalink: "https://vincent.is/some-tags/something/".to_string(),
-            pages: library.pages.keys().cloned().collect(),
+            path: term_path.to_string(),
+            permalink: format!("https://vincent.is{}", term_path),
+            pages,
         };
         let taxonomy = Taxonomy {
             kind: taxonomy_def,
@@ -408,6 +475,14 @@ mod tests {
             permalink: "https://vincent.is/some-tags/".to_string(),
             items: vec![taxonomy_item.clone()],
         };
+        (taxonomy, taxonomy_item)
+    }
+
+    #[test]
+    fn test_can_create_paginator_for_taxonomy() {
+        let (_, library) = create_library(false, 3, false);
+        let tera = Tera::default();
+        let (taxonomy, taxonomy_item) = create_taxonomy(&library, "/some-tags/something/");
         let paginator = Paginator::from_taxonomy(&taxonomy, &taxonomy_item, &library, &tera, &None);
         assert_eq!(paginator.pagers.len(), 2);
 
@@ -422,11 +497,54 @@ mod tests {
         assert_eq!(paginator.pagers[1].path, "/some-tags/something/page/2/");
     }
 
+    #[test]
+    fn taxonomy_paginator_keeps_the_language_of_the_term() {
+        let (_, library) = create_library(false, 3, false);
+        let tera = Tera::default();
+        let (taxonomy, taxonomy_item) = create_taxonomy(&library, "/fr/some-tags/something/");
+        let paginator = Paginator::from_taxonomy(&taxonomy, &taxonomy_item, &library, &tera, &None);
+        assert_eq!(paginator.pagers[0].path, "/fr/some-tags/something/");
+        assert_eq!(paginator.pagers[1].path, "/fr/some-tags/something/page/2/");
+    }
+
+    // Themes can use the same pagination partial for sections and taxonomy terms
+    #[test]
+    fn section_and_taxonomy_paginators_have_the_same_context() {
+        let (section, library) = create_library(false, 5, false);
+        let tera = Tera::default();
+        let (taxonomy, taxonomy_item) = create_taxonomy(&library, "/posts/");
+        let section_paginator = Paginator::from_section(&section, &library);
+        let taxonomy_paginator =
+            Paginator::from_taxonomy(&taxonomy, &taxonomy_item, &library, &tera, &None);
+        assert_eq!(section_paginator.pagers, taxonomy_paginator.pagers);
+
+        for pager in &section_paginator.pagers {
+            assert_eq!(
+                section_paginator.build_paginator_context(pager),
+                taxonomy_paginator.build_paginator_context(pager)
+            );
+        }
+    }
+
+    #[test]
+    fn test_can_list_pagers_in_context() {
+        let (section, library) = create_library(false, 3, false);
+        let paginator = Paginator::from_section(&section, &library);
+        let context = paginator.build_paginator_context(&paginator.pagers[1]);
+        let pagers = context["pagers"].as_array().unwrap();
+        assert_eq!(pagers.len(), 2);
+        assert_eq!(pagers[0]["index"], to_value(1).unwrap());
+        assert_eq!(pagers[0]["permalink"], to_value("https://vincent.is/posts/").unwrap());
+        assert_eq!(pagers[1]["index"], to_value(2).unwrap());
+        assert_eq!(pagers[1]["permalink"], to_value("https://vincent.is/posts/page/2/").unwrap());
+        assert_eq!(pagers[1]["path"], to_value("/posts/page/2/").unwrap());
+    }
+
     // https://github.com/getzola/zola/issues/866
     #[test]
     fn works_with_empty_paginate_path() {
         let (mut section, library) = create_library(false, 3, false);
-        section.meta.paginate_path = String::new();
+        section.meta.paginate_path = Some(String::new());
         let paginator = Paginator::from_section(&section, &library);
         assert_eq!(paginator.pagers.len(), 2);
 
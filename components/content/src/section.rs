@@ -4,14 +4,15 @@ use std::path::{Path, PathBuf};
 use libs::tera::{Context as TeraContext, Tera};
 
 use config::Config;
-use errors::{Context, Result};
-use markdown::{render_content, RenderContext};
+use errors::{set_source_error_file, Context, Result};
+use markdown::{render_content, render_gemtext, RenderContext};
 use utils::fs::read_file;
 use utils::table_of_contents::Heading;
 use utils::templates::{render_template, ShortcodeDefinition};
+use utils::types::{InsertAnchor, RawHtml};
 
 use crate::file_info::FileInfo;
-use crate::front_matter::{split_section_content, SectionFrontMatter};
+use crate::front_matter::{normalize_content, split_section_content, SectionFrontMatter};
 use crate::library::Library;
 use crate::ser::{SectionSerMode, SerializingSection};
 use crate::utils::{find_related_assets, get_reading_analytics, has_anchor};
@@ -31,6 +32,9 @@ pub struct Section {
     pub permalink: String,
     /// The actual content of the page, in markdown
     pub raw_content: String,
+    /// The number of lines of the file before the content, to point errors of the content at the
+    /// right line of the file
+    pub content_line_offset: usize,
     /// The HTML rendered of the page
     pub content: String,
     /// All the non-md files we found next to the .md file
@@ -39,6 +43,9 @@ pub struct Section {
     pub serialized_assets: Vec<String>,
     /// All direct pages of that section
     pub pages: Vec<PathBuf>,
+    /// The pages to paginate if `paginate_all_pages` or `paginate_sections` is set,
+    /// the section own pages are used otherwise
+    pub paginated_pages: Option<Vec<PathBuf>>,
     /// All pages that cannot be sorted in this section
     pub ignored_pages: Vec<PathBuf>,
     /// The list of parent sections relative paths
@@ -80,12 +87,17 @@ impl Section {
         config: &Config,
         base_path: &Path,
     ) -> Result<Section> {
-        let (meta, content) = split_section_content(file_path, content)?;
+        let content = normalize_content(content);
+        let file_content = content;
+        let (meta, content) = split_section_content(file_path, &file_content)?;
         let mut section = Section::new(file_path, meta, base_path);
         section.lang = section
             .file
             .find_language(&config.default_language, &config.other_languages_codes())?;
         section.raw_content = content.to_string();
+        // The content is what's left of the file after the front matter
+        section.content_line_offset =
+            file_content[..file_content.len() - content.len()].matches('\n').count();
         let (word_count, reading_time) = get_reading_analytics(&section.raw_content);
         section.word_count = Some(word_count);
         section.reading_time = Some(reading_time);
@@ -109,6 +121,9 @@ impl Section {
             .filter(|p| !p.is_empty())
             .collect::<Vec<_>>();
         section.permalink = config.make_permalink(&section.path);
+        if section.meta.paginate_path.is_none() {
+            section.meta.paginate_path = Some(config.paginate_path.clone());
+        }
         Ok(section)
     }
 
@@ -120,11 +135,25 @@ impl Section {
     ) -> Result<Section> {
         let path = path.as_ref();
         let content = read_file(path)?;
-        let mut section = Section::parse(path, &content, config, base_path)?;
+        Section::from_content(path, &content, config, base_path, true)
+    }
+
+    /// Parse the content of a .md file into a Section struct. The assets of the section are only
+    /// looked for next to it if the file is on disk.
+    pub fn from_content(
+        path: &Path,
+        content: &str,
+        config: &Config,
+        base_path: &Path,
+        on_disk: bool,
+    ) -> Result<Section> {
+        let mut section = Section::parse(path, content, config, base_path)?;
 
-        let parent_dir = path.parent().unwrap();
-        section.assets = find_related_assets(parent_dir, config, false);
-        section.serialized_assets = section.serialize_assets();
+        if on_disk {
+            let parent_dir = path.parent().unwrap();
+            section.assets = find_related_assets(parent_dir, config, false);
+            section.serialized_assets = section.serialize_assets();
+        }
 
         Ok(section)
     }
@@ -148,6 +177,7 @@ impl Section {
         permalinks: &HashMap<String, String>,
         tera: &Tera,
         config: &Config,
+        raw_html: RawHtml,
         shortcode_definitions: &HashMap<String, ShortcodeDefinition>,
     ) -> Result<()> {
         let mut context = RenderContext::new(
@@ -158,6 +188,7 @@ impl Section {
             permalinks,
             self.meta.insert_anchor_links,
         );
+        context.raw_html = raw_html;
         context.set_shortcode_definitions(shortcode_definitions);
         context.set_current_page_path(&self.file.relative);
         context
@@ -165,6 +196,7 @@ impl Section {
             .insert("section", &SerializingSection::new(self, SectionSerMode::ForMarkdown));
 
         let res = render_content(&self.raw_content, &context)
+            .map_err(|e| set_source_error_file(e, &self.file.path, self.content_line_offset))
             .with_context(|| format!("Failed to render content of {}", self.file.path.display()))?;
         self.content = res.body;
         self.toc = res.toc;
@@ -189,6 +221,64 @@ impl Section {
             .with_context(|| format!("Failed to render section '{}'", self.file.path.display()))
     }
 
+    /// Renders the gemtext version of the section with the `section.gmi` template
+    pub fn render_gemini(
+        &self,
+        tera: &Tera,
+        config: &Config,
+        library: &Library,
+        permalinks: &HashMap<String, String>,
+        shortcode_definitions: &HashMap<String, ShortcodeDefinition>,
+    ) -> Result<String> {
+        let mut render_context = RenderContext::new(
+            tera,
+            config,
+            &self.lang,
+            &self.permalink,
+            permalinks,
+            InsertAnchor::None,
+        );
+        render_context.set_shortcode_definitions(shortcode_definitions);
+        render_context.set_current_page_path(&self.file.relative);
+        render_context
+            .tera_context
+            .insert("section", &SerializingSection::new(self, SectionSerMode::ForMarkdown));
+        let gemtext = render_gemtext(&self.raw_content, &render_context)?;
+
+        let mut context = TeraContext::new();
+        context.insert("config", &config.serialize(&self.lang));
+        context.insert("current_url", &self.permalink);
+        context.insert("current_path", &self.path);
+        context.insert("section", &SerializingSection::new(self, SectionSerMode::Full(library)));
+        context.insert("lang", &self.lang);
+        context.insert("gemtext", &gemtext);
+        // Only the pages and subsections having a gemtext version can be linked to
+        let pages: Vec<_> = self
+            .pages
+            .iter()
+            .map(|p| &library.pages[p])
+            .filter(|p| p.has_gemtext())
+            .map(|p| p.serialize_for_listing(library))
+            .collect();
+        context.insert("pages", &pages);
+        let subsections: Vec<_> = self
+            .subsections
+            .iter()
+            .map(|p| &library.sections[p])
+            .filter(|s| s.has_gemtext())
+            .map(|s| &s.file.relative)
+            .collect();
+        context.insert("subsections", &subsections);
+        render_template("section.gmi", tera, context, &config.theme).with_context(|| {
+            format!("Failed to render the gemtext of section '{}'", self.file.path.display())
+        })
+    }
+
+    /// Whether the section is rendered to gemtext when `[gemini]` is enabled
+    pub fn has_gemtext(&self) -> bool {
+        self.meta.render && self.meta.redirect_to.is_none()
+    }
+
     /// Is this the index section?
     pub fn is_index(&self) -> bool {
         self.file.components.is_empty()
@@ -198,7 +288,7 @@ impl Section {
     fn serialize_assets(&self) -> Vec<String> {
         self.assets
             .iter()
-            .filter_map(|asset| asset.strip_prefix(&self.file.path.parent().unwrap()).ok())
+            .filter_map(|asset| asset.strip_prefix(self.file.path.parent().unwrap()).ok())
             .filter_map(|filename| filename.to_str())
             .map(|filename| format!("{}{}", self.path, filename))
             .collect()
@@ -218,6 +308,23 @@ impl Section {
         }
     }
 
+    /// The pages going in the pagers: the paginated pages, or the section pages if not set,
+    /// after applying `paginate_offset` and `paginate_limit`
+    pub fn pages_to_paginate(&self) -> &[PathBuf] {
+        let pages = self.paginated_pages.as_ref().unwrap_or(&self.pages);
+        let start = self.meta.paginate_offset.min(pages.len());
+        let end = match self.meta.paginate_limit {
+            Some(limit) => (start + limit).min(pages.len()),
+            None => pages.len(),
+        };
+        &pages[start..end]
+    }
+
+    /// The path used by the pagers, `page` if neither the section nor the config set one
+    pub fn paginate_path(&self) -> &str {
+        self.meta.paginate_path.as_deref().unwrap_or("page")
+    }
+
     pub fn serialize<'a>(&'a self, library: &'a Library) -> SerializingSection<'a> {
         SerializingSection::new(self, SectionSerMode::Full(library))
     }
@@ -243,8 +350,8 @@ mod tests {
     fn section_with_assets_gets_right_info() {
         let tmp_dir = tempdir().expect("create temp dir");
         let path = tmp_dir.path();
-        create_dir(&path.join("content")).expect("create content temp dir");
-        create_dir(&path.join("content").join("posts")).expect("create posts temp dir");
+        create_dir(path.join("content")).expect("create content temp dir");
+        create_dir(path.join("content").join("posts")).expect("create posts temp dir");
         let nested_path = path.join("content").join("posts").join("with-assets");
         create_dir(&nested_path).expect("create nested temp dir");
         let mut f = File::create(nested_path.join("_index.md")).unwrap();
@@ -297,6 +404,34 @@ mod tests {
         assert_eq!(page.assets[0].file_name().unwrap().to_str(), Some("graph.jpg"));
     }
 
+    #[test]
+    fn section_with_ignored_nested_assets_filters_out_correct_files() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let article_path = tmp_dir.path().join("content/posts/with-assets");
+        create_dir_all(article_path.join("foo/bar/baz/quux")).expect("create nested temp dir");
+        create_dir_all(article_path.join("foo/qux")).expect("create nested temp dir");
+        let mut f = File::create(article_path.join("_index.md")).unwrap();
+        f.write_all(b"+++\n+++\n").unwrap();
+        File::create(article_path.join("foo/bar/baz/quux/quo.xlsx")).unwrap();
+        File::create(article_path.join("foo/bar/quo.docx")).unwrap();
+        File::create(article_path.join("foo/qux/graph.jpg")).unwrap();
+
+        // The files of the asset directories are matched by their full path too
+        let mut gsb = GlobSetBuilder::new();
+        gsb.add(Glob::new("**/foo/bar/**/*.{xlsx,docx}").unwrap());
+        let mut config = Config::default();
+        config.ignored_content_globset = Some(gsb.build().unwrap());
+
+        let section =
+            Section::from_file(article_path.join("_index.md").as_path(), &config, &PathBuf::new())
+                .unwrap();
+        assert_eq!(section.assets.len(), 1);
+        assert_eq!(
+            section.assets[0].strip_prefix(&article_path).unwrap(),
+            Path::new("foo/qux/graph.jpg")
+        );
+    }
+
     #[test]
     fn can_specify_language_in_filename() {
         let mut config = Config::default();
@@ -336,6 +471,38 @@ Bonjour le monde"#
         assert_eq!(section.permalink, "http://a-website.com/fr/");
     }
 
+    #[test]
+    fn uses_paginate_path_from_config_by_default() {
+        let mut config = Config::default();
+        let section = Section::parse(
+            Path::new("content/blog/_index.md"),
+            "+++\n+++\n",
+            &config,
+            &PathBuf::new(),
+        )
+        .unwrap();
+        assert_eq!(section.paginate_path(), "page");
+
+        config.paginate_path = String::new();
+        let section = Section::parse(
+            Path::new("content/blog/_index.md"),
+            "+++\n+++\n",
+            &config,
+            &PathBuf::new(),
+        )
+        .unwrap();
+        assert_eq!(section.paginate_path(), "");
+
+        let section = Section::parse(
+            Path::new("content/blog/_index.md"),
+            "+++\npaginate_path = \"p\"\n+++\n",
+            &config,
+            &PathBuf::new(),
+        )
+        .unwrap();
+        assert_eq!(section.paginate_path(), "p");
+    }
+
     #[test]
     fn can_make_links_to_translated_subsections_with_trailing_slash() {
         let mut config = Config::default();
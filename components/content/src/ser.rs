@@ -1,8 +1,9 @@
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 use serde::Serialize;
 
+use crate::enclosure::Enclosure;
 use crate::library::Library;
 use crate::{Page, Section};
 use libs::tera::{Map, Value};
@@ -14,6 +15,13 @@ pub struct BackLink<'a> {
     pub title: &'a Option<String>,
 }
 
+/// The previous and next pages of a page in a taxonomy term
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SerializingTermSiblings<'a> {
+    prev: Option<Box<SerializingPage<'a>>>,
+    next: Option<Box<SerializingPage<'a>>>,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct TranslatedContent<'a> {
     pub lang: &'a str,
@@ -39,11 +47,25 @@ fn find_backlinks<'a>(relative_path: &str, library: &'a Library) -> Vec<BackLink
     backlinks
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PageSerMode {
+    /// Everything, including the previous/next/related pages: for the page being rendered
+    Full,
+    /// Everything but the previous/next/related pages, e.g. for feeds
+    WithoutSiblings,
+    /// Without the previous/next/related pages, and without the content as well when
+    /// `listings_without_content` is set: for the pages listed in other pages
+    Listing,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct SerializingPage<'a> {
     relative_path: &'a str,
-    content: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<&'a str>,
     permalink: &'a str,
+    amp_permalink: &'a Option<String>,
+    enclosure: &'a Option<Enclosure>,
     slug: &'a str,
     ancestors: &'a [String],
     pub(crate) title: &'a Option<String>,
@@ -53,7 +75,8 @@ pub struct SerializingPage<'a> {
     year: Option<i32>,
     month: Option<u8>,
     day: Option<u8>,
-    taxonomies: &'a HashMap<String, Vec<String>>,
+    taxonomies: BTreeMap<&'a str, &'a [String]>,
+    series: &'a Option<String>,
     extra: &'a Map<String, Value>,
     path: &'a str,
     components: &'a [String],
@@ -66,12 +89,18 @@ pub struct SerializingPage<'a> {
     lang: &'a str,
     lower: Option<Box<SerializingPage<'a>>>,
     higher: Option<Box<SerializingPage<'a>>>,
+    series_prev: Option<Box<SerializingPage<'a>>>,
+    series_next: Option<Box<SerializingPage<'a>>>,
+    book_prev: Option<Box<SerializingPage<'a>>>,
+    book_next: Option<Box<SerializingPage<'a>>>,
+    taxonomy_siblings: BTreeMap<&'a str, BTreeMap<&'a str, SerializingTermSiblings<'a>>>,
+    related: Vec<SerializingPage<'a>>,
     translations: Vec<TranslatedContent<'a>>,
     backlinks: Vec<BackLink<'a>>,
 }
 
 impl<'a> SerializingPage<'a> {
-    pub fn new(page: &'a Page, library: Option<&'a Library>, include_siblings: bool) -> Self {
+    pub fn new(page: &'a Page, library: Option<&'a Library>, mode: PageSerMode) -> Self {
         let mut year = None;
         let mut month = None;
         let mut day = None;
@@ -82,21 +111,67 @@ impl<'a> SerializingPage<'a> {
         }
         let mut lower = None;
         let mut higher = None;
+        let mut series_prev = None;
+        let mut series_next = None;
+        let mut book_prev = None;
+        let mut book_next = None;
+        let mut taxonomy_siblings = BTreeMap::new();
+        let mut related = vec![];
         let mut translations = vec![];
         let mut backlinks = vec![];
+        // The content of a password-protected page is only available to the page itself,
+        // which gets encrypted
+        let protected = page.meta.password.is_some();
 
         if let Some(lib) = library {
             translations = lib.find_translations(&page.file.canonical);
 
-            if include_siblings {
+            if mode == PageSerMode::Full {
                 lower = page
                     .lower
                     .as_ref()
-                    .map(|p| Box::new(Self::new(&lib.pages[p], Some(lib), false)));
+                    .map(|p| Box::new(Self::new(&lib.pages[p], Some(lib), PageSerMode::Listing)));
                 higher = page
                     .higher
                     .as_ref()
-                    .map(|p| Box::new(Self::new(&lib.pages[p], Some(lib), false)));
+                    .map(|p| Box::new(Self::new(&lib.pages[p], Some(lib), PageSerMode::Listing)));
+                series_prev = page
+                    .series_prev
+                    .as_ref()
+                    .map(|p| Box::new(Self::new(&lib.pages[p], Some(lib), PageSerMode::Listing)));
+                series_next = page
+                    .series_next
+                    .as_ref()
+                    .map(|p| Box::new(Self::new(&lib.pages[p], Some(lib), PageSerMode::Listing)));
+                let serialize_sibling = |p: &Option<PathBuf>| {
+                    p.as_ref().map(|p| {
+                        Box::new(Self::new(&lib.pages[p], Some(lib), PageSerMode::Listing))
+                    })
+                };
+                book_prev = serialize_sibling(&page.book_prev);
+                book_next = serialize_sibling(&page.book_next);
+                taxonomy_siblings = page
+                    .taxonomy_siblings
+                    .iter()
+                    .map(|(taxonomy, terms)| {
+                        let terms = terms
+                            .iter()
+                            .map(|(term, siblings)| {
+                                let siblings = SerializingTermSiblings {
+                                    prev: serialize_sibling(&siblings.prev),
+                                    next: serialize_sibling(&siblings.next),
+                                };
+                                (term.as_str(), siblings)
+                            })
+                            .collect();
+                        (taxonomy.as_str(), terms)
+                    })
+                    .collect();
+                related = page
+                    .related
+                    .iter()
+                    .map(|p| Self::new(&lib.pages[p], Some(lib), PageSerMode::Listing))
+                    .collect();
             }
 
             backlinks = find_backlinks(&page.file.relative, lib);
@@ -105,8 +180,16 @@ impl<'a> SerializingPage<'a> {
         Self {
             relative_path: &page.file.relative,
             ancestors: &page.ancestors,
-            content: &page.content,
+            content: match mode {
+                PageSerMode::Listing if library.map_or(false, |l| l.listings_without_content()) => {
+                    None
+                }
+                _ if protected => Some(""),
+                _ => Some(&page.content),
+            },
             permalink: &page.permalink,
+            amp_permalink: &page.amp_permalink,
+            enclosure: &page.enclosure,
             slug: &page.slug,
             title: &page.meta.title,
             description: &page.meta.description,
@@ -116,11 +199,18 @@ impl<'a> SerializingPage<'a> {
             year,
             month,
             day,
-            taxonomies: &page.meta.taxonomies,
+            // Sorted by name so they are listed in the same order in every build
+            taxonomies: page
+                .meta
+                .taxonomies
+                .iter()
+                .map(|(name, terms)| (name.as_str(), terms.as_slice()))
+                .collect(),
+            series: &page.meta.series,
             path: &page.path,
             components: &page.components,
-            summary: &page.summary,
-            toc: &page.toc,
+            summary: if protected { &None } else { &page.summary },
+            toc: if protected { &[] } else { &page.toc },
             word_count: page.word_count,
             reading_time: page.reading_time,
             assets: &page.serialized_assets,
@@ -128,10 +218,26 @@ impl<'a> SerializingPage<'a> {
             lang: &page.lang,
             lower,
             higher,
+            series_prev,
+            series_next,
+            book_prev,
+            book_next,
+            taxonomy_siblings,
+            related,
             translations,
             backlinks,
         }
     }
+
+    /// Adds back the content of a password-protected page, for its own page which gets encrypted
+    pub(crate) fn with_protected_content(mut self, page: &'a Page) -> Self {
+        if self.content.is_some() {
+            self.content = Some(&page.content);
+        }
+        self.summary = &page.summary;
+        self.toc = &page.toc;
+        self
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize)]
@@ -186,9 +292,15 @@ impl<'a> SerializingSection<'a> {
                     .collect();
 
                 // Fetching pages on top
+                // They have their siblings, unless they are serialized as light as possible
                 if let SectionSerMode::Full(_) = mode {
+                    let page_mode = if lib.listings_without_content() {
+                        PageSerMode::Listing
+                    } else {
+                        PageSerMode::Full
+                    };
                     for p in &section.pages {
-                        pages.push(SerializingPage::new(&lib.pages[p], Some(lib), true));
+                        pages.push(SerializingPage::new(&lib.pages[p], Some(lib), page_mode));
                     }
                 }
 
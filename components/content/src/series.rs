@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use config::Config;
+use errors::{Context as ErrorContext, Result};
+use libs::tera::{Context, Tera};
+use utils::slugs::slugify_paths;
+use utils::templates::render_template;
+
+use crate::library::Library;
+use crate::ser::{PageSerMode, SerializingPage};
+use crate::sorting::sort_pages;
+use crate::{Page, SortBy};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SerializedSeries<'a> {
+    name: &'a str,
+    slug: &'a str,
+    lang: &'a str,
+    path: &'a str,
+    permalink: &'a str,
+    pages: Vec<SerializingPage<'a>>,
+}
+
+impl<'a> SerializedSeries<'a> {
+    pub fn from_series(series: &'a Series, library: &'a Library) -> Self {
+        let pages = series
+            .pages
+            .iter()
+            .map(|p| SerializingPage::new(&library.pages[p], Some(library), PageSerMode::Listing))
+            .collect();
+
+        SerializedSeries {
+            name: &series.name,
+            slug: &series.slug,
+            lang: &series.lang,
+            path: &series.path,
+            permalink: &series.permalink,
+            pages,
+        }
+    }
+}
+
+/// A group of pages meant to be read in order, like a multi-part tutorial.
+/// Unlike sections, the pages of a series can come from anywhere in the content directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub name: String,
+    pub slug: String,
+    pub lang: String,
+    pub path: String,
+    pub permalink: String,
+    /// The pages of the series in reading order: oldest first, pages without a date at the end
+    pub pages: Vec<PathBuf>,
+}
+
+impl Series {
+    pub(crate) fn new(name: &str, lang: &str, series_pages: &[&Page], config: &Config) -> Self {
+        let slug = slugify_paths(name, config.slugify.paths);
+        let path = if lang != config.default_language {
+            format!("/{}/series/{}/", lang, slug)
+        } else {
+            format!("/series/{}/", slug)
+        };
+        let permalink = config.make_permalink(&path);
+
+        let (mut pages, mut undated_pages) = sort_pages(series_pages, SortBy::Date);
+        // `sort_pages` gives us the most recent first
+        pages.reverse();
+        undated_pages.sort();
+        pages.extend(undated_pages);
+
+        Series { name: name.to_string(), slug, lang: lang.to_string(), path, permalink, pages }
+    }
+
+    pub fn render_html(&self, tera: &Tera, config: &Config, library: &Library) -> Result<String> {
+        let mut context = Context::new();
+        context.insert("config", &config.serialize(&self.lang));
+        context.insert("lang", &self.lang);
+        context.insert("series", &self.serialize(library));
+        context.insert("current_url", &self.permalink);
+        context.insert("current_path", &self.path);
+
+        render_template("series.html", tera, context, &config.theme)
+            .with_context(|| format!("Failed to render series '{}'", self.name))
+    }
+
+    pub fn serialize<'a>(&'a self, library: &'a Library) -> SerializedSeries<'a> {
+        SerializedSeries::from_series(self, library)
+    }
+}
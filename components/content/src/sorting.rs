@@ -6,9 +6,10 @@ use libs::lexical_sort::natural_lexical_cmp;
 use libs::rayon::prelude::*;
 
 /// Sort by the field picked by the function.
-/// The pages permalinks are used to break the ties
+/// The pages permalinks are used to break the ties and to sort the pages that can't be sorted,
+/// so the order doesn't depend on the order of the pages given
 pub fn sort_pages(pages: &[&Page], sort_by: SortBy) -> (Vec<PathBuf>, Vec<PathBuf>) {
-    let (mut can_be_sorted, cannot_be_sorted): (Vec<&Page>, Vec<_>) =
+    let (mut can_be_sorted, mut cannot_be_sorted): (Vec<&Page>, Vec<&Page>) =
         pages.par_iter().partition(|page| match sort_by {
             SortBy::Date => page.meta.datetime.is_some(),
             SortBy::UpdateDate => {
@@ -42,9 +43,11 @@ pub fn sort_pages(pages: &[&Page], sort_by: SortBy) -> (Vec<PathBuf>, Vec<PathBu
         }
     });
 
+    cannot_be_sorted.sort_unstable_by(|a, b| a.permalink.cmp(&b.permalink));
+
     (
         can_be_sorted.iter().map(|p| p.file.path.clone()).collect(),
-        cannot_be_sorted.iter().map(|p: &&Page| p.file.path.clone()).collect(),
+        cannot_be_sorted.iter().map(|p| p.file.path.clone()).collect(),
     )
 }
 
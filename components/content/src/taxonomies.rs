@@ -11,7 +11,7 @@ use utils::slugs::slugify_paths;
 use utils::templates::{check_template_fallbacks, render_template};
 
 use crate::library::Library;
-use crate::ser::SerializingPage;
+use crate::ser::{PageSerMode, SerializingPage};
 use crate::{Page, SortBy};
 
 use crate::sorting::sort_pages;
@@ -30,7 +30,11 @@ impl<'a> SerializedTaxonomyTerm<'a> {
         let mut pages = vec![];
 
         for p in &item.pages {
-            pages.push(SerializingPage::new(&library.pages[p], Some(library), false));
+            pages.push(SerializingPage::new(
+                &library.pages[p],
+                Some(library),
+                PageSerMode::Listing,
+            ));
         }
 
         SerializedTaxonomyTerm {
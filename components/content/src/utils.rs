@@ -1,9 +1,11 @@
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
 use libs::unicode_segmentation::UnicodeSegmentation;
 use libs::walkdir::WalkDir;
 
 use config::Config;
+use utils::fs::{is_junk_file, is_unfollowed_symlink};
 use utils::table_of_contents::Heading;
 
 pub fn has_anchor(headings: &[Heading], anchor: &str) -> bool {
@@ -19,33 +21,70 @@ pub fn has_anchor(headings: &[Heading], anchor: &str) -> bool {
     false
 }
 
+/// Whether the file is a page or section, written in Markdown or in a format with a converter
+fn is_content_file(path: &Path, config: &Config) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(extension) => extension == "md" || config.converters.contains_key(extension),
+        None => false,
+    }
+}
+
 /// Looks into the current folder for the path and see if there's anything that is not a .md
-/// file. Those will be copied next to the rendered .html file
+/// file or a file with a converter. Those will be copied next to the rendered .html file, in
+/// the same subdirectories.
 /// If `recursive` is set to `true`, it will add all subdirectories assets as well. This should
-/// only be set when finding page assets currently.
-/// TODO: remove this flag once sections with assets behave the same as pages with assets
+/// only be set when finding page assets currently. Otherwise only the subdirectories without
+/// any .md file are looked into, since the other ones are subsections or pages.
+/// The directories matching `ignored_content`, by their full path or their path relative to
+/// `path`, are skipped and the assets matching `ignored_content` or `ignored_assets` left out.
 pub fn find_related_assets(path: &Path, config: &Config, recursive: bool) -> Vec<PathBuf> {
-    let mut assets = vec![];
+    let follow_symlinks = config.follow_symlinks;
+    let is_ignored_dir = |dir: &Path| {
+        let relative = dir.strip_prefix(path).unwrap_or(dir);
+        config
+            .ignored_content_globset
+            .as_ref()
+            .map_or(false, |gs| gs.is_match(dir) || gs.is_match(relative))
+            || config.ignored_assets_globset.as_ref().map_or(false, |gs| gs.is_match(relative))
+    };
+    let entries: Vec<_> = WalkDir::new(path)
+        .follow_links(follow_symlinks)
+        .into_iter()
+        .filter_entry(|e| {
+            !is_unfollowed_symlink(e, follow_symlinks)
+                && !is_junk_file(e.path())
+                && (e.depth() == 0 || !e.file_type().is_dir() || !is_ignored_dir(e.path()))
+        })
+        .filter_map(std::result::Result::ok)
+        .filter(|e| e.path().is_file())
+        .map(|e| e.into_path())
+        .collect();
 
-    let mut builder = WalkDir::new(path).follow_links(true);
+    // The subdirectories with a page or section somewhere in them, found in a single pass
+    let mut content_dirs = HashSet::new();
     if !recursive {
-        builder = builder.max_depth(1);
-    }
-    for entry in builder.into_iter().filter_map(std::result::Result::ok) {
-        let entry_path = entry.path();
-        if entry_path.is_file() {
-            match entry_path.extension() {
-                Some(e) => match e.to_str() {
-                    Some("md") => continue,
-                    _ => assets.push(entry_path.to_path_buf()),
-                },
-                None => assets.push(entry_path.to_path_buf()),
+        for entry in entries.iter().filter(|p| is_content_file(p, config)) {
+            for dir in entry.ancestors().skip(1).take_while(|d| *d != path) {
+                if !content_dirs.insert(dir) {
+                    break;
+                }
             }
         }
     }
+    let mut assets: Vec<_> = entries
+        .iter()
+        .filter(|p| !is_content_file(p, config))
+        .filter(|p| {
+            !p.ancestors().skip(1).take_while(|d| *d != path).any(|d| content_dirs.contains(d))
+        })
+        .cloned()
+        .collect();
 
     if let Some(ref globset) = config.ignored_content_globset {
-        assets = assets.into_iter().filter(|p| !globset.is_match(p)).collect();
+        assets.retain(|p| !globset.is_match(p));
+    }
+    if let Some(ref globset) = config.ignored_assets_globset {
+        assets.retain(|p| !globset.is_match(p.strip_prefix(path).unwrap_or(p)));
     }
 
     assets
@@ -110,6 +149,65 @@ mod tests {
             assert!(assets.iter().any(|p| p.strip_prefix(path).unwrap() == Path::new(asset)))
         }
     }
+    #[test]
+    fn can_find_related_assets_in_asset_directories() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let path = tmp_dir.path();
+        File::create(path.join("_index.md")).unwrap();
+        File::create(path.join("graph.jpg")).unwrap();
+        create_dir(path.join("images")).expect("create images temp dir");
+        create_dir(path.join("images").join("small")).expect("create small temp dir");
+        File::create(path.join("images").join("small").join("graph.jpg")).unwrap();
+        create_dir(path.join("subsection")).expect("create subsection temp dir");
+        create_dir(path.join("subsection").join("page")).expect("create page temp dir");
+        File::create(path.join("subsection").join("page").join("index.md")).unwrap();
+        File::create(path.join("subsection").join("page").join("example.js")).unwrap();
+
+        let assets = find_related_assets(path, &Config::default(), false);
+        assert_eq!(assets.len(), 2);
+        for asset in ["graph.jpg", "images/small/graph.jpg"] {
+            assert!(assets.iter().any(|p| p.strip_prefix(path).unwrap() == Path::new(asset)))
+        }
+    }
+
+    #[test]
+    fn converted_files_are_not_assets() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let path = tmp_dir.path();
+        File::create(path.join("_index.md")).unwrap();
+        File::create(path.join("post.org")).unwrap();
+        File::create(path.join("graph.jpg")).unwrap();
+        create_dir(path.join("bundle")).expect("create bundle temp dir");
+        File::create(path.join("bundle").join("index.rst")).unwrap();
+        File::create(path.join("bundle").join("example.js")).unwrap();
+        let config =
+            Config::parse("base_url = \"https://example.com\"\n[converters.org]\n[converters.rst]")
+                .unwrap();
+
+        let assets = find_related_assets(path, &config, false);
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].strip_prefix(path).unwrap(), Path::new("graph.jpg"));
+    }
+
+    #[test]
+    fn can_ignore_related_assets() {
+        let tmp_dir = tempdir().expect("create temp dir");
+        let path = tmp_dir.path();
+        File::create(path.join("index.md")).unwrap();
+        File::create(path.join("graph.jpg")).unwrap();
+        File::create(path.join("graph.psd")).unwrap();
+        create_dir(path.join("sources")).expect("create sources temp dir");
+        File::create(path.join("sources").join("graph.svg")).unwrap();
+        let config = Config::parse(
+            "base_url = \"https://example.com\"\nignored_assets = [\"*.psd\", \"sources/**\"]",
+        )
+        .unwrap();
+
+        let assets = find_related_assets(path, &config, true);
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].strip_prefix(path).unwrap(), Path::new("graph.jpg"));
+    }
+
     #[test]
     fn can_find_anchor_at_root() {
         let input = vec![
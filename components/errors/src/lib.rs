@@ -1 +1,127 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
 pub use anyhow::*;
+
+/// How many lines are shown before the one an error points at
+const SNIPPET_CONTEXT_LINES: usize = 2;
+
+/// An error pointing at a line of a file, like invalid front matter or a shortcode that couldn't
+/// be rendered, so it can be printed with the lines it comes from.
+/// Only the message is displayed, the location is printed by the CLI along with the lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceError {
+    pub message: String,
+    /// The file the error comes from, set by whatever knows about it
+    pub path: Option<PathBuf>,
+    /// 1-indexed
+    pub line: usize,
+    /// 1-indexed, if known
+    pub column: Option<usize>,
+    /// The line pointed at and the ones before it, with their 1-indexed number
+    pub lines: Vec<(usize, String)>,
+}
+
+impl SourceError {
+    /// `line` and `column` are 1-indexed and relative to `source`
+    pub fn new(
+        message: impl Into<String>,
+        source: &str,
+        line: usize,
+        column: Option<usize>,
+    ) -> Self {
+        let mut error =
+            SourceError { message: message.into(), path: None, line, column, lines: vec![] };
+        error.read_lines(source);
+        error
+    }
+
+    /// Takes the line pointed at and the ones before it from `source`, for errors created
+    /// where only the line is known
+    pub fn read_lines(&mut self, source: &str) {
+        let first = self.line.saturating_sub(SNIPPET_CONTEXT_LINES).max(1);
+        self.lines = source
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.to_string()))
+            .skip(first - 1)
+            .take(self.line + 1 - first)
+            .collect();
+        // Errors at the end of the source point at a line that isn't there
+        if self.lines.last().map(|(number, _)| *number) != Some(self.line) {
+            self.column = None;
+        }
+    }
+
+    /// Sets the file the source is from, `line_offset` being the number of lines of the file
+    /// before the source, eg the front matter for the content of a page
+    pub fn in_file(&mut self, path: &Path, line_offset: usize) {
+        self.path = Some(path.to_path_buf());
+        self.line += line_offset;
+        for (number, _) in &mut self.lines {
+            *number += line_offset;
+        }
+    }
+
+    /// Where the error is, eg `content/blog/hello.md:3:8`, or `line 3` if the file isn't known
+    pub fn location(&self) -> String {
+        let path = match self.path {
+            Some(ref p) => p.display().to_string(),
+            None => return format!("line {}", self.line),
+        };
+        match self.column {
+            Some(column) => format!("{}:{}:{}", path, self.line, column),
+            None => format!("{}:{}", path, self.line),
+        }
+    }
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+/// Gives the error its file, if it is or wraps a `SourceError` without one already
+pub fn set_source_error_file(mut error: Error, path: &Path, line_offset: usize) -> Error {
+    if let Some(source_error) = error.downcast_mut::<SourceError>() {
+        if source_error.path.is_none() {
+            source_error.in_file(path, line_offset);
+        }
+    }
+    error
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_create_source_error_with_lines_before() {
+        let source = "title = \"Hello\"\ndescription = \"Hey\"\ndate = 2022-13-01\nweight = 1";
+        let mut error = SourceError::new("invalid date", source, 3, Some(8));
+        assert_eq!(
+            error.lines,
+            vec![
+                (1, "title = \"Hello\"".to_string()),
+                (2, "description = \"Hey\"".to_string()),
+                (3, "date = 2022-13-01".to_string()),
+            ]
+        );
+        error.in_file(Path::new("content/hello.md"), 1);
+        assert_eq!(error.location(), "content/hello.md:4:8");
+        assert_eq!(error.lines[0].0, 2);
+        assert_eq!(error.to_string(), "invalid date");
+    }
+
+    #[test]
+    fn can_set_file_of_wrapped_source_error() {
+        let error = Error::new(SourceError::new("oops", "a\nb", 1, None)).context("Failed");
+        let error = set_source_error_file(error, Path::new("a.md"), 2);
+        let source_error = error.downcast_ref::<SourceError>().unwrap();
+        assert_eq!(source_error.location(), "a.md:3");
+        assert_eq!(source_error.lines, vec![(3, "a".to_string())]);
+    }
+}
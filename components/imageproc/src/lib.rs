@@ -24,6 +24,8 @@ use utils::fs as ufs;
 
 static RESIZED_SUBDIR: &str = "processed_images";
 const DEFAULT_Q_JPG: u8 = 75;
+/// The quality used when a copied JPEG needs to be rotated: it is meant to be shown as is
+const ROTATED_Q_JPG: u8 = 90;
 
 static RESIZED_FILENAME: Lazy<Regex> =
     Lazy::new(|| Regex::new(r#"([0-9a-f]{16})([0-9a-f]{2})[.](jpg|png|webp)"#).unwrap());
@@ -255,7 +257,7 @@ impl Format {
     }
 }
 
-#[allow(clippy::derive_hash_xor_eq)]
+#[allow(clippy::derived_hash_with_manual_eq)]
 impl Hash for Format {
     fn hash<H: Hasher>(&self, hasher: &mut H) {
         use Format::*;
@@ -371,6 +373,70 @@ pub fn fix_orientation(img: &DynamicImage, path: &Path) -> Option<DynamicImage>
     }
 }
 
+/// Copies a JPEG file without its EXIF and XMP metadata, which can contain private data
+/// like the location a photo was taken at.
+/// The image is rotated according to its EXIF orientation first if needed, otherwise it is
+/// copied without being re-encoded.
+pub fn copy_without_exif(src: &Path, dest: &Path) -> Result<()> {
+    if !ufs::file_stale(src, dest) {
+        return Ok(());
+    }
+
+    let img =
+        image::open(src).with_context(|| format!("Failed to open image {}", src.display()))?;
+    if let Some(img) = fix_orientation(&img, src) {
+        let mut f = File::create(dest)?;
+        img.write_to(&mut f, ImageOutputFormat::Jpeg(ROTATED_Q_JPG))?;
+        return Ok(());
+    }
+
+    let bytes = fs::read(src)?;
+    let stripped = strip_jpeg_metadata(&bytes)
+        .ok_or_else(|| anyhow!("Failed to read the JPEG segments of {}", src.display()))?;
+    fs::write(dest, stripped)?;
+    Ok(())
+}
+
+/// Removes the APP1 segments, holding the EXIF and XMP metadata, of a JPEG file.
+/// Returns `None` if the file doesn't look like a valid JPEG
+fn strip_jpeg_metadata(bytes: &[u8]) -> Option<Vec<u8>> {
+    const APP1: u8 = 0xE1;
+    const SOS: u8 = 0xDA;
+
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut res = Vec::with_capacity(bytes.len());
+    res.extend_from_slice(&bytes[..2]);
+    let mut pos = 2;
+    loop {
+        if bytes.get(pos)? != &0xFF {
+            return None;
+        }
+        let marker = *bytes.get(pos + 1)?;
+        // Markers without a length
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            res.extend_from_slice(&bytes[pos..pos + 2]);
+            pos += 2;
+            continue;
+        }
+        // The compressed data follows the start of scan, there are no metadata past that point
+        if marker == SOS {
+            res.extend_from_slice(&bytes[pos..]);
+            return Some(res);
+        }
+        let len = u16::from_be_bytes([*bytes.get(pos + 2)?, *bytes.get(pos + 3)?]) as usize;
+        let end = pos + 2 + len;
+        if end > bytes.len() {
+            return None;
+        }
+        if marker != APP1 {
+            res.extend_from_slice(&bytes[pos..end]);
+        }
+        pos = end;
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct EnqueueResponse {
     /// The final URL for that asset
@@ -581,7 +647,7 @@ impl From<ImageMeta> for ImageMetaResponse {
         Self {
             width: im.size.0,
             height: im.size.1,
-            format: im.format.and_then(|f| f.extensions_str().get(0)).copied(),
+            format: im.format.and_then(|f| f.extensions_str().first()).copied(),
         }
     }
 }
@@ -601,7 +667,7 @@ pub fn read_image_metadata<P: AsRef<Path>>(path: P) -> Result<ImageMetaResponse>
 
     match ext.as_str() {
         "svg" => {
-            let img = SvgMetadata::parse_file(&path).with_context(err_context)?;
+            let img = SvgMetadata::parse_file(path).with_context(err_context)?;
             match (img.height(), img.width(), img.view_box()) {
                 (Some(h), Some(w), _) => Ok((h, w)),
                 (_, _, Some(view_box)) => Ok((view_box.height, view_box.width)),
@@ -2,7 +2,9 @@ use std::env;
 use std::path::{PathBuf, MAIN_SEPARATOR as SLASH};
 
 use config::Config;
-use imageproc::{assert_processed_path_matches, fix_orientation, ImageMetaResponse, Processor};
+use imageproc::{
+    assert_processed_path_matches, copy_without_exif, fix_orientation, ImageMetaResponse, Processor,
+};
 use libs::image::{self, DynamicImage, GenericImageView, Pixel};
 use libs::once_cell::sync::Lazy;
 
@@ -51,7 +53,7 @@ fn image_op_test(
     proc.do_process().unwrap();
 
     let processed_path = PathBuf::from(&resp.static_path);
-    let processed_size = imageproc::read_image_metadata(&tmpdir.join(processed_path))
+    let processed_size = imageproc::read_image_metadata(tmpdir.join(processed_path))
         .map(|meta| (meta.width, meta.height))
         .unwrap();
     assert_eq!(processed_size, (expect_width, expect_height));
@@ -197,6 +199,21 @@ fn resize_image_applies_exif_rotation() {
     assert!(resize_and_check("exif_8.jpg"));
 }
 
+#[test]
+fn copy_without_exif_applies_rotation_and_strips_metadata() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    for i in 0..=8 {
+        let name = format!("exif_{}.jpg", i);
+        let dest = tmpdir.path().join(&name);
+        copy_without_exif(&TEST_IMGS.join(&name), &dest).unwrap();
+
+        assert!(check_img(image::open(&dest).unwrap()), "{} is not rotated", name);
+        let file = std::fs::File::open(&dest).unwrap();
+        let exif = exif::Reader::new().read_from_container(&mut std::io::BufReader::new(&file));
+        assert!(exif.is_err(), "{} still has EXIF data", name);
+    }
+}
+
 fn resize_and_check(source_img: &str) -> bool {
     let source_path = TEST_IMGS.join(source_img);
     let tmpdir = tempfile::tempdir().unwrap().into_path();
@@ -209,7 +226,7 @@ fn resize_and_check(source_img: &str) -> bool {
 
     proc.do_process().unwrap();
     let processed_path = PathBuf::from(&resp.static_path);
-    let img = image::open(&tmpdir.join(processed_path)).unwrap();
+    let img = image::open(tmpdir.join(processed_path)).unwrap();
     check_img(img)
 }
 
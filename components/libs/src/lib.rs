@@ -8,14 +8,20 @@ pub use ahash;
 pub use ammonia;
 pub use atty;
 pub use base64;
+pub use brotli;
+pub use chrono;
 pub use csv;
 pub use elasticlunr;
 pub use filetime;
+pub use flate2;
 pub use gh_emoji;
 pub use glob;
 pub use globset;
 pub use image;
 pub use lexical_sort;
+pub use log;
+pub use md5;
+pub use mime_guess;
 pub use minify_html;
 pub use nom_bibtex;
 pub use num_format;
@@ -27,6 +33,7 @@ pub use rayon;
 pub use regex;
 pub use relative_path;
 pub use reqwest;
+pub use ring;
 pub use sass_rs;
 pub use serde_json;
 pub use serde_yaml;
@@ -60,6 +60,55 @@ fn opening_html(
     html
 }
 
+/// Whether what follows the first colon of some inline code can be code rather than the rest of
+/// a path, a URL or a list like `c:\Windows`, `https://example.com`, `std::mem` or `r:w:x`
+fn can_be_hinted_code(code: &str) -> bool {
+    let is_word = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_');
+    let is_list = code.contains(':') && code.split(':').all(is_word);
+    !code.is_empty() && !code.starts_with([':', '/', '\\']) && !is_list
+}
+
+/// Highlights inline code starting with a language hint, like `rust:Vec<u8>`.
+/// Returns `None` if there is no hint or if the language isn't a known syntax.
+pub fn highlight_inline_code(code: &str, config: &Config) -> Option<String> {
+    let (lang, code) = code.split_once(':')?;
+    if lang.is_empty()
+        || !lang.chars().all(|c| c.is_ascii_alphanumeric() || "+#-_".contains(c))
+        || !can_be_hinted_code(code)
+    {
+        return None;
+    }
+
+    let syntax_and_theme = resolve_syntax_and_theme(Some(lang), config);
+    if !matches!(syntax_and_theme.source, HighlightSource::BuiltIn | HighlightSource::Extra) {
+        return None;
+    }
+    let mut highlighter = SyntaxHighlighter::new(true, syntax_and_theme);
+
+    let mut html = format!("<code class=\"language-{}", lang);
+    if let Some(class) = highlighter.pre_class() {
+        html.push(' ');
+        html.push_str(&class);
+    }
+    html.push_str("\" data-lang=\"");
+    html.push_str(lang);
+    html.push('"');
+    if let Some(style) = highlighter.pre_style() {
+        html.push_str(" style=\"");
+        html.push_str(&style);
+        html.push('"');
+    }
+    html.push('>');
+    // The syntaxes expect lines ending with a newline but inline code doesn't have any
+    html.push_str(&highlighter.highlight_line(&format!("{}\n", code)).replace('\n', ""));
+    if let Some(rest) = highlighter.finalize() {
+        html.push_str(&rest);
+    }
+    html.push_str("</code>");
+
+    Some(html)
+}
+
 pub struct CodeBlock<'config> {
     highlighter: SyntaxHighlighter<'config>,
     // fence options
@@ -80,9 +129,9 @@ impl<'config> CodeBlock<'config> {
         if syntax_and_theme.source == HighlightSource::NotFound {
             let lang = fence.language.unwrap();
             if let Some(p) = path {
-                eprintln!("Warning: Highlight language {} not found in {}", lang, p);
+                console::warn(&format!("Highlight language {} not found in {}", lang, p));
             } else {
-                eprintln!("Warning: Highlight language {} not found", lang);
+                console::warn(&format!("Highlight language {} not found", lang));
             }
         }
         let highlighter = SyntaxHighlighter::new(config.markdown.highlight_code, syntax_and_theme);
@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use config::Config;
 use libs::tera::{Context, Tera};
 use utils::templates::ShortcodeDefinition;
-use utils::types::InsertAnchor;
+use utils::types::{InsertAnchor, RawHtml};
 
 /// All the information from the zola site that is needed to render HTML from markdown
 #[derive(Debug)]
@@ -18,6 +18,11 @@ pub struct RenderContext<'a> {
     pub insert_anchor: InsertAnchor,
     pub lang: &'a str,
     pub shortcode_definitions: Cow<'a, HashMap<String, ShortcodeDefinition>>,
+    /// How many levels to demote the headings by, eg `1` to render `# Title` as a `<h2>`
+    pub heading_shift: u8,
+    /// What to do with the raw HTML of the content, `config.markdown.raw_html` unless the
+    /// section it belongs to sets its own
+    pub raw_html: RawHtml,
 }
 
 impl<'a> RenderContext<'a> {
@@ -43,6 +48,8 @@ impl<'a> RenderContext<'a> {
             config,
             lang,
             shortcode_definitions: Cow::Owned(HashMap::new()),
+            heading_shift: 0,
+            raw_html: config.markdown.raw_html,
         }
     }
 
@@ -71,6 +78,8 @@ impl<'a> RenderContext<'a> {
             config,
             lang: &config.default_language,
             shortcode_definitions: Cow::Owned(HashMap::new()),
+            heading_shift: 0,
+            raw_html: config.markdown.raw_html,
         }
     }
 }
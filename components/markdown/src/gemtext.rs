@@ -0,0 +1,258 @@
+use libs::once_cell::sync::Lazy;
+use libs::pulldown_cmark as cmark;
+use libs::regex::Regex;
+
+use crate::context::RenderContext;
+use crate::shortcode::{extract_shortcodes, insert_md_shortcodes, SHORTCODE_PLACEHOLDER};
+use errors::Result;
+use utils::site::resolve_internal_link;
+
+use self::cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag};
+
+static BLANK_LINES_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\n{3,}").unwrap());
+
+/// Builds the gemtext line by line: gemtext has no inline formatting, so links are written on
+/// their own lines after the block they were found in.
+struct GemtextWriter<'a> {
+    context: &'a RenderContext<'a>,
+    output: String,
+    line: String,
+    links: Vec<(String, String)>,
+    /// Where the text of the link being written starts in `line`, and its URL
+    current_link: Option<(usize, String)>,
+    in_code_block: bool,
+    in_blockquote: bool,
+}
+
+impl<'a> GemtextWriter<'a> {
+    fn new(context: &'a RenderContext<'a>) -> Self {
+        Self {
+            context,
+            output: String::new(),
+            line: String::new(),
+            links: Vec::new(),
+            current_link: None,
+            in_code_block: false,
+            in_blockquote: false,
+        }
+    }
+
+    /// Internal links point to the path of the page on the Gemini site
+    fn resolve_link(&self, link: &str) -> String {
+        if !link.starts_with("@/") {
+            return link.to_string();
+        }
+        match resolve_internal_link(link, &self.context.permalinks) {
+            Ok(resolved) => {
+                let base_url = self.context.config.base_url.trim_end_matches('/');
+                let permalink = resolved.permalink.split('#').next().unwrap_or_default();
+                permalink.strip_prefix(base_url).unwrap_or(permalink).to_string()
+            }
+            Err(_) => link.to_string(),
+        }
+    }
+
+    fn end_line(&mut self) {
+        let line = self.line.trim();
+        if !line.is_empty() {
+            if self.in_blockquote {
+                self.output.push_str("> ");
+            }
+            self.output.push_str(line);
+            self.output.push('\n');
+        }
+        self.line.clear();
+    }
+
+    /// Ends the current block with the links found in it
+    fn end_block(&mut self) {
+        self.end_line();
+        for (url, text) in self.links.drain(..) {
+            if text.is_empty() || text == url {
+                self.output.push_str(&format!("=> {}\n", url));
+            } else {
+                self.output.push_str(&format!("=> {} {}\n", url, text));
+            }
+        }
+        self.output.push('\n');
+    }
+
+    fn start_link(&mut self, url: &str) {
+        self.current_link = Some((self.line.len(), self.resolve_link(url)));
+    }
+
+    fn end_link(&mut self) {
+        if let Some((start, url)) = self.current_link.take() {
+            let text = self.line[start..].trim().to_string();
+            self.links.push((url, text));
+        }
+    }
+
+    fn write(&mut self, event: Event) {
+        match event {
+            Event::Start(Tag::Heading(level, _, _)) => {
+                self.end_line();
+                let level = match level {
+                    HeadingLevel::H1 => "#",
+                    HeadingLevel::H2 => "##",
+                    _ => "###",
+                };
+                self.line.push_str(level);
+                self.line.push(' ');
+            }
+            Event::Start(Tag::Item) => {
+                self.end_line();
+                self.line.push_str("* ");
+            }
+            Event::Start(Tag::BlockQuote) => {
+                self.end_line();
+                self.in_blockquote = true;
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                self.end_line();
+                self.in_code_block = true;
+                self.output.push_str("```");
+                if let CodeBlockKind::Fenced(info) = kind {
+                    let lang = info.split(',').next().unwrap_or_default().trim();
+                    self.output.push_str(lang);
+                }
+                self.output.push('\n');
+            }
+            Event::Start(Tag::Link(_, url, _)) => self.start_link(&url),
+            Event::Start(Tag::Image(_, url, _)) => self.start_link(&url),
+            Event::End(Tag::Link(..)) | Event::End(Tag::Image(..)) => self.end_link(),
+            Event::End(Tag::Heading(..))
+            | Event::End(Tag::Paragraph)
+            | Event::End(Tag::List(_))
+            | Event::End(Tag::Table(_)) => self.end_block(),
+            Event::End(Tag::Item) | Event::End(Tag::TableHead) | Event::End(Tag::TableRow) => {
+                self.end_line();
+            }
+            Event::End(Tag::TableCell) => self.line.push_str(" | "),
+            Event::End(Tag::BlockQuote) => {
+                self.end_block();
+                self.in_blockquote = false;
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                if !self.output.ends_with('\n') {
+                    self.output.push('\n');
+                }
+                self.output.push_str("```\n\n");
+                self.in_code_block = false;
+            }
+            Event::Text(text) if self.in_code_block => self.output.push_str(&text),
+            Event::Text(text) | Event::Code(text) => {
+                self.line.push_str(&text.replace(SHORTCODE_PLACEHOLDER, ""))
+            }
+            Event::SoftBreak => self.line.push(' '),
+            Event::HardBreak => self.end_line(),
+            Event::FootnoteReference(name) => self.line.push_str(&format!("[{}]", name)),
+            Event::TaskListMarker(done) => self.line.push_str(if done { "[x] " } else { "[ ] " }),
+            Event::Rule => {
+                self.end_line();
+                self.output.push_str("---\n\n");
+            }
+            // HTML can't be represented in gemtext
+            _ => (),
+        }
+    }
+}
+
+/// Renders markdown content to gemtext. Markdown shortcodes are rendered while HTML shortcodes
+/// and inline HTML are left out.
+pub fn render_gemtext(content: &str, context: &RenderContext) -> Result<String> {
+    let content = if content.contains("{{") || content.contains("{%") {
+        let (content, shortcodes) =
+            extract_shortcodes(content, context.shortcode_definitions.as_ref())?;
+        insert_md_shortcodes(content, shortcodes, &context.tera_context, &context.tera)?.0
+    } else {
+        content.to_string()
+    };
+
+    let mut opts = Options::empty();
+    opts.insert(Options::ENABLE_TABLES);
+    opts.insert(Options::ENABLE_FOOTNOTES);
+    opts.insert(Options::ENABLE_STRIKETHROUGH);
+    opts.insert(Options::ENABLE_TASKLISTS);
+
+    let mut writer = GemtextWriter::new(context);
+    for event in Parser::new_ext(&content, opts) {
+        writer.write(event);
+    }
+    writer.end_block();
+
+    let gemtext = writer.output.trim();
+    if gemtext.is_empty() {
+        return Ok(String::new());
+    }
+    Ok(BLANK_LINES_RE.replace_all(gemtext, "\n\n").into_owned() + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use config::Config;
+    use libs::tera::Tera;
+    use utils::types::InsertAnchor;
+
+    use super::render_gemtext;
+    use crate::RenderContext;
+
+    #[test]
+    fn can_render_gemtext() {
+        let config = Config::default_for_test();
+        let tera = Tera::default();
+        let mut permalinks = HashMap::new();
+        permalinks.insert(
+            "posts/other.md".to_string(),
+            format!("{}/posts/other/", config.base_url.trim_end_matches('/')),
+        );
+        let context = RenderContext::new(
+            &tera,
+            &config,
+            &config.default_language,
+            "",
+            &permalinks,
+            InsertAnchor::None,
+        );
+        let markdown = r#"# Title
+
+Some *text* with [a link](https://example.com) and [another](@/posts/other.md#part).
+
+#### Deep heading
+
+- one
+- two
+
+> quoted
+
+```rust
+let a = 1;
+```
+
+<div>HTML is left out</div>
+"#;
+        let gemtext = render_gemtext(markdown, &context).unwrap();
+        assert_eq!(
+            gemtext,
+            r#"# Title
+
+Some text with a link and another.
+=> https://example.com a link
+=> /posts/other/ another
+
+### Deep heading
+
+* one
+* two
+
+> quoted
+
+```rust
+let a = 1;
+```
+"#
+        );
+    }
+}
@@ -1,15 +1,18 @@
 mod codeblock;
 mod context;
+mod gemtext;
 mod markdown;
+mod raw_html;
 mod shortcode;
 
 use shortcode::{extract_shortcodes, insert_md_shortcodes};
 
-use errors::Result;
+use errors::{Result, SourceError};
 
 use crate::markdown::markdown_to_html;
 pub use crate::markdown::Rendered;
 pub use context::RenderContext;
+pub use gemtext::render_gemtext;
 
 pub fn render_content(content: &str, context: &RenderContext) -> Result<markdown::Rendered> {
     // avoid parsing the content if needed
@@ -17,6 +20,21 @@ pub fn render_content(content: &str, context: &RenderContext) -> Result<markdown
         return markdown_to_html(content, context, Vec::new());
     }
 
+    render_shortcodes_and_markdown(content, context).map_err(|mut e| {
+        // Shortcodes failing to render only know the line they are called on
+        if let Some(source_error) = e.downcast_mut::<SourceError>() {
+            if source_error.lines.is_empty() {
+                source_error.read_lines(content);
+            }
+        }
+        e
+    })
+}
+
+fn render_shortcodes_and_markdown(
+    content: &str,
+    context: &RenderContext,
+) -> Result<markdown::Rendered> {
     let definitions = context.shortcode_definitions.as_ref();
     // Extract all the defined shortcodes
     let (content, shortcodes) = extract_shortcodes(content, definitions)?;
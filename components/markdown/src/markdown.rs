@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::fmt::Write;
 
 use errors::bail;
@@ -13,14 +14,17 @@ use libs::regex::Regex;
 use utils::site::resolve_internal_link;
 use utils::slugs::slugify_anchors;
 use utils::table_of_contents::{make_table_of_contents, Heading};
-use utils::types::InsertAnchor;
+use utils::types::{InsertAnchor, RawHtml};
 
-use self::cmark::{Event, LinkType, Options, Parser, Tag};
-use crate::codeblock::{CodeBlock, FenceSettings};
+use self::cmark::{Event, HeadingLevel, LinkType, Options, Parser, Tag};
+use crate::codeblock::{highlight_inline_code, CodeBlock, FenceSettings};
+use crate::raw_html::{is_allowed_url, Sanitizer};
 use crate::shortcode::{Shortcode, SHORTCODE_PLACEHOLDER};
 
 const CONTINUE_READING: &str = "<span id=\"continue-reading\"></span>";
 const ANCHOR_LINK_TEMPLATE: &str = "anchor-link.html";
+const IMAGE_SHORTCODE_TEMPLATE: &str = "shortcodes/image.html";
+const PICTURE_SHORTCODE_TEMPLATE: &str = "shortcodes/picture.html";
 static EMOJI_REPLACER: Lazy<EmojiReplacer> = Lazy::new(EmojiReplacer::new);
 
 /// Although there exists [a list of registered URI schemes][uri-schemes], a link may use arbitrary,
@@ -59,6 +63,53 @@ fn is_colocated_asset_link(link: &str) -> bool {
     !link.starts_with('/') && !link.starts_with('#') && !STARTS_WITH_SCHEMA_RE.is_match(link)
 }
 
+/// Whether a colocated image can go through the `image` shortcode, ie it is one of the assets of
+/// the page or section being rendered and is in a raster format we can resize.
+/// Other images, like SVGs, GIFs or missing files, are rendered as is.
+fn is_resizable_image(context: &RenderContext, page_path: &str, src: &str) -> bool {
+    let extension = src.rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+    if !matches!(extension.as_deref(), Some("jpg" | "jpeg" | "png" | "webp" | "bmp")) {
+        return false;
+    }
+
+    let parent = page_path.rsplit_once('/').map(|(parent, _)| parent).unwrap_or("");
+    let asset =
+        if parent.is_empty() { format!("/{}", src) } else { format!("/{}/{}", parent, src) };
+    ["page", "section"].iter().filter_map(|key| context.tera_context.get(key)).any(|item| {
+        item.get("assets")
+            .and_then(|assets| assets.as_array())
+            .map_or(false, |assets| assets.iter().any(|a| a.as_str() == Some(asset.as_str())))
+    })
+}
+
+/// Renders a colocated image through the `image` shortcode so it gets a `srcset`,
+/// or the `picture` one if WebP versions are wanted as well
+fn render_responsive_image(
+    context: &RenderContext,
+    page_path: &str,
+    src: &str,
+    alt: &str,
+    title: &str,
+) -> Result<String> {
+    let parent = page_path.rsplit_once('/').map(|(parent, _)| parent).unwrap_or("");
+    let path =
+        if parent.is_empty() { format!("@/{}", src) } else { format!("@/{}/{}", parent, src) };
+
+    let mut c = tera::Context::new();
+    c.insert("path", &path);
+    c.insert("alt", alt);
+    if !title.is_empty() {
+        c.insert("title", title);
+    }
+    let template = if context.config.images.webp {
+        PICTURE_SHORTCODE_TEMPLATE
+    } else {
+        IMAGE_SHORTCODE_TEMPLATE
+    };
+    utils::templates::render_template(template, &context.tera, c, &context.config.theme)
+        .with_context(|| format!("Failed to render the responsive image {}", src))
+}
+
 #[derive(Debug)]
 pub struct Rendered {
     pub body: String,
@@ -135,6 +186,25 @@ fn is_external_link(link: &str) -> bool {
     link.starts_with("http:") || link.starts_with("https:")
 }
 
+/// Replaces an anchor written as the text of a heading between quotes, eg `@/blog/post.md#"Getting started"`,
+/// by the id the heading gets so the link doesn't depend on how the ids are generated.
+/// The anchor is then checked like any other once the site is rendered.
+fn resolve_heading_text_anchor<'a>(link: &'a str, context: &RenderContext) -> Cow<'a, str> {
+    match link.split_once('#') {
+        Some((path, anchor))
+            if anchor.len() > 1 && anchor.starts_with('"') && anchor.ends_with('"') =>
+        {
+            let text = anchor[1..anchor.len() - 1].trim();
+            Cow::Owned(format!(
+                "{}#{}",
+                path,
+                slugify_anchors(text, context.config.slugify.anchors)
+            ))
+        }
+        _ => Cow::Borrowed(link),
+    }
+}
+
 fn fix_link(
     link_type: LinkType,
     link: &str,
@@ -145,6 +215,7 @@ fn fix_link(
     if link_type == LinkType::Email {
         return Ok(link.to_string());
     }
+    let link = &*resolve_heading_text_anchor(link, context);
 
     // A few situations here:
     // - it could be a relative link (starting with `@/`)
@@ -205,6 +276,22 @@ fn get_text(parser_slice: &[Event]) -> String {
     title
 }
 
+/// Demotes all the headings by `shift` levels, h6 being the lowest level possible
+fn shift_heading_levels(events: &mut [Event], shift: u8) {
+    if shift == 0 {
+        return;
+    }
+
+    for event in events.iter_mut() {
+        if let Event::Start(Tag::Heading(level, _, _)) | Event::End(Tag::Heading(level, _, _)) =
+            event
+        {
+            let shifted = (*level as usize + shift as usize).min(6);
+            *level = HeadingLevel::try_from(shifted).expect("Heading level is between 1 and 6");
+        }
+    }
+}
+
 fn get_heading_refs(events: &[Event]) -> Vec<HeadingRef> {
     let mut heading_refs = vec![];
 
@@ -244,6 +331,8 @@ pub fn markdown_to_html(
     let mut error = None;
 
     let mut code_block: Option<CodeBlock> = None;
+    // (src, title, alt) of a colocated image being rendered with a srcset
+    let mut responsive_image: Option<(String, String, String)> = None;
 
     let mut headings: Vec<Heading> = vec![];
     let mut internal_links = Vec::new();
@@ -268,8 +357,26 @@ pub fn markdown_to_html(
     let mut next_shortcode = html_shortcodes.pop();
     let contains_shortcode = |txt: &str| -> bool { txt.contains(SHORTCODE_PLACEHOLDER) };
 
+    // Only created if the content has some raw HTML to sanitize
+    let mut sanitizer: Option<Sanitizer> = None;
+
     {
         let mut events = Vec::new();
+        // The raw HTML written in the content, as opposed to the one output by shortcodes
+        macro_rules! raw_html {
+            ($html:expr) => {
+                match context.raw_html {
+                    RawHtml::Pass => Event::Html($html),
+                    RawHtml::Escape => Event::Text($html),
+                    RawHtml::Sanitize => Event::Html(
+                        sanitizer
+                            .get_or_insert_with(|| Sanitizer::new(&context.config.markdown))
+                            .clean(&$html)
+                            .into(),
+                    ),
+                }
+            };
+        }
         macro_rules! render_shortcodes {
             ($is_text:expr, $text:expr, $range:expr) => {
                 let orig_range_start = $range.start;
@@ -289,7 +396,7 @@ pub fn markdown_to_html(
                             events.push(if $is_text {
                                 Event::Text(content)
                             } else {
-                                Event::Html(content)
+                                raw_html!(content)
                             });
                             $range.start = sc_span.start;
                         }
@@ -316,7 +423,7 @@ pub fn markdown_to_html(
                 if !$range.is_empty() {
                     // The $range value is for the whole document, not for this slice of text
                     let content = $text[($range.start - orig_range_start)..].to_string().into();
-                    events.push(if $is_text { Event::Text(content) } else { Event::Html(content) });
+                    events.push(if $is_text { Event::Text(content) } else { raw_html!(content) });
                 }
             };
         }
@@ -324,6 +431,16 @@ pub fn markdown_to_html(
         let mut accumulated_block = String::new();
         for (event, mut range) in Parser::new_ext(content, opts).into_offset_iter() {
             match event {
+                Event::Text(text) | Event::Code(text) if responsive_image.is_some() => {
+                    responsive_image.as_mut().unwrap().2 += &text;
+                }
+                Event::End(Tag::Image(..)) if responsive_image.is_some() => {
+                    let (src, title, alt) = responsive_image.take().unwrap();
+                    match render_responsive_image(context, path.unwrap(), &src, &alt, &title) {
+                        Ok(html) => events.push(Event::Html(html.into())),
+                        Err(e) => error = Some(e),
+                    }
+                }
                 Event::Text(text) => {
                     if let Some(ref mut _code_block) = code_block {
                         if contains_shortcode(text.as_ref()) {
@@ -365,6 +482,12 @@ pub fn markdown_to_html(
                         render_shortcodes!(true, text, range);
                     }
                 }
+                Event::Code(text) if context.config.markdown.highlight_inline_code => {
+                    match highlight_inline_code(&text, context.config) {
+                        Some(html) => events.push(Event::Html(html.into())),
+                        None => events.push(Event::Code(text)),
+                    }
+                }
                 Event::Start(Tag::CodeBlock(ref kind)) => {
                     let fence = match kind {
                         cmark::CodeBlockKind::Fenced(fence_info) => FenceSettings::new(fence_info),
@@ -385,6 +508,20 @@ pub fn markdown_to_html(
                     code_block = None;
                     events.push(Event::Html("</code></pre>\n".into()));
                 }
+                Event::Start(Tag::Image(_, src, title))
+                    if context.config.images.has_markdown_srcset()
+                        && path.is_some()
+                        && is_colocated_asset_link(&src)
+                        && is_resizable_image(context, path.unwrap(), &src) =>
+                {
+                    responsive_image = Some((src.to_string(), title.to_string(), String::new()));
+                }
+                // The policies for untrusted content don't let links or images run scripts either
+                Event::Start(Tag::Image(link_type, src, title))
+                    if context.raw_html != RawHtml::Pass && !is_allowed_url(&src) =>
+                {
+                    events.push(Event::Start(Tag::Image(link_type, "".into(), title)));
+                }
                 Event::Start(Tag::Image(link_type, src, title)) => {
                     if is_colocated_asset_link(&src) {
                         let link = format!("{}{}", context.current_page_permalink, &*src);
@@ -397,6 +534,11 @@ pub fn markdown_to_html(
                     error = Some(Error::msg("There is a link that is missing a URL"));
                     events.push(Event::Start(Tag::Link(link_type, "#".into(), title)));
                 }
+                Event::Start(Tag::Link(link_type, link, title))
+                    if context.raw_html != RawHtml::Pass && !is_allowed_url(&link) =>
+                {
+                    events.push(Event::Start(Tag::Link(link_type, "#".into(), title)));
+                }
                 Event::Start(Tag::Link(link_type, link, title)) => {
                     let fixed_link = match fix_link(
                         link_type,
@@ -466,7 +608,7 @@ pub fn markdown_to_html(
                         continue;
                     }
                     if !contains_shortcode(text.as_ref()) {
-                        events.push(Event::Html(text));
+                        events.push(raw_html!(text));
                         continue;
                     }
 
@@ -477,14 +619,12 @@ pub fn markdown_to_html(
         }
 
         // We remove all the empty things we might have pushed before so we don't get some random \n
-        events = events
-            .into_iter()
-            .filter(|e| match e {
-                Event::Text(text) | Event::Html(text) => !text.is_empty(),
-                _ => true,
-            })
-            .collect();
+        events.retain(|e| match e {
+            Event::Text(text) | Event::Html(text) => !text.is_empty(),
+            _ => true,
+        });
 
+        shift_heading_levels(&mut events, context.heading_shift);
         let heading_refs = get_heading_refs(&events);
 
         let mut anchors_to_insert = vec![];
@@ -526,13 +666,14 @@ pub fn markdown_to_html(
                 let mut c = tera::Context::new();
                 c.insert("id", &id);
                 c.insert("level", &heading_ref.level);
+                c.insert("title", &title);
                 c.insert("lang", &context.lang);
 
                 let anchor_link = utils::templates::render_template(
                     ANCHOR_LINK_TEMPLATE,
                     &context.tera,
                     c,
-                    &None,
+                    &context.config.theme,
                 )
                 .context("Failed to render anchor link template")?;
                 if context.insert_anchor != InsertAnchor::Heading {
@@ -0,0 +1,233 @@
+//! The `sanitize` raw HTML policy.
+//!
+//! pulldown-cmark gives us the raw HTML of the Markdown as fragments, eg an inline `<span>` on its
+//! own or a single line of an HTML block, which are not valid HTML documents by themselves.
+//! Each tag of a fragment is cleaned on its own with ammonia instead, keeping track of the
+//! comments and scripts spanning several fragments so their content can be removed.
+//! An opening tag spanning several lines of an HTML block is kept until its end is found, an
+//! unterminated tag at the end of the content being dropped.
+//! The URLs of the Markdown links and images are checked against the schemes ammonia allows.
+use std::collections::HashSet;
+
+use config::Markdown;
+use libs::ammonia::Builder;
+use libs::once_cell::sync::Lazy;
+use libs::regex::Regex;
+
+/// A comment or an opening/closing tag, the quoted attribute values being allowed to contain `>`
+static TAG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?s)<!--.*?(?:-->|$)|<(/?)([a-zA-Z][a-zA-Z0-9-]*)(?:\s(?:[^>"']|"[^"]*"|'[^']*')*)?>"#,
+    )
+    .unwrap()
+});
+
+/// The start of a tag that isn't terminated at the end of a fragment
+static UNTERMINATED_TAG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"</?[a-zA-Z][a-zA-Z0-9-]*(?:\s(?:[^>"']|"[^"]*"|'[^']*')*(?:"[^"]*|'[^']*)?)?$"#)
+        .unwrap()
+});
+
+/// The schemes allowed in the URLs of links and images, `javascript:` not being one of them
+static URL_SCHEMES: Lazy<HashSet<&'static str>> =
+    Lazy::new(|| Builder::default().clone_url_schemes());
+
+/// Whether the URL of a Markdown link or image is relative or has one of the allowed schemes
+pub fn is_allowed_url(url: &str) -> bool {
+    // Browsers ignore the tabs and newlines of URLs
+    let url: String = url.chars().filter(|c| !matches!(c, '\t' | '\n' | '\r')).collect();
+    let url = url.trim_start_matches(|c: char| c <= ' ');
+    match url.find([':', '/', '?', '#']) {
+        Some(idx) if url[idx..].starts_with(':') => {
+            URL_SCHEMES.contains(url[..idx].to_ascii_lowercase().as_str())
+        }
+        _ => true,
+    }
+}
+
+pub struct Sanitizer<'a> {
+    builder: Builder<'a>,
+    allowed_tags: HashSet<&'a str>,
+    /// What ends the comment or script we are removing the content of, if any
+    skip_until: Option<String>,
+    /// The start of a tag continuing in the next fragment
+    unterminated_tag: String,
+}
+
+impl<'a> Sanitizer<'a> {
+    pub fn new(config: &'a Markdown) -> Self {
+        let mut builder = Builder::default();
+        if !config.raw_html_allowed_tags.is_empty() {
+            builder.tags(config.raw_html_allowed_tags.iter().map(|t| t.as_str()).collect());
+        }
+        builder
+            .add_generic_attributes(config.raw_html_allowed_attributes.iter().map(|a| a.as_str()));
+        let allowed_tags = builder.clone_tags();
+
+        Self { builder, allowed_tags, skip_until: None, unterminated_tag: String::new() }
+    }
+
+    /// Cleans a fragment of HTML, which can be any part of a document
+    pub fn clean(&mut self, html: &str) -> String {
+        let mut out = String::with_capacity(html.len());
+        let html = std::mem::take(&mut self.unterminated_tag) + html;
+        let mut rest = html.as_str();
+
+        loop {
+            if let Some(end) = self.skip_until.as_deref() {
+                match rest.to_ascii_lowercase().find(end) {
+                    Some(idx) => {
+                        rest = &rest[idx + end.len()..];
+                        self.skip_until = None;
+                    }
+                    None => return out,
+                }
+            }
+
+            let caps = match TAG_RE.captures(rest) {
+                Some(caps) => caps,
+                None => {
+                    if let Some(tag) = UNTERMINATED_TAG_RE.find(rest) {
+                        self.unterminated_tag = tag.as_str().to_string();
+                        rest = &rest[..tag.start()];
+                    }
+                    out += &self.builder.clean(rest).to_string();
+                    return out;
+                }
+            };
+            let tag = caps.get(0).unwrap();
+            out += &self.builder.clean(&rest[..tag.start()]).to_string();
+            rest = &rest[tag.end()..];
+
+            let name = match caps.get(2) {
+                Some(name) => name.as_str().to_lowercase(),
+                None => {
+                    if !tag.as_str().ends_with("-->") {
+                        self.skip_until = Some("-->".to_string());
+                    }
+                    continue;
+                }
+            };
+            if &caps[1] == "/" {
+                if self.allowed_tags.contains(name.as_str()) {
+                    out += &format!("</{}>", name);
+                }
+            } else if Markdown::RAW_HTML_CLEANED_TAGS.contains(&name.as_str()) {
+                if !tag.as_str().ends_with("/>") {
+                    self.skip_until = Some(format!("</{}>", name));
+                }
+            } else if self.allowed_tags.contains(name.as_str()) {
+                out += self.clean_opening_tag(tag.as_str(), &name).as_deref().unwrap_or_default();
+            }
+        }
+    }
+
+    /// Removes the attributes that aren't allowed from an opening tag of an allowed element
+    fn clean_opening_tag(&self, tag: &str, name: &str) -> Option<String> {
+        // Parsing a table cell or row outside of a table would drop it
+        let parents = match name {
+            "td" | "th" => "<table><tr>",
+            "tr" | "tbody" | "thead" | "tfoot" | "caption" | "col" | "colgroup" => "<table>",
+            _ => "",
+        };
+        let cleaned = self.builder.clean(&format!("{}{}", parents, tag)).to_string();
+
+        let start = cleaned.match_indices(&format!("<{}", name)).map(|(i, _)| i).find(|i| {
+            matches!(cleaned.as_bytes().get(i + name.len() + 1), Some(b' ' | b'>' | b'/'))
+        })?;
+        // The serialized attribute values are always double quoted
+        let mut in_quotes = false;
+        for (i, c) in cleaned[start..].char_indices() {
+            match c {
+                '"' => in_quotes = !in_quotes,
+                '>' if !in_quotes => return Some(cleaned[start..=start + i].to_string()),
+                _ => (),
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sanitize(config: &Markdown, fragments: &[&str]) -> String {
+        let mut sanitizer = Sanitizer::new(config);
+        fragments.iter().map(|f| sanitizer.clean(f)).collect()
+    }
+
+    #[test]
+    fn keeps_allowed_tags_and_attributes() {
+        let config = Markdown::default();
+        assert_eq!(
+            sanitize(&config, &["<p title=\"a > b\" onclick=\"alert(1)\">", "Hi & bye", "</p>"]),
+            "<p title=\"a > b\">Hi &amp; bye</p>"
+        );
+        assert_eq!(
+            sanitize(&config, &["<a href=\"javascript:alert(1)\">", "<img src=\"a.png\">"]),
+            "<a rel=\"noopener noreferrer\"><img src=\"a.png\">"
+        );
+    }
+
+    #[test]
+    fn removes_comments_and_scripts_across_fragments() {
+        let config = Markdown::default();
+        assert_eq!(
+            sanitize(
+                &config,
+                &["<div><!-- a\n", "b --><b>1</b>", "<script>", "alert(1)", "</SCRIPT>2</div>"]
+            ),
+            "<div><b>1</b>2</div>"
+        );
+    }
+
+    #[test]
+    fn can_sanitize_tags_spanning_several_fragments() {
+        let config = Markdown::default();
+        assert_eq!(
+            sanitize(
+                &config,
+                &["<div\n", "  title=\"a\n", "b\"\n", "  onclick=\"alert(1)\">1</div>\n", "<p"]
+            ),
+            "<div title=\"a\nb\">1</div>\n"
+        );
+    }
+
+    #[test]
+    fn can_check_url_schemes() {
+        for url in ["https://example.com", "mailto:a@b.c", "/about/", "page.md#a:b", "?q=a:b"] {
+            assert!(is_allowed_url(url), "{} is not allowed", url);
+        }
+        for url in
+            ["javascript:alert(1)", "JavaScript:alert(1)", " java\tscript:x", "data:text/html,"]
+        {
+            assert!(!is_allowed_url(url), "{} is allowed", url);
+        }
+    }
+
+    #[test]
+    fn can_sanitize_table_cells() {
+        let config = Markdown::default();
+        assert_eq!(
+            sanitize(
+                &config,
+                &["<tr>", "<td colspan=\"2\" style=\"color: red\">", "1", "</td>", "</tr>"]
+            ),
+            "<tr><td colspan=\"2\">1</td></tr>"
+        );
+    }
+
+    #[test]
+    fn can_configure_allowed_tags_and_attributes() {
+        let config = Markdown {
+            raw_html_allowed_tags: vec!["span".to_string()],
+            raw_html_allowed_attributes: vec!["class".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            sanitize(&config, &["<span class=\"x\" id=\"y\">", "<b>1</b>", "</span>"]),
+            "<span class=\"x\">1</span>"
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use errors::{Error, Result};
+use errors::{Error, Result, SourceError};
 use libs::tera;
 use utils::templates::{ShortcodeDefinition, ShortcodeFileType};
 
@@ -18,8 +18,10 @@ pub fn extract_shortcodes(
     for sc in &mut shortcodes {
         if let Some(def) = definitions.get(&sc.name) {
             sc.tera_name = def.tera_name.clone();
+            sc.native = def.native.clone();
         } else {
-            return Err(Error::msg(format!("Found usage of a shortcode named `{}` but we do not know about. Make sure it's not a typo and that a field name `{}.{{html,md}} exists in the `templates/shortcodes` directory.", sc.name, sc.name)));
+            let message = format!("Found usage of a shortcode named `{}` but we do not know about. Make sure it's not a typo and that a field name `{}.{{html,md}} exists in the `templates/shortcodes` directory.", sc.name, sc.name);
+            return Err(Error::new(SourceError::new(message, source, sc.line, None)));
         }
     }
 
@@ -75,19 +77,23 @@ mod tests {
                 vec![
                     Shortcode {
                         name: "a".to_string(),
-                        args: to_value(&HashMap::<u8, u8>::new()).unwrap(),
+                        args: to_value(HashMap::<u8, u8>::new()).unwrap(),
                         span: 0..SHORTCODE_PLACEHOLDER.len(),
                         body: None,
                         nth: 1,
+                        line: 1,
                         tera_name: "shortcodes/a.md".to_owned(),
+                        native: None,
                     },
                     Shortcode {
                         name: "a".to_string(),
-                        args: to_value(&HashMap::<u8, u8>::new()).unwrap(),
+                        args: to_value(HashMap::<u8, u8>::new()).unwrap(),
                         span: SHORTCODE_PLACEHOLDER.len()..(2 * SHORTCODE_PLACEHOLDER.len()),
                         body: None,
                         nth: 2,
+                        line: 1,
                         tera_name: "shortcodes/a.md".to_owned(),
+                        native: None,
                     }
                 ],
                 &tera_context,
@@ -103,12 +109,13 @@ mod tests {
                 format!("Much wow {}", SHORTCODE_PLACEHOLDER),
                 vec![Shortcode {
                     name: "bodied".to_string(),
-                    args: to_value(&HashMap::<u8, u8>::new()).unwrap(),
+                    args: to_value(HashMap::<u8, u8>::new()).unwrap(),
                     span: 9..(9 + SHORTCODE_PLACEHOLDER.len()),
                     body: Some("Content of the body".to_owned()),
                     nth: 1,
-
+                    line: 1,
                     tera_name: "shortcodes/bodied.md".to_owned(),
+                    native: None,
                 },],
                 &tera_context,
                 &tera
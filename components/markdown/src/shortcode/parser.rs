@@ -1,12 +1,13 @@
 use std::ops::Range;
 
-use errors::{bail, Context as ErrorContext, Result};
+use errors::{Context as ErrorContext, Error, Result, SourceError};
 use libs::tera::{to_value, Context, Map, Tera, Value};
+use pest::error::LineColLocation;
 use pest::iterators::Pair;
 use pest::Parser;
 use pest_derive::Parser;
 use std::collections::HashMap;
-use utils::templates::ShortcodeFileType;
+use utils::templates::{NativeShortcode, ShortcodeFileType};
 
 pub const SHORTCODE_PLACEHOLDER: &str = "@@ZOLA_SC_PLACEHOLDER@@";
 
@@ -17,8 +18,12 @@ pub struct Shortcode {
     pub(crate) span: Range<usize>,
     pub(crate) body: Option<String>,
     pub(crate) nth: usize,
+    /// The line of the content the shortcode is called on, 1-indexed
+    pub(crate) line: usize,
     // set later down the line, for quick access without needing the definitions
     pub(crate) tera_name: String,
+    /// Set with `tera_name` for the shortcodes written in Rust
+    pub(crate) native: Option<NativeShortcode>,
 }
 
 impl Shortcode {
@@ -32,7 +37,12 @@ impl Shortcode {
 
     pub fn render(self, tera: &Tera, context: &Context) -> Result<String> {
         let name = self.name;
+        let line = self.line;
         let tpl_name = self.tera_name;
+        let args = match &self.args {
+            Value::Object(args) if self.native.is_some() => args.clone(),
+            _ => Map::new(),
+        };
         let mut new_context = Context::from_value(self.args)?;
 
         if let Some(body_content) = self.body {
@@ -42,9 +52,14 @@ impl Shortcode {
         new_context.insert("nth", &self.nth);
         new_context.extend(context.clone());
 
-        let res = utils::templates::render_template(&tpl_name, tera, new_context, &None)
-            .with_context(|| format!("Failed to render {} shortcode", name))?
-            .replace("\r\n", "\n");
+        let res = match self.native {
+            Some(native) => (native.0)(&args, &new_context),
+            None => utils::templates::render_template(&tpl_name, tera, new_context, &None),
+        }
+        .with_context(|| {
+            SourceError::new(format!("Failed to render {} shortcode", name), "", line, None)
+        })?
+        .replace("\r\n", "\n");
 
         Ok(res)
     }
@@ -55,11 +70,7 @@ impl Shortcode {
         }
 
         let rendered_end = sc_span.start + rendered_length;
-        let delta = if sc_span.end < rendered_end {
-            rendered_end - sc_span.end
-        } else {
-            sc_span.end - rendered_end
-        };
+        let delta = rendered_end.abs_diff(sc_span.end);
 
         if sc_span.end < rendered_end {
             self.span = (self.span.start + delta)..(self.span.end + delta);
@@ -196,7 +207,11 @@ pub fn parse_for_shortcodes(content: &str) -> Result<(String, Vec<Shortcode>)> {
                 Rule::page => "a page".to_string(),
                 Rule::WHITESPACE => "whitespace".to_string(),
             });
-            bail!("{}", fancy_e);
+            let (line, column) = match fancy_e.line_col {
+                LineColLocation::Pos(position) | LineColLocation::Span(position, _) => position,
+            };
+            let message = format!("Failed to parse the shortcodes: {}", fancy_e.variant.message());
+            return Err(Error::new(SourceError::new(message, content, line, Some(column))));
         }
     };
 
@@ -206,6 +221,7 @@ pub fn parse_for_shortcodes(content: &str) -> Result<(String, Vec<Shortcode>)> {
             Rule::text => output.push_str(p.as_span().as_str()),
             Rule::inline_shortcode => {
                 let start = output.len();
+                let line = p.as_span().start_pos().line_col().0;
                 let (name, args) = parse_shortcode_call(p);
                 let nth = get_invocation_count(&name);
                 shortcodes.push(Shortcode {
@@ -214,12 +230,15 @@ pub fn parse_for_shortcodes(content: &str) -> Result<(String, Vec<Shortcode>)> {
                     span: start..(start + SHORTCODE_PLACEHOLDER.len()),
                     body: None,
                     nth,
+                    line,
                     tera_name: String::new(),
+                    native: None,
                 });
                 output.push_str(SHORTCODE_PLACEHOLDER);
             }
             Rule::shortcode_with_body => {
                 let start = output.len();
+                let line = p.as_span().start_pos().line_col().0;
                 let mut inner = p.into_inner();
                 // 3 items in inner: call, body, end
                 // we don't care about the closing tag
@@ -232,7 +251,9 @@ pub fn parse_for_shortcodes(content: &str) -> Result<(String, Vec<Shortcode>)> {
                     span: start..(start + SHORTCODE_PLACEHOLDER.len()),
                     body: Some(body.to_string()),
                     nth,
+                    line,
                     tera_name: String::new(),
+                    native: None,
                 });
                 output.push_str(SHORTCODE_PLACEHOLDER)
             }
@@ -274,8 +295,8 @@ mod tests {
             let res = ContentParser::parse($rule, $input);
             println!("{:?}", $input);
             println!("{:#?}", res);
-            if res.is_err() {
-                println!("{}", res.unwrap_err());
+            if let Err(e) = &res {
+                println!("{}", e);
                 panic!();
             }
             assert!(res.is_ok());
@@ -374,7 +395,9 @@ mod tests {
             span: 10..20,
             body: None,
             nth: 0,
+            line: 1,
             tera_name: String::new(),
+            native: None,
         };
         // 6 -> 10 in length so +4 on both sides of the range
         sc.update_range(&(2..8), 10);
@@ -393,7 +416,9 @@ mod tests {
             span: 42..65,
             body: None,
             nth: 0,
+            line: 1,
             tera_name: String::new(),
+            native: None,
         };
         sc.update_range(&(9..32), 3);
         assert_eq!(sc.span, 22..45);
@@ -341,3 +341,43 @@ bar
     );
     insta::assert_snapshot!(body);
 }
+
+#[test]
+fn can_highlight_inline_code() {
+    let mut config = Config::default_for_test();
+    config.markdown.highlight_inline_code = true;
+    let body = common::render_with_config(
+        "Use `rust:Vec<u8>` or `python:bytes`, not `unknownlang:Vec<u8>` or `plain code`.",
+        config.clone(),
+    )
+    .unwrap()
+    .body;
+    insta::assert_snapshot!(body);
+
+    config.markdown.highlight_theme = "css".to_string();
+    let body = common::render_with_config("`rust:Vec<u8>`", config).unwrap().body;
+    insta::assert_snapshot!(body);
+}
+
+#[test]
+fn does_not_highlight_inline_code_looking_like_paths_or_urls() {
+    let mut config = Config::default_for_test();
+    config.markdown.highlight_inline_code = true;
+    let body = common::render_with_config(
+        r"`c:\Windows`, `d://example.com`, `c::mem`, `c:b:c` and `c:`",
+        config,
+    )
+    .unwrap()
+    .body;
+    assert_eq!(
+        body,
+        "<p><code>c:\\Windows</code>, <code>d://example.com</code>, <code>c::mem</code>, \
+<code>c:b:c</code> and <code>c:</code></p>\n"
+    );
+}
+
+#[test]
+fn does_not_highlight_inline_code_by_default() {
+    let body = render_codeblock("`rust:Vec<u8>`", true);
+    assert_eq!(body, "<p><code>rust:Vec&lt;u8&gt;</code></p>\n");
+}
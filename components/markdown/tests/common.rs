@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use libs::tera::Tera;
 
@@ -57,7 +58,11 @@ fn configurable_render(
 
     tera.register_filter(
         "markdown",
-        templates::filters::MarkdownFilter::new(config.clone(), permalinks.clone(), tera.clone()),
+        templates::filters::MarkdownFilter::new(
+            config.clone(),
+            Arc::new(permalinks.clone()),
+            tera.clone(),
+        ),
     );
     let mut context = RenderContext::new(
         &tera,
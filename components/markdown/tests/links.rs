@@ -53,3 +53,29 @@ Email: <foo@bar.baz>",
     assert!(res.is_err());
     assert_eq!(res.unwrap_err().to_string(), "There is a link that is missing a URL");
 }
+
+#[test]
+fn can_link_to_heading_by_its_text() {
+    let rendered = common::render(r#"[abc](<@/pages/about.md#"Getting Started!">)"#).unwrap();
+    assert_eq!(
+        rendered.internal_links,
+        vec![("pages/about.md".to_owned(), Some("getting-started".to_owned()))]
+    );
+    assert!(rendered.body.contains(r#"href="https://getzola.org/about/#getting-started""#));
+
+    let rendered = common::render(r#"[abc](<#"Hello World">)"#).unwrap();
+    assert_eq!(
+        rendered.internal_links[0],
+        ("my_page.md".to_owned(), Some("hello-world".to_owned()))
+    );
+
+    let mut config = config::Config::default_for_test();
+    config.slugify.anchors = utils::slugs::SlugifyStrategy::Safe;
+    let rendered =
+        common::render_with_config(r#"[abc](<@/pages/about.md#"Getting Started">)"#, config)
+            .unwrap();
+    assert_eq!(
+        rendered.internal_links,
+        vec![("pages/about.md".to_owned(), Some("Getting_Started".to_owned()))]
+    );
+}
@@ -6,13 +6,13 @@ use config::Config;
 use markdown::{render_content, RenderContext};
 use templates::ZOLA_TERA;
 use utils::slugs::SlugifyStrategy;
-use utils::types::InsertAnchor;
+use utils::types::{InsertAnchor, RawHtml};
 
 mod common;
 
 #[test]
 fn can_render_basic_markdown() {
-    let cases = vec![
+    let cases = [
         "Hello world",
         "# Hello world",
         "Hello *world*",
@@ -86,7 +86,7 @@ fn can_handle_heading_ids() {
 
 #[test]
 fn can_insert_anchors() {
-    let cases = vec![
+    let cases = [
         // Basic
         "# Hello\n# World",
         // https://github.com/Keats/gutenberg/issues/42
@@ -125,6 +125,58 @@ fn can_customise_anchor_template() {
     insta::assert_snapshot!(body);
 }
 
+#[test]
+fn can_use_anchor_template_from_theme() {
+    let mut tera = Tera::default();
+    tera.extend(&ZOLA_TERA).unwrap();
+    tera.add_raw_template(
+        "sample/templates/anchor-link.html",
+        "<a href=\"#{{ id }}\" aria-label=\"Link to {{ title }}\">h{{ level }}</a>",
+    )
+    .unwrap();
+    let permalinks_ctx = HashMap::new();
+    let mut config = Config::default_for_test();
+    config.theme = Some("sample".to_string());
+    let context = RenderContext::new(
+        &tera,
+        &config,
+        &config.default_language,
+        "",
+        &permalinks_ctx,
+        InsertAnchor::Right,
+    );
+    let body = render_content("## Hello *world*", &context).unwrap().body;
+    assert_eq!(
+        body,
+        "<h2 id=\"hello-world\">Hello <em>world</em><a href=\"#hello-world\" aria-label=\"Link to Hello world\">h2</a></h2>\n"
+    );
+}
+
+#[test]
+fn can_shift_heading_levels() {
+    let mut tera = Tera::default();
+    tera.extend(&ZOLA_TERA).unwrap();
+    let permalinks_ctx = HashMap::new();
+    let config = Config::default_for_test();
+    let mut context = RenderContext::new(
+        &tera,
+        &config,
+        &config.default_language,
+        "",
+        &permalinks_ctx,
+        InsertAnchor::None,
+    );
+    context.heading_shift = 2;
+    let res =
+        render_content("# Hello\n## World {#world}\n##### Deep\n###### Deeper", &context).unwrap();
+    assert_eq!(
+        res.body,
+        "<h3 id=\"hello\">Hello</h3>\n<h4 id=\"world\">World</h4>\n<h6 id=\"deep\">Deep</h6>\n<h6 id=\"deeper\">Deeper</h6>\n"
+    );
+    assert_eq!(res.toc[0].level, 3);
+    assert_eq!(res.toc[0].children[0].level, 4);
+}
+
 #[test]
 fn can_use_smart_punctuation() {
     let mut config = Config::default_for_test();
@@ -174,6 +226,76 @@ fn can_render_emojis() {
     assert_eq!(body, "<p>Hello, World! 😄</p>\n");
 }
 
+#[test]
+fn can_escape_raw_html() {
+    let mut config = Config::default_for_test();
+    config.markdown.raw_html = RawHtml::Escape;
+    let body =
+        common::render_with_config("Hello <b>world</b>\n\n<div>Bye</div>\n\n{{ ex1() }}", config)
+            .unwrap()
+            .body;
+    assert_eq!(body, "<p>Hello &lt;b&gt;world&lt;/b&gt;</p>\n&lt;div&gt;Bye&lt;/div&gt;\n1");
+}
+
+#[test]
+fn can_sanitize_raw_html() {
+    let mut config = Config::default_for_test();
+    config.markdown.raw_html = RawHtml::Sanitize;
+    let body = common::render_with_config(
+        r#"Hello <b onclick="steal()">world</b><!-- more -->
+
+<div>
+<script>
+steal();
+</script>
+<iframe src="https://example.com"></iframe>
+</div>
+
+{{ image(alt="kitten") }}"#,
+        config,
+    )
+    .unwrap()
+    .body;
+    assert_eq!(
+        body,
+        "<p>Hello <b>world</b><span id=\"continue-reading\"></span></p>\n<div>\n\n\n</div>\n\
+        <img src='https://placekitten.com/200/300' alt='kitten'></img>"
+    );
+}
+
+#[test]
+fn can_remove_script_urls_of_links_and_images() {
+    for raw_html in [RawHtml::Sanitize, RawHtml::Escape] {
+        let mut config = Config::default_for_test();
+        config.markdown.raw_html = raw_html;
+        let body = common::render_with_config(
+            "[click](javascript:alert(1)) <javascript:alert(2)> ![i](javascript:alert(3)) \
+             [ok](https://example.com)",
+            config,
+        )
+        .unwrap()
+        .body;
+        assert_eq!(
+            body,
+            "<p><a href=\"#\">click</a> <a href=\"#\">javascript:alert(2)</a> \
+             <img src=\"\" alt=\"i\" /> <a href=\"https://example.com\">ok</a></p>\n"
+        );
+    }
+}
+
+#[test]
+fn can_sanitize_raw_html_tags_spanning_several_lines() {
+    let mut config = Config::default_for_test();
+    config.markdown.raw_html = RawHtml::Sanitize;
+    let body = common::render_with_config(
+        "<div\n  title=\"note\"\n  onclick=\"steal()\">\nHi\n</div>",
+        config,
+    )
+    .unwrap()
+    .body;
+    assert_eq!(body, "<div title=\"note\">\nHi\n</div>");
+}
+
 // https://github.com/getzola/zola/issues/747
 // https://github.com/getzola/zola/issues/816
 #[test]
@@ -1,4 +1,5 @@
 use config::Config;
+use errors::SourceError;
 
 mod common;
 
@@ -164,7 +165,38 @@ fn errors_on_unknown_shortcodes() {
 {{ unknown() }}
     "#,
     );
-    assert!(body.is_err());
+    let err = body.unwrap_err();
+    let source_error = err.downcast_ref::<SourceError>().unwrap();
+    assert_eq!(source_error.line, 2);
+    assert_eq!(source_error.lines.last().unwrap().1, "{{ unknown() }}");
+}
+
+#[test]
+fn errors_point_at_the_line_of_the_shortcode_failing_to_render() {
+    let err = common::render(
+        r#"
+Hello
+
+{% quote() %}
+A quote
+{% end %}
+{{ out_put_id() }}
+    "#,
+    )
+    .unwrap_err();
+    let source_error = err.downcast_ref::<SourceError>().unwrap();
+    assert_eq!(source_error.message, "Failed to render out_put_id shortcode");
+    assert_eq!(source_error.location(), "line 7");
+    assert_eq!(
+        source_error.lines,
+        vec![
+            (5, "A quote".to_string()),
+            (6, "{% end %}".to_string()),
+            (7, "{{ out_put_id() }}".to_string())
+        ]
+    );
+    // The Tera error is still there
+    assert!(err.chain().any(|e| e.to_string().contains("Variable `id` not found")));
 }
 
 // https://github.com/getzola/zola/issues/1172
@@ -106,10 +106,11 @@ pub fn build_index(lang: &str, library: &Library, config: &Config) -> Result<Str
     index = build_fields(&language_options.search, index);
     let mut index = index.build();
 
-    for (_, section) in &library.sections {
-        if section.lang == lang {
-            add_section_to_index(&mut index, section, library, &language_options.search);
-        }
+    // The documents are in the index in the order they are added
+    let mut sections: Vec<_> = library.sections.values().filter(|s| s.lang == lang).collect();
+    sections.sort_by(|a, b| a.file.path.cmp(&b.file.path));
+    for section in sections {
+        add_section_to_index(&mut index, section, library, &language_options.search);
     }
 
     Ok(index.to_json())
@@ -129,7 +130,7 @@ fn add_section_to_index(
     if section.meta.redirect_to.is_none() {
         index.add_doc(
             &section.permalink,
-            &fill_index(
+            fill_index(
                 search_config,
                 &section.meta.title,
                 &section.meta.description,
@@ -141,13 +142,17 @@ fn add_section_to_index(
 
     for key in &section.pages {
         let page = &library.pages[key];
-        if !page.meta.in_search_index {
+        // Don't index redirecting pages either, nor protected ones which would leak their content
+        if !page.meta.in_search_index
+            || page.meta.redirect_to.is_some()
+            || page.meta.password.is_some()
+        {
             continue;
         }
 
         index.add_doc(
             &page.permalink,
-            &fill_index(
+            fill_index(
                 search_config,
                 &page.meta.title,
                 &page.meta.description,
@@ -194,7 +199,7 @@ mod tests {
 
         let res = fill_index(&config.search, &title, &description, &path, &content);
         assert_eq!(res.len(), 2);
-        assert_eq!(res[0], title.unwrap());
+        assert_eq!(res[0], "A title");
         assert_eq!(res[1], content);
     }
 
@@ -209,8 +214,8 @@ mod tests {
 
         let res = fill_index(&config.search, &title, &description, &path, &content);
         assert_eq!(res.len(), 3);
-        assert_eq!(res[0], title.unwrap());
-        assert_eq!(res[1], description.unwrap());
+        assert_eq!(res[0], "A title");
+        assert_eq!(res[1], "A description");
         assert_eq!(res[2], content);
     }
 
@@ -225,7 +230,7 @@ mod tests {
 
         let res = fill_index(&config.search, &title, &description, &path, &content);
         assert_eq!(res.len(), 2);
-        assert_eq!(res[0], title.unwrap());
+        assert_eq!(res[0], "A title");
         assert_eq!(res[1], content[..5]);
     }
 }
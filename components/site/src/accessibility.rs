@@ -0,0 +1,126 @@
+use std::path::PathBuf;
+
+use libs::once_cell::sync::Lazy;
+use libs::regex::Regex;
+
+use crate::Site;
+
+static IMG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<img\b([^>]*)>").unwrap());
+static LINK_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<a\b([^>]*)>(.*?)</a>").unwrap());
+static HEADING_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<h([1-6])\b").unwrap());
+static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+
+fn attribute_re(name: &str) -> Regex {
+    Regex::new(&format!(r#"(?is)\s{}\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#, name)).unwrap()
+}
+
+static ALT_RE: Lazy<Regex> = Lazy::new(|| attribute_re("alt"));
+static SRC_RE: Lazy<Regex> = Lazy::new(|| attribute_re("src"));
+static HREF_RE: Lazy<Regex> = Lazy::new(|| attribute_re("href"));
+static ARIA_LABEL_RE: Lazy<Regex> = Lazy::new(|| attribute_re("aria-label"));
+
+/// The value of an attribute, whichever quotes it uses
+fn attribute<'a>(re: &Regex, attributes: &'a str) -> Option<&'a str> {
+    re.captures(attributes)
+        .and_then(|c| c.get(1).or_else(|| c.get(2)).or_else(|| c.get(3)))
+        .map(|m| m.as_str())
+}
+
+fn has_text(re: &Regex, attributes: &str) -> bool {
+    attribute(re, attributes).map_or(false, |v| !v.trim().is_empty())
+}
+
+/// Finds the accessibility issues of rendered HTML: images without alt text, links without
+/// text and headings skipping a level, eg a `h4` right after a `h2`.
+/// The first heading can be a `h2` since the title of the page is usually the `h1` of the template.
+pub fn lint_html(html: &str) -> Vec<String> {
+    let mut issues = Vec::new();
+
+    for caps in IMG_RE.captures_iter(html) {
+        if !has_text(&ALT_RE, &caps[1]) {
+            let src = attribute(&SRC_RE, &caps[1]).unwrap_or_default();
+            issues.push(format!("Image `{}` has no alt text", src));
+        }
+    }
+
+    for caps in LINK_RE.captures_iter(html) {
+        // `<a>` elements without `href` are only anchors
+        let href = match attribute(&HREF_RE, &caps[1]) {
+            Some(href) => href,
+            None => continue,
+        };
+        let text = TAG_RE.replace_all(&caps[2], "");
+        let has_image_text = IMG_RE.captures_iter(&caps[2]).any(|c| has_text(&ALT_RE, &c[1]));
+        if text.trim().is_empty() && !has_image_text && !has_text(&ARIA_LABEL_RE, &caps[1]) {
+            issues.push(format!("Link to `{}` has no text", href));
+        }
+    }
+
+    let mut previous = 1;
+    for caps in HEADING_RE.captures_iter(html) {
+        let level: usize = caps[1].parse().unwrap();
+        if level > previous + 1 {
+            issues.push(format!("Heading h{} follows h{}, skipping a level", level, previous));
+        }
+        previous = level;
+    }
+
+    issues
+}
+
+/// Lints the rendered Markdown of all the pages and sections, returning the issues found for
+/// each file, sorted by path
+pub fn check_accessibility(site: &Site) -> Vec<(PathBuf, Vec<String>)> {
+    let library = site.library.read().unwrap();
+    let pages = library.pages.values().map(|p| (&p.file.path, &p.content));
+    let sections = library.sections.values().map(|s| (&s.file.path, &s.content));
+    let mut issues: Vec<_> = pages
+        .chain(sections)
+        .filter_map(|(path, content)| {
+            let issues = lint_html(content);
+            if issues.is_empty() {
+                None
+            } else {
+                Some((path.clone(), issues))
+            }
+        })
+        .collect();
+    issues.sort();
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_find_images_without_alt_text() {
+        let html =
+            r#"<p><img src="a.png" alt=""> <img src='b.png'> <img alt="A cat" src="c.png"></p>"#;
+        assert_eq!(
+            lint_html(html),
+            vec!["Image `a.png` has no alt text", "Image `b.png` has no alt text"]
+        );
+    }
+
+    #[test]
+    fn can_find_links_without_text() {
+        let html = r#"<a href="/a/"> </a><a href="/b/"><img src="b.png" alt="B"></a>
+<a href="/c/" aria-label="C"><span></span></a><a href="/d/"><em></em></a><a href="/e/">E</a><a id="f"></a>"#;
+        assert_eq!(lint_html(html), vec!["Link to `/a/` has no text", "Link to `/d/` has no text"]);
+    }
+
+    #[test]
+    fn can_find_heading_level_jumps() {
+        let html = "<h2>A</h2><h3>B</h3><h2>C</h2><h4 id=\"d\">D</h4><h1>E</h1><H3>F</H3>";
+        assert_eq!(
+            lint_html(html),
+            vec![
+                "Heading h4 follows h2, skipping a level",
+                "Heading h3 follows h1, skipping a level"
+            ]
+        );
+        assert!(lint_html("<h2>A</h2>").is_empty());
+        assert_eq!(lint_html("<h3>A</h3>"), vec!["Heading h3 follows h1, skipping a level"]);
+    }
+}
@@ -0,0 +1,31 @@
+use crate::head::insert_link;
+
+/// Points the HTML version of a page to its AMP version
+pub fn link_amp_version(html: String, amp_permalink: &str) -> String {
+    insert_link(html, "amphtml", amp_permalink)
+}
+
+/// Points the AMP version of a page to its HTML version
+pub fn link_canonical_version(amp: String, permalink: &str) -> String {
+    insert_link(amp, "canonical", permalink)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_link_both_versions() {
+        let html = "<html><head><title>Hello</title></head><body></body></html>".to_string();
+        assert_eq!(
+            link_amp_version(html.clone(), "https://example.com/hello/amp/"),
+            r#"<html><head><title>Hello</title><link rel="amphtml" href="https://example.com/hello/amp/"></head><body></body></html>"#
+        );
+        assert_eq!(
+            link_canonical_version(html, "https://example.com/hello/"),
+            r#"<html><head><title>Hello</title><link rel="canonical" href="https://example.com/hello/"></head><body></body></html>"#
+        );
+        // Nowhere to put the link
+        assert_eq!(link_amp_version("<p>Hello</p>".to_string(), "/amp/"), "<p>Hello</p>");
+    }
+}
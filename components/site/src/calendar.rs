@@ -0,0 +1,178 @@
+use libs::time::{OffsetDateTime, UtcOffset};
+
+use crate::Site;
+use content::{parse_datetime, Page};
+use errors::{anyhow, Result};
+
+/// Lines longer than this many bytes have to be folded
+const MAX_LINE_LENGTH: usize = 75;
+
+/// Escapes the characters that have a meaning in iCalendar text values
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Writes a content line, folding it on several lines if needed without splitting a character
+fn push_line(ics: &mut String, line: &str) {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            ics.push_str("\r\n ");
+            // The space starting the continuation line counts
+            length = 1;
+        }
+        ics.push(c);
+        length += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn format_utc(datetime: OffsetDateTime) -> String {
+    let utc = datetime.to_offset(UtcOffset::UTC);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        utc.year(),
+        u8::from(utc.month()),
+        utc.day(),
+        utc.hour(),
+        utc.minute(),
+        utc.second()
+    )
+}
+
+fn format_date(datetime: OffsetDateTime) -> String {
+    format!("{:04}{:02}{:02}", datetime.year(), u8::from(datetime.month()), datetime.day())
+}
+
+/// Dates without a time are all-day events
+fn is_date_only(date: &str) -> bool {
+    !date.contains('T') && !date.contains(' ')
+}
+
+/// Writes the `VEVENT` of a dated page, with the `end_date` and `location` of its `extra`
+fn push_event(ics: &mut String, page: &Page) -> Result<()> {
+    let date = page.meta.date.as_deref().unwrap_or_default();
+    let start = page.meta.datetime.expect("Only dated pages are in the calendar");
+    let all_day = is_date_only(date);
+    let end = match page.meta.extra.get("end_date") {
+        Some(end_date) => {
+            let end = end_date.as_str().and_then(parse_datetime).ok_or_else(|| {
+                anyhow!("Page `{}` has an invalid `end_date`", page.file.path.display())
+            })?;
+            Some(end)
+        }
+        None => None,
+    };
+
+    push_line(ics, "BEGIN:VEVENT");
+    push_line(ics, &format!("UID:{}", page.permalink));
+    push_line(ics, &format!("DTSTAMP:{}", format_utc(page.meta.updated_datetime.unwrap_or(start))));
+    if all_day {
+        push_line(ics, &format!("DTSTART;VALUE=DATE:{}", format_date(start)));
+        // The end date of all-day events is exclusive
+        if let Some(end) = end {
+            let end = end.date().next_day().map(|d| d.midnight().assume_utc()).unwrap_or(end);
+            push_line(ics, &format!("DTEND;VALUE=DATE:{}", format_date(end)));
+        }
+    } else {
+        push_line(ics, &format!("DTSTART:{}", format_utc(start)));
+        if let Some(end) = end {
+            push_line(ics, &format!("DTEND:{}", format_utc(end)));
+        }
+    }
+    let title = page.meta.title.as_deref().unwrap_or(&page.slug);
+    push_line(ics, &format!("SUMMARY:{}", escape_text(title)));
+    if let Some(ref description) = page.meta.description {
+        push_line(ics, &format!("DESCRIPTION:{}", escape_text(description)));
+    }
+    if let Some(location) = page.meta.extra.get("location").and_then(|l| l.as_str()) {
+        push_line(ics, &format!("LOCATION:{}", escape_text(location)));
+    }
+    push_line(ics, &format!("URL:{}", page.permalink));
+    push_line(ics, "END:VEVENT");
+    Ok(())
+}
+
+/// Renders `events.ics` with the dated pages of the sections in `calendar.sections`,
+/// including the pages of their subsections
+pub fn render_calendar(site: &Site) -> Result<String> {
+    let library = site.library.read().unwrap();
+    let section_files: Vec<String> = site
+        .config
+        .calendar
+        .sections
+        .iter()
+        .map(|s| {
+            let s = s.trim_matches('/');
+            if s.is_empty() {
+                "_index.md".to_string()
+            } else {
+                format!("{}/_index.md", s)
+            }
+        })
+        .collect();
+
+    let mut pages: Vec<&Page> = library
+        .pages
+        .values()
+        .filter(|p| {
+            p.meta.datetime.is_some()
+                && !p.is_draft_preview()
+                && p.meta.password.is_none()
+                && p.ancestors.iter().any(|a| section_files.contains(a))
+        })
+        .collect();
+    pages.sort_by(|a, b| a.meta.datetime.cmp(&b.meta.datetime).then(a.permalink.cmp(&b.permalink)));
+
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//Zola//Zola//EN");
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    if let Some(ref title) = site.config.title {
+        push_line(&mut ics, &format!("X-WR-CALNAME:{}", escape_text(title)));
+    }
+    for page in pages {
+        push_event(&mut ics, page)?;
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+    Ok(ics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_escape_text() {
+        assert_eq!(
+            escape_text("Rust, Zola; and\\more\nlater"),
+            "Rust\\, Zola\\; and\\\\more\\nlater"
+        );
+    }
+
+    #[test]
+    fn can_fold_long_lines() {
+        let mut ics = String::new();
+        push_line(&mut ics, &format!("SUMMARY:{}", "é".repeat(40)));
+        let lines: Vec<_> = ics.split("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|l| l.len() <= MAX_LINE_LENGTH));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(lines[2], "");
+        assert_eq!(ics.replace("\r\n ", ""), format!("SUMMARY:{}\r\n", "é".repeat(40)));
+    }
+
+    #[test]
+    fn can_format_dates() {
+        let datetime = parse_datetime("2022-06-01T18:30:00+02:00").unwrap();
+        assert_eq!(format_utc(datetime), "20220601T163000Z");
+        assert_eq!(format_date(datetime), "20220601");
+        assert!(is_date_only("2022-06-01"));
+        assert!(!is_date_only("2022-06-01T18:30:00"));
+    }
+}
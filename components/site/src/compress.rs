@@ -0,0 +1,63 @@
+use std::io::Write;
+use std::path::Path;
+
+use libs::brotli::CompressorWriter;
+use libs::flate2::write::GzEncoder;
+use libs::flate2::Compression;
+use libs::rayon::prelude::*;
+use libs::walkdir::WalkDir;
+
+use errors::{Context, Result};
+
+/// The extensions of the files that get precompressed variants
+const COMPRESSED_EXTENSIONS: &[&str] = &["html", "css", "js", "xml"];
+
+/// Writes a gzip (`.gz`) and a brotli (`.br`) compressed copy next to every HTML, CSS, JS
+/// and XML file of the output directory, for servers able to serve precompressed files.
+pub fn compress_output(output_path: &Path) -> Result<()> {
+    let files: Vec<_> = WalkDir::new(output_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .filter(|p| {
+            p.extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| COMPRESSED_EXTENSIONS.contains(&ext))
+                .unwrap_or(false)
+        })
+        .collect();
+
+    files.par_iter().try_for_each(|path| compress_file(path))
+}
+
+fn compress_file(path: &Path) -> Result<()> {
+    let content = std::fs::read(path)
+        .with_context(|| format!("Failed to read {} to compress it", path.display()))?;
+
+    // Compressing in memory means any error writing the files is reported, which isn't the case
+    // when the encoders are finalized on drop
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&content)?;
+    write(&with_added_extension(path, "gz"), &encoder.finish()?)?;
+
+    // Quality 11 and a 4MiB window are the values recommended for static content
+    let mut writer = CompressorWriter::new(Vec::new(), 4096, 11, 22);
+    writer.write_all(&content)?;
+    // `into_inner` finishes the stream
+    write(&with_added_extension(path, "br"), &writer.into_inner())?;
+
+    Ok(())
+}
+
+fn write(path: &Path, content: &[u8]) -> Result<()> {
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// `index.html` -> `index.html.gz`
+fn with_added_extension(path: &Path, extension: &str) -> std::path::PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+    name.into()
+}
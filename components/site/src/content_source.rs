@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use libs::walkdir::WalkDir;
+
+use config::Config;
+use errors::{anyhow, Result};
+use utils::fs::{check_symlink_loop, is_unfollowed_symlink, read_file};
+
+/// Where the Markdown files of the pages and sections of a site come from.
+/// The paths are relative to the `content` directory of the site since they still give the
+/// URLs of the pages and sections, whatever the source is.
+pub trait ContentSource: fmt::Debug + Send + Sync {
+    /// The files of the content. Only the Markdown ones are loaded, the `_index.md` ones as
+    /// sections and the others as pages.
+    fn list(&self, content_path: &Path, config: &Config) -> Result<Vec<PathBuf>>;
+
+    /// The content of one of the listed files
+    fn read(&self, content_path: &Path, path: &Path) -> Result<String>;
+
+    /// Whether the files are in the `content` directory on disk, with the assets of the pages
+    /// and sections next to them
+    fn is_on_disk(&self) -> bool {
+        false
+    }
+}
+
+/// The `content` directory of the site, the default source
+#[derive(Debug, Default)]
+pub struct FileSystemSource;
+
+impl ContentSource for FileSystemSource {
+    fn list(&self, content_path: &Path, config: &Config) -> Result<Vec<PathBuf>> {
+        let follow_symlinks = config.follow_symlinks;
+        let mut files = Vec::new();
+        for entry in WalkDir::new(content_path).follow_links(follow_symlinks) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    check_symlink_loop(&err)?;
+                    continue;
+                }
+            };
+            if is_unfollowed_symlink(&entry, follow_symlinks) || entry.path().is_dir() {
+                continue;
+            }
+            files.push(entry.path().strip_prefix(content_path).unwrap().to_path_buf());
+        }
+        Ok(files)
+    }
+
+    fn read(&self, content_path: &Path, path: &Path) -> Result<String> {
+        read_file(&content_path.join(path))
+    }
+
+    fn is_on_disk(&self) -> bool {
+        true
+    }
+}
+
+/// Files kept in memory, by path relative to the `content` directory, eg for tests or content
+/// fetched from elsewhere before the build
+#[derive(Debug, Default)]
+pub struct MemorySource {
+    files: BTreeMap<PathBuf, String>,
+}
+
+impl MemorySource {
+    pub fn new<P: Into<PathBuf>, C: Into<String>>(files: impl IntoIterator<Item = (P, C)>) -> Self {
+        Self { files: files.into_iter().map(|(p, c)| (p.into(), c.into())).collect() }
+    }
+}
+
+impl ContentSource for MemorySource {
+    fn list(&self, _: &Path, _: &Config) -> Result<Vec<PathBuf>> {
+        Ok(self.files.keys().cloned().collect())
+    }
+
+    fn read(&self, _: &Path, path: &Path) -> Result<String> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| anyhow!("{} is not in the content source", path.display()))
+    }
+}
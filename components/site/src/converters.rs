@@ -0,0 +1,625 @@
+//! Converts the pages written in Org-mode, reStructuredText or any format an external command
+//! can turn into Markdown, so they go through the same pipeline as the Markdown pages.
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use libs::once_cell::sync::Lazy;
+use libs::regex::{Captures, Regex};
+use libs::toml;
+
+use config::{Config, Converter, ConverterOutput};
+use errors::{bail, Context, Result};
+
+static DATE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\d{4}-\d{2}-\d{2}").unwrap());
+
+static ORG_KEYWORD_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^#\+([A-Za-z_]+):\s*(.*?)\s*$").unwrap());
+static ORG_HEADING_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\*+)\s+(.*?)(?:\s+:[\w@:]+:)?\s*$").unwrap());
+static ORG_CODE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(^|[\s(])[=~]([^\s=~](?:[^=~]*[^\s=~])?)[=~]").unwrap());
+static ORG_LINK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[\[([^\]]+)\](?:\[([^\]]+)\])?\]").unwrap());
+static ORG_BOLD_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(^|[\s(])\*([^\s*](?:[^*]*[^\s*])?)\*([\s.,;:!?)]|$)").unwrap());
+static ORG_ITALIC_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(^|[\s(])/([^\s/](?:[^/]*[^\s/])?)/([\s.,;:!?)]|$)").unwrap());
+
+static RST_FIELD_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^:(date|description|slug):\s*(.*?)\s*$").unwrap());
+static RST_CODE_DIRECTIVE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\.\.\s+(?:code-block|code|sourcecode)::\s*(\S*)").unwrap());
+static RST_IMAGE_DIRECTIVE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\.\.\s+(?:image|figure)::\s*(\S+)").unwrap());
+static RST_CODE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"``(.+?)``|:[a-z-]+:`([^`]+)`").unwrap());
+static RST_LINK_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"`([^`<]+?)\s*<([^>`]+)>`__?").unwrap());
+static RST_ENUMERATED_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\s*)#\.\s").unwrap());
+
+/// The characters reStructuredText headings can be underlined with
+const RST_ADORNMENTS: &str = "=-~^\"'`#*+:._";
+
+/// Splits the TOML or YAML front matter, with its delimiters, from the rest of the file.
+/// The front matter is empty if there is none.
+fn split_front_matter(content: &str) -> (&str, &str) {
+    let start = content.len() - content.trim_start().len();
+    for delimiter in ["+++", "---"] {
+        let rest = match content[start..].strip_prefix(delimiter) {
+            Some(rest) if rest.starts_with('\n') => rest,
+            _ => continue,
+        };
+        let closing = format!("\n{}", delimiter);
+        if let Some(end) = rest.find(&closing) {
+            let end = content.len() - rest.len() + end + closing.len();
+            return (&content[..end], &content[end..]);
+        }
+    }
+    ("", content)
+}
+
+/// The TOML front matter for the fields found in the keywords of a document
+fn make_front_matter(fields: &[(String, String)]) -> String {
+    let mut front_matter = String::from("+++\n");
+    for (key, value) in fields {
+        // Only the dates Zola understands are kept, eg not the weekday of the Org timestamps
+        if key == "date" {
+            if let Some(date) = DATE_RE.find(value) {
+                front_matter.push_str(&format!("date = {}\n", date.as_str()));
+            }
+        } else {
+            front_matter.push_str(&format!("{} = {}\n", key, toml::Value::String(value.clone())));
+        }
+    }
+    front_matter.push_str("+++\n");
+    front_matter
+}
+
+/// Converts the inline markup outside of the code spans, which are found first since their
+/// content is left as it is
+fn convert_inline(
+    line: &str,
+    code_re: &Regex,
+    code: impl Fn(&Captures) -> String,
+    convert: impl Fn(&str) -> String,
+) -> String {
+    let mut res = String::with_capacity(line.len());
+    let mut last = 0;
+    for caps in code_re.captures_iter(line) {
+        let m = caps.get(0).unwrap();
+        res.push_str(&convert(&line[last..m.start()]));
+        res.push_str(&code(&caps));
+        last = m.end();
+    }
+    res.push_str(&convert(&line[last..]));
+    res
+}
+
+fn org_inline(line: &str) -> String {
+    convert_inline(
+        line,
+        &ORG_CODE_RE,
+        |caps| format!("{}`{}`", &caps[1], &caps[2]),
+        |text| {
+            let text = ORG_LINK_RE.replace_all(text, |caps: &Captures| match caps.get(2) {
+                Some(description) => format!("[{}]({})", description.as_str(), &caps[1]),
+                None => format!("<{}>", &caps[1]),
+            });
+            // Adjacent markup shares the whitespace between them so a second pass is needed
+            let mut text = text.into_owned();
+            for _ in 0..2 {
+                text = ORG_BOLD_RE.replace_all(&text, "$1**$2**$3").into_owned();
+            }
+            for _ in 0..2 {
+                text = ORG_ITALIC_RE.replace_all(&text, "$1*$2*$3").into_owned();
+            }
+            text
+        },
+    )
+}
+
+/// A minimal Org-mode converter: headings, emphasis, code, links, lists, quotes and source
+/// blocks. The `TITLE`, `DATE` and `DESCRIPTION` keywords become the front matter if the file
+/// doesn't have one.
+pub fn convert_org(content: &str) -> String {
+    let (front_matter, body) = split_front_matter(content);
+    let mut fields = Vec::new();
+    let mut out = String::new();
+    // The kind of the block we are in: `src`, `example` or `quote`
+    let mut block: Option<&str> = None;
+
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        let lowercase = trimmed.to_lowercase();
+
+        if let Some(kind) = block {
+            if lowercase.starts_with("#+end_") {
+                if kind != "quote" {
+                    out.push_str("```\n");
+                }
+                block = None;
+            } else if kind == "quote" {
+                out.push_str(&format!("> {}\n", org_inline(trimmed)));
+            } else {
+                out.push_str(line);
+                out.push('\n');
+            }
+            continue;
+        }
+
+        if let Some(params) = lowercase.strip_prefix("#+begin_src") {
+            let language = params.split_whitespace().next().unwrap_or_default();
+            out.push_str(&format!("```{}\n", language));
+            block = Some("src");
+        } else if lowercase.starts_with("#+begin_example") {
+            out.push_str("```\n");
+            block = Some("example");
+        } else if lowercase.starts_with("#+begin_quote") {
+            block = Some("quote");
+        } else if let Some(caps) = ORG_KEYWORD_RE.captures(trimmed) {
+            let key = caps[1].to_lowercase();
+            if matches!(key.as_str(), "title" | "date" | "description") {
+                fields.push((key, caps[2].to_string()));
+            }
+        } else if trimmed == "#" || trimmed.starts_with("# ") {
+            // A comment
+        } else if let Some(caps) = ORG_HEADING_RE.captures(line) {
+            out.push_str(&format!("{} {}\n", "#".repeat(caps[1].len()), org_inline(&caps[2])));
+        } else if let Some(item) = trimmed.strip_prefix("+ ") {
+            let indent = &line[..line.len() - trimmed.len()];
+            out.push_str(&format!("{}- {}\n", indent, org_inline(item)));
+        } else {
+            out.push_str(&org_inline(line));
+            out.push('\n');
+        }
+    }
+    if block.map_or(false, |kind| kind != "quote") {
+        out.push_str("```\n");
+    }
+
+    if front_matter.is_empty() {
+        format!("{}{}", make_front_matter(&fields), out)
+    } else {
+        format!("{}\n{}", front_matter, out)
+    }
+}
+
+fn rst_inline(line: &str) -> String {
+    convert_inline(
+        line,
+        &RST_CODE_RE,
+        |caps| format!("`{}`", caps.get(1).or_else(|| caps.get(2)).unwrap().as_str()),
+        |text| RST_LINK_RE.replace_all(text, "[$1]($2)").into_owned(),
+    )
+}
+
+/// The adornment character of a line underlining or overlining a heading
+fn rst_adornment(line: &str) -> Option<char> {
+    let line = line.trim_end();
+    let c = line.chars().next()?;
+    let is_adornment =
+        line.len() >= 2 && RST_ADORNMENTS.contains(c) && line.chars().all(|other| other == c);
+    if is_adornment {
+        Some(c)
+    } else {
+        None
+    }
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Takes the lines indented more than `indent` from `start`, as well as the blank lines
+/// between them, and returns them without that indentation along with the index of the first
+/// line after them
+fn rst_indented_block(lines: &[&str], start: usize, indent: usize) -> (Vec<String>, usize) {
+    let mut end = start;
+    let mut i = start;
+    while i < lines.len() && (lines[i].trim().is_empty() || indentation(lines[i]) > indent) {
+        i += 1;
+        // The blank lines after the block are not part of it
+        if !lines[i - 1].trim().is_empty() {
+            end = i;
+        }
+    }
+    let block = &lines[start..end];
+    let dedent = block.iter().filter(|l| !l.trim().is_empty()).map(|l| indentation(l)).min();
+    let mut res: Vec<String> =
+        block.iter().map(|l| l.get(dedent.unwrap_or(0)..).unwrap_or("").to_string()).collect();
+    while res.first().map_or(false, |l| l.is_empty()) {
+        res.remove(0);
+    }
+    (res, end)
+}
+
+/// A minimal reStructuredText converter: headings, emphasis, code, links, lists, quotes,
+/// literal blocks and the `code-block` and `image` directives, the other directives being
+/// left out. The first heading and the `date` and `description` fields become the front
+/// matter if the file doesn't have one.
+pub fn convert_rst(content: &str) -> String {
+    let (front_matter, body) = split_front_matter(content);
+    let lines: Vec<&str> = body.lines().collect();
+    let mut fields = Vec::new();
+    let mut out = String::new();
+    // The adornments of the headings, by level
+    let mut levels: Vec<(char, bool)> = Vec::new();
+    let mut title_taken = false;
+    let mut seen_text = false;
+    let mut in_list = false;
+    let mut literal_next = false;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() {
+            out.push('\n');
+            i += 1;
+            continue;
+        }
+
+        // A heading, with or without an overline
+        let overline = rst_adornment(line);
+        let (heading, next) = match overline {
+            Some(c) if i + 2 < lines.len() && rst_adornment(lines[i + 2]) == Some(c) => {
+                (Some((lines[i + 1].trim(), (c, true))), i + 3)
+            }
+            _ => match lines.get(i + 1).and_then(|l| rst_adornment(l)) {
+                Some(c) if indentation(line) == 0 && lines[i + 1].trim().len() >= trimmed.len() => {
+                    (Some((trimmed, (c, false))), i + 2)
+                }
+                _ => (None, i),
+            },
+        };
+        if let Some((text, adornment)) = heading {
+            let level = match levels.iter().position(|l| *l == adornment) {
+                Some(position) => position + 1,
+                None => {
+                    levels.push(adornment);
+                    levels.len()
+                }
+            };
+            if front_matter.is_empty() && !title_taken && !seen_text {
+                fields.push(("title".to_string(), text.to_string()));
+                title_taken = true;
+            } else {
+                let level = if title_taken { (level - 1).max(1) } else { level };
+                out.push_str(&format!("{} {}\n", "#".repeat(level), rst_inline(text)));
+            }
+            in_list = false;
+            i = next;
+            continue;
+        }
+
+        let indent = indentation(line);
+        if literal_next && indent > 0 {
+            let (block, end) = rst_indented_block(&lines, i, 0);
+            out.push_str(&format!("```\n{}\n```\n", block.join("\n")));
+            literal_next = false;
+            i = end;
+            continue;
+        }
+        literal_next = false;
+
+        if let Some(caps) = RST_FIELD_RE.captures(trimmed).filter(|_| !seen_text) {
+            fields.push((caps[1].to_string(), caps[2].to_string()));
+            i += 1;
+            continue;
+        }
+
+        if trimmed.starts_with("..") {
+            let (block, end) = rst_indented_block(&lines, i + 1, indent);
+            if let Some(caps) = RST_CODE_DIRECTIVE_RE.captures(trimmed) {
+                let code: Vec<_> = block.into_iter().skip_while(|l| l.starts_with(':')).collect();
+                let code: Vec<_> = code.into_iter().skip_while(|l| l.is_empty()).collect();
+                out.push_str(&format!("```{}\n{}\n```\n", &caps[1], code.join("\n")));
+            } else if let Some(caps) = RST_IMAGE_DIRECTIVE_RE.captures(trimmed) {
+                let alt = block
+                    .iter()
+                    .find_map(|l| l.strip_prefix(":alt:"))
+                    .map(|alt| alt.trim())
+                    .unwrap_or_default();
+                out.push_str(&format!("![{}]({})\n", alt, &caps[1]));
+            }
+            seen_text = true;
+            i = end;
+            continue;
+        }
+
+        seen_text = true;
+        let is_item = ["* ", "- ", "+ "].iter().any(|b| trimmed.starts_with(b))
+            || RST_ENUMERATED_RE.is_match(line)
+            || trimmed.split_once(". ").map_or(false, |(n, _)| n.parse::<u32>().is_ok());
+        if is_item {
+            in_list = true;
+        } else if indent == 0 {
+            in_list = false;
+        }
+
+        // A paragraph ending with `::` is followed by a literal block
+        let mut text = line.to_string();
+        if let Some(paragraph) = trimmed.strip_suffix("::") {
+            literal_next = true;
+            if paragraph.trim().is_empty() {
+                i += 1;
+                continue;
+            }
+            text = format!("{}{}:", &line[..indent], paragraph.trim_end());
+        }
+
+        let converted = rst_inline(&RST_ENUMERATED_RE.replace(&text, "${1}1. "));
+        if indent > 0 && !in_list {
+            // An indented paragraph is a quote, it would be code in Markdown
+            out.push_str(&format!("> {}\n", converted.trim_start()));
+        } else {
+            out.push_str(&converted);
+            out.push('\n');
+        }
+        i += 1;
+    }
+
+    if front_matter.is_empty() {
+        format!("{}{}", make_front_matter(&fields), out)
+    } else {
+        format!("{}\n{}", front_matter, out)
+    }
+}
+
+/// Runs a command with `input` on its standard input, from the directory of the site, and
+/// returns its standard output. `action` says what it was run for in the errors, eg
+/// `convert content/a.org`.
+/// Also used to run the plugins.
+pub(crate) fn run_command(
+    command: &[String],
+    input: &str,
+    base_path: &Path,
+    action: &str,
+) -> Result<String> {
+    let display = command.join(" ");
+    let mut child = Command::new(&command[0])
+        .args(&command[1..])
+        .current_dir(base_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run `{}` to {}", display, action))?;
+
+    // Written from another thread so a command writing a lot before reading all of its input
+    // doesn't block
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to run `{}` to {}", display, action))?;
+    // The command can exit without reading all of its input, only its exit status matters
+    let _ = writer.join();
+
+    if !output.status.success() {
+        bail!(
+            "`{}` failed to {}: {}",
+            display,
+            action,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    String::from_utf8(output.stdout)
+        .with_context(|| format!("`{}` didn't output UTF-8 to {}", display, action))
+}
+
+/// The converter of a file, if it is not a Markdown file and its extension has one in the config
+pub fn find_converter<'c>(config: &'c Config, path: &Path) -> Option<&'c Converter> {
+    let extension = path.extension()?.to_str()?;
+    config.converters.get(extension)
+}
+
+/// Whether the converter of a file outputs HTML, to use as the content of its page as it is
+pub fn outputs_html(config: &Config, path: &Path) -> bool {
+    find_converter(config, path).map_or(false, |c| c.output == ConverterOutput::Html)
+}
+
+/// Converts a page to Markdown, or HTML if its converter outputs it, with the converter of its
+/// extension, the content of the Markdown files being returned as it is.
+/// The front matter of a file given to a command is kept as it is and only the rest is
+/// converted. A file without one needs the command to output it.
+pub fn convert(config: &Config, base_path: &Path, path: &Path, content: String) -> Result<String> {
+    let converter = match find_converter(config, path) {
+        Some(converter) => converter,
+        None => return Ok(content),
+    };
+    let content = content.replace("\r\n", "\n");
+    if converter.command.is_empty() {
+        return Ok(match path.extension().and_then(|e| e.to_str()) {
+            Some("org") => convert_org(&content),
+            _ => convert_rst(&content),
+        });
+    }
+
+    let (front_matter, body) = split_front_matter(&content);
+    let action = format!("convert {}", path.display());
+    let converted = run_command(&converter.command, body, base_path, &action)?;
+    if front_matter.is_empty() {
+        Ok(converted)
+    } else {
+        Ok(format!("{}\n{}", front_matter, converted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_split_front_matter() {
+        assert_eq!(
+            split_front_matter("+++\ntitle = \"Hello\"\n+++\nBody"),
+            ("+++\ntitle = \"Hello\"\n+++", "\nBody")
+        );
+        assert_eq!(
+            split_front_matter("---\ntitle: Hello\n---\n"),
+            ("---\ntitle: Hello\n---", "\n")
+        );
+        assert_eq!(split_front_matter("* Heading\n"), ("", "* Heading\n"));
+        // A heading with an overline is not a front matter
+        assert_eq!(split_front_matter("-----\nTitle\n-----\n"), ("", "-----\nTitle\n-----\n"));
+    }
+
+    #[test]
+    fn can_convert_org() {
+        let org = r#"#+TITLE: Hello "world"
+#+DATE: <2022-03-04 Fri>
+#+OPTIONS: toc:nil
+
+* First heading :tag:
+Some *bold*, /italic/ and =code *not bold*= text with a [[https://example.com][link]].
+# A comment
++ one
++ two
+** Nested
+#+BEGIN_SRC rust
+fn main() {}
+#+END_SRC
+#+begin_quote
+Quoted *text*
+#+end_quote
+"#;
+        assert_eq!(
+            convert_org(org),
+            r#"+++
+title = "Hello \"world\""
+date = 2022-03-04
++++
+
+# First heading
+Some **bold**, *italic* and `code *not bold*` text with a [link](https://example.com).
+- one
+- two
+## Nested
+```rust
+fn main() {}
+```
+> Quoted **text**
+"#
+        );
+    }
+
+    #[test]
+    fn keeps_front_matter_of_org_files() {
+        let org = "+++\ntitle = \"Front matter\"\n+++\n#+TITLE: Ignored\n* Heading\n";
+        assert_eq!(convert_org(org), "+++\ntitle = \"Front matter\"\n+++\n\n# Heading\n");
+    }
+
+    #[test]
+    fn can_convert_rst() {
+        let rst = r#"=====
+Hello
+=====
+
+:date: 2022-03-04
+:description: A page
+
+Intro with **bold**, ``code *not italic*`` and a `link <https://example.com>`_.
+
+Section
+=======
+
+* one
+* two
+
+#. first
+#. second
+
+Sub-section
+-----------
+
+    A quote.
+
+Some code::
+
+    let a = 1;
+
+.. code-block:: rust
+   :linenos:
+
+   fn main() {}
+
+.. note:: Left out
+
+.. image:: /img.png
+   :alt: An image
+"#;
+        assert_eq!(
+            convert_rst(rst),
+            r#"+++
+title = "Hello"
+date = 2022-03-04
+description = "A page"
++++
+
+
+Intro with **bold**, `code *not italic*` and a [link](https://example.com).
+
+# Section
+
+* one
+* two
+
+1. first
+1. second
+
+## Sub-section
+
+> A quote.
+
+Some code:
+
+```
+let a = 1;
+```
+
+```rust
+fn main() {}
+```
+
+
+![An image](/img.png)
+"#
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn can_convert_with_command() {
+        let mut config = Config::default();
+        config.converters.insert(
+            "txt".to_string(),
+            Converter {
+                command: vec!["tr".to_string(), "a-z".to_string(), "A-Z".to_string()],
+                ..Default::default()
+            },
+        );
+        let converted = convert(
+            &config,
+            Path::new("."),
+            Path::new("content/page.txt"),
+            "+++\ntitle = \"kept\"\n+++\nhello".to_string(),
+        )
+        .unwrap();
+        assert_eq!(converted, "+++\ntitle = \"kept\"\n+++\n\nHELLO");
+
+        // Markdown files are not converted
+        let converted =
+            convert(&config, Path::new("."), Path::new("content/page.md"), "hello".to_string())
+                .unwrap();
+        assert_eq!(converted, "hello");
+
+        config.converters.insert(
+            "bad".to_string(),
+            Converter { command: vec!["false".to_string()], ..Default::default() },
+        );
+        assert!(convert(&config, Path::new("."), Path::new("a.bad"), String::new()).is_err());
+    }
+}
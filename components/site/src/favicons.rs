@@ -0,0 +1,126 @@
+//! Generates the icons of `[favicons]` and the `site.webmanifest` listing them from a single image
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Mutex;
+
+use libs::image::codecs::ico::{IcoEncoder, IcoFrame};
+use libs::image::imageops::FilterType;
+use libs::image::{self, ColorType, DynamicImage, ImageOutputFormat, RgbaImage};
+use libs::once_cell::sync::Lazy;
+use libs::serde_json::{self, json};
+use libs::sha2::{Digest, Sha256};
+
+use config::{Config, Favicons};
+use errors::{Context, Result};
+
+/// The generated icons, by filename
+type Icons = Vec<(&'static str, Vec<u8>)>;
+/// The hash of the source image and the icons generated from it
+type HashedIcons = (Vec<u8>, Icons);
+
+/// The icons generated by the last build, with the hash of their source, so `zola serve` only
+/// generates them again when the source image changes
+static GENERATED_ICONS: Lazy<Mutex<Option<HashedIcons>>> = Lazy::new(|| Mutex::new(None));
+
+/// A square icon, cropping the source image if it isn't square
+fn resize(image: &DynamicImage, size: u32) -> RgbaImage {
+    image.resize_to_fill(size, size, FilterType::Lanczos3).to_rgba8()
+}
+
+/// The PNG icons and `favicon.ico` generated from the source image, by filename.
+/// They are only generated again if the path or the content of the source changed.
+pub fn generate_icons(source: &Path) -> Result<Icons> {
+    let content = std::fs::read(source)
+        .with_context(|| format!("Failed to read the favicon source `{}`", source.display()))?;
+    let mut hasher = Sha256::new();
+    hasher.update(source.to_string_lossy().as_bytes());
+    hasher.update(&content);
+    let hash = hasher.finalize().to_vec();
+
+    let mut generated = GENERATED_ICONS.lock().unwrap();
+    if let Some((ref generated_hash, ref icons)) = *generated {
+        if *generated_hash == hash {
+            return Ok(icons.clone());
+        }
+    }
+
+    let image = image::load_from_memory(&content)
+        .with_context(|| format!("Failed to read the favicon source `{}`", source.display()))?;
+
+    let mut icons = Vec::with_capacity(Favicons::ICONS.len() + 1);
+    for icon in Favicons::ICONS {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgba8(resize(&image, icon.size))
+            .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+            .with_context(|| format!("Failed to generate `{}`", icon.filename))?;
+        icons.push((icon.filename, png));
+    }
+
+    let images: Vec<_> = Favicons::ICO_SIZES.iter().map(|size| resize(&image, *size)).collect();
+    let frames = images
+        .iter()
+        .map(|i| IcoFrame::as_png(i.as_raw(), i.width(), i.height(), ColorType::Rgba8))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let mut ico = Vec::new();
+    IcoEncoder::new(&mut ico).encode_images(&frames).context("Failed to generate `favicon.ico`")?;
+    icons.push(("favicon.ico", ico));
+
+    *generated = Some((hash, icons.clone()));
+    Ok(icons)
+}
+
+/// The `site.webmanifest` of the site, listing the icons that don't have a `<link>` tag
+pub fn render_webmanifest(config: &Config) -> Result<String> {
+    let favicons = &config.favicons;
+    let name = favicons.name.as_ref().or(config.title.as_ref());
+    let short_name = favicons.short_name.as_ref().or(name);
+    let icons: Vec<_> = Favicons::ICONS
+        .iter()
+        .filter(|i| i.rel.is_none())
+        .map(|i| {
+            json!({
+                "src": i.filename,
+                "sizes": format!("{0}x{0}", i.size),
+                "type": "image/png",
+            })
+        })
+        .collect();
+
+    let manifest = json!({
+        "name": name,
+        "short_name": short_name,
+        "icons": icons,
+        "start_url": config.make_permalink(""),
+        "lang": config.default_language,
+        "dir": config.languages[&config.default_language].text_direction(),
+        "theme_color": favicons.theme_color,
+        "background_color": favicons.background_color,
+        "display": favicons.display,
+    });
+    Ok(serde_json::to_string_pretty(&manifest)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_render_webmanifest() {
+        let mut config = Config::default_for_test();
+        config.title = Some("My site".to_string());
+        config.favicons.short_name = Some("Site".to_string());
+        config.favicons.theme_color = "#663399".to_string();
+        config.languages.get_mut("en").unwrap().rtl = true;
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&render_webmanifest(&config).unwrap()).unwrap();
+        assert_eq!(manifest["name"], "My site");
+        assert_eq!(manifest["short_name"], "Site");
+        assert_eq!(manifest["theme_color"], "#663399");
+        assert_eq!(manifest["start_url"], "http://a-website.com/");
+        assert_eq!(manifest["lang"], "en");
+        assert_eq!(manifest["dir"], "rtl");
+        assert_eq!(manifest["icons"][0]["src"], "android-chrome-192x192.png");
+        assert_eq!(manifest["icons"][1]["sizes"], "512x512");
+    }
+}
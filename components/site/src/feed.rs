@@ -5,7 +5,7 @@ use libs::rayon::prelude::*;
 use libs::tera::Context;
 use serde::Serialize;
 
-use crate::Site;
+use crate::{insert_current_path, Site};
 use content::{Page, TaxonomyTerm};
 use errors::Result;
 use utils::templates::render_template;
@@ -32,9 +32,10 @@ pub fn render_feed(
     all_pages: Vec<&Page>,
     lang: &str,
     base_path: Option<&PathBuf>,
+    template: Option<&str>,
     additional_context_fn: impl Fn(Context) -> Context,
 ) -> Result<Option<String>> {
-    let mut pages = all_pages.into_iter().filter(|p| p.meta.date.is_some()).collect::<Vec<_>>();
+    let mut pages = all_pages.into_iter().filter(|p| p.is_in_feed()).collect::<Vec<_>>();
 
     // Don't generate a feed if none of the pages has a date
     if pages.is_empty() {
@@ -72,19 +73,116 @@ pub fn render_feed(
     context.insert("pages", &p);
     context.insert("config", &site.config.serialize(lang));
     context.insert("lang", lang);
+    context.insert("dir", site.config.languages[lang].text_direction());
 
     let feed_filename = &site.config.feed_filename;
-    let feed_url = if let Some(base) = base_path {
-        site.config.make_permalink(&base.join(feed_filename).to_string_lossy().replace('\\', "/"))
-    } else {
-        site.config.make_permalink(feed_filename)
+    let feed_path = match base_path {
+        Some(base) => base.join(feed_filename).to_string_lossy().replace('\\', "/"),
+        None => feed_filename.to_string(),
     };
+    let feed_url = site.config.make_permalink(&feed_path);
 
     context.insert("feed_url", &feed_url);
+    insert_current_path(&mut context, &site.config, &feed_path);
 
     context = additional_context_fn(context);
 
-    let feed = render_template(feed_filename, &site.tera, context, &site.config.theme)?;
+    let template = template.unwrap_or(feed_filename);
+    let feed = render_template(template, &site.tera, context, &site.config.theme)?;
 
     Ok(Some(feed))
 }
+
+/// A feed of the site as listed in `feeds.opml`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeedOutline {
+    title: String,
+    xml_url: String,
+    /// The page the feed is about
+    html_url: String,
+}
+
+/// Renders `feeds.opml` listing all the feeds of the site, so readers can subscribe to all of
+/// them at once. Returns `None` if the site doesn't have any section or taxonomy feed.
+pub fn render_feeds_opml(site: &Site) -> Result<Option<String>> {
+    let config = &site.config;
+    let library = site.library.read().unwrap();
+    let feed_filename = &config.feed_filename;
+    let mut feeds = Vec::new();
+
+    if config.generate_feed {
+        let is_multilingual = config.is_multilingual();
+        let has_entries = library.pages.values().any(|p| {
+            p.is_in_feed()
+                && !p.is_draft_preview()
+                && (!is_multilingual || p.lang == config.default_language)
+        });
+        if has_entries {
+            feeds.push(FeedOutline {
+                title: config.title.clone().unwrap_or_else(|| config.base_url.clone()),
+                xml_url: config.make_permalink(feed_filename),
+                html_url: config.make_permalink(""),
+            });
+        }
+    }
+    for (code, language) in &config.other_languages() {
+        if !language.generate_feed {
+            continue;
+        }
+        let has_entries = library
+            .pages
+            .values()
+            .any(|p| &p.lang == code && p.is_in_feed() && !p.is_draft_preview());
+        if has_entries {
+            feeds.push(FeedOutline {
+                title: language
+                    .title
+                    .clone()
+                    .or_else(|| config.title.clone())
+                    .unwrap_or_else(|| config.base_url.clone()),
+                xml_url: config.make_permalink(&format!("{}/{}", code, feed_filename)),
+                html_url: config.make_permalink(code),
+            });
+        }
+    }
+    let site_feeds = feeds.len();
+
+    let mut sections = library
+        .sections
+        .values()
+        .filter(|s| s.meta.generate_feed && s.pages.iter().any(|p| library.pages[p].is_in_feed()))
+        .collect::<Vec<_>>();
+    sections.sort_by(|a, b| a.permalink.cmp(&b.permalink));
+    for section in sections {
+        feeds.push(FeedOutline {
+            title: section.meta.title.clone().unwrap_or_else(|| section.path.clone()),
+            xml_url: format!("{}{}", section.permalink, feed_filename),
+            html_url: section.permalink.clone(),
+        });
+    }
+
+    for taxonomy in site.taxonomies.iter().filter(|t| t.kind.feed) {
+        for item in &taxonomy.items {
+            if !item.pages.iter().any(|p| library.pages[p].is_in_feed()) {
+                continue;
+            }
+            feeds.push(FeedOutline {
+                title: format!("{}: {}", taxonomy.kind.name, item.name),
+                xml_url: format!("{}{}", item.permalink, feed_filename),
+                html_url: item.permalink.clone(),
+            });
+        }
+    }
+
+    if feeds.len() == site_feeds {
+        return Ok(None);
+    }
+
+    let mut context = Context::new();
+    context.insert("config", &config.serialize(&config.default_language));
+    context.insert("lang", &config.default_language);
+    context.insert("feeds", &feeds);
+    insert_current_path(&mut context, config, "feeds.opml");
+    let opml = render_template("feeds.opml", &site.tera, context, &config.theme)?;
+    Ok(Some(opml))
+}
@@ -0,0 +1,72 @@
+use std::fs::{copy, remove_dir_all};
+use std::path::{Path, PathBuf};
+
+use libs::rayon::prelude::*;
+
+use crate::Site;
+use errors::{Context, Result};
+use utils::fs::{create_directory, create_file};
+
+/// The directory of a page or section in the Gemini output directory
+fn gemini_dir(output_path: &Path, path: &str) -> PathBuf {
+    path.split('/').filter(|c| !c.is_empty()).fold(output_path.to_path_buf(), |p, c| p.join(c))
+}
+
+fn write_gemtext(dir: &Path, content: &str) -> Result<()> {
+    create_directory(dir)?;
+    create_file(&dir.join("index.gmi"), content)
+}
+
+/// Renders the pages and sections to gemtext in `gemini.output_dir`, using the same paths as
+/// the HTML site. Redirecting and protected pages are left out.
+pub fn render_gemini(site: &Site) -> Result<()> {
+    let output_path = site.emptied_dir("gemini.output_dir", &site.config.gemini.output_dir)?;
+    if output_path.exists() {
+        remove_dir_all(&output_path).context("Couldn't delete the Gemini output directory")?;
+    }
+
+    let library = site.library.read().unwrap();
+    library
+        .pages
+        .values()
+        .filter(|p| p.has_gemtext())
+        .collect::<Vec<_>>()
+        .par_iter()
+        .map(|page| {
+            let content = page.render_gemini(
+                &site.tera,
+                &site.config,
+                &library,
+                &site.permalinks,
+                &site.shortcode_definitions,
+            )?;
+            let dir = gemini_dir(&output_path, &page.path);
+            write_gemtext(&dir, &content)?;
+            // Co-located images and files linked from the page
+            for asset in &page.assets {
+                let filename = asset.file_name().expect("Asset without a file name");
+                copy(asset, dir.join(filename))
+                    .with_context(|| format!("Failed to copy {}", asset.display()))?;
+            }
+            Ok(())
+        })
+        .collect::<Result<()>>()?;
+
+    library
+        .sections
+        .values()
+        .filter(|s| s.has_gemtext())
+        .collect::<Vec<_>>()
+        .par_iter()
+        .map(|section| {
+            let content = section.render_gemini(
+                &site.tera,
+                &site.config,
+                &library,
+                &site.permalinks,
+                &site.shortcode_definitions,
+            )?;
+            write_gemtext(&gemini_dir(&output_path, &section.path), &content)
+        })
+        .collect::<Result<()>>()
+}
@@ -0,0 +1,79 @@
+use libs::regex::Regex;
+
+/// Whether the HTML already has a `<link>` tag with that `rel`
+fn has_link(html: &str, rel: &str) -> bool {
+    let re = Regex::new(&format!(
+        r#"(?i)<link\s[^>]*rel\s*=\s*["']?{}["'\s/>]"#,
+        libs::regex::escape(rel)
+    ))
+    .unwrap();
+    re.is_match(html)
+}
+
+/// Inserts a `<link>` tag at the end of the `<head>` of the HTML, if it has one
+pub fn insert_link(mut html: String, rel: &str, href: &str) -> String {
+    if let Some(index) = html.find("</head>") {
+        html.insert_str(index, &format!(r#"<link rel="{}" href="{}">"#, rel, href));
+    }
+    html
+}
+
+/// Adds the canonical URL of a pager and the URLs of the pagers before and after it,
+/// unless the template already added them
+pub fn link_pagination(
+    mut html: String,
+    canonical: &str,
+    previous: Option<&str>,
+    next: Option<&str>,
+) -> String {
+    for (rel, href) in [("canonical", Some(canonical)), ("prev", previous), ("next", next)] {
+        if let Some(href) = href {
+            if !has_link(&html, rel) {
+                html = insert_link(html, rel, href);
+            }
+        }
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_find_existing_links() {
+        assert!(has_link(r#"<link rel="canonical" href="/">"#, "canonical"));
+        assert!(has_link("<LINK href='/' rel='prev'>", "prev"));
+        assert!(has_link("<link rel=next href=/>", "next"));
+        assert!(!has_link(r#"<link rel="canonicalish" href="/">"#, "canonical"));
+        assert!(!has_link(r#"<a rel="next" href="/">"#, "next"));
+    }
+
+    #[test]
+    fn can_link_pagination() {
+        let html = "<html><head><title>Blog</title></head></html>".to_string();
+        assert_eq!(
+            link_pagination(
+                html,
+                "https://example.com/page/2/",
+                Some("https://example.com/"),
+                None
+            ),
+            r#"<html><head><title>Blog</title><link rel="canonical" href="https://example.com/page/2/"><link rel="prev" href="https://example.com/"></head></html>"#
+        );
+
+        // The links of the template are kept
+        let html =
+            r#"<html><head><link rel="canonical" href="https://example.com/"></head></html>"#
+                .to_string();
+        assert_eq!(
+            link_pagination(
+                html,
+                "https://example.com/page/2/",
+                None,
+                Some("https://example.com/page/3/")
+            ),
+            r#"<html><head><link rel="canonical" href="https://example.com/"><link rel="next" href="https://example.com/page/3/"></head></html>"#
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use libs::once_cell::sync::Lazy;
+use libs::rayon::prelude::*;
+use libs::regex::Regex;
+
+use crate::Site;
+use content::Paginator;
+use errors::Result;
+
+/// A start or end tag, the attributes can contain `>` when quoted
+static TAG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"^<(/?)([a-zA-Z][a-zA-Z0-9:-]*)((?:"[^"]*"|'[^']*'|[^'">])*?)(/?)>"#).unwrap()
+});
+static ID_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)(?:^|\s)id\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap()
+});
+
+/// Elements that can't have content or an end tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements whose end tag can be left out
+const OPTIONAL_END_ELEMENTS: &[&str] = &[
+    "html", "head", "body", "p", "li", "dt", "dd", "tr", "td", "th", "thead", "tbody", "tfoot",
+    "option", "optgroup", "colgroup", "caption", "rt", "rp",
+];
+
+/// Elements whose content is not HTML
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+
+/// The HTML issues of a page or section, along with the template that rendered it
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HtmlIssues {
+    pub source: PathBuf,
+    pub template: String,
+    pub issues: Vec<String>,
+}
+
+fn line_of(html: &str, position: usize) -> usize {
+    html[..position].matches('\n').count() + 1
+}
+
+/// Finds the unclosed elements, the end tags without a start tag and the duplicate ids of a
+/// HTML document. This is not a full HTML5 parser: the elements whose end tag is optional,
+/// like `p` or `li`, are never reported as unclosed.
+pub fn validate_html(html: &str) -> Vec<String> {
+    let mut issues = Vec::new();
+    // The open elements with the line they were opened on
+    let mut open: Vec<(String, usize)> = Vec::new();
+    let mut ids = HashSet::new();
+    let mut position = 0;
+
+    while let Some(offset) = html[position..].find('<') {
+        position += offset;
+        let rest = &html[position..];
+        if rest.starts_with("<!--") {
+            position += rest.find("-->").map(|i| i + 3).unwrap_or(rest.len());
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            position += rest.find('>').map(|i| i + 1).unwrap_or(rest.len());
+            continue;
+        }
+        let caps = match TAG_RE.captures(rest) {
+            Some(caps) => caps,
+            None => {
+                position += 1;
+                continue;
+            }
+        };
+        let line = line_of(html, position);
+        position += caps[0].len();
+        let name = caps[2].to_lowercase();
+        let is_end = !caps[1].is_empty();
+        let is_self_closing = !caps[4].is_empty();
+
+        if is_end {
+            match open.iter().rposition(|(n, _)| *n == name) {
+                Some(index) => {
+                    for (unclosed, opened_on) in open.drain(index..).skip(1) {
+                        if !OPTIONAL_END_ELEMENTS.contains(&unclosed.as_str()) {
+                            issues.push(format!(
+                                "line {}: `<{}>` is not closed before `</{}>` on line {}",
+                                opened_on, unclosed, name, line
+                            ));
+                        }
+                    }
+                }
+                None if VOID_ELEMENTS.contains(&name.as_str()) => (),
+                None => issues.push(format!("line {}: `</{}>` has no start tag", line, name)),
+            }
+            continue;
+        }
+
+        if let Some(id) = ID_RE.captures(&caps[3]) {
+            let id = id.get(1).or_else(|| id.get(2)).or_else(|| id.get(3)).unwrap().as_str();
+            if !ids.insert(id.to_string()) {
+                issues.push(format!("line {}: duplicate id `{}`", line, id));
+            }
+        }
+
+        if is_self_closing || VOID_ELEMENTS.contains(&name.as_str()) {
+            continue;
+        }
+        if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+            let end_tag = format!("</{}", name);
+            match html[position..].to_ascii_lowercase().find(&end_tag) {
+                Some(i) => position += i,
+                None => {
+                    issues.push(format!("line {}: `<{}>` is not closed", line, name));
+                    break;
+                }
+            }
+        }
+        open.push((name, line));
+    }
+
+    for (unclosed, opened_on) in open {
+        if !OPTIONAL_END_ELEMENTS.contains(&unclosed.as_str()) {
+            issues.push(format!("line {}: `<{}>` is not closed", opened_on, unclosed));
+        }
+    }
+    issues
+}
+
+/// Renders the HTML of the pages and sections and validates it, returning the issues found with
+/// the file and the template they come from, sorted by file.
+/// Only the first pager of paginated sections is validated since they all use the same template.
+pub fn validate_site_html(site: &Site) -> Result<Vec<HtmlIssues>> {
+    let library = site.library.read().unwrap();
+    let pages = library
+        .pages
+        .values()
+        .filter(|p| p.has_html_page(&site.config))
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|page| {
+            let html = page.render_html(&site.tera, &site.config, &library)?;
+            let template = page.meta.template.clone().unwrap_or_else(|| "page.html".to_string());
+            Ok((page.file.path.clone(), template, html))
+        });
+    let sections = library
+        .sections
+        .values()
+        .filter(|s| s.meta.render && s.meta.redirect_to.is_none())
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|section| {
+            let html = if section.meta.is_paginated() {
+                let paginator = Paginator::from_section(section, &library);
+                match paginator.pagers.first() {
+                    Some(pager) => {
+                        paginator.render_pager(pager, &site.config, &site.tera, &library)?
+                    }
+                    None => String::new(),
+                }
+            } else {
+                section.render_html(&site.tera, &site.config, &library)?
+            };
+            Ok((section.file.path.clone(), section.get_template_name().to_string(), html))
+        });
+
+    let rendered = pages.chain(sections).collect::<Result<Vec<_>>>()?;
+    let mut issues: Vec<_> = rendered
+        .into_iter()
+        .filter_map(|(source, template, html)| {
+            let issues = validate_html(&html);
+            if issues.is_empty() {
+                None
+            } else {
+                Some(HtmlIssues { source, template, issues })
+            }
+        })
+        .collect();
+    issues.sort();
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_html() {
+        let html = r#"<!DOCTYPE html>
+<html><head><title>A <b> title</title><meta charset="utf-8">
+<script>if (a < b && "</div>") {}</script></head>
+<body><!-- <div> --><ul><li>One<li>Two</ul><p>Hi<br/><img src="a.png" alt="a > b">
+<svg><path d="M0" /></svg><div id="a"></div><div id='b'></div></body></html>"#;
+        assert_eq!(validate_html(html), Vec::<String>::new());
+    }
+
+    #[test]
+    fn can_find_unclosed_and_stray_tags() {
+        let html = "<div>\n<section><span>Hi</div>\n</article>\n<main>";
+        assert_eq!(
+            validate_html(html),
+            vec![
+                "line 2: `<section>` is not closed before `</div>` on line 2",
+                "line 2: `<span>` is not closed before `</div>` on line 2",
+                "line 3: `</article>` has no start tag",
+                "line 4: `<main>` is not closed",
+            ]
+        );
+    }
+
+    #[test]
+    fn can_find_duplicate_ids() {
+        let html = r#"<h2 id="intro">A</h2><p data-id="intro"></p><h2 ID=intro>B</h2>"#;
+        assert_eq!(validate_html(html), vec!["line 1: duplicate id `intro`"]);
+    }
+}
@@ -1,36 +1,69 @@
+pub mod accessibility;
+mod amp;
+mod calendar;
+mod compress;
+pub mod content_source;
+mod converters;
+mod favicons;
 pub mod feed;
+mod gemini;
+mod head;
+pub mod html_validation;
 pub mod link_checking;
+pub mod manifest;
 mod minify;
+pub mod netlify;
+mod newsletter;
+mod offline_mirror;
+mod plugins;
+mod protected;
+mod render_cache;
+pub mod report;
 pub mod sass;
 pub mod sitemap;
 pub mod tpls;
+pub mod unused;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::remove_dir_all;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
 
+use libs::log::{debug, trace};
 use libs::once_cell::sync::Lazy;
 use libs::rayon::prelude::*;
-use libs::tera::{Context, Tera};
-use libs::walkdir::{DirEntry, WalkDir};
-
-use config::{get_config, Config};
-use content::{Library, Page, Paginator, Section, Taxonomy};
+use libs::ring::rand::{SecureRandom, SystemRandom};
+use libs::serde_json;
+use libs::sha2::{Digest, Sha256};
+use libs::tera::{Context, Filter, Function, Map, Tera, Value};
+use libs::time::OffsetDateTime;
+
+use config::{get_config, Config, OutputFormat, PluginHook};
+use content::{Archive, Library, Page, Paginator, Section, Series, Taxonomy};
 use errors::{anyhow, bail, Context as ErrorContext, Result};
 use libs::relative_path::RelativePathBuf;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use templates::{load_tera, render_redirect_template};
 use utils::fs::{
     copy_directory, copy_file_if_needed, create_directory, create_file, ensure_directory_exists,
+    is_ignored_file, read_file,
 };
 use utils::net::get_available_port;
 use utils::templates::{render_template, ShortcodeDefinition};
-use utils::types::InsertAnchor;
+use utils::types::{InsertAnchor, RawHtml};
+
+pub use utils::templates::ShortcodeFileType;
 
 pub static SITE_CONTENT: Lazy<Arc<RwLock<HashMap<RelativePathBuf, String>>>> =
     Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
 
+type BinaryContent = HashMap<RelativePathBuf, Vec<u8>>;
+
+/// The binary files generated by `zola serve`, like the favicons, served from memory as well
+pub static SITE_BINARY_CONTENT: Lazy<Arc<RwLock<BinaryContent>>> =
+    Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
+
 /// Where are we building the site
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum BuildMode {
@@ -50,28 +83,73 @@ pub struct Site {
     imageproc: Arc<Mutex<imageproc::Processor>>,
     // the live reload port to be used if there is one
     pub live_reload: Option<u16>,
+    /// The path `zola serve --path-prefix` serves the site under, empty otherwise
+    serve_path_prefix: String,
     pub output_path: PathBuf,
     content_path: PathBuf,
     pub static_path: PathBuf,
     pub taxonomies: Vec<Taxonomy>,
+    /// All the series found in the pages front matter
+    pub series: Vec<Series>,
+    /// The yearly and monthly archives, only filled if `generate_archive` is enabled
+    pub archives: Vec<Archive>,
     /// A map of all .md files (section and pages) and their permalink
     /// We need that if there are relative links in the content that need to be resolved
-    pub permalinks: HashMap<String, String>,
+    /// Shared with the template functions and filters resolving internal links, there can be
+    /// tens of thousands of them on big sites. Only the map is shared: the pages, sections and
+    /// taxonomies still own a copy of their permalink and path, they are not interned since
+    /// those strings are a small part of the memory used by a build.
+    pub permalinks: Arc<HashMap<String, String>>,
     /// Contains all pages and sections of the site
     pub library: Arc<RwLock<Library>>,
     /// Whether to load draft pages
     include_drafts: bool,
+    /// Whether to rewrite the internal links of the HTML files to relative paths, set by
+    /// `enable_offline_mirror`
+    offline_mirror: bool,
     build_mode: BuildMode,
     shortcode_definitions: HashMap<String, ShortcodeDefinition>,
+    /// Set by `get_file_hash` and `get_integrity`: the rendered templates depend on the content
+    /// of some static files
+    file_hashes_used: Arc<AtomicBool>,
+    /// Only set when a build report was requested with `enable_build_report`
+    build_report: Option<report::BuildReport>,
+    /// Only set when `render_cache` is enabled in the config
+    render_cache: Option<render_cache::RenderCache>,
+    /// The directories of the output created by the current build, writing several files in
+    /// the same directory only creates it once
+    created_directories: Mutex<HashSet<PathBuf>>,
+    /// How many files of the `static` directories were copied by the last build
+    static_files_copied: AtomicUsize,
+    /// How many pagers of the paginated sections and taxonomy terms the last build rendered
+    paginated_pages_rendered: AtomicUsize,
+    /// Used for the preview tokens of drafts when `drafts.preview_secret` isn't set
+    random_preview_secret: String,
+    /// Where the Markdown files of the pages and sections are read from
+    content_source: Box<dyn content_source::ContentSource>,
+    /// The date set by `SOURCE_DATE_EPOCH`, used instead of the current one in templates
+    source_date_epoch: Option<OffsetDateTime>,
+    /// The Tera filters and functions registered by the users of the crate
+    custom_tera_fns: tpls::CustomTeraFns,
 }
 
 impl Site {
     /// Parse a site at the given path. Defaults to the current dir
     /// Passing in a path is used in tests and when --root argument is passed
     pub fn new<P: AsRef<Path>, P2: AsRef<Path>>(path: P, config_file: P2) -> Result<Site> {
+        Site::new_in_env(path, config_file, None)
+    }
+
+    /// Same as `Site::new` but with the overlay of the given environment merged in the config,
+    /// eg `config.staging.toml` for `staging`
+    pub fn new_in_env<P: AsRef<Path>, P2: AsRef<Path>>(
+        path: P,
+        config_file: P2,
+        env: Option<&str>,
+    ) -> Result<Site> {
         let path = path.as_ref();
         let config_file = config_file.as_ref();
-        let mut config = get_config(&path.join(config_file))?;
+        let mut config = get_config(&path.join(config_file), env)?;
 
         if let Some(theme) = config.theme.clone() {
             // Grab data from the extra section of the theme
@@ -85,6 +163,8 @@ impl Site {
         let static_path = path.join("static");
         let imageproc = imageproc::Processor::new(path.to_path_buf(), &config);
         let output_path = path.join(config.output_dir.clone());
+        let source_date_epoch =
+            parse_source_date_epoch(std::env::var("SOURCE_DATE_EPOCH").ok().as_deref())?;
 
         let site = Site {
             base_path: path.to_path_buf(),
@@ -92,16 +172,30 @@ impl Site {
             tera,
             imageproc: Arc::new(Mutex::new(imageproc)),
             live_reload: None,
+            serve_path_prefix: String::new(),
             output_path,
             content_path,
             static_path,
             taxonomies: Vec::new(),
-            permalinks: HashMap::new(),
+            series: Vec::new(),
+            archives: Vec::new(),
+            permalinks: Arc::new(HashMap::new()),
             include_drafts: false,
+            offline_mirror: false,
             // We will allocate it properly later on
             library: Arc::new(RwLock::new(Library::default())),
             build_mode: BuildMode::Disk,
             shortcode_definitions,
+            file_hashes_used: Arc::new(AtomicBool::new(false)),
+            build_report: None,
+            render_cache: None,
+            created_directories: Mutex::new(HashSet::new()),
+            static_files_copied: AtomicUsize::new(0),
+            paginated_pages_rendered: AtomicUsize::new(0),
+            random_preview_secret: random_secret()?,
+            content_source: Box::new(content_source::FileSystemSource),
+            source_date_epoch,
+            custom_tera_fns: tpls::CustomTeraFns::default(),
         };
 
         Ok(site)
@@ -110,16 +204,62 @@ impl Site {
     /// Enable some `zola serve` related options
     pub fn enable_serve_mode(&mut self) {
         SITE_CONTENT.write().unwrap().clear();
+        SITE_BINARY_CONTENT.write().unwrap().clear();
         self.config.enable_serve_mode();
         self.build_mode = BuildMode::Memory;
     }
 
+    /// Set the path the site is served under in `zola serve`, eg `/preview`, so the live
+    /// reload script can be found behind a proxy
+    pub fn set_serve_path_prefix(&mut self, path_prefix: &str) {
+        self.serve_path_prefix = path_prefix.to_string();
+    }
+
+    /// Set where the pages and sections are read from instead of the `content` directory.
+    /// Needs to be called before loading the site
+    pub fn set_content_source(&mut self, source: impl content_source::ContentSource + 'static) {
+        self.content_source = Box::new(source);
+    }
+
+    /// Adds a shortcode written in Rust, eg for embeds too slow to compute in a template.
+    /// It is used instead of a template shortcode of the same name and needs to be registered
+    /// before loading the site.
+    /// `render` is given the arguments of the call and the context of a template shortcode,
+    /// and returns the HTML or Markdown to insert depending on `file_type`.
+    pub fn register_shortcode(
+        &mut self,
+        name: &str,
+        file_type: ShortcodeFileType,
+        render: impl Fn(&Map<String, Value>, &Context) -> Result<String> + Send + Sync + 'static,
+    ) {
+        self.shortcode_definitions
+            .insert(name.to_string(), ShortcodeDefinition::native(name, file_type, render));
+    }
+
+    /// Adds a Tera filter to the templates, replacing the built-in one of the same name if
+    /// there is one. Needs to be called before loading the site to be usable in shortcodes.
+    pub fn register_filter(&mut self, name: &str, filter: impl Filter + 'static) {
+        self.custom_tera_fns.filters.push((name.to_string(), Arc::new(filter)));
+    }
+
+    /// Adds a Tera function to the templates, replacing the built-in one of the same name if
+    /// there is one. Needs to be called before loading the site to be usable in shortcodes.
+    pub fn register_function(&mut self, name: &str, function: impl Function + 'static) {
+        self.custom_tera_fns.functions.push((name.to_string(), Arc::new(function)));
+    }
+
     /// Set the site to load the drafts.
     /// Needs to be called before loading it
     pub fn include_drafts(&mut self) {
         self.include_drafts = true;
     }
 
+    /// Rewrite the internal links of the HTML files to relative paths ending with `index.html`,
+    /// so the output can be browsed from the filesystem, eg on an offline machine
+    pub fn enable_offline_mirror(&mut self) {
+        self.offline_mirror = true;
+    }
+
     /// The index sections are ALWAYS at those paths
     /// There are one index section for the default language + 1 per language
     fn index_section_paths(&self) -> Vec<(PathBuf, Option<&str>)> {
@@ -145,10 +285,107 @@ impl Site {
     /// Reloads the templates and rebuild the site without re-markdown the Markdown.
     pub fn reload_templates(&mut self) -> Result<()> {
         self.tera.full_reload()?;
+        self.load_render_cache()?;
         // TODO: be smarter than that, no need to recompile sass for example
         self.build()
     }
 
+    /// Parses again a template of the `templates` directory that changed and only renders the
+    /// pages and sections using it, directly or not. The whole site is rebuilt with
+    /// `reload_templates` if other outputs use it or if the template was added or removed.
+    pub fn reload_template(&mut self, path: &Path) -> Result<()> {
+        let name = match path.strip_prefix(self.base_path.join("templates")) {
+            Ok(relative) if path.is_file() => relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            _ => return self.reload_templates(),
+        };
+        if !self.tera.templates.contains_key(&name) {
+            return self.reload_templates();
+        }
+        self.tera.add_template_file(path, Some(&name))?;
+        self.load_render_cache()?;
+
+        let using = unused::find_templates_using(&self.tera, &name, self.config.theme.as_deref());
+        let library = self.library.read().unwrap();
+        let pages: Vec<_> =
+            library.pages.values().filter(|p| using.contains(p.template_name())).collect();
+        let sections: Vec<_> = library
+            .sections
+            .values()
+            .filter(|s| s.meta.render && using.contains(s.get_template_name()))
+            .collect();
+        let rendered_by_pages_or_sections = |template: &String| {
+            pages.iter().any(|p| p.template_name() == template)
+                || sections.iter().any(|s| s.get_template_name() == template)
+        };
+        let used_elsewhere = self
+            .find_rendered_template_names()
+            .iter()
+            .any(|t| using.contains(t) && !rendered_by_pages_or_sections(t));
+        if used_elsewhere {
+            drop(library);
+            return self.build();
+        }
+
+        pages.par_iter().map(|p| self.render_page(p)).collect::<Result<()>>()?;
+        sections.par_iter().map(|s| self.render_section(s, false)).collect::<Result<()>>()
+    }
+
+    /// Whether the templates hash some files, in which case the site needs to be re-rendered
+    /// when a static or Sass file changes
+    pub fn uses_file_hashes(&self) -> bool {
+        self.file_hashes_used.load(Ordering::Relaxed)
+    }
+
+    /// Keep track of the content rendered, to call `write_build_report` after building
+    pub fn enable_build_report(&mut self) {
+        self.build_report = Some(report::BuildReport::default());
+    }
+
+    /// Writes the JSON report of the build to the given path: its summary and the files of
+    /// the output directory
+    pub fn write_build_report(
+        &self,
+        report_path: &Path,
+        summary: &report::BuildSummary,
+    ) -> Result<()> {
+        match self.build_report {
+            Some(ref report) => {
+                report.write(&self.output_path, &self.base_path, report_path, summary)
+            }
+            None => bail!("The build report was not enabled"),
+        }
+    }
+
+    /// Counts what the last build generated, `build_time` being how long loading and building
+    /// the site took. The output directory is walked to measure it, so only call it when the
+    /// summary is used
+    pub fn build_summary(&self, build_time: Duration) -> Result<report::BuildSummary> {
+        let library = self.library.read().unwrap();
+        let (output_files, output_size) = report::measure_directory(&self.output_path)?;
+
+        Ok(report::BuildSummary {
+            pages: library.pages.len(),
+            sections: library.sections.len() - 1, // -1 since we do not count the index as a section
+            orphan_pages: library.get_all_orphan_pages().len(),
+            taxonomy_terms: self.taxonomies.iter().map(|t| t.items.len()).sum(),
+            paginated_pages: self.paginated_pages_rendered.load(Ordering::Relaxed),
+            static_files: self.static_files_copied.load(Ordering::Relaxed),
+            output_files,
+            output_size,
+            build_time_ms: build_time.as_secs_f64() * 1000.0,
+        })
+    }
+
+    fn record_render(&self, output_file: PathBuf, source: &Path, start: Instant) {
+        if let Some(ref report) = self.build_report {
+            report.record(output_file, source, start.elapsed());
+        }
+    }
+
     pub fn set_base_url(&mut self, base_url: String) {
         self.config.base_url = base_url;
         let mut imageproc = self.imageproc.lock().expect("Couldn't lock imageproc (set_base_url)");
@@ -159,110 +396,151 @@ impl Site {
         self.output_path = path.as_ref().to_path_buf();
     }
 
-    /// Reads all .md files in the `content` directory and create pages/sections
-    /// out of them
+    /// Hashes the templates and the permalinks for the render cache if it is enabled, they can't
+    /// change during a build
+    fn load_render_cache(&mut self) -> Result<()> {
+        self.render_cache = None;
+        if !self.config.render_cache.enabled {
+            return Ok(());
+        }
+        let mut template_dirs = vec![self.base_path.join("templates")];
+        template_dirs.extend(self.config.templates.load.iter().map(|d| self.base_path.join(d)));
+        if let Some(ref theme) = self.config.theme {
+            template_dirs.push(self.base_path.join("themes").join(theme).join("templates"));
+        }
+        let uncacheable =
+            render_cache::find_uncacheable_templates(&self.tera, self.config.theme.as_deref());
+        debug!(target: "render", "Templates left out of the render cache: {:?}", uncacheable);
+        self.render_cache = Some(render_cache::RenderCache::new(
+            self.generated_dir("render_cache.path", &self.config.render_cache.path)?,
+            &template_dirs,
+            &self.permalinks,
+            uncacheable,
+        )?);
+        Ok(())
+    }
+
+    /// The directory of a setting like `render_cache.path` that Zola deletes files from: it can't
+    /// contain the site or overlap its source directories or the output directory
+    fn generated_dir(&self, setting: &str, dir: &str) -> Result<PathBuf> {
+        if Path::new(dir).components().any(|c| c == Component::ParentDir) {
+            bail!("`{}` can't go up a directory with `..`, `{}` does", setting, dir);
+        }
+        let path = self.base_path.join(dir);
+        if self.base_path.starts_with(&path) {
+            bail!("`{}` can't be `{}`, which contains the site", setting, dir);
+        }
+        let mut protected = vec![
+            self.content_path.clone(),
+            self.base_path.join("templates"),
+            self.static_path.clone(),
+            self.base_path.join("sass"),
+            self.base_path.join("themes"),
+            self.output_path.clone(),
+        ];
+        protected.extend(self.config.templates.load.iter().map(|d| self.base_path.join(d)));
+        for protected in protected {
+            if path.starts_with(&protected) || protected.starts_with(&path) {
+                bail!(
+                    "`{}` can't be `{}`, it overlaps with `{}` where Zola would delete files",
+                    setting,
+                    dir,
+                    protected.display()
+                );
+            }
+        }
+        Ok(path)
+    }
+
+    /// A directory like `gemini.output_dir` that is emptied by every build, which has to be a
+    /// relative path to a directory inside the site on top of the checks of `generated_dir`
+    fn emptied_dir(&self, setting: &str, dir: &str) -> Result<PathBuf> {
+        if Path::new(dir).is_absolute() {
+            bail!("`{}` must be relative to the root of the site, `{}` isn't", setting, dir);
+        }
+        self.generated_dir(setting, dir)
+    }
+
+    /// Reads all .md files of the content source, the `content` directory by default, and
+    /// create pages/sections out of them
     pub fn load(&mut self) -> Result<()> {
         self.library = Arc::new(RwLock::new(Library::new(&self.config)));
-        let mut pages_insert_anchors = HashMap::new();
-
-        // not the most elegant loop, but this is necessary to use skip_current_dir
-        // which we can only decide to use after we've deserialised the section
-        // so it's kinda necessecary
-        let mut dir_walker =
-            WalkDir::new(self.base_path.join("content")).follow_links(true).into_iter();
         let mut allowed_index_filenames: Vec<_> = self
             .config
             .other_languages()
-            .iter()
-            .map(|(code, _)| format!("_index.{}.md", code))
+            .keys()
+            .map(|code| format!("_index.{}.md", code))
             .collect();
         allowed_index_filenames.push("_index.md".to_string());
+        let is_ignored = |path: &Path| {
+            self.config.ignored_content_globset.as_ref().map_or(false, |gs| gs.is_match(path))
+        };
 
-        loop {
-            let entry: DirEntry = match dir_walker.next() {
-                None => break,
-                Some(Err(_)) => continue,
-                Some(Ok(entry)) => entry,
-            };
-            let path = entry.path();
-            let file_name = match path.file_name() {
+        // The sections are loaded before the pages since the pages need their parent section
+        let mut section_files = Vec::new();
+        let mut page_files = Vec::new();
+        for relative_path in self.content_source.list(&self.content_path, &self.config)? {
+            let path = self.content_path.join(&relative_path);
+            let file_name = match path.file_name().and_then(|n| n.to_str()) {
                 None => continue,
-                Some(name) => name.to_str().unwrap(),
+                Some(name) => name,
             };
-
-            // ignore excluded content
-            match &self.config.ignored_content_globset {
-                Some(gs) => {
-                    if gs.is_match(path) {
-                        continue;
-                    }
-                }
-
-                None => (),
+            if is_ignored(&path) {
+                continue;
             }
 
-            // we process a section when we encounter the dir
-            // so we can process it before any of the pages
-            // therefore we should skip the actual file to avoid duplication
             if file_name.starts_with("_index.") {
+                // the sections of ignored directories are left out
+                let dir_is_ignored = path.parent().map_or(false, is_ignored);
+                if allowed_index_filenames.iter().any(|f| f == file_name) && !dir_is_ignored {
+                    section_files.push((relative_path, path));
+                }
+            } else if (file_name.ends_with(".md")
+                || converters::find_converter(&self.config, &path).is_some())
+                && !file_name.starts_with('.')
+            {
+                // skip hidden files and the files that are not Markdown or converted to it
+                page_files.push((relative_path, path));
+            }
+        }
+        // Parent sections first so the content of drafted ones can be skipped
+        section_files.sort_by_key(|(relative_path, _)| relative_path.components().count());
+
+        let on_disk = self.content_source.is_on_disk();
+        let mut drafted_dirs: Vec<PathBuf> = Vec::new();
+        for (relative_path, path) in section_files {
+            let dir = path.parent().unwrap().to_path_buf();
+            if drafted_dirs.iter().any(|d| dir != *d && dir.starts_with(d)) {
                 continue;
             }
+            let content = self.content_source.read(&self.content_path, &relative_path)?;
+            let section =
+                Section::from_content(&path, &content, &self.config, &self.base_path, on_disk)?;
 
-            // skip hidden files and non md files
-            if !path.is_dir() && (!file_name.ends_with(".md") || file_name.starts_with('.')) {
+            // if the section is drafted we skip the entire dir
+            if section.meta.draft && !self.include_drafts {
+                drafted_dirs.push(dir);
                 continue;
             }
 
-            // is it a section or not?
-            if path.is_dir() {
-                // if we are processing a section we have to collect
-                // index files for all languages and process them simultaneously
-                // before any of the pages
-                let index_files = WalkDir::new(&path)
-                    .follow_links(true)
-                    .max_depth(1)
-                    .into_iter()
-                    .filter_map(|e| match e {
-                        Err(_) => None,
-                        Ok(f) => {
-                            let path_str = f.path().file_name().unwrap().to_str().unwrap();
-                            // https://github.com/getzola/zola/issues/1244
-                            if f.path().is_file()
-                                && allowed_index_filenames.iter().any(|s| s == path_str)
-                            {
-                                Some(f)
-                            } else {
-                                None
-                            }
-                        }
-                    })
-                    .collect::<Vec<DirEntry>>();
-
-                for index_file in index_files {
-                    let section =
-                        Section::from_file(index_file.path(), &self.config, &self.base_path)?;
-
-                    // if the section is drafted we can skip the enitre dir
-                    if section.meta.draft && !self.include_drafts {
-                        dir_walker.skip_current_dir();
-                        continue;
-                    }
-
-                    self.add_section(section, false)?;
-                }
-            } else {
-                let page = Page::from_file(path, &self.config, &self.base_path)?;
+            self.add_section(section, false)?;
+        }
 
-                // should we skip drafts?
-                if page.meta.draft && !self.include_drafts {
-                    continue;
-                }
-                pages_insert_anchors.insert(
-                    page.file.path.clone(),
-                    self.find_parent_section_insert_anchor(&page.file.parent.clone(), &page.lang),
-                );
-                self.add_page(page, false)?;
+        for (relative_path, path) in page_files {
+            if drafted_dirs.iter().any(|d| path.starts_with(d)) {
+                continue;
+            }
+            let content = self.content_source.read(&self.content_path, &relative_path)?;
+            let content = converters::convert(&self.config, &self.base_path, &path, content)?;
+            let mut page =
+                Page::from_content(&path, &content, &self.config, &self.base_path, on_disk)?;
+            page.raw_content_is_html = converters::outputs_html(&self.config, &path);
+
+            // should we skip drafts?
+            if page.meta.draft && !self.include_drafts {
+                continue;
             }
+            self.add_page(page, false)?;
         }
         self.create_default_index_sections()?;
 
@@ -278,18 +556,19 @@ impl Site {
                 return Err(anyhow!(msg));
             }
         }
+        self.config.resolve_menus(&self.permalinks)?;
 
         // taxonomy Tera fns are loaded in `register_early_global_fns`
         // so we do need to populate it first.
         self.populate_taxonomies()?;
+        self.check_portable_paths()?;
         tpls::register_early_global_fns(self)?;
         self.populate_sections();
+        self.populate_series();
+        self.populate_archives();
         self.render_markdown()?;
-        {
-            let mut lib = self.library.write().unwrap();
-            lib.fill_backlinks();
-        }
         tpls::register_tera_global_fns(self);
+        self.load_render_cache()?;
 
         // Needs to be done after rendering markdown as we only get the anchors at that point
         let internal_link_messages = link_checking::check_internal_links_with_anchors(self);
@@ -336,6 +615,50 @@ impl Site {
         Ok(())
     }
 
+    /// Checks that the output paths of the pages, sections, aliases and taxonomies can be
+    /// created on Windows, so `public/` can be checked out anywhere. It is an error when building
+    /// on Windows and a warning on the other platforms.
+    pub fn check_portable_paths(&self) -> Result<()> {
+        let library = self.library.read().unwrap();
+        let mut paths: Vec<(String, String)> = library
+            .reverse_aliases
+            .iter()
+            .flat_map(|(path, files)| {
+                files.iter().map(move |f| (path.clone(), format!("`{}`", f.display())))
+            })
+            .collect();
+        for taxonomy in &self.taxonomies {
+            for term in &taxonomy.items {
+                let source =
+                    format!("the `{}` term of the `{}` taxonomy", term.name, taxonomy.kind.name);
+                paths.push((term.path.clone(), source));
+            }
+        }
+        paths.sort();
+
+        let messages: Vec<String> = paths
+            .iter()
+            .filter_map(|(path, source)| {
+                let issue = utils::fs::find_unportable_path_issue(path)?;
+                Some(format!("- `{}` from {}: {}", path, source, issue))
+            })
+            .collect();
+        if messages.is_empty() {
+            return Ok(());
+        }
+        let msg = format!(
+            "Found {} output path(s) that can't be created on Windows, \
+            set a `slug` or a `path` in their front matter to change them:\n{}",
+            messages.len(),
+            messages.join("\n")
+        );
+        if cfg!(windows) {
+            return Err(anyhow!(msg));
+        }
+        console::warn(&msg);
+        Ok(())
+    }
+
     /// Insert a default index section for each language if necessary so we don't need to create
     /// a _index.md to render the index page at the root of the site
     pub fn create_default_index_sections(&mut self) -> Result<()> {
@@ -353,6 +676,7 @@ impl Site {
             // Not in else because of borrow checker
             if !library.sections.contains_key(&index_path) {
                 let mut index_section = Section::default();
+                index_section.meta.paginate_path = Some(self.config.paginate_path.clone());
                 index_section.file.parent = self.content_path.clone();
                 index_section.file.filename =
                     index_path.file_name().unwrap().to_string_lossy().to_string();
@@ -383,7 +707,7 @@ impl Site {
         Ok(())
     }
 
-    /// Render the markdown of all pages/sections
+    /// Render the markdown of all pages/sections and find the backlinks from their internal links
     /// Used in a build and in `serve` if a shortcode has changed
     pub fn render_markdown(&mut self) -> Result<()> {
         // Another silly thing needed to not borrow &self in parallel and
@@ -394,18 +718,24 @@ impl Site {
 
         // This is needed in the first place because of silly borrow checker
         let mut pages_insert_anchors = HashMap::new();
+        let mut raw_html_policies = HashMap::new();
         for (_, p) in &self.library.read().unwrap().pages {
             pages_insert_anchors.insert(
                 p.file.path.clone(),
                 self.find_parent_section_insert_anchor(&p.file.parent.clone(), &p.lang),
             );
+            raw_html_policies
+                .insert(p.file.path.clone(), self.find_raw_html_policy(&p.file.parent, &p.lang));
+        }
+        for (_, s) in &self.library.read().unwrap().sections {
+            raw_html_policies
+                .insert(s.file.path.clone(), self.find_raw_html_policy(&s.file.parent, &s.lang));
         }
 
         let mut library = self.library.write().expect("Get lock for render_markdown");
-        library
-            .pages
-            .values_mut()
-            .collect::<Vec<_>>()
+        let mut pages: Vec<_> = library.pages.values_mut().collect();
+        plugins::run_page_hook(config, &self.base_path, PluginHook::ParseMarkdown, &mut pages)?;
+        pages
             .par_iter_mut()
             .map(|page| {
                 let insert_anchor = pages_insert_anchors[&page.file.path];
@@ -414,10 +744,12 @@ impl Site {
                     tera,
                     config,
                     insert_anchor,
+                    raw_html_policies[&page.file.path],
                     &self.shortcode_definitions,
                 )
             })
             .collect::<Result<()>>()?;
+        plugins::run_page_hook(config, &self.base_path, PluginHook::PreRenderPage, &mut pages)?;
 
         library
             .sections
@@ -425,10 +757,18 @@ impl Site {
             .collect::<Vec<_>>()
             .par_iter_mut()
             .map(|section| {
-                section.render_markdown(permalinks, tera, config, &self.shortcode_definitions)
+                section.render_markdown(
+                    permalinks,
+                    tera,
+                    config,
+                    raw_html_policies[&section.file.path],
+                    &self.shortcode_definitions,
+                )
             })
             .collect::<Result<()>>()?;
 
+        library.fill_backlinks();
+
         Ok(())
     }
 
@@ -445,22 +785,40 @@ impl Site {
             }
         }
 
-        self.permalinks.insert(page.file.relative.clone(), page.permalink.clone());
+        if let Some(pattern) = self.find_page_permalink_pattern(&page.file.parent, &page.lang) {
+            page.apply_permalink_pattern(&pattern, &self.config)?;
+        }
+        if page.meta.draft && self.config.drafts.preview_tokens {
+            page.apply_preview_token(self.draft_preview_token(&page), &self.config);
+        }
+
+        Arc::make_mut(&mut self.permalinks)
+            .insert(page.file.relative.clone(), page.permalink.clone());
         if render_md {
             let insert_anchor =
                 self.find_parent_section_insert_anchor(&page.file.parent, &page.lang);
+            let hook = PluginHook::ParseMarkdown;
+            plugins::run_page_hook(&self.config, &self.base_path, hook, &mut [&mut page])?;
+            let raw_html = self.find_raw_html_policy(&page.file.parent, &page.lang);
             page.render_markdown(
                 &self.permalinks,
                 &self.tera,
                 &self.config,
                 insert_anchor,
+                raw_html,
                 &self.shortcode_definitions,
             )?;
+            let hook = PluginHook::PreRenderPage;
+            plugins::run_page_hook(&self.config, &self.base_path, hook, &mut [&mut page])?;
         }
 
         let mut library = self.library.write().expect("Get lock for add_page");
         library.pages.remove(&page.file.path);
         library.insert_page(page);
+        // The internal links of that page might have changed
+        if render_md {
+            library.fill_backlinks();
+        }
 
         Ok(())
     }
@@ -468,30 +826,57 @@ impl Site {
     /// Adds a page to the site and render it
     /// Only used in `zola serve --fast`
     pub fn add_and_render_page(&mut self, path: &Path) -> Result<()> {
-        let page = Page::from_file(path, &self.config, &self.base_path)?;
+        let content = converters::convert(&self.config, &self.base_path, path, read_file(path)?)?;
+        let mut page = Page::from_content(path, &content, &self.config, &self.base_path, true)?;
+        page.raw_content_is_html = converters::outputs_html(&self.config, path);
         self.add_page(page, true)?;
         self.populate_sections();
         self.populate_taxonomies()?;
+        self.populate_series();
+        self.populate_archives();
         let library = self.library.read().unwrap();
         let page = library.pages.get(path).unwrap();
         self.render_page(page)
     }
 
+    /// The token is derived from the secret and the file of the draft so the preview link
+    /// doesn't change between builds when a secret is set
+    fn draft_preview_token(&self, page: &Page) -> String {
+        let mut hasher = Sha256::new();
+        let secret =
+            self.config.drafts.preview_secret.as_deref().unwrap_or(&self.random_preview_secret);
+        hasher.update(secret.as_bytes());
+        hasher.update(b":");
+        hasher.update(page.file.relative.as_bytes());
+        let hash = format!("{:x}", hasher.finalize());
+        hash[..32].to_string()
+    }
+
     /// Add a section to the site
     /// The `render` parameter is used in the serve command with --fast, when rebuilding a page.
     pub fn add_section(&mut self, mut section: Section, render_md: bool) -> Result<()> {
-        self.permalinks.insert(section.file.relative.clone(), section.permalink.clone());
+        Arc::make_mut(&mut self.permalinks)
+            .insert(section.file.relative.clone(), section.permalink.clone());
         if render_md {
+            // Its own policy is not in the library yet
+            let raw_html = section.meta.raw_html.unwrap_or_else(|| {
+                let parent = section.file.parent.parent().unwrap_or(&section.file.parent);
+                self.find_raw_html_policy(parent, &section.lang)
+            });
             section.render_markdown(
                 &self.permalinks,
                 &self.tera,
                 &self.config,
+                raw_html,
                 &self.shortcode_definitions,
             )?;
         }
         let mut library = self.library.write().expect("Get lock for add_section");
         library.sections.remove(&section.file.path);
         library.insert_section(section);
+        if render_md {
+            library.fill_backlinks();
+        }
 
         Ok(())
     }
@@ -525,6 +910,45 @@ impl Site {
         }
     }
 
+    /// Finds the raw HTML policy of the content of the directory at `path`: the one of the
+    /// nearest section setting it, starting with the section of that directory itself, or the
+    /// `markdown.raw_html` of the config if none does. With `markdown.raw_html_locked`, the one
+    /// of the config is used if it is stricter than the one of the section.
+    pub fn find_raw_html_policy(&self, path: &Path, lang: &str) -> RawHtml {
+        let index = if lang != self.config.default_language {
+            format!("_index.{}.md", lang)
+        } else {
+            "_index.md".to_string()
+        };
+        let library = self.library.read().unwrap();
+        let site_policy = self.config.markdown.raw_html;
+        match path
+            .ancestors()
+            .find_map(|dir| library.sections.get(&dir.join(&index)).and_then(|s| s.meta.raw_html))
+        {
+            Some(policy) if self.config.markdown.raw_html_locked => policy.strictest(site_policy),
+            Some(policy) => policy,
+            None => site_policy,
+        }
+    }
+
+    /// Finds the permalink pattern of the pages in that directory: the one of its section if set,
+    /// the `default_permalink` of the config otherwise
+    pub fn find_page_permalink_pattern(&self, parent_path: &Path, lang: &str) -> Option<String> {
+        let parent = if lang != self.config.default_language {
+            parent_path.join(format!("_index.{}.md", lang))
+        } else {
+            parent_path.join("_index.md")
+        };
+        self.library
+            .read()
+            .unwrap()
+            .sections
+            .get(&parent)
+            .and_then(|s| s.meta.permalink.clone())
+            .or_else(|| self.config.default_permalink.clone())
+    }
+
     /// Find out the direct subsections of each subsection if there are some
     /// as well as the pages for each section
     pub fn populate_sections(&mut self) {
@@ -533,16 +957,33 @@ impl Site {
     }
 
     /// Find all the tags and categories if it's asked in the config
+    /// as well as the related pages and the siblings in each term of every page
     pub fn populate_taxonomies(&mut self) -> Result<()> {
-        self.taxonomies = self.library.read().unwrap().find_taxonomies(&self.config);
+        let mut library = self.library.write().expect("Get lock for populate_taxonomies");
+        self.taxonomies = library.find_taxonomies(&self.config);
+        library.fill_taxonomy_siblings(&self.taxonomies);
+        library.fill_related_pages(&self.config);
         Ok(())
     }
 
+    /// Find all the series and the previous/next page in the series of each page
+    pub fn populate_series(&mut self) {
+        let mut library = self.library.write().expect("Get lock for populate_series");
+        self.series = library.find_series(&self.config);
+    }
+
+    /// Group the dated pages by year and month if it's asked in the config
+    pub fn populate_archives(&mut self) {
+        self.archives = self.library.read().unwrap().find_archives(&self.config);
+    }
+
     /// Inject live reload script tag if in live reload mode
     fn inject_livereload(&self, mut html: String) -> String {
         if let Some(port) = self.live_reload {
-            let script =
-                format!(r#"<script src="/livereload.js?port={}&amp;mindelay=10"></script>"#, port,);
+            let script = format!(
+                r#"<script src="{}/livereload.js?port={}&amp;mindelay=10"></script>"#,
+                self.serve_path_prefix, port,
+            );
             if let Some(index) = html.rfind("</body>") {
                 html.insert_str(index, &script);
             } else {
@@ -555,22 +996,50 @@ impl Site {
 
     /// Copy the main `static` folder and the theme `static` folder if a theme is used
     pub fn copy_static_directories(&self) -> Result<()> {
+        let mut copied = 0;
         // The user files will overwrite the theme files
         if let Some(ref theme) = self.config.theme {
-            copy_directory(
+            copied += copy_directory(
                 &self.base_path.join("themes").join(theme).join("static"),
                 &self.output_path,
-                false,
+                None,
+                self.config.follow_symlinks,
+                self.config.ignored_static_globset.as_ref(),
             )?;
         }
         // We're fine with missing static folders
         if self.static_path.exists() {
-            copy_directory(&self.static_path, &self.output_path, self.config.hard_link_static)?;
+            copied += copy_directory(
+                &self.static_path,
+                &self.output_path,
+                self.config.hard_link_static.then(|| self.config.hard_link_static_min_size),
+                self.config.follow_symlinks,
+                self.config.ignored_static_globset.as_ref(),
+            )?;
         }
+        self.static_files_copied.store(copied, Ordering::Relaxed);
+        debug!(target: "copy", "Copied {} static files", copied);
 
         Ok(())
     }
 
+    /// Whether that file of the `static` directory, or that colocated asset, is hard-linked
+    /// instead of copied
+    pub fn hard_links_static_file(&self, path: &Path) -> bool {
+        self.config.hard_link_static
+            && path.metadata().map_or(false, |m| m.len() >= self.config.hard_link_static_min_size)
+    }
+
+    /// Whether that file of the `static` directory is not copied to the output directory, because
+    /// it or one of its parent directories is ignored
+    pub fn is_ignored_static_file(&self, path: &Path) -> bool {
+        let relative_path = path.strip_prefix(&self.static_path).unwrap_or(path);
+        relative_path
+            .ancestors()
+            .filter(|p| !p.as_os_str().is_empty())
+            .any(|p| is_ignored_file(p, self.config.ignored_static_globset.as_ref()))
+    }
+
     pub fn num_img_ops(&self) -> usize {
         let imageproc = self.imageproc.lock().expect("Couldn't lock imageproc (num_img_ops)");
         imageproc.num_img_ops()
@@ -593,6 +1062,22 @@ impl Site {
         Ok(())
     }
 
+    /// Creates a directory of the output and its parents unless this build already did, which
+    /// saves checking for their existence for every file written in them
+    fn create_output_directory(&self, path: &Path) -> Result<()> {
+        if self.created_directories.lock().unwrap().contains(path) {
+            return Ok(());
+        }
+        create_directory(path)?;
+        let mut created = self.created_directories.lock().unwrap();
+        for ancestor in path.ancestors() {
+            if !created.insert(ancestor.to_path_buf()) || ancestor == self.output_path {
+                break;
+            }
+        }
+        Ok(())
+    }
+
     /// Handles whether to write to disk or to memory
     pub fn write_content(
         &self,
@@ -602,24 +1087,24 @@ impl Site {
         create_dirs: bool,
     ) -> Result<PathBuf> {
         let write_dirs = self.build_mode == BuildMode::Disk || create_dirs;
-        ensure_directory_exists(&self.output_path)?;
 
         let mut site_path = RelativePathBuf::new();
         let mut current_path = self.output_path.to_path_buf();
-
         for component in components {
             current_path.push(component);
             site_path.push(component);
-
-            if !current_path.exists() && write_dirs {
-                create_directory(&current_path)?;
-            }
         }
 
         if write_dirs {
-            create_directory(&current_path)?;
+            self.create_output_directory(&current_path)?;
         }
 
+        let content = if filename.ends_with("html") && self.offline_mirror {
+            let depth = components.iter().filter(|c| !c.is_empty()).count();
+            offline_mirror::LinkRewriter::new(&self.config.base_url).rewrite(&content, depth)
+        } else {
+            content
+        };
         let final_content = if !filename.ends_with("html") || !self.config.minify_html {
             content
         } else {
@@ -645,17 +1130,113 @@ impl Site {
         Ok(current_path)
     }
 
+    /// The `redirect_to` of pages and sections is either an internal path or a full URL
+    fn redirect_permalink(&self, redirect_to: &str) -> String {
+        if redirect_to.contains("://") {
+            redirect_to.to_string()
+        } else {
+            self.config.make_permalink(redirect_to)
+        }
+    }
+
     fn copy_asset(&self, src: &Path, dest: &Path) -> Result<()> {
-        copy_file_if_needed(src, dest, self.config.hard_link_static)
+        if self.config.images.strip_exif && is_jpeg(src) {
+            return imageproc::copy_without_exif(src, dest).with_context(|| {
+                format!("Failed to copy asset {} to {}", src.display(), dest.display())
+            });
+        }
+        copy_file_if_needed(src, dest, self.hard_links_static_file(src))
+    }
+
+    /// Renders the HTML of a page, or takes it from the render cache if the page didn't change
+    /// since the last build. The password-protected pages are not cached.
+    fn render_page_html(&self, page: &Page) -> Result<String> {
+        let library = self.library.read().unwrap();
+        match self.render_cache {
+            Some(ref cache)
+                if self.build_mode == BuildMode::Disk && page.meta.password.is_none() =>
+            {
+                let context = page.render_context(&self.config, &library);
+                cache.get_or_render(page.template_name(), &context, || {
+                    page.render_html_with_context(&self.tera, &self.config, context.clone())
+                })
+            }
+            _ => page.render_html(&self.tera, &self.config, &library),
+        }
     }
 
     /// Renders a single content page
     pub fn render_page(&self, page: &Page) -> Result<()> {
-        let output = page.render_html(&self.tera, &self.config, &self.library.read().unwrap())?;
-        let content = self.inject_livereload(output);
+        trace!(target: "render", "Rendering {}", page.file.path.display());
+        let start = Instant::now();
+        let outputs = page.meta.outputs.as_ref().unwrap_or(&self.config.outputs);
         let components: Vec<&str> = page.path.split('/').collect();
-        let current_path =
-            self.write_content(&components, "index.html", content, !page.assets.is_empty())?;
+        let mut current_path = None;
+
+        if outputs.contains(&OutputFormat::Html) || page.meta.redirect_to.is_some() {
+            let content = match page.meta.redirect_to {
+                Some(ref redirect_to) => {
+                    render_redirect_template(&self.redirect_permalink(redirect_to), &self.tera)?
+                }
+                None => {
+                    let mut output = self.render_page_html(page)?;
+                    if page.meta.password.is_some() {
+                        output = protected::render_protected_page(page, &output, &self.tera)?;
+                    }
+                    if let Some(ref amp_permalink) = page.amp_permalink {
+                        output = amp::link_amp_version(output, amp_permalink);
+                    }
+                    self.inject_livereload(output)
+                }
+            };
+            let path =
+                self.write_content(&components, "index.html", content, !page.assets.is_empty())?;
+            self.record_render(path.join("index.html"), &page.file.path, start);
+            current_path = Some(path);
+
+            if page.amp_permalink.is_some() {
+                let output =
+                    page.render_amp(&self.tera, &self.config, &self.library.read().unwrap())?;
+                let output = amp::link_canonical_version(output, &page.permalink);
+                let mut amp_components = components.clone();
+                amp_components.push("amp");
+                let path = self.write_content(
+                    &amp_components,
+                    "index.html",
+                    self.inject_livereload(output),
+                    false,
+                )?;
+                self.record_render(path.join("index.html"), &page.file.path, start);
+            }
+        }
+
+        // The other formats would give away the content of protected pages
+        if page.meta.redirect_to.is_none() && page.meta.password.is_none() {
+            let library = self.library.read().unwrap();
+            for format in outputs {
+                let (filename, content) = match format {
+                    OutputFormat::Html => continue,
+                    OutputFormat::Json => (
+                        "index.json",
+                        serde_json::to_string(&page.serialize(&library)).with_context(|| {
+                            format!("Failed to serialize {}", page.file.path.display())
+                        })?,
+                    ),
+                    OutputFormat::Txt => {
+                        ("index.txt", page.render_txt(&self.tera, &self.config, &library)?)
+                    }
+                };
+                let path =
+                    self.write_content(&components, filename, content, !page.assets.is_empty())?;
+                self.record_render(path.join(filename), &page.file.path, start);
+                current_path = Some(path);
+            }
+        }
+
+        let current_path = match current_path {
+            Some(p) => p,
+            None => return Ok(()),
+        };
 
         // Copy any asset we found previously into the same directory as the index.html
         for asset in &page.assets {
@@ -664,7 +1245,7 @@ impl Site {
                 asset_path,
                 &current_path.join(
                     asset_path
-                        .strip_prefix(&page.file.path.parent().unwrap())
+                        .strip_prefix(page.file.path.parent().unwrap())
                         .expect("Couldn't get filename from page asset"),
                 ),
             )?;
@@ -680,6 +1261,8 @@ impl Site {
         if self.build_mode == BuildMode::Disk {
             self.clean()?;
         }
+        self.created_directories.lock().unwrap().clear();
+        self.paginated_pages_rendered.store(0, Ordering::Relaxed);
         start = log_time(start, "Cleaned folder");
 
         // Generate/move all assets before markdown any content
@@ -704,22 +1287,33 @@ impl Site {
         // Render aliases first to allow overwriting
         self.render_aliases()?;
         start = log_time(start, "Rendered aliases");
+        self.render_netlify_files()?;
+        start = log_time(start, "Rendered Netlify files");
         self.render_sections()?;
         start = log_time(start, "Rendered sections");
         self.render_orphan_pages()?;
         start = log_time(start, "Rendered orphan pages");
+        if self.config.orphan_report.generate {
+            self.render_orphan_report()?;
+            start = log_time(start, "Rendered orphan report");
+        }
+        if self.config.drafts.preview_tokens {
+            self.render_draft_previews()?;
+            start = log_time(start, "Rendered draft previews");
+        }
         self.render_sitemap()?;
         start = log_time(start, "Rendered sitemap");
 
         let library = self.library.read().unwrap();
         if self.config.generate_feed {
             let is_multilingual = self.config.is_multilingual();
-            let pages: Vec<_> = if is_multilingual {
-                library.pages.values().filter(|p| p.lang == self.config.default_language).collect()
-            } else {
-                library.pages.values().collect()
-            };
-            self.render_feed(pages, None, &self.config.default_language, |c| c)?;
+            let pages: Vec<_> = library
+                .pages
+                .values()
+                .filter(|p| !is_multilingual || p.lang == self.config.default_language)
+                .filter(|p| !p.is_draft_preview())
+                .collect();
+            self.render_feed(pages, None, &self.config.default_language, None, |c| c)?;
             start = log_time(start, "Generated feed in default language");
         }
 
@@ -727,8 +1321,12 @@ impl Site {
             if !language.generate_feed {
                 continue;
             }
-            let pages: Vec<_> = library.pages.values().filter(|p| &p.lang == code).collect();
-            self.render_feed(pages, Some(&PathBuf::from(code)), code, |c| c)?;
+            let pages: Vec<_> = library
+                .pages
+                .values()
+                .filter(|p| &p.lang == code && !p.is_draft_preview())
+                .collect();
+            self.render_feed(pages, Some(&PathBuf::from(code)), code, None, |c| c)?;
             start = log_time(start, "Generated feed in other language");
         }
         self.render_themes_css()?;
@@ -737,15 +1335,67 @@ impl Site {
         start = log_time(start, "Rendered 404");
         self.render_robots()?;
         start = log_time(start, "Rendered robots.txt");
+        if self.config.humans_txt.generate {
+            self.render_humans_txt()?;
+            start = log_time(start, "Rendered humans.txt");
+        }
+        if self.config.security_txt.generate {
+            self.render_security_txt()?;
+            start = log_time(start, "Rendered security.txt");
+        }
+        if self.config.favicons.is_enabled() {
+            self.render_favicons()?;
+            start = log_time(start, "Rendered favicons");
+        }
         self.render_taxonomies()?;
         start = log_time(start, "Rendered taxonomies");
+        self.render_feeds_opml()?;
+        start = log_time(start, "Rendered feeds.opml");
+        if !self.config.calendar.sections.is_empty() {
+            self.render_calendar()?;
+            start = log_time(start, "Rendered events.ics");
+        }
+        self.render_all_series()?;
+        start = log_time(start, "Rendered series");
+        self.render_archives()?;
+        start = log_time(start, "Rendered archives");
         // We process images at the end as we might have picked up images to process from markdown
         // or from templates
         self.process_images()?;
         start = log_time(start, "Processed images");
         // Processed images will be in static so the last step is to copy it
         self.copy_static_directories()?;
-        log_time(start, "Copied static dir");
+        start = log_time(start, "Copied static dir");
+        if self.config.gemini.generate && self.build_mode == BuildMode::Disk {
+            gemini::render_gemini(self)?;
+            start = log_time(start, "Rendered gemtext");
+        }
+        if self.config.newsletter.generate && self.build_mode == BuildMode::Disk {
+            newsletter::render_newsletter(self)?;
+            start = log_time(start, "Rendered newsletter");
+        }
+        if self.build_mode == BuildMode::Disk
+            && self.config.plugins.iter().any(|p| p.runs_at(PluginHook::PostBuild))
+        {
+            let pages: Vec<_> = library.pages.values().collect();
+            plugins::run_post_build(&self.config, &self.base_path, &self.output_path, &pages)?;
+            start = log_time(start, "Ran post-build plugins");
+        }
+        if self.config.generate_asset_manifest && self.build_mode == BuildMode::Disk {
+            manifest::write_manifest(&self.output_path, &self.config)?;
+            start = log_time(start, "Wrote asset manifest");
+        }
+        // Compressing needs to happen once everything has been written
+        if self.config.precompress && self.build_mode == BuildMode::Disk {
+            compress::compress_output(&self.output_path)?;
+            start = log_time(start, "Precompressed output");
+        }
+        if let Some(ref cache) = self.render_cache {
+            if self.build_mode == BuildMode::Disk {
+                cache.prune()?;
+                log_time(start, "Pruned render cache");
+            }
+        }
 
         Ok(())
     }
@@ -770,7 +1420,7 @@ impl Site {
 
         // index first
         create_file(
-            &self.output_path.join(&format!("search_index.{}.js", self.config.default_language)),
+            &self.output_path.join(format!("search_index.{}.js", self.config.default_language)),
             &format!(
                 "window.searchIndex = {};",
                 search::build_index(
@@ -784,7 +1434,7 @@ impl Site {
         for (code, language) in &self.config.other_languages() {
             if code != &self.config.default_language && language.build_search_index {
                 create_file(
-                    &self.output_path.join(&format!("search_index.{}.js", &code)),
+                    &self.output_path.join(format!("search_index.{}.js", &code)),
                     &format!(
                         "window.searchIndex = {};",
                         search::build_index(code, &self.library.read().unwrap(), &self.config)?
@@ -835,12 +1485,25 @@ impl Site {
         Ok(())
     }
 
+    /// Renders the Netlify `_redirects` and `_headers` files if they are enabled
+    pub fn render_netlify_files(&self) -> Result<()> {
+        if self.config.netlify.generate_redirects {
+            let content = netlify::render_redirects(&self.library.read().unwrap(), &self.config);
+            self.write_content(&[], "_redirects", content, false)?;
+        }
+        if !self.config.netlify.headers.is_empty() {
+            self.write_content(&[], "_headers", netlify::render_headers(&self.config), false)?;
+        }
+        Ok(())
+    }
+
     /// Renders 404.html
     pub fn render_404(&self) -> Result<()> {
         ensure_directory_exists(&self.output_path)?;
         let mut context = Context::new();
         context.insert("config", &self.config.serialize(&self.config.default_language));
         context.insert("lang", &self.config.default_language);
+        insert_current_path(&mut context, &self.config, "404.html");
         let output = render_template("404.html", &self.tera, context, &self.config.theme)?;
         let content = self.inject_livereload(output);
         self.write_content(&[], "404.html", content, false)?;
@@ -852,11 +1515,36 @@ impl Site {
         ensure_directory_exists(&self.output_path)?;
         let mut context = Context::new();
         context.insert("config", &self.config.serialize(&self.config.default_language));
+        insert_current_path(&mut context, &self.config, "robots.txt");
         let content = render_template("robots.txt", &self.tera, context, &self.config.theme)?;
         self.write_content(&[], "robots.txt", content, false)?;
         Ok(())
     }
 
+    /// Renders humans.txt
+    pub fn render_humans_txt(&self) -> Result<()> {
+        ensure_directory_exists(&self.output_path)?;
+        let mut context = Context::new();
+        context.insert("config", &self.config.serialize(&self.config.default_language));
+        context.insert("humans_txt", &self.config.humans_txt);
+        insert_current_path(&mut context, &self.config, "humans.txt");
+        let content = render_template("humans.txt", &self.tera, context, &self.config.theme)?;
+        self.write_content(&[], "humans.txt", content, false)?;
+        Ok(())
+    }
+
+    /// Renders .well-known/security.txt
+    pub fn render_security_txt(&self) -> Result<()> {
+        ensure_directory_exists(&self.output_path)?;
+        let mut context = Context::new();
+        context.insert("config", &self.config.serialize(&self.config.default_language));
+        context.insert("security_txt", &self.config.security_txt);
+        insert_current_path(&mut context, &self.config, ".well-known/security.txt");
+        let content = render_template("security.txt", &self.tera, context, &self.config.theme)?;
+        self.write_content(&[".well-known"], "security.txt", content, false)?;
+        Ok(())
+    }
+
     /// Renders all taxonomies
     pub fn render_taxonomies(&self) -> Result<()> {
         for taxonomy in &self.taxonomies {
@@ -924,6 +1612,7 @@ impl Site {
                         item.pages.iter().map(|p| library.pages.get(p).unwrap()).collect(),
                         Some(&tax_path),
                         &taxonomy.lang,
+                        taxonomy.kind.feed_template.as_deref(),
                         |mut context: Context| {
                             context.insert("taxonomy", &taxonomy.kind);
                             context
@@ -938,19 +1627,52 @@ impl Site {
             .collect::<Result<()>>()
     }
 
+    /// Renders the index page of every series
+    pub fn render_all_series(&self) -> Result<()> {
+        ensure_directory_exists(&self.output_path)?;
+        let library = self.library.read().unwrap();
+        self.series
+            .par_iter()
+            .map(|series| {
+                let output = series.render_html(&self.tera, &self.config, &library)?;
+                let content = self.inject_livereload(output);
+                let components: Vec<&str> = series.path.split('/').collect();
+                self.write_content(&components, "index.html", content, false)?;
+                Ok(())
+            })
+            .collect::<Result<()>>()
+    }
+
+    /// Renders the yearly and monthly archive pages
+    pub fn render_archives(&self) -> Result<()> {
+        ensure_directory_exists(&self.output_path)?;
+        let library = self.library.read().unwrap();
+        self.archives
+            .par_iter()
+            .map(|archive| {
+                let output = archive.render_html(&self.tera, &self.config, &library)?;
+                let content = self.inject_livereload(output);
+                let components: Vec<&str> = archive.path.split('/').collect();
+                self.write_content(&components, "index.html", content, false)?;
+                Ok(())
+            })
+            .collect::<Result<()>>()
+    }
+
     /// What it says on the tin
     pub fn render_sitemap(&self) -> Result<()> {
         ensure_directory_exists(&self.output_path)?;
 
         let library = self.library.read().unwrap();
         let all_sitemap_entries =
-            { sitemap::find_entries(&library, &self.taxonomies[..], &self.config) };
+            sitemap::find_entries(&library, &self.taxonomies[..], &self.archives[..], &self.config);
         let sitemap_limit = 30000;
 
         if all_sitemap_entries.len() < sitemap_limit {
             // Create single sitemap
             let mut context = Context::new();
             context.insert("entries", &all_sitemap_entries);
+            insert_current_path(&mut context, &self.config, "sitemap.xml");
             let sitemap = render_template("sitemap.xml", &self.tera, context, &self.config.theme)?;
             self.write_content(&[], "sitemap.xml", sitemap, false)?;
             return Ok(());
@@ -961,10 +1683,11 @@ impl Site {
         for (i, chunk) in
             all_sitemap_entries.iter().collect::<Vec<_>>().chunks(sitemap_limit).enumerate()
         {
+            let file_name = format!("sitemap{}.xml", i + 1);
             let mut context = Context::new();
             context.insert("entries", &chunk);
+            insert_current_path(&mut context, &self.config, &file_name);
             let sitemap = render_template("sitemap.xml", &self.tera, context, &self.config.theme)?;
-            let file_name = format!("sitemap{}.xml", i + 1);
             self.write_content(&[], &file_name, sitemap, false)?;
             let mut sitemap_url = self.config.make_permalink(&file_name);
             sitemap_url.pop(); // Remove trailing slash
@@ -974,6 +1697,7 @@ impl Site {
         // Create main sitemap that reference numbered sitemaps
         let mut main_context = Context::new();
         main_context.insert("sitemaps", &sitemap_index);
+        insert_current_path(&mut main_context, &self.config, "sitemap.xml");
         let sitemap = render_template(
             "split_sitemap_index.xml",
             &self.tera,
@@ -993,12 +1717,19 @@ impl Site {
         all_pages: Vec<&Page>,
         base_path: Option<&PathBuf>,
         lang: &str,
+        template: Option<&str>,
         additional_context_fn: impl Fn(Context) -> Context,
     ) -> Result<()> {
         ensure_directory_exists(&self.output_path)?;
 
-        let feed = match feed::render_feed(self, all_pages, lang, base_path, additional_context_fn)?
-        {
+        let feed = match feed::render_feed(
+            self,
+            all_pages,
+            lang,
+            base_path,
+            template,
+            additional_context_fn,
+        )? {
             Some(v) => v,
             None => return Ok(()),
         };
@@ -1021,9 +1752,64 @@ impl Site {
         Ok(())
     }
 
+    /// Renders `feeds.opml` listing all the feeds, if there are section or taxonomy feeds
+    pub fn render_feeds_opml(&self) -> Result<()> {
+        if let Some(opml) = feed::render_feeds_opml(self)? {
+            self.write_content(&[], "feeds.opml", opml, false)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the icons generated from `favicons.source` and the `site.webmanifest` listing them.
+    /// The icons found in the `static` folder of the site or of its theme are left untouched.
+    pub fn render_favicons(&self) -> Result<()> {
+        let source = match self.config.favicons.source {
+            Some(ref source) => self.base_path.join(source),
+            None => return Ok(()),
+        };
+        for (filename, content) in favicons::generate_icons(&source)? {
+            if self.is_static_file(filename) {
+                debug!("Not generating `{}` which is a static file", filename);
+                continue;
+            }
+            match self.build_mode {
+                BuildMode::Disk => {
+                    ensure_directory_exists(&self.output_path)?;
+                    let path = self.output_path.join(filename);
+                    std::fs::write(&path, content)
+                        .with_context(|| format!("Failed to write `{}`", path.display()))?;
+                }
+                BuildMode::Memory => {
+                    SITE_BINARY_CONTENT
+                        .write()
+                        .unwrap()
+                        .insert(RelativePathBuf::from(filename), content);
+                }
+            }
+        }
+        let manifest = favicons::render_webmanifest(&self.config)?;
+        self.write_content(&[], "site.webmanifest", manifest, false)?;
+        Ok(())
+    }
+
+    /// Whether `filename` is at the root of the `static` folder of the site or of its theme
+    fn is_static_file(&self, filename: &str) -> bool {
+        self.static_path.join(filename).exists()
+            || self.config.theme.as_ref().map_or(false, |theme| {
+                self.base_path.join("themes").join(theme).join("static").join(filename).exists()
+            })
+    }
+
+    /// Renders `events.ics` with the dated pages of the sections in `calendar.sections`
+    pub fn render_calendar(&self) -> Result<()> {
+        let ics = calendar::render_calendar(self)?;
+        self.write_content(&[], "events.ics", ics, false)?;
+        Ok(())
+    }
+
     /// Renders a single section
     pub fn render_section(&self, section: &Section, render_pages: bool) -> Result<()> {
-        ensure_directory_exists(&self.output_path)?;
+        trace!(target: "render", "Rendering {}", section.file.path.display());
         let mut output_path = self.output_path.clone();
         let mut components: Vec<&str> = Vec::new();
         let create_directories = self.build_mode == BuildMode::Disk || !section.assets.is_empty();
@@ -1031,19 +1817,13 @@ impl Site {
         if section.lang != self.config.default_language {
             components.push(&section.lang);
             output_path.push(&section.lang);
-
-            if !output_path.exists() && create_directories {
-                create_directory(&output_path)?;
-            }
         }
-
         for component in &section.file.components {
             components.push(component);
             output_path.push(component);
-
-            if !output_path.exists() && create_directories {
-                create_directory(&output_path)?;
-            }
+        }
+        if create_directories {
+            self.create_output_directory(&output_path)?;
         }
 
         if section.meta.generate_feed {
@@ -1053,6 +1833,7 @@ impl Site {
                 pages,
                 Some(&PathBuf::from(&section.path[1..])),
                 &section.lang,
+                section.meta.feed_template.as_deref(),
                 |mut context: Context| {
                     context.insert("section", &section.serialize(library));
                     context
@@ -1067,7 +1848,7 @@ impl Site {
                 asset_path,
                 &output_path.join(
                     asset_path
-                        .strip_prefix(&section.file.path.parent().unwrap())
+                        .strip_prefix(section.file.path.parent().unwrap())
                         .expect("Failed to get asset filename for section"),
                 ),
             )?;
@@ -1086,7 +1867,7 @@ impl Site {
         }
 
         if let Some(ref redirect_to) = section.meta.redirect_to {
-            let permalink = self.config.make_permalink(redirect_to);
+            let permalink = self.redirect_permalink(redirect_to);
             self.write_content(
                 &components,
                 "index.html",
@@ -1103,24 +1884,28 @@ impl Site {
                 &Paginator::from_section(section, &self.library.read().unwrap()),
             )?;
         } else {
+            let start = Instant::now();
             let output =
                 section.render_html(&self.tera, &self.config, &self.library.read().unwrap())?;
             let content = self.inject_livereload(output);
-            self.write_content(&components, "index.html", content, false)?;
+            let current_path = self.write_content(&components, "index.html", content, false)?;
+            self.record_render(current_path.join("index.html"), &section.file.path, start);
         }
 
         Ok(())
     }
 
-    /// Renders all sections
+    /// Renders all sections, one after the other with `low_memory` so only the pages of one
+    /// section are rendered at a time. Their Markdown was already rendered when loading the site.
     pub fn render_sections(&self) -> Result<()> {
-        self.library
-            .read()
-            .unwrap()
-            .sections
-            .par_iter()
-            .map(|(_, s)| self.render_section(s, true))
-            .collect::<Result<()>>()
+        let library = self.library.read().unwrap();
+        if self.config.low_memory && self.build_mode == BuildMode::Disk {
+            for section in library.sections.values() {
+                self.render_section(section, true)?;
+            }
+            return Ok(());
+        }
+        library.sections.par_iter().map(|(_, s)| self.render_section(s, true)).collect()
     }
 
     /// Renders all pages that do not belong to any sections
@@ -1134,6 +1919,43 @@ impl Site {
         Ok(())
     }
 
+    /// Renders the `orphans.html` report listing the pages that are not in any section, so they
+    /// can be found even though no section links to them
+    pub fn render_orphan_report(&self) -> Result<()> {
+        let library = self.library.read().unwrap();
+        let mut orphans: Vec<_> =
+            library.get_all_orphan_pages().into_iter().filter(|p| !p.is_draft_preview()).collect();
+        orphans.sort_by(|a, b| a.file.path.cmp(&b.file.path));
+        let orphans: Vec<_> =
+            orphans.into_iter().map(|p| p.serialize_for_listing(&library)).collect();
+
+        let mut context = Context::new();
+        context.insert("config", &self.config.serialize(&self.config.default_language));
+        context.insert("lang", &self.config.default_language);
+        context.insert("orphans", &orphans);
+        let components: Vec<&str> =
+            self.config.orphan_report.path.split('/').filter(|c| !c.is_empty()).collect();
+        insert_current_path(&mut context, &self.config, &format!("{}/", components.join("/")));
+        let output = render_template("orphans.html", &self.tera, context, &self.config.theme)?;
+        let content = self.inject_livereload(output);
+        self.write_content(&components, "index.html", content, false)?;
+        Ok(())
+    }
+
+    /// Renders the drafts only reachable through their preview token, which are not
+    /// listed in any section
+    pub fn render_draft_previews(&self) -> Result<()> {
+        let library = self.library.read().unwrap();
+        library
+            .pages
+            .values()
+            .filter(|p| p.is_draft_preview() && !p.ancestors.is_empty())
+            .collect::<Vec<_>>()
+            .par_iter()
+            .map(|page| self.render_page(page))
+            .collect::<Result<()>>()
+    }
+
     /// Renders a list of pages when the section/index is wanting pagination.
     pub fn render_paginated<'a>(
         &self,
@@ -1141,6 +1963,7 @@ impl Site {
         paginator: &'a Paginator,
     ) -> Result<()> {
         ensure_directory_exists(&self.output_path)?;
+        self.paginated_pages_rendered.fetch_add(paginator.pagers.len(), Ordering::Relaxed);
 
         let index_components = components.clone();
 
@@ -1148,6 +1971,7 @@ impl Site {
             .pagers
             .par_iter()
             .map(|pager| {
+                let start = Instant::now();
                 let mut pager_components = index_components.clone();
                 pager_components.push(&paginator.paginate_path);
                 let pager_path = format!("{}", pager.index);
@@ -1158,18 +1982,31 @@ impl Site {
                     &self.tera,
                     &self.library.read().unwrap(),
                 )?;
+                let previous = paginator.pagers.get(pager.index.wrapping_sub(2));
+                let next = paginator.pagers.get(pager.index);
+                let output = head::link_pagination(
+                    output,
+                    &pager.permalink,
+                    previous.map(|p| p.permalink.as_str()),
+                    next.map(|p| p.permalink.as_str()),
+                );
                 let content = self.inject_livereload(output);
 
-                if pager.index > 1 {
-                    self.write_content(&pager_components, "index.html", content, false)?;
+                let current_path = if pager.index > 1 {
+                    self.write_content(&pager_components, "index.html", content, false)?
                 } else {
-                    self.write_content(&index_components, "index.html", content, false)?;
+                    let current_path =
+                        self.write_content(&index_components, "index.html", content, false)?;
                     self.write_content(
                         &pager_components,
                         "index.html",
                         render_redirect_template(&paginator.permalink, &self.tera)?,
                         false,
                     )?;
+                    current_path
+                };
+                if let Some(source) = paginator.source() {
+                    self.record_render(current_path.join("index.html"), source, start);
                 }
 
                 Ok(())
@@ -1178,11 +2015,78 @@ impl Site {
     }
 }
 
+/// Inserts `current_url` and `current_path` in the context of an output that is not a page,
+/// section or taxonomy, eg `404.html`, like the other templates have them
+pub(crate) fn insert_current_path(context: &mut Context, config: &Config, path: &str) {
+    let path = path.trim_start_matches('/');
+    let mut url = config.make_permalink(path);
+    // A file like `404.html` doesn't have a trailing slash
+    if !path.is_empty() && !path.ends_with('/') && url.ends_with('/') {
+        url.pop();
+    }
+    context.insert("current_url", &url);
+    context.insert("current_path", &format!("/{}", path));
+}
+
+/// Reads the value of `SOURCE_DATE_EPOCH`, set by packagers to get reproducible builds.
+/// See <https://reproducible-builds.org/specs/source-date-epoch/>
+fn parse_source_date_epoch(value: Option<&str>) -> Result<Option<OffsetDateTime>> {
+    let value = match value.map(str::trim) {
+        Some(v) if !v.is_empty() => v,
+        _ => return Ok(None),
+    };
+    let timestamp = value.parse::<i64>().map_err(|_| {
+        anyhow!(
+            "SOURCE_DATE_EPOCH should be a number of seconds since the Unix epoch, got `{}`",
+            value
+        )
+    })?;
+    let datetime = OffsetDateTime::from_unix_timestamp(timestamp)
+        .with_context(|| format!("SOURCE_DATE_EPOCH `{}` is out of range", value))?;
+    Ok(Some(datetime))
+}
+
+/// 32 random bytes, hex encoded, as the secret of the preview tokens can be guessed otherwise
+fn random_secret() -> Result<String> {
+    let mut secret = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut secret)
+        .map_err(|_| anyhow!("Failed to generate the secret of the draft previews"))?;
+    Ok(secret.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
 fn log_time(start: Instant, message: &str) -> Instant {
-    let do_print = std::env::var("ZOLA_PERF_LOG").is_ok();
     let now = Instant::now();
-    if do_print {
-        println!("{} took {}ms", message, now.duration_since(start).as_millis());
+    let elapsed = now.duration_since(start).as_millis();
+    // `ZOLA_PERF_LOG` predates `-v` and still prints the timings whatever the verbosity
+    if std::env::var("ZOLA_PERF_LOG").is_ok() {
+        println!("{} took {}ms", message, elapsed);
+    } else {
+        debug!(target: "render", "{} took {}ms", message, elapsed);
     }
     now
 }
+
+fn is_jpeg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("jpg") || ext.eq_ignore_ascii_case("jpeg"))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_source_date_epoch() {
+        assert_eq!(parse_source_date_epoch(None).unwrap(), None);
+        assert_eq!(parse_source_date_epoch(Some("")).unwrap(), None);
+        assert_eq!(
+            parse_source_date_epoch(Some("1654086600")).unwrap(),
+            Some(OffsetDateTime::from_unix_timestamp(1654086600).unwrap())
+        );
+        assert!(parse_source_date_epoch(Some("yesterday")).is_err());
+        assert!(parse_source_date_epoch(Some("99999999999999999")).is_err());
+    }
+}
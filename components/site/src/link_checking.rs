@@ -17,7 +17,7 @@ use libs::url::Url;
 /// are encountered, the `internal_level` setting in config.toml will determine whether they are
 /// treated as warnings or errors.
 pub fn check_internal_links_with_anchors(site: &Site) -> Vec<String> {
-    println!("Checking all internal links with anchors.");
+    console::plain("Checking all internal links with anchors.");
     let library = site.library.write().expect("Get lock for check_internal_links_with_anchors");
 
     // Chain all internal links, from both sections and pages.
@@ -90,13 +90,16 @@ pub fn check_internal_links_with_anchors(site: &Site) -> Vec<String> {
 
     // Finally emit a summary, and return overall anchors-checking result.
     if messages.is_empty() {
-        println!("> Successfully checked {} internal link(s) with anchors.", anchors_total);
+        console::plain(&format!(
+            "> Successfully checked {} internal link(s) with anchors.",
+            anchors_total
+        ));
     } else {
-        println!(
+        console::plain(&format!(
             "> Checked {} internal link(s) with anchors: {} target(s) missing.",
             anchors_total,
             messages.len(),
-        );
+        ));
     }
     messages
 }
@@ -106,13 +109,13 @@ fn should_skip_by_prefix(link: &str, skip_prefixes: &[String]) -> bool {
 }
 
 fn get_link_domain(link: &str) -> Result<String> {
-    return match Url::parse(link) {
+    match Url::parse(link) {
         Ok(url) => match url.host_str().map(String::from) {
             Some(domain_str) => Ok(domain_str),
             None => bail!("could not parse domain `{}` from link", link),
         },
         Err(err) => bail!("could not parse domain `{}` from link: `{}`", link, err),
-    };
+    }
 }
 
 /// Checks all external links and returns all the errors that were encountered.
@@ -170,7 +173,7 @@ pub fn check_external_links(site: &Site) -> Vec<String> {
         }
     }
 
-    println!(
+    console::plain(&format!(
         "Checking {} external link(s). Skipping {} external link(s).{}",
         checked_links.len(),
         skipped_link_count,
@@ -179,7 +182,7 @@ pub fn check_external_links(site: &Site) -> Vec<String> {
         } else {
             format!(" {} link(s) had unparseable URLs.", invalid_url_links)
         }
-    );
+    ));
 
     if checked_links.is_empty() {
         return Vec::new();
@@ -237,11 +240,11 @@ pub fn check_external_links(site: &Site) -> Vec<String> {
                     .collect::<Vec<_>>()
             });
 
-            println!(
+            console::plain(&format!(
                 "> Checked {} external link(s): {} error(s) found.",
                 checked_links.len(),
                 errors.len()
-            );
+            ));
 
             for (page_path, link, check_res) in errors {
                 messages.push(format!(
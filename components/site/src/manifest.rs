@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use libs::sha2::{Digest, Sha256};
+use libs::url::Url;
+use libs::walkdir::WalkDir;
+use serde::Serialize;
+
+use config::Config;
+use errors::{Context, Result};
+use utils::fs::create_file;
+
+pub const MANIFEST_FILENAME: &str = "zola-manifest.json";
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ManifestEntry {
+    /// The path the asset is served at, with its hash as query string like
+    /// `get_url(cachebust=true)` does
+    pub path: String,
+    /// The size of the file in bytes
+    pub size: u64,
+}
+
+/// Writes a `zola-manifest.json` at the root of the output directory, mapping the path of every
+/// file that isn't an HTML page to its final URL path and size.
+pub fn write_manifest(output_path: &Path, config: &Config) -> Result<()> {
+    let base_path = Url::parse(&config.base_url)
+        .map(|url| url.path().trim_end_matches('/').to_string())
+        .unwrap_or_default();
+
+    let mut entries = BTreeMap::new();
+    for entry in WalkDir::new(output_path).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path();
+        if path.extension().map(|ext| ext == "html").unwrap_or(false) {
+            continue;
+        }
+        let relative = path
+            .strip_prefix(output_path)
+            .expect("Walked outside the output directory")
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if relative == MANIFEST_FILENAME {
+            continue;
+        }
+
+        let mut hasher = Sha256::new();
+        let mut file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let size = io::copy(&mut file, &mut hasher)?;
+        let path = format!("{}/{}?h={:x}", base_path, relative, hasher.finalize());
+        entries.insert(relative, ManifestEntry { path, size });
+    }
+
+    let json = libs::serde_json::to_string_pretty(&entries)?;
+    create_file(&output_path.join(MANIFEST_FILENAME), &json)
+}
@@ -0,0 +1,111 @@
+use std::fmt::Write;
+
+use config::Config;
+use content::Library;
+use libs::url::Url;
+
+/// The path of the `base_url` without its trailing slash, eg `/docs` for a site served from
+/// `https://example.com/docs/`, which all the internal paths start with
+fn base_path(config: &Config) -> String {
+    Url::parse(&config.base_url)
+        .map(|url| url.path().trim_end_matches('/').to_string())
+        .unwrap_or_default()
+}
+
+/// The target of a `redirect_to` of a page or section, which is either an internal path or a URL
+fn redirect_target(redirect_to: &str, base_path: &str) -> String {
+    if redirect_to.contains("://") {
+        redirect_to.to_string()
+    } else {
+        format!("{}/{}", base_path, redirect_to.trim_start_matches('/'))
+    }
+}
+
+/// Renders the Netlify `_redirects` file: a permanent redirect for every alias of the pages
+/// and sections and for the ones with a `redirect_to`, followed by the redirects set in the config.
+/// The paths of the pages and sections start with the path of the `base_url` while the redirects
+/// of the config are written as they are.
+pub fn render_redirects(library: &Library, config: &Config) -> String {
+    let base_path = base_path(config);
+    let mut aliases: Vec<_> = library
+        .pages
+        .values()
+        .flat_map(|p| p.meta.aliases.iter().map(move |a| (a, &p.path)))
+        .chain(
+            library
+                .sections
+                .values()
+                .flat_map(|s| s.meta.aliases.iter().map(move |a| (a, &s.path))),
+        )
+        .map(|(alias, path)| {
+            (
+                format!("{}/{}", base_path, alias.trim_start_matches('/')),
+                format!("{}{}", base_path, path),
+            )
+        })
+        .collect();
+    aliases.sort();
+
+    let mut redirects: Vec<_> = library
+        .pages
+        .values()
+        .map(|p| (&p.path, &p.meta.redirect_to))
+        .chain(library.sections.values().map(|s| (&s.path, &s.meta.redirect_to)))
+        .filter_map(|(path, redirect_to)| {
+            let target = redirect_target(redirect_to.as_ref()?, &base_path);
+            Some((format!("{}{}", base_path, path), target))
+        })
+        .collect();
+    redirects.sort();
+
+    let mut out = String::new();
+    for (from, to) in aliases {
+        writeln!(out, "{} {} 301", from, to).unwrap();
+    }
+    for (from, to) in redirects {
+        writeln!(out, "{} {} 301", from, to).unwrap();
+    }
+    for redirect in &config.netlify.redirects {
+        writeln!(out, "{} {} {}", redirect.from, redirect.to, redirect.status).unwrap();
+    }
+    out
+}
+
+/// Renders the Netlify `_headers` file from the config
+pub fn render_headers(config: &Config) -> String {
+    let mut out = String::new();
+    for headers in &config.netlify.headers {
+        writeln!(out, "{}", headers.path).unwrap();
+        for (name, value) in &headers.values {
+            writeln!(out, "  {}: {}", name, value).unwrap();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_render_headers() {
+        let config = Config::parse(
+            r#"
+base_url = "https://example.com"
+
+[[netlify.headers]]
+for = "/*"
+values = { X-Frame-Options = "DENY", Content-Security-Policy = "default-src 'self'" }
+
+[[netlify.headers]]
+for = "/fonts/*"
+values = { Cache-Control = "public, max-age=31536000" }
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            render_headers(&config),
+            "/*\n  Content-Security-Policy: default-src 'self'\n  X-Frame-Options: DENY\n/fonts/*\n  Cache-Control: public, max-age=31536000\n"
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use std::fs::remove_dir_all;
+use std::path::{Path, PathBuf};
+
+use libs::once_cell::sync::Lazy;
+use libs::rayon::prelude::*;
+use libs::regex::{Captures, Regex};
+
+use crate::Site;
+use errors::{Context, Result};
+use utils::fs::{create_directory, create_file};
+
+static STYLE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<style[^>]*>(.*?)</style>").unwrap());
+static CSS_COMMENT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)/\*.*?\*/").unwrap());
+static SIMPLE_SELECTOR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([a-zA-Z][a-zA-Z0-9-]*)?((?:[.#][a-zA-Z0-9_-]+)*)$").unwrap());
+static TAG_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<([a-zA-Z][a-zA-Z0-9-]*)((?:\s[^>]*?)?)(/?)>").unwrap());
+static URL_ATTRIBUTE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(\s(?:href|src)\s*=\s*")([^"]*)(")"#).unwrap());
+static SCHEME_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-zA-Z][a-zA-Z0-9+.-]*:").unwrap());
+
+fn attribute_re(name: &str) -> Regex {
+    Regex::new(&format!(r#"(?i)\s{}\s*=\s*"([^"]*)""#, name)).unwrap()
+}
+
+static CLASS_RE: Lazy<Regex> = Lazy::new(|| attribute_re("class"));
+static ID_RE: Lazy<Regex> = Lazy::new(|| attribute_re("id"));
+static STYLE_ATTRIBUTE_RE: Lazy<Regex> = Lazy::new(|| attribute_re("style"));
+
+/// A CSS rule whose selector only uses a tag name, classes and an id, which can be applied
+/// with a `style` attribute
+#[derive(Debug, PartialEq, Eq)]
+struct InlineRule {
+    tag: Option<String>,
+    classes: Vec<String>,
+    id: Option<String>,
+    declarations: String,
+}
+
+impl InlineRule {
+    fn parse(selector: &str, declarations: &str) -> Option<Self> {
+        let caps = SIMPLE_SELECTOR_RE.captures(selector)?;
+        let mut rule = InlineRule {
+            tag: caps.get(1).map(|m| m.as_str().to_lowercase()),
+            classes: Vec::new(),
+            id: None,
+            declarations: declarations.to_string(),
+        };
+        let mut rest = &caps[2];
+        while !rest.is_empty() {
+            let end = rest[1..].find(['.', '#']).map(|i| i + 1).unwrap_or(rest.len());
+            let name = rest[1..end].to_string();
+            if rest.starts_with('#') {
+                rule.id = Some(name);
+            } else {
+                rule.classes.push(name);
+            }
+            rest = &rest[end..];
+        }
+        if rule.tag.is_none() && rule.classes.is_empty() && rule.id.is_none() {
+            return None;
+        }
+        Some(rule)
+    }
+
+    fn specificity(&self) -> (usize, usize, usize) {
+        (self.id.is_some() as usize, self.classes.len(), self.tag.is_some() as usize)
+    }
+
+    fn matches(&self, tag: &str, classes: &[&str], id: Option<&str>) -> bool {
+        self.tag.as_ref().map(|t| t.eq_ignore_ascii_case(tag)).unwrap_or(true)
+            && self.id.as_deref().map(|i| Some(i) == id).unwrap_or(true)
+            && self.classes.iter().all(|c| classes.contains(&c.as_str()))
+    }
+}
+
+/// Splits the CSS in the rules that can be inlined and the CSS that has to stay in a
+/// `<style>` element, like media queries or selectors depending on other elements
+fn parse_css(css: &str) -> (Vec<InlineRule>, String) {
+    let css = CSS_COMMENT_RE.replace_all(css, "");
+    let mut rules = Vec::new();
+    let mut remaining = String::new();
+    let mut rest = css.trim();
+
+    while let Some(open) = rest.find('{') {
+        let prelude = rest[..open].trim();
+        // Find the matching closing brace, at-rules like `@media` can contain other rules
+        let mut depth = 0;
+        let mut close = rest.len();
+        for (i, c) in rest[open..].char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = open + i;
+                        break;
+                    }
+                }
+                _ => (),
+            }
+        }
+        let body = rest[open + 1..close].trim();
+
+        if prelude.starts_with('@') {
+            remaining.push_str(&format!("{} {{ {} }}\n", prelude, body));
+        } else {
+            for selector in prelude.split(',').map(str::trim) {
+                match InlineRule::parse(selector, body) {
+                    Some(rule) => rules.push(rule),
+                    None => remaining.push_str(&format!("{} {{ {} }}\n", selector, body)),
+                }
+            }
+        }
+        rest = rest.get(close + 1..).unwrap_or_default().trim_start();
+    }
+
+    (rules, remaining)
+}
+
+/// Joins the declarations, the later ones taking precedence like in CSS
+fn join_declarations<'a>(declarations: impl Iterator<Item = &'a str>) -> String {
+    declarations
+        .flat_map(|d| d.split(';'))
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .collect::<Vec<_>>()
+        .join("; ")
+        // Double quotes would end the attribute
+        .replace('"', "'")
+}
+
+/// Moves the CSS of the `<style>` elements to `style` attributes, since a lot of email clients
+/// ignore stylesheets. What can't be inlined is kept in a single `<style>` element.
+pub fn inline_styles(html: &str) -> String {
+    let mut rules = Vec::new();
+    let mut remaining = String::new();
+    for caps in STYLE_RE.captures_iter(html) {
+        let (r, css) = parse_css(&caps[1]);
+        rules.extend(r);
+        remaining.push_str(&css);
+    }
+    if rules.is_empty() && remaining.is_empty() {
+        return html.to_string();
+    }
+    // Later rules win when they are as specific as earlier ones, so the sort needs to be stable
+    rules.sort_by_key(|r| r.specificity());
+
+    let mut first = true;
+    let html = STYLE_RE.replace_all(html, |_: &Captures| {
+        if first && !remaining.is_empty() {
+            first = false;
+            format!("<style>\n{}</style>", remaining)
+        } else {
+            String::new()
+        }
+    });
+
+    TAG_RE
+        .replace_all(&html, |caps: &Captures| {
+            let tag = &caps[1];
+            let attributes = &caps[2];
+            let classes: Vec<&str> = CLASS_RE
+                .captures(attributes)
+                .map(|c| c.get(1).unwrap().as_str().split_whitespace().collect())
+                .unwrap_or_default();
+            let id = ID_RE.captures(attributes).map(|c| c.get(1).unwrap().as_str());
+            let existing =
+                STYLE_ATTRIBUTE_RE.captures(attributes).map(|c| c.get(1).unwrap().as_str());
+
+            let matching: Vec<&str> = rules
+                .iter()
+                .filter(|r| r.matches(tag, &classes, id))
+                .map(|r| r.declarations.as_str())
+                .collect();
+            if matching.is_empty() {
+                return caps[0].to_string();
+            }
+            // The style already on the element takes precedence over the stylesheet
+            let style = join_declarations(matching.into_iter().chain(existing));
+            let attributes = STYLE_ATTRIBUTE_RE.replace(attributes, "");
+            format!("<{}{} style=\"{}\"{}>", tag, attributes, style, &caps[3])
+        })
+        .into_owned()
+}
+
+/// Makes the `href` and `src` attributes absolute since the email won't be read on the site.
+/// Relative URLs are resolved from the permalink of the page.
+pub fn absolute_urls(html: &str, base_url: &str, permalink: &str) -> String {
+    let origin = match base_url.find("://") {
+        Some(i) => match base_url[i + 3..].find('/') {
+            Some(j) => &base_url[..i + 3 + j],
+            None => base_url,
+        },
+        None => base_url.trim_end_matches('/'),
+    };
+    let page_dir = match permalink.rfind('/') {
+        Some(i) => &permalink[..=i],
+        None => permalink,
+    };
+
+    URL_ATTRIBUTE_RE
+        .replace_all(html, |caps: &Captures| {
+            let url = &caps[2];
+            let absolute = if url.is_empty()
+                || url.starts_with('#')
+                || url.starts_with("//")
+                || SCHEME_RE.is_match(url)
+            {
+                url.to_string()
+            } else if url.starts_with('/') {
+                format!("{}{}", origin, url)
+            } else {
+                format!("{}{}", page_dir, url.trim_start_matches("./"))
+            };
+            format!("{}{}{}", &caps[1], absolute, &caps[3])
+        })
+        .into_owned()
+}
+
+fn newsletter_dir(output_path: &Path, path: &str) -> PathBuf {
+    path.split('/').filter(|c| !c.is_empty()).fold(output_path.to_path_buf(), |p, c| p.join(c))
+}
+
+/// Renders the dated pages with the `newsletter.html` template to `newsletter.output_dir`,
+/// with the styles inlined and absolute URLs so they can be pasted in an email.
+/// Redirecting, protected and draft pages are left out.
+pub fn render_newsletter(site: &Site) -> Result<()> {
+    let output_path =
+        site.emptied_dir("newsletter.output_dir", &site.config.newsletter.output_dir)?;
+    if output_path.exists() {
+        remove_dir_all(&output_path).context("Couldn't delete the newsletter output directory")?;
+    }
+
+    let library = site.library.read().unwrap();
+    library
+        .pages
+        .values()
+        .filter(|p| {
+            p.meta.date.is_some()
+                && !p.meta.draft
+                && p.meta.redirect_to.is_none()
+                && p.meta.password.is_none()
+        })
+        .collect::<Vec<_>>()
+        .par_iter()
+        .map(|page| {
+            let html = page.render_newsletter(&site.tera, &site.config, &library)?;
+            let html = absolute_urls(&inline_styles(&html), &site.config.base_url, &page.permalink);
+            let dir = newsletter_dir(&output_path, &page.path);
+            create_directory(&dir)?;
+            create_file(&dir.join("index.html"), &html)
+        })
+        .collect::<Result<()>>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_inline_styles() {
+        let html = r#"<html><head><style>
+/* Only simple selectors can be inlined */
+p, .note { color: red; margin: 0 }
+p.lead { font-size: 20px; }
+#main { padding: 4px; }
+ul li { list-style: none; }
+@media (max-width: 600px) { p { color: blue; } }
+</style></head><body><div id="main"><p class="lead" style="color: green">Hello</p><p>World</p><br/></div></body></html>"#;
+        assert_eq!(
+            inline_styles(html),
+            r#"<html><head><style>
+ul li { list-style: none; }
+@media (max-width: 600px) { p { color: blue; } }
+</style></head><body><div id="main" style="padding: 4px"><p class="lead" style="color: red; margin: 0; font-size: 20px; color: green">Hello</p><p style="color: red; margin: 0">World</p><br/></div></body></html>"#
+        );
+    }
+
+    #[test]
+    fn applies_more_specific_rules_last() {
+        let html = "<style>.a.b { color: red } .a { color: blue }</style><p class=\"a b\">Hi</p>";
+        assert_eq!(inline_styles(html), r#"<p class="a b" style="color: blue; color: red">Hi</p>"#);
+    }
+
+    #[test]
+    fn can_make_urls_absolute() {
+        let html = r##"<a href="/about/">About</a> <img src="cat.png"> <a href="./dog.png">Dog</a>
+<a href="https://example.org">Out</a> <a href="mailto:me@example.com">Mail</a> <a href="#top">Top</a>"##;
+        assert_eq!(
+            absolute_urls(html, "https://example.com/blog", "https://example.com/blog/hello/"),
+            r##"<a href="https://example.com/about/">About</a> <img src="https://example.com/blog/hello/cat.png"> <a href="https://example.com/blog/hello/dog.png">Dog</a>
+<a href="https://example.org">Out</a> <a href="mailto:me@example.com">Mail</a> <a href="#top">Top</a>"##
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Rewrites the internal links of the HTML files to relative paths, so the output can be browsed
+//! from the filesystem without a web server, eg once zipped or copied on a USB stick
+use libs::once_cell::sync::Lazy;
+use libs::regex::{Captures, Regex};
+
+/// The attributes holding a URL, and the URL of the `<meta http-equiv="refresh">` of redirects
+static URL_ATTRIBUTE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)(\s(?:href|src|poster|action)\s*=\s*["']|\scontent\s*=\s*["']\d+;\s*url=)([^"']*)"#,
+    )
+    .unwrap()
+});
+/// `srcset` holds several URLs followed by their width or pixel density
+static SRCSET_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)(\ssrcset\s*=\s*["'])([^"']*)"#).unwrap());
+
+pub struct LinkRewriter<'a> {
+    /// The base URL without its trailing slash, eg `https://example.com/docs`
+    base_url: &'a str,
+    /// Its path, eg `/docs`, as root-relative links include it
+    base_path: &'a str,
+}
+
+impl<'a> LinkRewriter<'a> {
+    pub fn new(base_url: &'a str) -> Self {
+        let base_url = base_url.trim_end_matches('/');
+        let base_path = match base_url.find("://") {
+            Some(idx) => base_url[idx + 3..].find('/').map_or("", |i| &base_url[idx + 3 + i..]),
+            None => "",
+        };
+        Self { base_url, base_path }
+    }
+
+    /// Rewrites the links of an HTML file written `depth` directories below the output root
+    pub fn rewrite(&self, html: &str, depth: usize) -> String {
+        let html = URL_ATTRIBUTE_RE.replace_all(html, |caps: &Captures| {
+            format!("{}{}", &caps[1], self.rewrite_url(&caps[2], depth))
+        });
+        SRCSET_RE
+            .replace_all(&html, |caps: &Captures| {
+                let candidates: Vec<_> = caps[2]
+                    .split(',')
+                    .map(|candidate| {
+                        let candidate = candidate.trim();
+                        match candidate.split_once(' ') {
+                            Some((url, size)) => {
+                                format!("{} {}", self.rewrite_url(url, depth), size)
+                            }
+                            None => self.rewrite_url(candidate, depth),
+                        }
+                    })
+                    .collect();
+                format!("{}{}", &caps[1], candidates.join(", "))
+            })
+            .into_owned()
+    }
+
+    /// Makes an internal URL relative to the directory `depth` levels below the output root,
+    /// linking to the `index.html` of directories explicitly
+    fn rewrite_url(&self, url: &str, depth: usize) -> String {
+        // Tera escapes the slashes of the URLs printed without the `safe` filter
+        let unescaped = url.replace("&#x2F;", "/");
+        let path = if let Some(rest) = unescaped.strip_prefix(self.base_url) {
+            rest
+        } else if unescaped.starts_with('/') && !unescaped.starts_with("//") {
+            unescaped.strip_prefix(self.base_path).unwrap_or(&unescaped)
+        } else {
+            return url.to_string();
+        };
+        // `https://example.com/docs` doesn't contain `https://example.com/documentation`
+        if !path.is_empty() && !path.starts_with(['/', '?', '#']) {
+            return url.to_string();
+        }
+
+        let end = path.find(['?', '#']).unwrap_or(path.len());
+        let (path, suffix) = path.split_at(end);
+        let path = path.trim_start_matches('/');
+        let mut relative = "../".repeat(depth);
+        relative.push_str(path);
+        if path.is_empty() || path.ends_with('/') {
+            relative.push_str("index.html");
+        }
+        relative.push_str(suffix);
+        relative
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_rewrite_internal_links() {
+        let rewriter = LinkRewriter::new("https://example.com/");
+        let html = r##"<a href="https://example.com/">Home</a>
+<a href='https://example.com/blog/post/#intro'>Post</a>
+<a href="https:&#x2F;&#x2F;example.com&#x2F;about&#x2F;">About</a>
+<img src="/blog/post/cat.jpg" srcset="/cat-1x.jpg 1x, https://example.com/cat-2x.jpg 2x">
+<link rel="stylesheet" href="https://example.com/style.css?h=123">
+<a href="https://other.com/">Other</a> <a href="//cdn.com/lib.js">CDN</a> <a href="#top">Top</a>"##;
+        assert_eq!(
+            rewriter.rewrite(html, 2),
+            r##"<a href="../../index.html">Home</a>
+<a href='../../blog/post/index.html#intro'>Post</a>
+<a href="../../about/index.html">About</a>
+<img src="../../blog/post/cat.jpg" srcset="../../cat-1x.jpg 1x, ../../cat-2x.jpg 2x">
+<link rel="stylesheet" href="../../style.css?h=123">
+<a href="https://other.com/">Other</a> <a href="//cdn.com/lib.js">CDN</a> <a href="#top">Top</a>"##
+        );
+    }
+
+    #[test]
+    fn can_rewrite_links_of_a_base_url_with_a_path() {
+        let rewriter = LinkRewriter::new("https://example.com/docs");
+        assert_eq!(
+            rewriter.rewrite(r#"<a href="https://example.com/docs/api/">API</a>"#, 0),
+            r#"<a href="api/index.html">API</a>"#
+        );
+        assert_eq!(
+            rewriter.rewrite(r#"<a href="/docs/">Docs</a>"#, 1),
+            r#"<a href="../index.html">Docs</a>"#
+        );
+        assert_eq!(
+            rewriter.rewrite(r#"<a href="https://example.com/documentation/">Docs</a>"#, 0),
+            r#"<a href="https://example.com/documentation/">Docs</a>"#
+        );
+    }
+
+    #[test]
+    fn can_rewrite_redirects() {
+        let rewriter = LinkRewriter::new("https://example.com");
+        assert_eq!(
+            rewriter.rewrite(
+                r#"<meta http-equiv="refresh" content="0; url=https://example.com/new/">"#,
+                1
+            ),
+            r#"<meta http-equiv="refresh" content="0; url=../new/index.html">"#
+        );
+    }
+}
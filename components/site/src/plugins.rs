@@ -0,0 +1,235 @@
+//! Runs the external programs of the `plugins` config at some steps of the build.
+//! A plugin is given the pages as JSON on its standard input and writes the pages it changed as
+//! JSON on its standard output, so it can be written in any language without forking Zola.
+use std::collections::HashMap;
+use std::path::Path;
+
+use libs::serde_json;
+use libs::tera::{Map, Value};
+use serde::{Deserialize, Serialize};
+
+use config::{Config, PluginHook};
+use content::Page;
+use errors::{Context, Result};
+
+use crate::converters::run_command;
+
+/// What a plugin receives: the hook it is run for and the data of the pages
+#[derive(Debug, Serialize)]
+struct PluginInput<'a> {
+    hook: &'static str,
+    /// Only set for `post_build`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_path: Option<&'a Path>,
+    pages: Vec<PluginPage<'a>>,
+}
+
+/// A page as given to the plugins
+#[derive(Debug, Serialize)]
+struct PluginPage<'a> {
+    /// The path of the file relative to the `content` directory, identifying the page
+    path: &'a str,
+    permalink: &'a str,
+    lang: &'a str,
+    title: &'a Option<String>,
+    description: &'a Option<String>,
+    date: &'a Option<String>,
+    taxonomies: &'a HashMap<String, Vec<String>>,
+    extra: &'a Map<String, Value>,
+    raw_content: &'a str,
+    content: &'a str,
+    summary: &'a Option<String>,
+}
+
+impl<'a> PluginPage<'a> {
+    fn new(page: &'a Page) -> Self {
+        Self {
+            path: &page.file.relative,
+            permalink: &page.permalink,
+            lang: &page.lang,
+            title: &page.meta.title,
+            description: &page.meta.description,
+            date: &page.meta.date,
+            taxonomies: &page.meta.taxonomies,
+            extra: &page.meta.extra,
+            raw_content: &page.raw_content,
+            content: &page.content,
+            summary: &page.summary,
+        }
+    }
+}
+
+/// What a plugin outputs: the pages it changed, with only the fields it changed
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PluginOutput {
+    pages: Vec<PageChanges>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PageChanges {
+    path: String,
+    title: Option<String>,
+    description: Option<String>,
+    extra: Option<Map<String, Value>>,
+    raw_content: Option<String>,
+    content: Option<String>,
+    summary: Option<String>,
+}
+
+impl PageChanges {
+    fn apply(self, page: &mut Page) {
+        if self.title.is_some() {
+            page.meta.title = self.title;
+        }
+        if self.description.is_some() {
+            page.meta.description = self.description;
+        }
+        if let Some(extra) = self.extra {
+            page.meta.extra = extra;
+        }
+        if let Some(raw_content) = self.raw_content {
+            page.raw_content = raw_content;
+        }
+        if let Some(content) = self.content {
+            page.content = content;
+        }
+        if self.summary.is_some() {
+            page.summary = self.summary;
+        }
+    }
+}
+
+/// Runs the plugins of a hook changing the pages, `parse_markdown` or `pre_render_page`, one
+/// after the other on all the pages at once
+pub fn run_page_hook(
+    config: &Config,
+    base_path: &Path,
+    hook: PluginHook,
+    pages: &mut [&mut Page],
+) -> Result<()> {
+    for plugin in config.plugins.iter().filter(|p| p.runs_at(hook)) {
+        let input = PluginInput {
+            hook: hook.name(),
+            output_path: None,
+            pages: pages.iter().map(|p| PluginPage::new(p)).collect(),
+        };
+        let action = format!("run the `{}` hook of the plugin `{}`", hook.name(), plugin.name);
+        let stdout =
+            run_command(&plugin.command, &serde_json::to_string(&input)?, base_path, &action)?;
+        // A plugin changing nothing doesn't need to output anything
+        if stdout.trim().is_empty() {
+            continue;
+        }
+        let output: PluginOutput = serde_json::from_str(&stdout).with_context(|| {
+            format!("The plugin `{}` didn't output valid JSON for `{}`", plugin.name, hook.name())
+        })?;
+
+        let mut by_path: HashMap<_, _> =
+            pages.iter_mut().map(|p| (p.file.relative.clone(), p)).collect();
+        for changes in output.pages {
+            match by_path.get_mut(&changes.path) {
+                Some(page) => changes.apply(page),
+                None => console::warn(&format!(
+                    "The plugin `{}` changed the page `{}` which doesn't exist",
+                    plugin.name, changes.path
+                )),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs the `post_build` plugins once the site is written in `output_path`. Their output is
+/// ignored, they can change the files of the site directly.
+pub fn run_post_build(
+    config: &Config,
+    base_path: &Path,
+    output_path: &Path,
+    pages: &[&Page],
+) -> Result<()> {
+    let hook = PluginHook::PostBuild;
+    for plugin in config.plugins.iter().filter(|p| p.runs_at(hook)) {
+        let input = PluginInput {
+            hook: hook.name(),
+            output_path: Some(output_path),
+            pages: pages.iter().map(|p| PluginPage::new(p)).collect(),
+        };
+        let action = format!("run the `{}` hook of the plugin `{}`", hook.name(), plugin.name);
+        run_command(&plugin.command, &serde_json::to_string(&input)?, base_path, &action)?;
+    }
+    Ok(())
+}
+
+// The plugins of the tests are shell commands
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use config::Plugin;
+
+    fn config_with_plugin(command: &[&str], hooks: Vec<PluginHook>) -> Config {
+        let mut config = Config::default_for_test();
+        config.plugins = vec![Plugin {
+            name: "test".to_string(),
+            command: command.iter().map(|s| s.to_string()).collect(),
+            hooks,
+        }];
+        config
+    }
+
+    fn page(relative: &str, raw_content: &str) -> Page {
+        let mut page = Page::default();
+        page.file.relative = relative.to_string();
+        page.raw_content = raw_content.to_string();
+        page
+    }
+
+    #[test]
+    fn can_change_pages_with_plugin() {
+        let output = r#"{"pages": [{"path": "a.md", "raw_content": "Changed", "title": "A"}]}"#;
+        let config = config_with_plugin(
+            &["sh", "-c", &format!("cat > /dev/null; echo '{}'", output)],
+            vec![PluginHook::ParseMarkdown],
+        );
+        let mut a = page("a.md", "Hello");
+        let mut b = page("b.md", "World");
+        run_page_hook(&config, Path::new("."), PluginHook::ParseMarkdown, &mut [&mut a, &mut b])
+            .unwrap();
+        assert_eq!(a.raw_content, "Changed");
+        assert_eq!(a.meta.title.as_deref(), Some("A"));
+        assert_eq!(b.raw_content, "World");
+
+        // Only the plugins of the hook run
+        run_page_hook(&config, Path::new("."), PluginHook::PreRenderPage, &mut [&mut b]).unwrap();
+        assert_eq!(b.raw_content, "World");
+    }
+
+    #[test]
+    fn plugin_receives_pages_as_json() {
+        // Echoes the input back: it has the same shape as the output for the fields it shares
+        let config = config_with_plugin(&["cat"], vec![PluginHook::PreRenderPage]);
+        let mut a = page("a.md", "Hello");
+        a.content = "<p>Hello</p>".to_string();
+        run_page_hook(&config, Path::new("."), PluginHook::PreRenderPage, &mut [&mut a]).unwrap();
+        assert_eq!(a.content, "<p>Hello</p>");
+        assert_eq!(a.raw_content, "Hello");
+    }
+
+    #[test]
+    fn errors_on_failing_plugin() {
+        let config =
+            config_with_plugin(&["sh", "-c", "echo oops >&2; exit 1"], vec![PluginHook::PostBuild]);
+        let err = run_post_build(&config, Path::new("."), Path::new("public"), &[]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`sh -c echo oops >&2; exit 1` failed to run the `post_build` hook of the plugin \
+             `test`: oops"
+        );
+
+        let config = config_with_plugin(&["echo", "not json"], vec![PluginHook::ParseMarkdown]);
+        let err =
+            run_page_hook(&config, Path::new("."), PluginHook::ParseMarkdown, &mut []).unwrap_err();
+        assert!(err.to_string().contains("didn't output valid JSON"));
+    }
+}
@@ -0,0 +1,110 @@
+use std::num::NonZeroU32;
+
+use libs::base64;
+use libs::ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use libs::ring::pbkdf2;
+use libs::ring::rand::{SecureRandom, SystemRandom};
+use libs::tera::{Context, Tera};
+use serde::Serialize;
+
+use content::Page;
+use errors::{anyhow, bail, Context as ErrorContext, Result};
+
+/// The number of PBKDF2 iterations used to derive the key from the password.
+/// The browser has to do the same work once when the page is unlocked.
+const ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+
+/// What the page needs to decrypt its content in the browser, everything base64 encoded
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct Encrypted {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+    iterations: u32,
+}
+
+fn derive_key(password: &str, salt: &[u8]) -> Result<LessSafeKey> {
+    let mut key = [0; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(ITERATIONS).unwrap(),
+        salt,
+        password.as_bytes(),
+        &mut key,
+    );
+    let key = UnboundKey::new(&AES_256_GCM, &key).map_err(|_| anyhow!("Invalid AES key"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Encrypts the HTML with AES-GCM, using a key derived from the password with PBKDF2 so
+/// the Web Crypto API can decrypt it
+fn encrypt(html: &str, password: &str) -> Result<Encrypted> {
+    let rng = SystemRandom::new();
+    let mut salt = [0; SALT_LEN];
+    let mut nonce = [0; NONCE_LEN];
+    rng.fill(&mut salt).map_err(|_| anyhow!("Failed to generate a random salt"))?;
+    rng.fill(&mut nonce).map_err(|_| anyhow!("Failed to generate a random nonce"))?;
+
+    let key = derive_key(password, &salt)?;
+    let mut in_out = html.as_bytes().to_vec();
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut in_out)
+        .map_err(|_| anyhow!("Failed to encrypt the page"))?;
+
+    Ok(Encrypted {
+        salt: base64::encode(salt),
+        nonce: base64::encode(nonce),
+        ciphertext: base64::encode(in_out),
+        iterations: ITERATIONS,
+    })
+}
+
+/// Renders the `internal/protected.html` template asking for the password of the page,
+/// with the rendered HTML of the page encrypted inside
+pub fn render_protected_page(page: &Page, html: &str, tera: &Tera) -> Result<String> {
+    let password = page.meta.password.as_deref().unwrap_or_default();
+    if password.is_empty() {
+        bail!("Page `{}` has an empty password", page.file.path.display());
+    }
+
+    let mut context = Context::new();
+    context.insert("title", &page.meta.title);
+    context.insert("lang", &page.lang);
+    context.insert("encrypted", &encrypt(html, password)?);
+
+    tera.render("internal/protected.html", &context)
+        .with_context(|| format!("Failed to render protected page '{}'", page.file.path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decrypt(encrypted: &Encrypted, password: &str) -> Option<String> {
+        let salt = base64::decode(&encrypted.salt).unwrap();
+        let nonce = base64::decode(&encrypted.nonce).unwrap();
+        let mut in_out = base64::decode(&encrypted.ciphertext).unwrap();
+        let key = derive_key(password, &salt).unwrap();
+        let nonce = Nonce::try_assume_unique_for_key(&nonce).unwrap();
+        let plaintext = key.open_in_place(nonce, Aad::empty(), &mut in_out).ok()?;
+        Some(String::from_utf8(plaintext.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn can_encrypt_and_decrypt_html() {
+        let html = "<html><body><p>Secret</p></body></html>";
+        let encrypted = encrypt(html, "hunter2").unwrap();
+        assert!(!encrypted.ciphertext.contains("Secret"));
+        assert_eq!(decrypt(&encrypted, "hunter2"), Some(html.to_string()));
+        assert_eq!(decrypt(&encrypted, "hunter3"), None);
+    }
+
+    #[test]
+    fn uses_a_new_salt_and_nonce_every_time() {
+        let first = encrypt("<p>Secret</p>", "hunter2").unwrap();
+        let second = encrypt("<p>Secret</p>", "hunter2").unwrap();
+        assert_ne!(first.salt, second.salt);
+        assert_ne!(first.nonce, second.nonce);
+        assert_ne!(first.ciphertext, second.ciphertext);
+    }
+}
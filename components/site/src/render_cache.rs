@@ -0,0 +1,275 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use libs::once_cell::sync::Lazy;
+use libs::regex::Regex;
+use libs::sha2::{Digest, Sha256};
+use libs::tera::{Context, Tera};
+use libs::walkdir::WalkDir;
+
+use errors::{Context as _, Result};
+use utils::fs::{create_directory, create_file};
+
+use crate::unused::find_templates_using;
+
+/// Keeps the HTML of the pages rendered by the previous builds so the pages whose templates
+/// and template context didn't change are not rendered again.
+/// The context of a page contains its content, the config and the pages it links to as
+/// siblings so any change to those gives a different entry. The password-protected pages are
+/// never cached since their HTML would be kept in plain text, and neither are the pages whose
+/// template can give another output for the same context, see `find_uncacheable_templates`.
+#[derive(Debug)]
+pub struct RenderCache {
+    dir: PathBuf,
+    /// The hash of what all the pages depend on: the templates, the Zola binary and the
+    /// permalinks of the site, which internal links are resolved with
+    base_hash: Vec<u8>,
+    /// The templates whose pages are always rendered
+    uncacheable_templates: HashSet<String>,
+    /// The entries used by the current build, the other ones are removed at the end of it
+    used: Mutex<HashSet<String>>,
+}
+
+/// The functions whose calls are needed for their side effects, like processing images, or whose
+/// output depends on more than the context of the page: other pages, data files or the time
+const UNCACHEABLE_TEMPLATE_FNS: &[&str] = &[
+    "resize_image",
+    "responsive_image",
+    "get_page",
+    "get_section",
+    "get_section_tree",
+    "get_taxonomy",
+    "get_taxonomy_term",
+    "get_taxonomy_url",
+    "get_random_pages",
+    "load_data",
+    "get_file_hash",
+    "get_integrity",
+    "now",
+];
+
+static UNCACHEABLE_CALL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(r"\b(?:{})\s*\(|\bcachebust\b", UNCACHEABLE_TEMPLATE_FNS.join("|")))
+        .unwrap()
+});
+
+/// The templates calling one of `UNCACHEABLE_TEMPLATE_FNS` or `get_url` with `cachebust`, and the
+/// ones extending, including or importing them: a page taken from the cache would miss the images
+/// they generate or the changes of what they load. The shortcodes are rendered with the Markdown
+/// so they don't matter, and the built-in templates, which have no path, don't call them.
+pub fn find_uncacheable_templates(tera: &Tera, theme: Option<&str>) -> HashSet<String> {
+    let mut found = HashSet::new();
+    for template in tera.templates.values() {
+        let source = match template.path {
+            Some(ref path) => fs::read_to_string(path).unwrap_or_default(),
+            None => continue,
+        };
+        if is_uncacheable(&source) {
+            found.extend(find_templates_using(tera, &template.name, theme));
+        }
+    }
+    found
+}
+
+impl RenderCache {
+    pub fn new(
+        dir: PathBuf,
+        template_dirs: &[PathBuf],
+        permalinks: &HashMap<String, String>,
+        uncacheable_templates: HashSet<String>,
+    ) -> Result<Self> {
+        let mut hasher = Sha256::new();
+        // A new version of Zola can render the same templates differently
+        if let Ok(metadata) = std::env::current_exe().and_then(fs::metadata) {
+            hasher.update(metadata.len().to_le_bytes());
+            if let Ok(modified) = metadata.modified() {
+                hasher.update(format!("{:?}", modified).as_bytes());
+            }
+        }
+        for template_dir in template_dirs {
+            let mut entries: Vec<_> = WalkDir::new(template_dir)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .map(|e| e.into_path())
+                .collect();
+            entries.sort();
+            for path in entries {
+                let content = fs::read(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                hasher.update(path.to_string_lossy().as_bytes());
+                hasher.update(&content);
+            }
+        }
+        let mut permalinks: Vec<_> = permalinks.iter().collect();
+        permalinks.sort();
+        for (path, permalink) in permalinks {
+            hasher.update(path.as_bytes());
+            hasher.update(b"\0");
+            hasher.update(permalink.as_bytes());
+            hasher.update(b"\0");
+        }
+
+        Ok(Self {
+            dir,
+            base_hash: hasher.finalize().to_vec(),
+            uncacheable_templates,
+            used: Mutex::new(HashSet::new()),
+        })
+    }
+
+    fn key(&self, template: &str, context: &Context) -> Result<String> {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.base_hash);
+        hasher.update(template.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(libs::serde_json::to_vec(&context.clone().into_json())?);
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.html", key))
+    }
+
+    /// Returns the HTML rendered by a previous build for the same template and context, or
+    /// renders it and keeps it for the next builds
+    pub fn get_or_render(
+        &self,
+        template: &str,
+        context: &Context,
+        render: impl FnOnce() -> Result<String>,
+    ) -> Result<String> {
+        if self.uncacheable_templates.contains(template) {
+            return render();
+        }
+        let key = self.key(template, context)?;
+        let path = self.entry_path(&key);
+        self.used.lock().unwrap().insert(key);
+
+        if let Ok(html) = fs::read_to_string(&path) {
+            return Ok(html);
+        }
+        let html = render()?;
+        create_directory(&self.dir)?;
+        create_file(&path, &html)?;
+        Ok(html)
+    }
+
+    /// Removes the entries that were not used by this build, they belong to pages that
+    /// changed or don't exist anymore
+    pub fn prune(&self) -> Result<()> {
+        if !self.dir.exists() {
+            return Ok(());
+        }
+        let used = self.used.lock().unwrap();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let is_used =
+                path.file_stem().and_then(|s| s.to_str()).map_or(false, |s| used.contains(s));
+            if !is_used && is_cache_entry(&path) {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_uncacheable(template: &str) -> bool {
+    UNCACHEABLE_CALL_RE.is_match(template)
+}
+
+/// Only the files named after a key are entries, nothing else in the directory is removed
+fn is_cache_entry(path: &Path) -> bool {
+    let is_key = |stem: &str| stem.len() == 64 && stem.bytes().all(|b| b.is_ascii_hexdigit());
+    path.extension().map_or(false, |ext| ext == "html")
+        && path.file_stem().and_then(|s| s.to_str()).map_or(false, is_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn can_reuse_pages_with_the_same_context_and_templates() {
+        let tmp = tempdir().unwrap();
+        let templates = tmp.path().join("templates");
+        create_directory(&templates).unwrap();
+        create_file(&templates.join("page.html"), "{{ page.title }}").unwrap();
+        let cache_dir = tmp.path().join(".zola-cache");
+        let mut permalinks = HashMap::new();
+        permalinks.insert("blog/hello.md".to_string(), "https://example.com/hello/".to_string());
+        let new_cache = |permalinks: &HashMap<String, String>, uncacheable: &[&str]| {
+            let uncacheable = uncacheable.iter().map(|t| t.to_string()).collect();
+            RenderCache::new(
+                cache_dir.clone(),
+                std::slice::from_ref(&templates),
+                permalinks,
+                uncacheable,
+            )
+            .unwrap()
+        };
+
+        let mut context = Context::new();
+        context.insert("title", "Hello");
+        let cache = new_cache(&permalinks, &[]);
+        let html = cache.get_or_render("page.html", &context, || Ok("first".to_string())).unwrap();
+        assert_eq!(html, "first");
+        let html = cache.get_or_render("page.html", &context, || panic!("Not cached")).unwrap();
+        assert_eq!(html, "first");
+        // Another template or context is another page
+        let html = cache.get_or_render("other.html", &context, || Ok("other".to_string())).unwrap();
+        assert_eq!(html, "other");
+        context.insert("title", "Bonjour");
+        let html = cache.get_or_render("page.html", &context, || Ok("second".to_string())).unwrap();
+        assert_eq!(html, "second");
+        assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 3);
+
+        // Changing a template invalidates everything
+        create_file(&templates.join("page.html"), "{{ page.title | upper }}").unwrap();
+        let cache = new_cache(&permalinks, &[]);
+        let html = cache.get_or_render("page.html", &context, || Ok("third".to_string())).unwrap();
+        assert_eq!(html, "third");
+        create_file(&cache_dir.join("index.html"), "Not an entry").unwrap();
+        cache.prune().unwrap();
+        assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 2);
+        assert!(cache_dir.join("index.html").exists());
+
+        // A permalink changing invalidates everything as well
+        permalinks.insert("blog/hello.md".to_string(), "https://example.com/bonjour/".to_string());
+        let cache = new_cache(&permalinks, &[]);
+        let html = cache.get_or_render("page.html", &context, || Ok("fourth".to_string())).unwrap();
+        assert_eq!(html, "fourth");
+
+        // The pages of the templates that can't be cached are always rendered
+        let cache = new_cache(&permalinks, &["page.html"]);
+        let html = cache.get_or_render("page.html", &context, || Ok("fifth".to_string())).unwrap();
+        assert_eq!(html, "fifth");
+    }
+
+    #[test]
+    fn can_find_templates_that_cannot_be_cached() {
+        assert!(is_uncacheable("{{ resize_image(path=page.image) }}"));
+        assert!(is_uncacheable("{% set s = get_section(path='blog/_index.md') %}"));
+        assert!(is_uncacheable("{% set data = load_data (path='data.toml') %}"));
+        assert!(is_uncacheable("{{ now() | date }}"));
+        assert!(is_uncacheable("{{ get_url(path='style.css', cachebust=true) }}"));
+        assert!(!is_uncacheable("{{ get_url(path='style.css') }} {{ known }} {{ page.now }}"));
+
+        let tmp = tempdir().unwrap();
+        for (name, content) in [
+            ("base.html", "{{ now() }}{% block content %}{% endblock content %}"),
+            ("page.html", r#"{% extends "base.html" %}"#),
+            ("blog-page.html", "{{ page.title }}"),
+        ] {
+            create_file(&tmp.path().join(name), content).unwrap();
+        }
+        let tera = Tera::new(&format!("{}/*.html", tmp.path().display())).unwrap();
+        let mut found: Vec<_> = find_uncacheable_templates(&tera, None).into_iter().collect();
+        found.sort();
+        assert_eq!(found, vec!["base.html", "page.html"]);
+    }
+}
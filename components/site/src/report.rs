@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use libs::walkdir::WalkDir;
+use serde::Serialize;
+
+use errors::{Context, Result};
+use utils::fs::create_file;
+
+/// How a file of the output directory was rendered
+#[derive(Debug)]
+struct Rendered {
+    /// The content file it was rendered from
+    source: PathBuf,
+    render_time: Duration,
+}
+
+/// Keeps track of the content rendered during a build, for `zola build --output-report`
+#[derive(Debug, Default)]
+pub struct BuildReport {
+    /// By full path of the output file, as pages and sections are rendered in parallel
+    rendered: Mutex<HashMap<PathBuf, Rendered>>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ReportEntry {
+    /// The path of the file relative to the output directory
+    pub path: String,
+    /// The content file it was rendered from, relative to the root of the site.
+    /// `None` for the files that don't come from a single content file, like feeds or static files
+    pub source: Option<String>,
+    /// The size of the file in bytes
+    pub size: u64,
+    /// How long rendering the file took, in milliseconds
+    pub render_time_ms: Option<f64>,
+}
+
+/// What a build generated, printed after `zola build` and added to the build report
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BuildSummary {
+    pub pages: usize,
+    /// Not counting the index section
+    pub sections: usize,
+    /// The pages in a directory without `_index.md`
+    pub orphan_pages: usize,
+    /// The terms of all the taxonomies, in all languages
+    pub taxonomy_terms: usize,
+    /// The pagers of the paginated sections and taxonomy terms
+    pub paginated_pages: usize,
+    /// The files of the `static` directories, whether they had to be copied again or not
+    pub static_files: usize,
+    /// All the files of the output directory
+    pub output_files: usize,
+    /// The size of the output directory in bytes
+    pub output_size: u64,
+    /// How long loading and building the site took, in milliseconds
+    pub build_time_ms: f64,
+}
+
+/// The JSON written by `zola build --output-report`
+#[derive(Serialize)]
+struct Report<'a> {
+    summary: &'a BuildSummary,
+    files: Vec<ReportEntry>,
+}
+
+/// Counts the files of a directory and their total size in bytes
+pub fn measure_directory(path: &Path) -> Result<(usize, u64)> {
+    let mut files = 0;
+    let mut size = 0;
+    for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+        if entry.file_type().is_file() {
+            files += 1;
+            size += entry
+                .metadata()
+                .with_context(|| {
+                    format!("Failed to read the metadata of {}", entry.path().display())
+                })?
+                .len();
+        }
+    }
+    Ok((files, size))
+}
+
+/// Joins the components of a path with `/` so reports are the same on every platform
+fn to_slash_path(path: &Path) -> String {
+    path.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+}
+
+impl BuildReport {
+    pub fn record(&self, output_file: PathBuf, source: &Path, render_time: Duration) {
+        self.rendered
+            .lock()
+            .unwrap()
+            .insert(output_file, Rendered { source: source.to_path_buf(), render_time });
+    }
+
+    /// Lists every file of the output directory, sorted by path
+    pub fn entries(&self, output_path: &Path, base_path: &Path) -> Result<Vec<ReportEntry>> {
+        let rendered = self.rendered.lock().unwrap();
+        let mut entries = Vec::new();
+        for entry in WalkDir::new(output_path).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path = entry.path();
+            let size = entry
+                .metadata()
+                .with_context(|| format!("Failed to read the metadata of {}", path.display()))?
+                .len();
+            let rendered = rendered.get(path);
+            entries.push(ReportEntry {
+                path: to_slash_path(
+                    path.strip_prefix(output_path).expect("Walked outside the output directory"),
+                ),
+                source: rendered
+                    .map(|r| to_slash_path(r.source.strip_prefix(base_path).unwrap_or(&r.source))),
+                size,
+                render_time_ms: rendered.map(|r| r.render_time.as_secs_f64() * 1000.0),
+            });
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(entries)
+    }
+
+    /// Writes the summary of the build and the files of the output directory as JSON to
+    /// `report_path`
+    pub fn write(
+        &self,
+        output_path: &Path,
+        base_path: &Path,
+        report_path: &Path,
+        summary: &BuildSummary,
+    ) -> Result<()> {
+        let files = self.entries(output_path, base_path)?;
+        let json = libs::serde_json::to_string_pretty(&Report { summary, files })?;
+        create_file(report_path, &json)
+    }
+}
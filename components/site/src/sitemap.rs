@@ -5,7 +5,7 @@ use std::hash::{Hash, Hasher};
 use serde::Serialize;
 
 use config::Config;
-use content::{Library, Taxonomy};
+use content::{Archive, Library, Taxonomy};
 use libs::tera::{Map, Value};
 use std::cmp::Ordering;
 
@@ -44,7 +44,7 @@ impl<'a> SitemapEntry<'a> {
 
 impl<'a> PartialOrd for SitemapEntry<'a> {
     fn partial_cmp(&self, other: &SitemapEntry) -> Option<Ordering> {
-        Some(self.permalink.as_ref().cmp(other.permalink.as_ref()))
+        Some(self.cmp(other))
     }
 }
 
@@ -54,16 +54,18 @@ impl<'a> Ord for SitemapEntry<'a> {
     }
 }
 
-/// Finds out all the links to put in a sitemap from the pages/sections/taxonomies
+/// Finds out all the links to put in a sitemap from the pages/sections/taxonomies/archives
 /// There are no duplicate permalinks in the output vec
 pub fn find_entries<'a>(
     library: &'a Library,
     taxonomies: &'a [Taxonomy],
+    archives: &'a [Archive],
     config: &'a Config,
 ) -> Vec<SitemapEntry<'a>> {
     let mut entries = HashSet::new();
 
-    for p in library.pages.values() {
+    // Redirects and the pages only written in other formats than HTML are left out
+    for p in library.pages.values().filter(|p| !p.is_draft_preview() && p.has_html_page(config)) {
         let mut entry = SitemapEntry::new(
             Cow::Borrowed(&p.permalink),
             if p.meta.updated.is_some() { &p.meta.updated } else { &p.meta.date },
@@ -80,9 +82,14 @@ pub fn find_entries<'a>(
         }
 
         if let Some(paginate_by) = s.paginate_by() {
-            let number_pagers = (s.pages.len() as f64 / paginate_by as f64).ceil() as isize;
+            let num_pages = s.pages_to_paginate().len();
+            let number_pagers = (num_pages as f64 / paginate_by as f64).ceil() as isize;
             for i in 1..=number_pagers {
-                let permalink = format!("{}{}/{}/", s.permalink, s.meta.paginate_path, i);
+                let permalink = if s.paginate_path().is_empty() {
+                    format!("{}{}/", s.permalink, i)
+                } else {
+                    format!("{}{}/{}/", s.permalink, s.paginate_path(), i)
+                };
                 entries.insert(SitemapEntry::new(Cow::Owned(permalink), &None));
             }
         }
@@ -102,16 +109,12 @@ pub fn find_entries<'a>(
             ));
 
             if taxonomy.kind.is_paginated() {
-                let number_pagers = (item.pages.len() as f64
-                    / taxonomy.kind.paginate_by.unwrap() as f64)
-                    .ceil() as isize;
+                let number_pagers =
+                    (item.pages.len() as f64 / taxonomy.kind.paginate_by as f64).ceil() as isize;
                 for i in 1..=number_pagers {
                     let permalink = config.make_permalink(&format!(
                         "{}/{}/{}/{}",
-                        name,
-                        item.slug,
-                        taxonomy.kind.paginate_path(),
-                        i
+                        name, item.slug, &taxonomy.kind.paginate_path, i
                     ));
                     entries.insert(SitemapEntry::new(Cow::Owned(permalink), &None));
                 }
@@ -119,6 +122,10 @@ pub fn find_entries<'a>(
         }
     }
 
+    for archive in archives {
+        entries.insert(SitemapEntry::new(Cow::Borrowed(&archive.permalink), &None));
+    }
+
     let mut entries = entries.into_iter().collect::<Vec<_>>();
     entries.sort();
     entries
@@ -1,13 +1,72 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
 use crate::Site;
-use libs::tera::Result as TeraResult;
+use libs::tera::{Filter, Function, Result as TeraResult, Value};
 use templates::{filters, global_fns};
 
+/// The filters and functions added by the users of the crate with `Site::register_filter` and
+/// `Site::register_function`. They are registered after the built-in ones so they can replace
+/// them.
+#[derive(Clone, Default)]
+pub struct CustomTeraFns {
+    pub(crate) filters: Vec<(String, Arc<dyn Filter>)>,
+    pub(crate) functions: Vec<(String, Arc<dyn Function>)>,
+}
+
+impl fmt::Debug for CustomTeraFns {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomTeraFns")
+            .field("filters", &self.filters.iter().map(|(n, _)| n).collect::<Vec<_>>())
+            .field("functions", &self.functions.iter().map(|(n, _)| n).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Tera takes ownership of what it registers, so the custom filters are shared to be
+/// registered again on every load
+struct SharedFilter(Arc<dyn Filter>);
+
+impl Filter for SharedFilter {
+    fn filter(&self, value: &Value, args: &HashMap<String, Value>) -> TeraResult<Value> {
+        self.0.filter(value, args)
+    }
+
+    fn is_safe(&self) -> bool {
+        self.0.is_safe()
+    }
+}
+
+struct SharedFunction(Arc<dyn Function>);
+
+impl Function for SharedFunction {
+    fn call(&self, args: &HashMap<String, Value>) -> TeraResult<Value> {
+        self.0.call(args)
+    }
+
+    fn is_safe(&self) -> bool {
+        self.0.is_safe()
+    }
+}
+
+fn register_custom_fns(site: &mut Site) {
+    for (name, filter) in &site.custom_tera_fns.filters {
+        site.tera.register_filter(name, SharedFilter(filter.clone()));
+    }
+    for (name, function) in &site.custom_tera_fns.functions {
+        site.tera.register_function(name, SharedFunction(function.clone()));
+    }
+}
+
 /// Adds global fns that are to be available to shortcodes while rendering markdown
 pub fn register_early_global_fns(site: &mut Site) -> TeraResult<()> {
     site.tera.register_filter(
         "num_format",
         filters::NumFormatFilter::new(&site.config.default_language),
     );
+    site.tera.register_filter("slugify", filters::SlugifyFilter::new(site.config.slugify.clone()));
+    site.tera.register_filter("date", filters::DateFilter::new(site.config.clone()));
 
     site.tera.register_function(
         "get_url",
@@ -27,6 +86,16 @@ pub fn register_early_global_fns(site: &mut Site) -> TeraResult<()> {
             site.output_path.clone(),
         ),
     );
+    site.tera.register_function(
+        "responsive_image",
+        global_fns::ResponsiveImage::new(
+            site.base_path.clone(),
+            site.imageproc.clone(),
+            site.config.theme.clone(),
+            site.output_path.clone(),
+            site.config.images.clone(),
+        ),
+    );
     site.tera.register_function(
         "get_image_metadata",
         global_fns::GetImageMetadata::new(
@@ -43,7 +112,13 @@ pub fn register_early_global_fns(site: &mut Site) -> TeraResult<()> {
             site.output_path.clone(),
         ),
     );
+    if let Some(datetime) = site.source_date_epoch {
+        site.tera.register_function("now", global_fns::Now::new(datetime));
+    }
     site.tera.register_function("trans", global_fns::Trans::new(site.config.clone()));
+    site.tera
+        .register_function("text_direction", global_fns::TextDirection::new(site.config.clone()));
+    site.tera.register_function("favicon_tags", global_fns::FaviconTags::new(site.config.clone()));
     site.tera.register_function(
         "get_taxonomy_url",
         global_fns::GetTaxonomyUrl::new(
@@ -52,23 +127,23 @@ pub fn register_early_global_fns(site: &mut Site) -> TeraResult<()> {
             site.config.slugify.taxonomies,
         ),
     );
-    site.tera.register_function(
-        "get_file_hash",
-        global_fns::GetFileHash::new(
-            site.base_path.clone(),
-            site.config.theme.clone(),
-            site.output_path.clone(),
-        ),
+    let file_search = global_fns::FileSearch::new(
+        site.base_path.clone(),
+        site.config.theme.clone(),
+        site.output_path.clone(),
+        site.file_hashes_used.clone(),
     );
+    site.tera.register_function("get_file_hash", global_fns::GetFileHash::new(file_search.clone()));
+    site.tera.register_function("get_integrity", global_fns::GetIntegrity::new(file_search));
 
-    site.tera.register_filter(
-        "markdown",
-        filters::MarkdownFilter::new(
-            site.config.clone(),
-            site.permalinks.clone(),
-            site.tera.clone(),
-        ),
+    let mut markdown_filter = filters::MarkdownFilter::new(
+        site.config.clone(),
+        site.permalinks.clone(),
+        site.tera.clone(),
     );
+    markdown_filter.set_shortcode_definitions(site.shortcode_definitions.clone());
+    site.tera.register_filter("markdown", markdown_filter);
+    register_custom_fns(site);
 
     Ok(())
 }
@@ -79,10 +154,34 @@ pub fn register_tera_global_fns(site: &mut Site) {
         "get_page",
         global_fns::GetPage::new(site.base_path.clone(), site.library.clone()),
     );
+    site.tera.register_function(
+        "get_random_pages",
+        global_fns::GetRandomPages::new(
+            site.base_path.clone(),
+            site.library.clone(),
+            &site.config.default_language,
+        ),
+    );
     site.tera.register_function(
         "get_section",
         global_fns::GetSection::new(site.base_path.clone(), site.library.clone()),
     );
+    site.tera.register_function(
+        "get_section_tree",
+        global_fns::GetSectionTree::new(site.base_path.clone(), site.library.clone()),
+    );
+    site.tera.register_function(
+        "feed_tags",
+        global_fns::FeedTags::new(
+            site.base_path.clone(),
+            site.config.clone(),
+            site.library.clone(),
+        ),
+    );
+    site.tera.register_function(
+        "get_menu_state",
+        global_fns::GetMenuState::new(site.permalinks.clone()),
+    );
     site.tera.register_function(
         "get_taxonomy",
         global_fns::GetTaxonomy::new(
@@ -91,4 +190,5 @@ pub fn register_tera_global_fns(site: &mut Site) {
             site.library.clone(),
         ),
     );
+    register_custom_fns(site);
 }
@@ -0,0 +1,303 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use libs::once_cell::sync::Lazy;
+use libs::regex::Regex;
+use libs::tera::ast::Node;
+use libs::tera::{Template, Tera};
+use templates::ZOLA_TERA;
+
+use crate::Site;
+
+/// The templates Zola renders by name that have no built-in version, the ones with a built-in
+/// version being found in `ZOLA_TERA`
+const RENDERED_TEMPLATES: &[&str] = &[
+    "index.html",
+    "section.html",
+    "page.html",
+    "amp.html",
+    "archive.html",
+    "series.html",
+    "taxonomy_list.html",
+    "taxonomy_single.html",
+];
+
+/// The built-in templates Zola renders by name, which a site can override: all of them but
+/// the built-in shortcodes
+fn builtin_template_names() -> impl Iterator<Item = &'static str> {
+    ZOLA_TERA.templates.keys().filter_map(|name| {
+        let name = name.strip_prefix("__zola_builtins/")?;
+        if name.starts_with("shortcodes/") {
+            None
+        } else {
+            Some(name)
+        }
+    })
+}
+
+/// A shortcode call in the content, `{{ name(` or `{% name(`
+static SHORTCODE_CALL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{[{%]-?\s*([a-zA-Z_][a-zA-Z0-9_-]*)\s*\(").unwrap());
+
+/// The templates and shortcodes of the `templates` directory of the site that are never used
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct UnusedTemplates {
+    /// The names of the templates, eg `partials/footer.html`
+    pub templates: Vec<String>,
+    /// The names of the shortcodes, eg `youtube`
+    pub shortcodes: Vec<String>,
+}
+
+/// Finds the templates a template extends, includes or imports macros from
+pub(crate) fn find_dependencies(template: &Template) -> Vec<String> {
+    fn walk(nodes: &[Node], found: &mut Vec<String>) {
+        for node in nodes {
+            match node {
+                Node::Extends(_, name) | Node::ImportMacro(_, name, _) => found.push(name.clone()),
+                Node::Include(_, names, _) => found.extend(names.iter().cloned()),
+                Node::MacroDefinition(_, definition, _) => walk(&definition.body, found),
+                Node::FilterSection(_, section, _) => walk(&section.body, found),
+                Node::Block(_, block, _) => walk(&block.body, found),
+                Node::Forloop(_, forloop, _) => {
+                    walk(&forloop.body, found);
+                    if let Some(ref body) = forloop.empty_body {
+                        walk(body, found);
+                    }
+                }
+                Node::If(condition, _) => {
+                    for (_, _, body) in &condition.conditions {
+                        walk(body, found);
+                    }
+                    if let Some((_, ref body)) = condition.otherwise {
+                        walk(body, found);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    let mut found = Vec::new();
+    walk(&template.ast, &mut found);
+    found
+}
+
+/// Finds the templates extending, including or importing macros from the given one, directly or
+/// not, and the template itself. The templates of the theme are also returned without their
+/// prefix since they are rendered by that name when the site doesn't override them.
+pub(crate) fn find_templates_using(
+    tera: &Tera,
+    name: &str,
+    theme: Option<&str>,
+) -> HashSet<String> {
+    let theme_prefix = theme.map(|t| format!("{}/templates/", t));
+    // The templates of the theme use the ones of the site with the same name
+    let unprefixed = |name: &str| -> String {
+        theme_prefix
+            .as_ref()
+            .and_then(|prefix| name.strip_prefix(prefix.as_str()))
+            .unwrap_or(name)
+            .to_string()
+    };
+    let mut users: HashMap<String, Vec<&str>> = HashMap::new();
+    for template in tera.templates.values() {
+        for dependency in find_dependencies(template) {
+            users.entry(dependency).or_default().push(&template.name);
+        }
+    }
+
+    let mut found = HashSet::new();
+    let mut to_visit = vec![name.to_string()];
+    while let Some(name) = to_visit.pop() {
+        if !found.insert(name.clone()) {
+            continue;
+        }
+        let name = unprefixed(&name);
+        if let Some(users) = users.get(&name) {
+            to_visit.extend(users.iter().map(|u| u.to_string()));
+        }
+        found.insert(name);
+    }
+    found
+}
+
+impl Site {
+    /// The names of the templates the site renders: the ones Zola always looks for and the ones
+    /// set in the front matter and in the config
+    pub(crate) fn find_rendered_template_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = RENDERED_TEMPLATES
+            .iter()
+            .copied()
+            .chain(builtin_template_names())
+            .map(String::from)
+            .collect();
+        names.push(self.config.feed_filename.clone());
+        names.extend(self.config.languages.values().map(|l| l.feed_filename.clone()));
+
+        let library = self.library.read().unwrap();
+        for page in library.pages.values() {
+            names.extend(page.meta.template.clone());
+        }
+        for section in library.sections.values() {
+            names.extend(section.meta.template.clone());
+            names.extend(section.meta.page_template.clone());
+            names.extend(section.meta.feed_template.clone());
+        }
+        for taxonomy in &self.taxonomies {
+            names.push(format!("{}/list.html", taxonomy.kind.name));
+            names.push(format!("{}/single.html", taxonomy.kind.name));
+            names.extend(taxonomy.kind.feed_template.clone());
+        }
+        names
+    }
+
+    /// The names of the shortcodes called in the content of the pages and sections
+    fn find_called_shortcodes(&self) -> HashSet<String> {
+        let library = self.library.read().unwrap();
+        let contents = library
+            .pages
+            .values()
+            .map(|p| &p.raw_content)
+            .chain(library.sections.values().map(|s| &s.raw_content));
+        contents
+            .flat_map(|content| SHORTCODE_CALL_RE.captures_iter(content).map(|c| c[1].to_string()))
+            .collect()
+    }
+
+    /// Finds the templates and shortcodes of the `templates` directory that no page, section,
+    /// taxonomy or other template uses, directly or not.
+    /// The templates of the theme are left out since a site doesn't need all of them.
+    pub fn find_unused_templates(&self) -> UnusedTemplates {
+        let theme_prefix = self.config.theme.as_ref().map(|t| format!("{}/templates/", t));
+        let resolve = |name: &str| -> Option<&Template> {
+            self.tera.templates.get(name).or_else(|| {
+                theme_prefix
+                    .as_ref()
+                    .and_then(|prefix| self.tera.templates.get(&format!("{}{}", prefix, name)))
+            })
+        };
+
+        let mut to_visit = self.find_rendered_template_names();
+        to_visit.extend(
+            self.find_called_shortcodes()
+                .into_iter()
+                .filter_map(|name| self.shortcode_definitions.get(&name))
+                .map(|definition| definition.tera_name.clone()),
+        );
+        let mut used_files = HashSet::new();
+        let mut visited = HashSet::new();
+        while let Some(name) = to_visit.pop() {
+            if !visited.insert(name.clone()) {
+                continue;
+            }
+            if let Some(template) = resolve(&name) {
+                used_files.extend(template.path.clone());
+                to_visit.extend(find_dependencies(template));
+            }
+        }
+
+        let templates_path = self.base_path.join("templates");
+        let mut unused = UnusedTemplates::default();
+        for template in self.tera.templates.values() {
+            let path = match template.path {
+                Some(ref p) => p,
+                None => continue,
+            };
+            if used_files.contains(path) || !Path::new(path).starts_with(&templates_path) {
+                continue;
+            }
+            match template.name.strip_prefix("shortcodes/") {
+                Some(shortcode) => {
+                    let name = shortcode.rsplit_once('.').map_or(shortcode, |(name, _)| name);
+                    unused.shortcodes.push(name.to_string());
+                }
+                None => unused.templates.push(template.name.clone()),
+            }
+        }
+        unused.templates.sort();
+        unused.shortcodes.sort();
+        unused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_find_dependencies_of_template() {
+        let template = Template::new(
+            "page.html",
+            None,
+            r#"{% extends "base.html" %}
+{% import "macros.html" as macros %}
+{% block content %}
+{% for p in pages %}{% include "card.html" %}{% else %}{% include "empty.html" %}{% endfor %}
+{% if a %}{% include ["a.html", "b.html"] ignore missing %}{% endif %}
+{% endblock content %}"#,
+        )
+        .unwrap();
+        let mut dependencies = find_dependencies(&template);
+        dependencies.sort();
+        assert_eq!(
+            dependencies,
+            vec!["a.html", "b.html", "base.html", "card.html", "empty.html", "macros.html"]
+        );
+    }
+
+    #[test]
+    fn can_find_templates_using_template() {
+        let mut tera = Tera::default();
+        tera.add_raw_templates(vec![
+            ("base.html", "{% block content %}{% endblock content %}"),
+            ("macros.html", "{% macro hello() %}Hello{% endmacro hello %}"),
+            ("page.html", r#"{% extends "base.html" %}{% import "macros.html" as macros %}"#),
+            ("blog-page.html", r#"{% extends "page.html" %}"#),
+            ("section.html", r#"{% extends "base.html" %}"#),
+            ("theme/templates/404.html", r#"{% include "macros.html" %}"#),
+        ])
+        .unwrap();
+
+        let sorted = |found: HashSet<String>| {
+            let mut found: Vec<_> = found.into_iter().collect();
+            found.sort();
+            found
+        };
+        assert_eq!(
+            sorted(find_templates_using(&tera, "page.html", Some("theme"))),
+            vec!["blog-page.html", "page.html"]
+        );
+        assert_eq!(
+            sorted(find_templates_using(&tera, "macros.html", Some("theme"))),
+            vec![
+                "404.html",
+                "blog-page.html",
+                "macros.html",
+                "page.html",
+                "theme/templates/404.html"
+            ]
+        );
+        assert_eq!(
+            sorted(find_templates_using(&tera, "base.html", None)),
+            vec!["base.html", "blog-page.html", "page.html", "section.html"]
+        );
+    }
+
+    #[test]
+    fn can_list_the_builtin_templates_rendered_by_name() {
+        let names: Vec<_> = builtin_template_names().collect();
+        for name in ["404.html", "podcast.xml", "feeds.opml", "section.gmi", "anchor-link.html"] {
+            assert!(names.contains(&name), "{} is missing", name);
+        }
+        assert!(!names.iter().any(|n| n.starts_with("shortcodes/")));
+    }
+
+    #[test]
+    fn can_find_shortcode_calls() {
+        let content =
+            "{{ youtube(id=1) }} {%- quote(author=\"Vincent\") -%}Hi{% end %} {{ not_a_call }}";
+        let calls: Vec<_> =
+            SHORTCODE_CALL_RE.captures_iter(content).map(|c| c[1].to_string()).collect();
+        assert_eq!(calls, vec!["youtube", "quote"]);
+    }
+}
@@ -4,12 +4,17 @@ use std::collections::HashMap;
 use std::env;
 use std::path::{Path, PathBuf};
 
-use common::{build_site, build_site_with_setup};
-use config::TaxonomyConfig;
-use content::Page;
+use common::{build_site, build_site_with_setup, site_tempdir};
+use config::{OutputFormat, TaxonomyConfig};
+use content::{Enclosure, Page, SortBy};
 use libs::ahash::AHashMap;
+use libs::globset::{Glob, GlobSetBuilder};
+use libs::relative_path::RelativePathBuf;
+use libs::tera::Context;
 use site::sitemap;
-use site::Site;
+use site::{Site, SITE_BINARY_CONTENT};
+use tempfile::tempdir;
+use utils::types::RawHtml;
 
 #[test]
 fn can_parse_site() {
@@ -92,7 +97,8 @@ fn can_parse_site() {
         prog_section.meta.extra.get("we_have_extra").and_then(|s| s.as_str()),
         Some("variables")
     );
-    let sitemap_entries = sitemap::find_entries(&library, &site.taxonomies[..], &site.config);
+    let sitemap_entries =
+        sitemap::find_entries(&library, &site.taxonomies[..], &site.archives[..], &site.config);
     let sitemap_entry = sitemap_entries
         .iter()
         .find(|e| e.permalink.ends_with("tutorials/programming/"))
@@ -115,111 +121,1181 @@ fn errors_on_unknown_taxonomies() {
     );
 }
 
+#[test]
+fn can_find_backlinks() {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let config_file = path.join("config.toml");
+    let mut site = Site::new(&path, &config_file).unwrap();
+    site.include_drafts();
+    site.load().unwrap();
+    let draft_path = path.join("content").join("posts").join("draft.md");
+    assert_eq!(
+        site.library.read().unwrap().backlinks["hello.md"],
+        vec![draft_path.clone()].into_iter().collect()
+    );
+
+    // Backlinks are updated when a single page is re-rendered, as in `zola serve --fast`
+    let new_page_path = path.join("content").join("posts").join("new.md");
+    let page = Page::parse(
+        &new_page_path,
+        "+++\ntitle = \"New\"\n+++\nLink to [root](@/hello.md).",
+        &site.config,
+        &path,
+    )
+    .unwrap();
+    site.add_page(page, true).unwrap();
+    assert_eq!(
+        site.library.read().unwrap().backlinks["hello.md"],
+        vec![draft_path, new_page_path].into_iter().collect()
+    );
+}
+
 #[test]
 fn can_build_site_without_live_reload() {
     let (_, _tmp_dir, public) = build_site("test_site");
 
-    assert!(&public.exists());
-    assert!(file_exists!(public, "index.html"));
-    assert!(file_exists!(public, "sitemap.xml"));
-    assert!(file_exists!(public, "robots.txt"));
-    assert!(file_exists!(public, "a-fixed-url/index.html"));
-    // the config.title is there
-    assert!(file_contains!(public, "index.html", "My Integration Testing site"));
+    assert!(&public.exists());
+    assert!(file_exists!(public, "index.html"));
+    assert!(file_exists!(public, "sitemap.xml"));
+    assert!(file_exists!(public, "robots.txt"));
+    assert!(file_exists!(public, "a-fixed-url/index.html"));
+    // the config.title is there
+    assert!(file_contains!(public, "index.html", "My Integration Testing site"));
+
+    assert!(file_exists!(public, "posts/python/index.html"));
+    // Shortcodes work
+    assert!(file_contains!(public, "posts/python/index.html", "Basic shortcode"));
+    assert!(file_contains!(public, "posts/python/index.html", "Arrrh Bob"));
+    assert!(file_contains!(public, "posts/python/index.html", "Arrrh Bob_Sponge"));
+    // The menu entries of the page and of its section are active, not the home page one
+    assert!(file_contains!(public, "posts/python/index.html", "Active menu: Posts\n"));
+    assert!(file_contains!(public, "posts/python/index.html", "Active menu: Python (current)"));
+    assert!(!file_contains!(public, "posts/python/index.html", "Active menu: Home"));
+    assert!(file_contains!(public, "posts/simple/index.html", "Active menu: Posts\n"));
+    assert!(!file_contains!(public, "posts/simple/index.html", "Active menu: Python"));
+    assert!(file_exists!(public, "posts/tutorials/devops/nix/index.html"));
+    assert!(file_exists!(public, "posts/with-assets/index.html"));
+    assert!(file_exists!(public, "posts/no-section/simple/index.html"));
+
+    // Sections
+    assert!(file_exists!(public, "posts/index.html"));
+    assert!(file_exists!(public, "posts/tutorials/index.html"));
+    assert!(file_exists!(public, "posts/tutorials/devops/index.html"));
+    assert!(file_exists!(public, "posts/tutorials/programming/index.html"));
+    // Ensure subsection pages are correctly filled
+    assert!(file_contains!(public, "posts/tutorials/index.html", "Sub-pages: 2"));
+
+    // Pages and section get their relative path
+    assert!(file_contains!(public, "posts/tutorials/index.html", "posts/tutorials/_index.md"));
+    assert!(file_contains!(
+        public,
+        "posts/tutorials/devops/nix/index.html",
+        "posts/tutorials/devops/nix.md"
+    ));
+
+    // aliases work
+    assert!(file_exists!(public, "an-old-url/old-page/index.html"));
+    assert!(file_contains!(public, "an-old-url/old-page/index.html", "something-else"));
+    assert!(file_contains!(public, "another-old-url/index.html", "posts/"));
+
+    // html aliases work
+    assert!(file_exists!(public, "an-old-url/an-old-alias.html"));
+    assert!(file_contains!(public, "an-old-url/an-old-alias.html", "something-else"));
+
+    // redirect_to works
+    assert!(file_exists!(public, "posts/tutorials/devops/index.html"));
+    assert!(file_contains!(public, "posts/tutorials/devops/index.html", "docker"));
+
+    // We do have categories
+    assert!(file_exists!(public, "categories/index.html"));
+    assert!(file_exists!(public, "categories/a-category/index.html"));
+    assert!(file_exists!(public, "categories/a-category/atom.xml"));
+    // feed_tags links to the feeds of the site and of the term or the section
+    assert!(file_contains!(
+        public,
+        "categories/a-category/index.html",
+        r#"<link rel="alternate" type="application/atom+xml" title="My Integration Testing site" href="https://replace-this-with-your-url.com/atom.xml">"#
+    ));
+    assert!(file_contains!(
+        public,
+        "categories/a-category/index.html",
+        r#"<link rel="alternate" type="application/atom+xml" title="categories: a-category" href="https://replace-this-with-your-url.com/categories/a-category/atom.xml">"#
+    ));
+    assert!(file_contains!(
+        public,
+        "posts/index.html",
+        r#"href="https://replace-this-with-your-url.com/posts/atom.xml">"#
+    ));
+    // Only the site feed for a section without one
+    assert!(!file_contains!(public, "posts/tutorials/index.html", "tutorials/atom.xml"));
+    // and podcast_authors (https://github.com/getzola/zola/issues/1177)
+    assert!(file_exists!(public, "podcast-authors/index.html"));
+    assert!(file_exists!(public, "podcast-authors/some-person/index.html"));
+    assert!(file_exists!(public, "podcast-authors/some-person/atom.xml"));
+    // But no tags
+    assert!(!file_exists!(public, "tags/index.html"));
+
+    // Theme files are there
+    assert!(file_exists!(public, "sample.css"));
+    assert!(file_exists!(public, "some.js"));
+
+    // SASS and SCSS files compile correctly
+    assert!(file_exists!(public, "blog.css"));
+    assert!(file_contains!(public, "blog.css", "red"));
+    assert!(file_contains!(public, "blog.css", "blue"));
+    assert!(!file_contains!(public, "blog.css", "@import \"included\""));
+    assert!(file_contains!(public, "blog.css", "2rem")); // check include
+    assert!(!file_exists!(public, "_included.css"));
+    assert!(file_exists!(public, "scss.css"));
+    assert!(file_exists!(public, "sass.css"));
+    assert!(file_exists!(public, "nested_sass/sass.css"));
+    assert!(file_exists!(public, "nested_sass/scss.css"));
+
+    assert!(!file_exists!(public, "secret_section/index.html"));
+    assert!(!file_exists!(public, "secret_section/page.html"));
+    assert!(!file_exists!(public, "secret_section/secret_sub_section/hello.html"));
+    // no live reload code
+    assert!(!file_contains!(public, "index.html", "/livereload.js?port=1112&amp;mindelay=10"),);
+
+    // Both pages and sections are in the sitemap
+    assert!(file_contains!(
+        public,
+        "sitemap.xml",
+        "<loc>https://replace-this-with-your-url.com/posts/simple/</loc>"
+    ));
+    assert!(file_contains!(
+        public,
+        "sitemap.xml",
+        "<loc>https://replace-this-with-your-url.com/posts/</loc>"
+    ));
+    // Drafts are not in the sitemap
+    assert!(!file_contains!(public, "sitemap.xml", "draft"));
+    // render: false sections are not in the sitemap either
+    assert!(!file_contains!(public, "sitemap.xml", "posts/2018/</loc>"));
+
+    // Series get an index page and their pages a navigation across sections
+    assert!(file_exists!(public, "series/learning/index.html"));
+    assert!(file_contains!(public, "series/learning/index.html", "Series: Learning"));
+    assert!(file_contains!(
+        public,
+        "posts/python/index.html",
+        "Previous in series: https://replace-this-with-your-url.com/posts/tutorials/programming/rust/"
+    ));
+    assert!(file_contains!(
+        public,
+        "posts/python/index.html",
+        "Next in series: https://replace-this-with-your-url.com/posts/simple/"
+    ));
+    assert!(!file_contains!(public, "posts/simple/index.html", "Next in series"));
+
+    // robots.txt has been rendered from the template
+    assert!(file_contains!(public, "robots.txt", "User-agent: zola"));
+    assert!(file_contains!(
+        public,
+        "robots.txt",
+        "Sitemap: https://replace-this-with-your-url.com/sitemap.xml"
+    ));
+    // The menu of the config is resolved and sorted by weight
+    assert!(file_contains!(
+        public,
+        "index.html",
+        "<a href=\"https://replace-this-with-your-url.com/\">Home</a>"
+    ));
+    assert!(file_contains!(
+        public,
+        "index.html",
+        "<a href=\"https://replace-this-with-your-url.com/posts/\">Posts</a>"
+    ));
+    assert!(file_contains!(
+        public,
+        "index.html",
+        "<a href=\"https://replace-this-with-your-url.com/posts/python/\">Python</a>"
+    ));
+
+    // and has a current_url/current_path like every other template
+    assert!(file_contains!(
+        public,
+        "robots.txt",
+        "# Rendered at https://replace-this-with-your-url.com/robots.txt (/robots.txt)"
+    ));
+}
+
+#[test]
+fn can_build_site_with_permalink_pattern() {
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.default_permalink = Some("/read/:section/:slug/".to_string());
+        (site, true)
+    });
+
+    let library = site.library.read().unwrap();
+    let page = &library.pages[&site.base_path.join("content").join("posts").join("simple.md")];
+    assert_eq!(page.path, "/read/posts/simple/");
+    assert_eq!(page.permalink, "https://replace-this-with-your-url.com/read/posts/simple/");
+    // `path` in the front matter wins
+    let page = &library.pages[&site.base_path.join("content").join("posts").join("fixed-url.md")];
+    assert_eq!(page.path, "/a-fixed-url/");
+
+    assert!(file_exists!(public, "read/posts/simple/index.html"));
+    assert!(!file_exists!(public, "posts/simple/index.html"));
+    assert!(file_exists!(public, "read/posts/tutorials/programming/rust/index.html"));
+    // sections are not affected
+    assert!(file_exists!(public, "posts/index.html"));
+    assert!(file_contains!(
+        public,
+        "sitemap.xml",
+        "<loc>https://replace-this-with-your-url.com/read/posts/simple/</loc>"
+    ));
+}
+
+#[test]
+fn can_build_site_with_responsive_images() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.images.breakpoints = vec![100, 200];
+        site.config.images.sizes = Some("(max-width: 600px) 100vw, 50vw".to_string());
+        site.config.images.markdown_srcset = true;
+        (site, true)
+    });
+
+    assert!(file_contains!(public, "posts/with-assets/index.html", " 100w, "));
+    assert!(file_contains!(public, "posts/with-assets/index.html", " 200w\""));
+    assert!(file_contains!(
+        public,
+        "posts/with-assets/index.html",
+        "sizes=\"(max-width: 600px) 100vw, 50vw\""
+    ));
+    assert!(file_contains!(public, "posts/with-assets/index.html", "alt=\"The Zola logo\""));
+    assert!(file_contains!(public, "posts/with-assets/index.html", "width=\"200\""));
+    assert!(file_exists!(public, "processed_images"));
+    assert!(!file_contains!(public, "posts/with-assets/index.html", "<picture>"));
+    // Images we can't resize are left as is
+    assert!(file_contains!(
+        public,
+        "posts/with-assets/index.html",
+        r#"<img src="https://replace-this-with-your-url.com/posts/with-assets/logo.svg" alt="The Zola logo as SVG" />"#
+    ));
+    assert!(file_contains!(
+        public,
+        "posts/with-assets/index.html",
+        r#"<img src="https://replace-this-with-your-url.com/posts/with-assets/missing.png" alt="A missing image" />"#
+    ));
+}
+
+#[test]
+fn can_build_site_with_webp_responsive_images() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.images.breakpoints = vec![100];
+        site.config.images.markdown_srcset = true;
+        site.config.images.webp = true;
+        (site, true)
+    });
+
+    assert!(file_contains!(
+        public,
+        "posts/with-assets/index.html",
+        "<picture><source type=\"image/webp\""
+    ));
+    assert!(file_contains!(public, "posts/with-assets/index.html", ".webp 100w"));
+    assert!(file_contains!(public, "posts/with-assets/index.html", ".png 100w"));
+}
+
+#[test]
+fn can_build_site_stripping_exif_of_assets() {
+    let has_exif = |path: &Path| std::fs::read(path).unwrap().windows(4).any(|w| w == b"Exif");
+
+    let (site, _tmp_dir, public) = build_site("test_site");
+    assert!(has_exif(&site.base_path.join("content/posts/with-assets/photo.jpg")));
+    assert!(has_exif(&public.join("posts/with-assets/photo.jpg")));
+
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.images.strip_exif = true;
+        (site, true)
+    });
+    assert!(!has_exif(&public.join("posts/with-assets/photo.jpg")));
+    // other assets are copied as usual
+    assert!(file_exists!(public, "posts/with-assets/zola.png"));
+}
+
+#[test]
+fn can_build_site_with_precompressed_output() {
+    use std::fs::File;
+    use std::io::Read;
+
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.precompress = true;
+        (site, true)
+    });
+
+    let html = std::fs::read_to_string(public.join("index.html")).unwrap();
+    let mut gz = String::new();
+    libs::flate2::read::GzDecoder::new(File::open(public.join("index.html.gz")).unwrap())
+        .read_to_string(&mut gz)
+        .unwrap();
+    assert_eq!(gz, html);
+    let mut br = String::new();
+    libs::brotli::Decompressor::new(File::open(public.join("index.html.br")).unwrap(), 4096)
+        .read_to_string(&mut br)
+        .unwrap();
+    assert_eq!(br, html);
+
+    assert!(file_exists!(public, "sitemap.xml.gz"));
+    assert!(file_exists!(public, "sitemap.xml.br"));
+    assert!(file_exists!(public, "posts/index.html.br"));
+    // Only text files are compressed
+    assert!(!file_exists!(public, "posts/with-assets/zola.png.gz"));
+    assert!(!file_exists!(public, "index.html.gz.gz"));
+}
+
+#[test]
+fn can_build_site_with_asset_manifest() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.generate_asset_manifest = true;
+        (site, true)
+    });
+
+    let manifest: libs::serde_json::Value = libs::serde_json::from_str(
+        &std::fs::read_to_string(public.join("zola-manifest.json")).unwrap(),
+    )
+    .unwrap();
+    let manifest = manifest.as_object().unwrap();
+
+    let js = &manifest["scripts/hello.js"];
+    let size = std::fs::metadata(public.join("scripts/hello.js")).unwrap().len();
+    assert_eq!(js["size"], libs::serde_json::json!(size));
+    assert!(js["path"].as_str().unwrap().starts_with("/scripts/hello.js?h="));
+    // Compiled Sass files are listed too
+    assert!(manifest.contains_key("scss.css"));
+    assert!(manifest.contains_key("sitemap.xml"));
+    // but not the pages or the manifest itself
+    assert!(!manifest.contains_key("index.html"));
+    assert!(!manifest.contains_key("zola-manifest.json"));
+}
+
+#[test]
+fn can_summarize_build() {
+    let (site, _tmp_dir, public) = build_site("test_site");
+    let summary = site.build_summary(std::time::Duration::from_secs(1)).unwrap();
+    let library = site.library.read().unwrap();
+    assert_eq!(summary.pages, library.pages.len());
+    assert_eq!(summary.sections, library.sections.len() - 1);
+    assert_eq!(summary.orphan_pages, 1);
+    assert_eq!(
+        summary.taxonomy_terms,
+        site.taxonomies.iter().map(|t| t.items.len()).sum::<usize>()
+    );
+    // The posts section is paginated
+    assert!(summary.paginated_pages > 1);
+    assert!(summary.static_files > 0);
+    assert!(summary.output_files > summary.static_files);
+    let sitemap_size = std::fs::metadata(public.join("sitemap.xml")).unwrap().len();
+    assert!(summary.output_size > sitemap_size);
+    assert_eq!(summary.build_time_ms, 1000.0);
+}
+
+#[test]
+fn can_merge_env_overlay_into_config() {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let config_file = path.join("config.toml");
+
+    let site = Site::new_in_env(&path, &config_file, Some("staging")).unwrap();
+    assert_eq!(site.config.base_url, "https://staging.com");
+    assert_eq!(site.config.extra["environment"].as_str(), Some("staging"));
+    // The tables are merged rather than replaced
+    assert_eq!(site.config.extra["author"]["name"].as_str(), Some("Vincent Prouillet"));
+    assert!(site.config.compile_sass);
+
+    assert!(Site::new_in_env(&path, &config_file, Some("production")).is_err());
+}
+
+#[test]
+fn builds_are_reproducible() {
+    let read_output = |public: &std::path::Path| {
+        libs::walkdir::WalkDir::new(public)
+            .sort_by_file_name()
+            .into_iter()
+            .map(|e| e.unwrap())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| {
+                let path = e.path().strip_prefix(public).unwrap().to_path_buf();
+                (path, std::fs::read(e.path()).unwrap())
+            })
+            .collect::<Vec<_>>()
+    };
+    let (_, _tmp_dir, public) = build_site("test_site");
+    let (_, _other_tmp_dir, other_public) = build_site("test_site");
+    let (first, second) = (read_output(&public), read_output(&other_public));
+    assert_eq!(first.len(), second.len());
+    for ((path, content), (other_path, other_content)) in first.iter().zip(&second) {
+        assert_eq!(path, other_path);
+        assert!(content == other_content, "{} differs between builds", path.display());
+    }
+}
+
+#[test]
+fn can_build_site_with_build_report() {
+    let (site, tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.enable_build_report();
+        (site, true)
+    });
+    let report_path = tmp_dir.path().join("report.json");
+    let summary = site.build_summary(std::time::Duration::from_millis(1500)).unwrap();
+    site.write_build_report(&report_path, &summary).unwrap();
+
+    let report: libs::serde_json::Value =
+        libs::serde_json::from_str(&std::fs::read_to_string(report_path).unwrap()).unwrap();
+    assert_eq!(report["summary"]["pages"], libs::serde_json::json!(summary.pages));
+    assert_eq!(report["summary"]["build_time_ms"], libs::serde_json::json!(1500.0));
+    let report = report["files"].as_array().unwrap();
+    let entry = |path: &str| report.iter().find(|e| e["path"] == path).unwrap();
+
+    let page = entry("posts/simple/index.html");
+    assert_eq!(page["source"], "content/posts/simple.md");
+    let size = std::fs::metadata(public.join("posts/simple/index.html")).unwrap().len();
+    assert_eq!(page["size"], libs::serde_json::json!(size));
+    assert!(page["render_time_ms"].is_f64());
+    // The first pager is rendered at the section path, the others come from the same content file
+    assert_eq!(entry("posts/index.html")["source"], "content/posts/_index.md");
+    assert_eq!(entry("posts/page/2/index.html")["source"], "content/posts/_index.md");
+    // Files not coming from a content file are listed without source
+    let sitemap = entry("sitemap.xml");
+    assert!(sitemap["source"].is_null());
+    assert!(sitemap["render_time_ms"].is_null());
+    assert!(report.iter().any(|e| e["path"] == "scripts/hello.js"));
+}
+
+#[test]
+fn can_build_site_with_netlify_files() {
+    let (_, _tmp_dir, public) = build_site("test_site");
+    assert!(!file_exists!(public, "_redirects"));
+    assert!(!file_exists!(public, "_headers"));
+
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.netlify = libs::toml::from_str(
+            r#"
+generate_redirects = true
+redirects = [{ from = "/old-blog/*", to = "/posts/:splat", status = 302 }]
+headers = [{ for = "/*", values = { X-Frame-Options = "DENY" } }]
+"#,
+        )
+        .unwrap();
+        (site, true)
+    });
+
+    let redirects = std::fs::read_to_string(public.join("_redirects")).unwrap();
+    assert_eq!(
+        redirects.lines().collect::<Vec<_>>(),
+        vec![
+            "/an-old-url/an-old-alias.html /posts/something-else/ 301",
+            "/an-old-url/old-page /posts/something-else/ 301",
+            "/another-old-url/index.html /posts/ 301",
+            "/top-level.html /posts/top-level-alias/ 301",
+            "/posts/tutorials/devops/ /posts/tutorials/devops/docker 301",
+            "/old-blog/* /posts/:splat 302",
+        ]
+    );
+    assert!(file_contains!(public, "_headers", "/*\n  X-Frame-Options: DENY\n"));
+    // the meta-refresh pages are still rendered for other hosts
+    assert!(file_exists!(public, "top-level.html"));
+
+    // The paths include the one of the base URL of sites served from a subdirectory
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.base_url = "https://example.com/docs/".to_string();
+        site.config.netlify.generate_redirects = true;
+        (site, true)
+    });
+    assert!(file_contains!(
+        public,
+        "_redirects",
+        "/docs/top-level.html /docs/posts/top-level-alias/ 301\n"
+    ));
+    assert!(file_contains!(
+        public,
+        "_redirects",
+        "/docs/posts/tutorials/devops/ /docs/posts/tutorials/devops/docker 301\n"
+    ));
+}
+
+#[test]
+fn can_build_site_with_redirecting_pages() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.netlify.generate_redirects = true;
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let content = site.base_path.join("content").join("posts");
+            library.pages.get_mut(&content.join("simple.md")).unwrap().meta.redirect_to =
+                Some("posts/python".to_string());
+            library.pages.get_mut(&content.join("extra_syntax.md")).unwrap().meta.redirect_to =
+                Some("https://example.com/elsewhere".to_string());
+        }
+        (site, false)
+    });
+
+    assert!(file_contains!(
+        public,
+        "posts/simple/index.html",
+        r#"<meta http-equiv="refresh" content="0; url=https://replace-this-with-your-url.com/posts/python/">"#
+    ));
+    assert!(!file_contains!(public, "posts/simple/index.html", "A simple page"));
+    assert!(file_contains!(
+        public,
+        "posts/extra-syntax/index.html",
+        "https://example.com/elsewhere"
+    ));
+    let redirects = std::fs::read_to_string(public.join("_redirects")).unwrap();
+    assert!(redirects.contains("/posts/simple/ /posts/python 301\n"));
+    assert!(redirects.contains("/posts/extra-syntax/ https://example.com/elsewhere 301\n"));
+    assert!(!file_contains!(public, "sitemap.xml", "/posts/simple/</loc>"));
+}
+
+#[test]
+fn can_build_site_with_page_outputs() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let content = site.base_path.join("content").join("posts");
+            library.pages.get_mut(&content.join("python.md")).unwrap().meta.outputs =
+                Some(vec![OutputFormat::Html, OutputFormat::Json, OutputFormat::Txt]);
+            library.pages.get_mut(&content.join("simple.md")).unwrap().meta.outputs =
+                Some(vec![OutputFormat::Json]);
+        }
+        (site, false)
+    });
+
+    assert!(file_exists!(public, "posts/python/index.html"));
+    assert!(file_contains!(public, "posts/python/index.json", r#""title":"Python in posts""#));
+    assert!(file_contains!(public, "posts/python/index.json", "Same filename but different path"));
+    assert!(file_contains!(
+        public,
+        "posts/python/index.txt",
+        "Python in posts\n\nSame filename but different path"
+    ));
+    assert!(!file_contains!(public, "posts/python/index.txt", "<p>"));
+    assert!(file_exists!(public, "posts/simple/index.json"));
+    assert!(!file_exists!(public, "posts/simple/index.html"));
+    // The sitemap only lists HTML pages
+    assert!(file_contains!(public, "sitemap.xml", "/posts/python/</loc>"));
+    assert!(!file_contains!(public, "sitemap.xml", "/posts/simple/</loc>"));
+    // Only HTML by default
+    assert!(!file_exists!(public, "posts/fixed-slug/index.json"));
+}
+
+#[test]
+fn can_share_macros_between_site_and_theme() {
+    let (site, _tmp_dir, _public) = build_site("test_site");
+
+    // Theme macros can be imported by their name or in the namespace of the theme
+    let output = site.tera.render("theme-macros.html", &Context::new()).unwrap();
+    assert!(output.contains("site-site"));
+    assert!(output.contains("namespaced-namespaced"));
+    // And theme templates can import the macros of the site
+    let output = site.tera.render("sample/templates/site-macros.html", &Context::new()).unwrap();
+    assert!(output.contains("THEME!"));
+}
+
+#[test]
+fn can_build_feeds_opml() {
+    let (_, _tmp_dir, public) = build_site("test_site");
+
+    assert!(file_contains!(
+        public,
+        "feeds.opml",
+        r#"<outline type="rss" text="My Integration Testing site" title="My Integration Testing site" xmlUrl="https://replace-this-with-your-url.com/atom.xml" htmlUrl="https://replace-this-with-your-url.com/"/>"#
+    ));
+    assert!(file_contains!(
+        public,
+        "feeds.opml",
+        r#"xmlUrl="https://replace-this-with-your-url.com/posts/tutorials/programming/atom.xml""#
+    ));
+    assert!(file_contains!(
+        public,
+        "feeds.opml",
+        r#"text="categories: a-category" title="categories: a-category" xmlUrl="https://replace-this-with-your-url.com/categories/a-category/atom.xml""#
+    ));
+    // Only the feeds that were generated are listed
+    assert!(!file_contains!(public, "feeds.opml", "/tags/"));
+}
+
+#[test]
+fn can_use_a_different_feed_template_for_sections_and_taxonomies() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        for taxonomy in site.config.languages.get_mut("en").unwrap().taxonomies.iter_mut() {
+            if taxonomy.name == "podcast_authors" {
+                taxonomy.feed_template = Some("custom_feed.xml".to_string());
+            }
+        }
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let posts = site.base_path.join("content").join("posts").join("_index.md");
+            library.sections.get_mut(&posts).unwrap().meta.feed_template =
+                Some("custom_feed.xml".to_string());
+        }
+        (site, false)
+    });
+
+    // The feeds keep their name but use the other template
+    assert!(file_contains!(public, "posts/atom.xml", "<itunes:explicit>false</itunes:explicit>"));
+    assert!(file_contains!(
+        public,
+        "podcast-authors/some-person/atom.xml",
+        "<itunes:explicit>false</itunes:explicit>"
+    ));
+    // The other feeds are untouched
+    assert!(!file_contains!(public, "posts/tutorials/programming/atom.xml", "itunes"));
+    assert!(!file_contains!(public, "categories/a-category/atom.xml", "itunes"));
+    assert!(!file_contains!(public, "atom.xml", "itunes"));
+}
+
+#[test]
+fn can_render_orphan_report() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.orphan_report.generate = true;
+        site.config.orphan_report.path = "internal/orphans".to_string();
+        (site, true)
+    });
+
+    assert!(file_exists!(public, "internal/orphans/index.html"));
+    assert!(file_contains!(
+        public,
+        "internal/orphans/index.html",
+        "1 page(s) are not in any section"
+    ));
+    assert!(file_contains!(
+        public,
+        "internal/orphans/index.html",
+        r#"<a href="https://replace-this-with-your-url.com/posts/no-section/simple/">Simple</a>"#
+    ));
+    assert!(file_contains!(public, "internal/orphans/index.html", r#"content="noindex""#));
+    assert!(!file_contains!(public, "sitemap.xml", "internal/orphans"));
+}
+
+#[test]
+fn can_build_podcast_feeds() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.podcast.author = Some("Some Person".to_string());
+        site.config.podcast.owner_email = Some("person@example.com".to_string());
+        site.config.podcast.image = Some("/cover.png".to_string());
+        site.config.podcast.categories = vec!["Society & Culture/Documentary".to_string()];
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let posts = site.base_path.join("content").join("posts");
+            library.sections.get_mut(&posts.join("_index.md")).unwrap().meta.feed_template =
+                Some("podcast.xml".to_string());
+            library.pages.get_mut(&posts.join("with-assets").join("index.md")).unwrap().enclosure =
+                Some(Enclosure {
+                    url: "https://replace-this-with-your-url.com/posts/with-assets/zola.png"
+                        .to_string(),
+                    length: 1234,
+                    mime_type: "image/png".to_string(),
+                });
+        }
+        (site, false)
+    });
+
+    assert!(file_contains!(
+        public,
+        "posts/atom.xml",
+        r#"xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd""#
+    ));
+    assert!(file_contains!(public, "posts/atom.xml", "<itunes:author>Some Person</itunes:author>"));
+    assert!(file_contains!(
+        public,
+        "posts/atom.xml",
+        "<itunes:email>person@example.com</itunes:email>"
+    ));
+    assert!(file_contains!(
+        public,
+        "posts/atom.xml",
+        r#"<itunes:image href="https://replace-this-with-your-url.com/cover.png"/>"#
+    ));
+    assert!(file_contains!(
+        public,
+        "posts/atom.xml",
+        r#"<itunes:category text="Society &amp; Culture"><itunes:category text="Documentary"/></itunes:category>"#
+    ));
+    assert!(file_contains!(public, "posts/atom.xml", "<itunes:explicit>false</itunes:explicit>"));
+    assert!(file_contains!(
+        public,
+        "posts/atom.xml",
+        r#"<enclosure url="https://replace-this-with-your-url.com/posts/with-assets/zola.png" length="1234" type="image/png"/>"#
+    ));
+    // The other feeds have the enclosure as well
+    assert!(file_contains!(
+        public,
+        "atom.xml",
+        r#"<link rel="enclosure" href="https://replace-this-with-your-url.com/posts/with-assets/zola.png" length="1234" type="image/png"/>"#
+    ));
+    assert!(!file_contains!(public, "atom.xml", "itunes"));
+}
+
+#[test]
+fn can_copy_nested_assets_and_ignore_some() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        let mut builder = GlobSetBuilder::new();
+        builder.add(Glob::new("*.js").unwrap());
+        site.config.ignored_assets_globset = Some(builder.build().unwrap());
+        (site, true)
+    });
+
+    // The subdirectories of the assets are kept, for sections as well
+    assert!(file_exists!(public, "posts/with-assets/sources/notes.txt"));
+    assert!(file_exists!(public, "posts/tutorials/diagrams/flow.txt"));
+    assert!(file_exists!(public, "posts/with-assets/zola.png"));
+    assert!(!file_exists!(public, "posts/with-assets/with.js"));
+}
+
+#[cfg(unix)]
+#[test]
+fn only_hard_links_the_assets_above_the_minimum_size() {
+    use std::os::unix::fs::MetadataExt;
+
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.hard_link_static = true;
+        site.config.hard_link_static_min_size = 1000;
+        (site, true)
+    });
+
+    let content = site.base_path.join("content").join("posts").join("with-assets");
+    let is_hard_link = |name: &str| {
+        std::fs::metadata(public.join("posts/with-assets").join(name)).unwrap().ino()
+            == std::fs::metadata(content.join(name)).unwrap().ino()
+    };
+    assert!(std::fs::metadata(content.join("zola.png")).unwrap().len() >= 1000);
+    assert!(is_hard_link("zola.png"));
+    assert!(std::fs::metadata(content.join("with.js")).unwrap().len() < 1000);
+    assert!(!is_hard_link("with.js"));
+}
+
+#[test]
+fn ignores_the_static_files_of_ignored_directories() {
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        let mut builder = GlobSetBuilder::new();
+        builder.add(Glob::new("scripts").unwrap());
+        site.config.ignored_static_globset = Some(builder.build().unwrap());
+        (site, true)
+    });
+
+    assert!(!file_exists!(public, "scripts/hello.js"));
+    assert!(site.is_ignored_static_file(&site.static_path.join("scripts").join("hello.js")));
+    assert!(!site.is_ignored_static_file(&site.static_path.join("site.css")));
+}
+
+#[test]
+fn can_build_site_with_calendar() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.calendar.sections = vec!["posts/tutorials".to_string()];
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let content = site.base_path.join("content").join("posts").join("tutorials");
+            let extra = &mut library
+                .pages
+                .get_mut(&content.join("programming").join("python.md"))
+                .unwrap()
+                .meta
+                .extra;
+            extra.insert("end_date".to_string(), "2017-01-02".into());
+            extra.insert("location".to_string(), "Paris, France".into());
+        }
+        (site, false)
+    });
+
+    assert!(file_contains!(public, "events.ics", "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(file_contains!(
+        public,
+        "events.ics",
+        "DTSTART;VALUE=DATE:20170101\r\nDTEND;VALUE=DATE:20170103\r\n"
+    ));
+    assert!(file_contains!(public, "events.ics", "LOCATION:Paris\\, France\r\n"));
+    assert!(file_contains!(public, "events.ics", "SUMMARY:Python tutorial\r\n"));
+    // Only the pages of the sections in the config are events
+    assert!(!file_contains!(public, "events.ics", "/posts/simple/"));
+}
+
+#[test]
+fn can_build_site_with_amp_pages() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let page = library
+                .pages
+                .get_mut(&site.base_path.join("content").join("posts").join("python.md"))
+                .unwrap();
+            page.amp_permalink = Some(format!("{}amp/", page.permalink));
+        }
+        (site, false)
+    });
+
+    assert!(file_contains!(
+        public,
+        "posts/python/index.html",
+        r#"<link rel="amphtml" href="https://replace-this-with-your-url.com/posts/python/amp/"></head>"#
+    ));
+    assert!(file_contains!(public, "posts/python/amp/index.html", "<html amp"));
+    assert!(file_contains!(
+        public,
+        "posts/python/amp/index.html",
+        r#"<link rel="canonical" href="https://replace-this-with-your-url.com/posts/python/"></head>"#
+    ));
+    assert!(file_contains!(
+        public,
+        "posts/python/amp/index.html",
+        "Same filename but different path"
+    ));
+    assert!(!file_exists!(public, "posts/simple/amp/index.html"));
+}
+
+#[test]
+fn can_build_site_with_newsletter() {
+    // The newsletter output has to be inside the site
+    let newsletter_dir = site_tempdir("test_site");
+    let newsletter = newsletter_dir.path().to_path_buf();
+    build_site_with_setup("test_site", |mut site| {
+        site.config.newsletter.generate = true;
+        site.config.newsletter.output_dir =
+            newsletter.file_name().unwrap().to_string_lossy().to_string();
+        (site, true)
+    });
+
+    assert!(file_contains!(
+        newsletter,
+        "posts/python/index.html",
+        "Same filename but different path"
+    ));
+    // The styles of the built-in template are inlined
+    assert!(file_contains!(
+        newsletter,
+        "posts/python/index.html",
+        r#"<div class="container" style="max-width: 600px"#
+    ));
+    assert!(!file_contains!(newsletter, "posts/python/index.html", "<style>"));
+    assert!(file_contains!(
+        newsletter,
+        "posts/python/index.html",
+        r#"href="https://replace-this-with-your-url.com/posts/python/""#
+    ));
+    // Only dated pages are exported
+    assert!(!file_exists!(newsletter, "posts/skip_prefixes/index.html"));
+    assert!(!file_exists!(newsletter, "posts/index.html"));
+}
+
+#[test]
+fn cannot_write_gemini_output_outside_the_site() {
+    for output_dir in ["content", ".", "../gemini", "themes/sample", "/tmp/gemini"] {
+        let mut path =
+            env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+        path.push("test_site");
+        let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+        site.config.gemini.generate = true;
+        site.config.gemini.output_dir = output_dir.to_string();
+        site.load().unwrap();
+        let tmp_dir = tempdir().expect("create temp dir");
+        site.set_output_path(tmp_dir.path().join("public"));
+        assert!(site.build().is_err(), "`{}` was accepted as Gemini output", output_dir);
+    }
+}
+
+#[test]
+fn cannot_write_newsletter_outside_the_site() {
+    for output_dir in ["templates", ".", "../newsletter", "static/newsletter", "/tmp/newsletter"] {
+        let mut path =
+            env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+        path.push("test_site");
+        let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+        site.config.newsletter.generate = true;
+        site.config.newsletter.output_dir = output_dir.to_string();
+        site.load().unwrap();
+        let tmp_dir = tempdir().expect("create temp dir");
+        site.set_output_path(tmp_dir.path().join("public"));
+        assert!(site.build().is_err(), "`{}` was accepted as newsletter output", output_dir);
+    }
+}
+
+#[test]
+fn can_build_site_with_gemini_output() {
+    // The Gemini output has to be inside the site
+    let gemini_dir = site_tempdir("test_site");
+    let gemini = gemini_dir.path().to_path_buf();
+    let (_, _tmp_dir, _public) = build_site_with_setup("test_site", |mut site| {
+        site.config.gemini.generate = true;
+        // An absolute path keeps the output out of the test site
+        site.config.gemini.output_dir = gemini.file_name().unwrap().to_string_lossy().to_string();
+        (site, true)
+    });
+
+    assert!(file_contains!(gemini, "posts/python/index.gmi", "# Python in posts\n\n2017-03-01"));
+    assert!(file_contains!(gemini, "posts/python/index.gmi", "Same filename but different path"));
+    assert!(!file_contains!(gemini, "posts/python/index.gmi", "<p>"));
+    assert!(file_contains!(gemini, "index.gmi", "=> /posts/"));
+    assert!(file_contains!(
+        gemini,
+        "posts/index.gmi",
+        "=> /posts/python/ 2017-03-01 Python in posts"
+    ));
+    // Co-located assets are copied next to the page
+    assert!(file_exists!(gemini, "posts/with-assets/with.js"));
+    // Sections only link to what has a gemtext version: not to `render = false` sections
+    // or redirects
+    assert!(!file_contains!(gemini, "posts/index.gmi", "=> /posts/2018/ "));
+    assert!(!file_contains!(gemini, "posts/tutorials/index.gmi", "=> /posts/tutorials/devops/"));
+    assert!(file_contains!(
+        gemini,
+        "posts/tutorials/index.gmi",
+        "=> /posts/tutorials/programming/"
+    ));
+}
+
+#[test]
+fn can_build_site_with_gemini_output_without_protected_pages() {
+    // The Gemini output has to be inside the site
+    let gemini_dir = site_tempdir("test_site");
+    let gemini = gemini_dir.path().to_path_buf();
+    let (_, _tmp_dir, _public) = build_site_with_setup("test_site", |mut site| {
+        site.config.gemini.generate = true;
+        site.config.gemini.output_dir = gemini.file_name().unwrap().to_string_lossy().to_string();
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let content = site.base_path.join("content").join("posts");
+            library.pages.get_mut(&content.join("python.md")).unwrap().meta.password =
+                Some("hunter2".to_string());
+        }
+        (site, false)
+    });
+
+    assert!(!file_exists!(gemini, "posts/python/index.gmi"));
+    assert!(!file_contains!(gemini, "posts/index.gmi", "=> /posts/python/"));
+    assert!(file_contains!(gemini, "posts/index.gmi", "=> /posts/simple/"));
+}
+
+#[test]
+fn can_build_site_with_protected_pages() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let content = site.base_path.join("content").join("posts");
+            library.pages.get_mut(&content.join("python.md")).unwrap().meta.password =
+                Some("hunter2".to_string());
+        }
+        (site, false)
+    });
+
+    assert!(file_contains!(public, "posts/python/index.html", "<title>Python in posts</title>"));
+    assert!(file_contains!(public, "posts/python/index.html", r#""ciphertext":"#));
+    assert!(!file_contains!(public, "posts/python/index.html", "Same filename but different path"));
+    assert!(!file_contains!(public, "posts/python/index.html", "hunter2"));
+    // Nor is the content given away by the feed
+    assert!(!file_contains!(public, "atom.xml", "Same filename but different path"));
+}
+
+#[test]
+fn can_build_site_with_draft_preview_tokens() {
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.drafts.preview_tokens = true;
+        site.config.drafts.preview_secret = Some("secret".to_string());
+        site.include_drafts();
+        (site, true)
+    });
+
+    let library = site.library.read().unwrap();
+    let draft = library.pages.values().find(|p| p.file.relative == "posts/draft.md").unwrap();
+    let token = draft.preview_token.clone().unwrap();
+    assert_eq!(token.len(), 32);
+    assert_eq!(draft.path, format!("/{}/posts/draft/", token));
+
+    // The draft is only reachable through its preview link
+    assert!(!file_exists!(public, "posts/draft/index.html"));
+    assert!(file_exists!(public, &format!("{}/posts/draft/index.html", token)));
+    assert!(!file_contains!(public, "sitemap.xml", "draft"));
+    assert!(!file_contains!(public, "posts/index.html", "draft"));
+    assert!(!file_contains!(public, "atom.xml", "draft"));
+
+    // The token only depends on the secret and the file of the draft
+    let (site, _tmp_dir, _public) = build_site_with_setup("test_site", |mut site| {
+        site.config.drafts.preview_tokens = true;
+        site.config.drafts.preview_secret = Some("secret".to_string());
+        site.include_drafts();
+        site.load().unwrap();
+        (site, false)
+    });
+    let library = site.library.read().unwrap();
+    let draft = library.pages.values().find(|p| p.file.relative == "posts/draft.md").unwrap();
+    assert_eq!(draft.preview_token, Some(token));
+}
+
+#[test]
+fn can_build_site_with_humans_and_security_txt() {
+    let (_, _tmp_dir, public) = build_site("test_site");
+    assert!(!file_exists!(public, "humans.txt"));
+    assert!(!file_exists!(public, ".well-known/security.txt"));
+
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.humans_txt = libs::toml::from_str(
+            r#"
+generate = true
+team = ["Developer: Jane Doe", "Designer: John Doe"]
+thanks = ["The Zola contributors"]
+"#,
+        )
+        .unwrap();
+        site.config.security_txt = libs::toml::from_str(
+            r#"
+generate = true
+contact = ["mailto:security@example.com", "https://example.com/security"]
+expires = "2030-01-01T00:00:00Z"
+preferred_languages = ["en", "fr"]
+"#,
+        )
+        .unwrap();
+        (site, true)
+    });
+
+    assert_eq!(
+        std::fs::read_to_string(public.join("humans.txt")).unwrap(),
+        "/* TEAM */\nDeveloper: Jane Doe\nDesigner: John Doe\n\n/* THANKS */\nThe Zola contributors\n\n/* SITE */\nSoftware: Zola\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(public.join(".well-known").join("security.txt")).unwrap(),
+        "Contact: mailto:security@example.com\n\
+         Contact: https://example.com/security\n\
+         Expires: 2030-01-01T00:00:00Z\n\
+         Preferred-Languages: en, fr\n\
+         Canonical: https://replace-this-with-your-url.com/.well-known/security.txt\n"
+    );
+}
+
+#[test]
+fn can_build_site_with_raw_html_policies() {
+    let (_, _tmp_dir, public) = build_site("test_site");
+
+    // The tutorials escape the raw HTML, but the programming ones sanitize it instead
+    assert!(file_contains!(
+        public,
+        "posts/tutorials/devops/docker/index.html",
+        r#"Press &lt;kbd onclick=&quot;run()&quot;&gt;Enter&lt;/kbd&gt;"#
+    ));
+    assert!(file_contains!(
+        public,
+        "posts/tutorials/programming/python/index.html",
+        "Press <kbd>Enter</kbd>"
+    ));
+}
+
+#[test]
+fn sections_cannot_loosen_a_locked_raw_html_policy() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.markdown.raw_html = RawHtml::Escape;
+        site.config.markdown.raw_html_locked = true;
+        (site, true)
+    });
 
-    assert!(file_exists!(public, "posts/python/index.html"));
-    // Shortcodes work
-    assert!(file_contains!(public, "posts/python/index.html", "Basic shortcode"));
-    assert!(file_contains!(public, "posts/python/index.html", "Arrrh Bob"));
-    assert!(file_contains!(public, "posts/python/index.html", "Arrrh Bob_Sponge"));
-    assert!(file_exists!(public, "posts/tutorials/devops/nix/index.html"));
-    assert!(file_exists!(public, "posts/with-assets/index.html"));
-    assert!(file_exists!(public, "posts/no-section/simple/index.html"));
+    // The programming section asks to sanitize its raw HTML, which is escaped anyway
+    assert!(file_contains!(
+        public,
+        "posts/tutorials/programming/python/index.html",
+        r#"Press &lt;kbd onclick=&quot;run()&quot;&gt;Enter&lt;/kbd&gt;"#
+    ));
+}
 
-    // Sections
-    assert!(file_exists!(public, "posts/index.html"));
-    assert!(file_exists!(public, "posts/tutorials/index.html"));
-    assert!(file_exists!(public, "posts/tutorials/devops/index.html"));
-    assert!(file_exists!(public, "posts/tutorials/programming/index.html"));
-    // Ensure subsection pages are correctly filled
-    assert!(file_contains!(public, "posts/tutorials/index.html", "Sub-pages: 2"));
+#[test]
+fn can_build_site_as_offline_mirror() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.enable_offline_mirror();
+        (site, true)
+    });
 
-    // Pages and section get their relative path
-    assert!(file_contains!(public, "posts/tutorials/index.html", "posts/tutorials/_index.md"));
+    assert!(file_contains!(public, "index.html", r#"<a href="posts/index.html">Posts</a>"#));
+    assert!(file_contains!(public, "index.html", r#"<a href="hello/index.html">"#));
     assert!(file_contains!(
         public,
-        "posts/tutorials/devops/nix/index.html",
-        "posts/tutorials/devops/nix.md"
+        "posts/tutorials/programming/python/index.html",
+        r#"<script src="../../../../scripts/hello.js""#
+    ));
+    // The feeds need absolute URLs
+    assert!(file_contains!(
+        public,
+        "atom.xml",
+        "<id>https://replace-this-with-your-url.com/atom.xml</id>"
     ));
+}
 
-    // aliases work
-    assert!(file_exists!(public, "an-old-url/old-page/index.html"));
-    assert!(file_contains!(public, "an-old-url/old-page/index.html", "something-else"));
-    assert!(file_contains!(public, "another-old-url/index.html", "posts/"));
+#[test]
+fn can_build_site_with_favicons() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.favicons.source = Some("content/posts/with-assets/zola.png".to_string());
+        site.config.favicons.name = Some("Zola".to_string());
+        (site, true)
+    });
 
-    // html aliases work
-    assert!(file_exists!(public, "an-old-url/an-old-alias.html"));
-    assert!(file_contains!(public, "an-old-url/an-old-alias.html", "something-else"));
+    let ico = libs::image::open(public.join("favicon.ico")).unwrap();
+    // The largest image of the icon is used
+    assert_eq!((ico.width(), ico.height()), (48, 48));
+    let touch_icon = libs::image::open(public.join("apple-touch-icon.png")).unwrap();
+    assert_eq!((touch_icon.width(), touch_icon.height()), (180, 180));
+    assert!(file_exists!(public, "android-chrome-512x512.png"));
+    assert!(file_contains!(public, "site.webmanifest", r#""name": "Zola""#));
+    assert!(file_contains!(public, "site.webmanifest", r#""src": "android-chrome-192x192.png""#));
+}
 
-    // redirect_to works
-    assert!(file_exists!(public, "posts/tutorials/devops/index.html"));
-    assert!(file_contains!(public, "posts/tutorials/devops/index.html", "docker"));
+#[test]
+fn does_not_overwrite_the_static_favicons() {
+    let static_dir = tempdir().unwrap();
+    std::fs::write(static_dir.path().join("favicon.ico"), "my icon").unwrap();
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.favicons.source = Some("content/posts/with-assets/zola.png".to_string());
+        site.static_path = static_dir.path().to_path_buf();
+        (site, true)
+    });
 
-    // We do have categories
-    assert!(file_exists!(public, "categories/index.html"));
-    assert!(file_exists!(public, "categories/a-category/index.html"));
-    assert!(file_exists!(public, "categories/a-category/atom.xml"));
-    // and podcast_authors (https://github.com/getzola/zola/issues/1177)
-    assert!(file_exists!(public, "podcast-authors/index.html"));
-    assert!(file_exists!(public, "podcast-authors/some-person/index.html"));
-    assert!(file_exists!(public, "podcast-authors/some-person/atom.xml"));
-    // But no tags
-    assert!(!file_exists!(public, "tags/index.html"));
+    assert_eq!(std::fs::read_to_string(public.join("favicon.ico")).unwrap(), "my icon");
+    assert!(file_exists!(public, "favicon-32x32.png"));
+}
 
-    // Theme files are there
-    assert!(file_exists!(public, "sample.css"));
-    assert!(file_exists!(public, "some.js"));
+#[test]
+fn keeps_the_favicons_in_memory_when_serving() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.favicons.source = Some("content/posts/with-assets/zola.png".to_string());
+        site.enable_serve_mode();
+        (site, true)
+    });
 
-    // SASS and SCSS files compile correctly
-    assert!(file_exists!(public, "blog.css"));
-    assert!(file_contains!(public, "blog.css", "red"));
-    assert!(file_contains!(public, "blog.css", "blue"));
-    assert!(!file_contains!(public, "blog.css", "@import \"included\""));
-    assert!(file_contains!(public, "blog.css", "2rem")); // check include
-    assert!(!file_exists!(public, "_included.css"));
-    assert!(file_exists!(public, "scss.css"));
-    assert!(file_exists!(public, "sass.css"));
-    assert!(file_exists!(public, "nested_sass/sass.css"));
-    assert!(file_exists!(public, "nested_sass/scss.css"));
+    assert!(!file_exists!(public, "favicon.ico"));
+    let icons = SITE_BINARY_CONTENT.read().unwrap();
+    assert!(icons.contains_key(&RelativePathBuf::from("favicon.ico")));
+    assert!(icons.contains_key(&RelativePathBuf::from("apple-touch-icon.png")));
+}
 
-    assert!(!file_exists!(public, "secret_section/index.html"));
-    assert!(!file_exists!(public, "secret_section/page.html"));
-    assert!(!file_exists!(public, "secret_section/secret_sub_section/hello.html"));
-    // no live reload code
-    assert!(!file_contains!(public, "index.html", "/livereload.js?port=1112&amp;mindelay=10"),);
+#[test]
+fn can_build_site_with_archives() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.generate_archive = true;
+        (site, true)
+    });
+
+    assert!(file_exists!(public, "2017/index.html"));
+    assert!(file_contains!(public, "2017/index.html", "Archive: 2017"));
+    assert!(file_contains!(public, "2017/index.html", "Simple article with shortcodes"));
+    assert!(file_exists!(public, "2017/03/index.html"));
+    assert!(file_contains!(public, "2017/03/index.html", "Archive: 2017/3"));
+    assert!(!file_contains!(public, "2017/03/index.html", "Simple article with shortcodes"));
+    // drafts are not included
+    assert!(!file_exists!(public, "2016/03/index.html"));
 
-    // Both pages and sections are in the sitemap
     assert!(file_contains!(
         public,
         "sitemap.xml",
-        "<loc>https://replace-this-with-your-url.com/posts/simple/</loc>"
+        "<loc>https://replace-this-with-your-url.com/2017/</loc>"
     ));
     assert!(file_contains!(
         public,
         "sitemap.xml",
-        "<loc>https://replace-this-with-your-url.com/posts/</loc>"
-    ));
-    // Drafts are not in the sitemap
-    assert!(!file_contains!(public, "sitemap.xml", "draft"));
-    // render: false sections are not in the sitemap either
-    assert!(!file_contains!(public, "sitemap.xml", "posts/2018/</loc>"));
-
-    // robots.txt has been rendered from the template
-    assert!(file_contains!(public, "robots.txt", "User-agent: zola"));
-    assert!(file_contains!(
-        public,
-        "robots.txt",
-        "Sitemap: https://replace-this-with-your-url.com/sitemap.xml"
+        "<loc>https://replace-this-with-your-url.com/2017/03/</loc>"
     ));
 }
 
@@ -440,6 +1516,28 @@ fn can_build_site_with_pagination_for_section() {
         "posts/page/2/index.html",
         "Last: https://replace-this-with-your-url.com/posts/page/5/"
     ));
+    // Each pager is its own canonical URL and links to the pagers around it
+    assert!(file_contains!(
+        public,
+        "posts/page/2/index.html",
+        r#"<link rel="canonical" href="https://replace-this-with-your-url.com/posts/page/2/">"#
+    ));
+    assert!(file_contains!(
+        public,
+        "posts/page/2/index.html",
+        r#"<link rel="prev" href="https://replace-this-with-your-url.com/posts/">"#
+    ));
+    assert!(file_contains!(
+        public,
+        "posts/page/2/index.html",
+        r#"<link rel="next" href="https://replace-this-with-your-url.com/posts/page/3/">"#
+    ));
+    assert!(file_contains!(
+        public,
+        "posts/index.html",
+        r#"<link rel="canonical" href="https://replace-this-with-your-url.com/posts/">"#
+    ));
+    assert!(!file_contains!(public, "posts/index.html", r#"rel="prev""#));
 
     assert!(file_exists!(public, "posts/page/3/index.html"));
     assert!(file_contains!(public, "posts/page/3/index.html", "Num pagers: 5"));
@@ -562,16 +1660,69 @@ fn can_build_site_with_pagination_for_index() {
     assert!(file_contains!(public, "paginated/index.html", &current_path("/paginated/")));
 }
 
+#[test]
+fn can_build_site_with_default_paginate_path_from_config() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.paginate_path = String::new();
+        (site, true)
+    });
+
+    assert!(file_exists!(public, "posts/2/index.html"));
+    assert!(file_contains!(public, "posts/2/index.html", "Num pagers: 5"));
+    assert!(!file_exists!(public, "posts/page/2/index.html"));
+    assert!(file_contains!(
+        public,
+        "sitemap.xml",
+        "<loc>https://replace-this-with-your-url.com/posts/2/</loc>"
+    ));
+    assert!(!file_contains!(public, "sitemap.xml", "posts//2/"));
+}
+
+#[test]
+fn can_build_site_with_index_paginating_other_sections() {
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let index = library
+                .sections
+                .get_mut(&site.base_path.join("content").join("_index.md"))
+                .unwrap();
+            index.meta.paginate_by = Some(2);
+            index.meta.sort_by = SortBy::Date;
+            index.meta.paginate_sections = vec!["posts".to_string()];
+            index.meta.template = Some("index_paginated.html".to_string());
+        }
+        site.populate_sections();
+        (site, false)
+    });
+
+    let library = site.library.read().unwrap();
+    let index = &library.sections[&site.base_path.join("content").join("_index.md")];
+    let paginated_pages = index.paginated_pages.as_ref().unwrap();
+    let content_path = site.base_path.join("content");
+    assert_eq!(paginated_pages[0], content_path.join("posts/2018/transparent-page.md"));
+    assert!(paginated_pages.contains(&content_path.join("posts/tutorials/devops/nix.md")));
+    assert!(!paginated_pages.iter().any(|p| p.starts_with(content_path.join("secret_section"))));
+
+    assert!(file_contains!(public, "index.html", "A transparent page"));
+    assert!(file_contains!(public, "index.html", "has_next"));
+    let num_pagers = (paginated_pages.len() + 1) / 2;
+    assert!(file_contains!(public, "index.html", &format!("Num pages: {}", num_pagers)));
+    assert!(file_exists!(public, &format!("page/{}/index.html", num_pagers)));
+}
+
 #[test]
 fn can_build_site_with_pagination_for_taxonomy() {
     let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
         site.config.languages.get_mut("en").unwrap().taxonomies.push(TaxonomyConfig {
             name: "tags".to_string(),
             slug: "tags".to_string(),
-            paginate_by: Some(2),
-            paginate_path: None,
+            paginate_by: 2,
+            paginate_path: "page".to_string(),
             render: true,
             feed: true,
+            feed_template: None,
         });
         site.load().unwrap();
         {
@@ -782,7 +1933,7 @@ fn can_cachebust_static_files() {
 
 #[test]
 fn can_get_hash_for_static_files() {
-    let (_, _tmp_dir, public) = build_site("test_site");
+    let (site, _tmp_dir, public) = build_site("test_site");
     assert!(file_contains!(
         public,
         "index.html",
@@ -793,6 +1944,13 @@ fn can_get_hash_for_static_files() {
         "index.html",
         "integrity=\"sha384-AUIvMeqnIabErIxvoJon3ZJZ4N/PPHWT14ENkSqd5covWC35eFN7zRD3aJbbYfu5\""
     ));
+    assert!(file_contains!(
+        public,
+        "index.html",
+        "integrity=\"sha256-ZKjntawAERRkw65leGfeymUmzRw8AcwkZ77ktvDQ7FY=\""
+    ));
+    // so `zola serve` knows to render the pages again when a static file changes
+    assert!(site.uses_file_hashes());
 }
 
 #[test]
@@ -840,3 +1998,470 @@ fn panics_on_invalid_external_domain() {
 fn current_path(path: &str) -> String {
     format!("[current_path]({})", path)
 }
+
+#[test]
+fn can_find_unused_templates_and_shortcodes() {
+    let (site, _tmp_dir, _public) = build_site("test_site");
+    let unused = site.find_unused_templates();
+    // There is no `tags` taxonomy
+    assert!(unused.templates.contains(&"tags/list.html".to_string()));
+    assert!(unused.templates.contains(&"tags/single.html".to_string()));
+    // Only used when set in a test
+    assert!(unused.templates.contains(&"custom_feed.xml".to_string()));
+    // Set in the front matter of sections and pages, and a taxonomy template
+    assert!(!unused.templates.contains(&"page_template.html".to_string()));
+    assert!(!unused.templates.contains(&"page_template_override.html".to_string()));
+    assert!(!unused.templates.contains(&"categories/list.html".to_string()));
+    // `pirate_included` is included by the `pirate` shortcode of the theme
+    assert!(unused.shortcodes.is_empty());
+}
+
+#[test]
+fn can_check_accessibility_of_content() {
+    let (site, _tmp_dir, _public) = build_site("test_site");
+    let issues = site::accessibility::check_accessibility(&site);
+    assert_eq!(issues.len(), 1);
+    assert!(issues[0].0.ends_with("posts/python.md"));
+    assert_eq!(issues[0].1, vec!["Heading h4 follows h1, skipping a level"]);
+}
+
+#[test]
+fn can_validate_html_of_pages() {
+    let (site, _tmp_dir, _public) = build_site_with_setup("test_site", |mut site| {
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let path = site.base_path.join("content").join("posts").join("python.md");
+            library.pages.get_mut(&path).unwrap().meta.template =
+                Some("invalid_html.html".to_string());
+        }
+        (site, false)
+    });
+
+    let issues = site::html_validation::validate_site_html(&site).unwrap();
+    assert_eq!(issues.len(), 1);
+    assert!(issues[0].source.ends_with("posts/python.md"));
+    assert_eq!(issues[0].template, "invalid_html.html");
+    // The lines are the ones of the rendered HTML, which includes the content of the page
+    assert_eq!(
+        issues[0].issues,
+        vec![
+            "line 5: `<section>` is not closed before `</div>` on line 15",
+            "line 16: duplicate id `content`",
+        ]
+    );
+}
+
+#[test]
+fn can_reuse_rendered_pages_from_render_cache() {
+    let cache_dir = tempdir().unwrap();
+    let cache_path = cache_dir.path().join("cache");
+    let enable_cache = |site: &mut Site| {
+        site.config.render_cache.enabled = true;
+        site.config.render_cache.path = cache_path.to_string_lossy().to_string();
+    };
+    // The templates of the test site call `get_file_hash`, so use one that can be cached
+    let setup = |mut site: Site| {
+        enable_cache(&mut site);
+        site.tera
+            .add_raw_template("page.html", "{{ page.title }} {{ page.content | safe }}")
+            .unwrap();
+        (site, true)
+    };
+    let cache_entries = || {
+        let mut entries: Vec<_> =
+            std::fs::read_dir(&cache_path).unwrap().map(|e| e.unwrap().file_name()).collect();
+        entries.sort();
+        entries
+    };
+
+    // None of the pages can be cached with the templates of the test site
+    build_site_with_setup("test_site", |mut site| {
+        enable_cache(&mut site);
+        (site, true)
+    });
+    assert!(!cache_path.exists());
+
+    let (_site, _tmp_dir, public) = build_site_with_setup("test_site", setup);
+    let first = std::fs::read_to_string(public.join("posts/python/index.html")).unwrap();
+    let entries = cache_entries();
+    assert!(!entries.is_empty());
+
+    // The same pages give the same entries, which are used as they are
+    let (_site, _tmp_dir, public) = build_site_with_setup("test_site", setup);
+    assert_eq!(cache_entries(), entries);
+    let second = std::fs::read_to_string(public.join("posts/python/index.html")).unwrap();
+    assert_eq!(first, second);
+
+    // The password-protected pages are left out of the cache
+    build_site_with_setup("test_site", |site| {
+        let (mut site, _) = setup(site);
+        site.load().unwrap();
+        let python = site.base_path.join("content/posts/python.md");
+        site.library.write().unwrap().pages.get_mut(&python).unwrap().meta.password =
+            Some("hunter2".to_string());
+        (site, false)
+    });
+    assert_eq!(cache_entries().len(), entries.len() - 1);
+}
+
+#[test]
+fn render_cache_cannot_be_in_the_site_sources() {
+    for path in ["templates", "content/posts", ".", "../cache"] {
+        let mut path_buf =
+            env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+        path_buf.push("test_site");
+        let mut site = Site::new(&path_buf, path_buf.join("config.toml")).unwrap();
+        site.config.render_cache.enabled = true;
+        site.config.render_cache.path = path.to_string();
+        assert!(site.load().is_err(), "`{}` was accepted as render cache", path);
+    }
+}
+
+#[test]
+fn can_build_site_in_low_memory_mode() {
+    let (_, _tmp_dir, public) = build_site("test_site");
+    let (_, _low_memory_tmp_dir, low_memory_public) =
+        build_site_with_setup("test_site", |mut site| {
+            site.config.low_memory = true;
+            (site, true)
+        });
+
+    for path in ["index.html", "posts/index.html", "posts/python/index.html", "sitemap.xml"] {
+        assert_eq!(
+            std::fs::read_to_string(public.join(path)).unwrap(),
+            std::fs::read_to_string(low_memory_public.join(path)).unwrap()
+        );
+    }
+}
+
+#[test]
+fn can_load_content_from_another_source() {
+    let tmp_dir = tempdir().unwrap();
+    let path = tmp_dir.path();
+    std::fs::write(path.join("config.toml"), "base_url = \"https://example.com\"\n").unwrap();
+    std::fs::create_dir(path.join("templates")).unwrap();
+    for (name, template) in [
+        ("index.html", "{% for p in section.pages %}{{ p.title }}{% endfor %}"),
+        (
+            "section.html",
+            "{{ section.title }}: {% for p in section.pages %}{{ p.title }}{% endfor %}",
+        ),
+        ("page.html", "{{ page.title }} {{ page.content | safe }}"),
+    ] {
+        std::fs::write(path.join("templates").join(name), template).unwrap();
+    }
+
+    let mut site = Site::new(path, "config.toml").unwrap();
+    site.set_content_source(site::content_source::MemorySource::new([
+        ("_index.md", "+++\n+++\n"),
+        ("hello.md", "+++\ntitle = \"Hello\"\n+++\nFrom **memory**"),
+        ("blog/_index.md", "+++\ntitle = \"Blog\"\n+++\n"),
+        ("blog/first.md", "+++\ntitle = \"First\"\n+++\n"),
+        ("drafts/_index.md", "+++\ndraft = true\n+++\n"),
+        ("drafts/wip.md", "+++\ntitle = \"Wip\"\n+++\n"),
+        ("drafts/nested/_index.md", "+++\n+++\n"),
+        ("notes.txt", "Not Markdown"),
+    ]));
+    site.load().unwrap();
+    {
+        let library = site.library.read().unwrap();
+        assert_eq!(library.pages.len(), 2);
+        assert_eq!(library.sections.len(), 2);
+        assert!(library.pages.contains_key(&path.join("content").join("blog").join("first.md")));
+    }
+
+    let public = path.join("public");
+    site.set_output_path(&public);
+    site.build().unwrap();
+    assert!(file_contains!(
+        public,
+        "hello/index.html",
+        "Hello <p>From <strong>memory</strong></p>"
+    ));
+    assert!(file_contains!(public, "blog/index.html", "Blog: First"));
+    assert!(!file_exists!(public, "drafts/index.html"));
+}
+
+/// A site on disk with a blog section, the Org files of `files` being converted
+fn write_site_with_org_pages(path: &Path, files: &[(&str, &str)]) {
+    std::fs::write(
+        path.join("config.toml"),
+        "base_url = \"https://example.com\"\n[converters.org]\n",
+    )
+    .unwrap();
+    std::fs::create_dir(path.join("templates")).unwrap();
+    std::fs::write(path.join("templates").join("page.html"), "{{ page.content | safe }}").unwrap();
+    let blog = path.join("content").join("blog");
+    std::fs::create_dir_all(blog.join("bundle")).unwrap();
+    std::fs::write(path.join("content").join("_index.md"), "+++\n+++\n").unwrap();
+    std::fs::write(blog.join("_index.md"), "+++\n+++\n").unwrap();
+    for (name, content) in files {
+        std::fs::write(blog.join(name), content).unwrap();
+    }
+}
+
+#[test]
+fn does_not_copy_converted_files_as_assets() {
+    let tmp_dir = tempdir().unwrap();
+    let path = tmp_dir.path();
+    write_site_with_org_pages(
+        path,
+        &[
+            ("post.org", "#+TITLE: Post\n\nText"),
+            ("bundle/index.org", "#+TITLE: Bundle\n\nText"),
+            ("bundle/image.jpg", "Not an image"),
+        ],
+    );
+    let mut site = Site::new(path, "config.toml").unwrap();
+    site.load().unwrap();
+    let public = path.join("public");
+    site.set_output_path(&public);
+    site.build().unwrap();
+
+    assert!(file_exists!(public, "blog/post/index.html"));
+    assert!(file_exists!(public, "blog/bundle/index.html"));
+    assert!(file_exists!(public, "blog/bundle/image.jpg"));
+    assert!(!file_exists!(public, "blog/post.org"));
+    assert!(!file_exists!(public, "blog/bundle/index.org"));
+}
+
+#[test]
+fn cannot_have_markdown_and_converted_pages_with_the_same_path() {
+    let tmp_dir = tempdir().unwrap();
+    let path = tmp_dir.path();
+    write_site_with_org_pages(
+        path,
+        &[("post.org", "#+TITLE: Post\n\nText"), ("post.md", "+++\ntitle = \"Post\"\n+++\n")],
+    );
+    let mut site = Site::new(path, "config.toml").unwrap();
+    let err = site.load().unwrap_err();
+    assert!(err.to_string().contains("Found path collisions"), "{}", err);
+}
+
+#[cfg(unix)]
+#[test]
+fn can_load_pages_converted_from_other_formats() {
+    let tmp_dir = tempdir().unwrap();
+    let path = tmp_dir.path();
+    std::fs::write(
+        path.join("config.toml"),
+        r#"base_url = "https://example.com"
+[converters.org]
+[converters.rst]
+[converters.htm]
+command = ["cat"]
+output = "html"
+"#,
+    )
+    .unwrap();
+    std::fs::create_dir(path.join("templates")).unwrap();
+    std::fs::write(
+        path.join("templates").join("page.html"),
+        "{{ page.title }} {{ page.content | safe }}",
+    )
+    .unwrap();
+
+    let mut site = Site::new(path, "config.toml").unwrap();
+    site.set_content_source(site::content_source::MemorySource::new([
+        ("_index.md", "+++\n+++\n"),
+        ("org.org", "#+TITLE: From Org\n\nSome *bold* text"),
+        ("rst.rst", "From RST\n========\n\nSome **bold** text"),
+        ("html.htm", "+++\ntitle = \"From HTML\"\n+++\n<p>Not *Markdown*</p>\n<!-- more -->\n"),
+        ("ignored.adoc", "= Not converted"),
+    ]));
+    site.load().unwrap();
+    {
+        let library = site.library.read().unwrap();
+        assert_eq!(library.pages.len(), 3);
+        let html = &library.pages[&path.join("content").join("html.htm")];
+        assert_eq!(html.summary.as_deref(), Some("<p>Not *Markdown*</p>"));
+    }
+
+    let public = path.join("public");
+    site.set_output_path(&public);
+    site.build().unwrap();
+    assert!(file_contains!(
+        public,
+        "org/index.html",
+        "From Org <p>Some <strong>bold</strong> text"
+    ));
+    assert!(file_contains!(
+        public,
+        "rst/index.html",
+        "From RST <p>Some <strong>bold</strong> text"
+    ));
+    assert!(file_contains!(public, "html/index.html", "From HTML <p>Not *Markdown*</p>"));
+}
+
+#[cfg(unix)]
+#[test]
+fn can_transform_pages_with_plugins() {
+    let tmp_dir = tempdir().unwrap();
+    let path = tmp_dir.path();
+    std::fs::write(
+        path.join("config.toml"),
+        r#"base_url = "https://example.com"
+
+[[plugins]]
+name = "shout"
+command = ["sh", "-c", "cat > /dev/null; echo '{\"pages\": [{\"path\": \"hello.md\", \"raw_content\": \"HELLO *WORLD*\"}]}'"]
+hooks = ["parse_markdown"]
+
+[[plugins]]
+name = "sign"
+command = ["sh", "-c", "cat > /dev/null; echo '{\"pages\": [{\"path\": \"hello.md\", \"content\": \"<p>Signed</p>\"}]}'"]
+hooks = ["pre_render_page"]
+
+[[plugins]]
+name = "stamp"
+command = ["sh", "-c", "cat > public/built.json"]
+hooks = ["post_build"]
+"#,
+    )
+    .unwrap();
+    std::fs::create_dir(path.join("templates")).unwrap();
+    std::fs::write(path.join("templates").join("page.html"), "{{ page.content | safe }}").unwrap();
+
+    let mut site = Site::new(path, "config.toml").unwrap();
+    site.set_content_source(site::content_source::MemorySource::new([
+        ("_index.md", "+++\n+++\n"),
+        ("hello.md", "+++\n+++\nHello *world*"),
+        ("other.md", "+++\n+++\nUnchanged"),
+    ]));
+    site.load().unwrap();
+    {
+        let library = site.library.read().unwrap();
+        let hello = &library.pages[&path.join("content").join("hello.md")];
+        assert_eq!(hello.raw_content, "HELLO *WORLD*");
+        assert_eq!(hello.content, "<p>Signed</p>");
+        let other = &library.pages[&path.join("content").join("other.md")];
+        assert_eq!(other.content, "<p>Unchanged</p>\n");
+    }
+
+    let public = path.join("public");
+    site.set_output_path(&public);
+    site.build().unwrap();
+    assert!(file_contains!(public, "hello/index.html", "<p>Signed</p>"));
+    assert!(file_contains!(public, "built.json", r#""hook":"post_build""#));
+    assert!(file_contains!(public, "built.json", r#""path":"other.md""#));
+}
+
+#[test]
+fn can_render_native_shortcodes() {
+    let tmp_dir = tempdir().unwrap();
+    let path = tmp_dir.path();
+    std::fs::write(path.join("config.toml"), "base_url = \"https://example.com\"\n").unwrap();
+    std::fs::create_dir_all(path.join("templates").join("shortcodes")).unwrap();
+    std::fs::write(path.join("templates").join("shortcodes").join("bars.html"), "template")
+        .unwrap();
+
+    let mut site = Site::new(path, "config.toml").unwrap();
+    site.register_shortcode("bars", site::ShortcodeFileType::Html, |args, context| {
+        let values = args["values"].as_array().cloned().unwrap_or_default();
+        let title = context.get("page").and_then(|p| p["title"].as_str()).unwrap_or_default();
+        Ok(format!("<svg aria-label=\"{}\">{}</svg>", title, "<rect/>".repeat(values.len())))
+    });
+    site.register_shortcode("emphasis", site::ShortcodeFileType::Markdown, |_, context| {
+        Ok(format!("*{}*", context.get("body").and_then(|b| b.as_str()).unwrap_or_default()))
+    });
+    site.set_content_source(site::content_source::MemorySource::new([
+        ("_index.md", "+++\n+++\n"),
+        (
+            "chart.md",
+            "+++\ntitle = \"Sales\"\n+++\n{{ bars(values=[1, 2, 3]) }}\n\n{% emphasis() %}Go{% end %}",
+        ),
+    ]));
+    site.load().unwrap();
+
+    let library = site.library.read().unwrap();
+    let page = &library.pages[&path.join("content").join("chart.md")];
+    assert!(page.content.contains("<svg aria-label=\"Sales\"><rect/><rect/><rect/></svg>"));
+    assert!(page.content.contains("<p><em>Go</em></p>"));
+}
+
+#[test]
+fn can_load_other_template_dirs_and_custom_tera_fns() {
+    let tmp_dir = tempdir().unwrap();
+    let path = tmp_dir.path();
+    std::fs::write(
+        path.join("config.toml"),
+        "base_url = \"https://example.com\"\n[templates]\nload = [\"shared\"]\n",
+    )
+    .unwrap();
+    std::fs::create_dir_all(path.join("templates")).unwrap();
+    std::fs::create_dir_all(path.join("shared").join("shortcodes")).unwrap();
+    std::fs::write(path.join("templates").join("index.html"), "site index").unwrap();
+    std::fs::write(path.join("shared").join("index.html"), "shared index").unwrap();
+    std::fs::write(
+        path.join("shared").join("page.html"),
+        "{{ page.title | shout }} {{ answer() }} {{ page.content | safe }}",
+    )
+    .unwrap();
+    std::fs::write(path.join("shared").join("shortcodes").join("hi.html"), "{{ 'hi' | shout }}")
+        .unwrap();
+
+    let mut site = Site::new(path, "config.toml").unwrap();
+    site.register_filter(
+        "shout",
+        |value: &libs::tera::Value, _: &HashMap<String, libs::tera::Value>| {
+            Ok(libs::tera::to_value(format!("{}!", value.as_str().unwrap_or_default())).unwrap())
+        },
+    );
+    site.register_function("answer", |_: &HashMap<String, libs::tera::Value>| {
+        Ok(libs::tera::to_value(42).unwrap())
+    });
+    site.set_content_source(site::content_source::MemorySource::new([
+        ("_index.md", "+++\n+++\n"),
+        ("hello.md", "+++\ntitle = \"Hello\"\n+++\n{{ hi() }}"),
+    ]));
+    site.load().unwrap();
+
+    let public = path.join("public");
+    site.set_output_path(&public);
+    site.build().unwrap();
+    assert!(file_contains!(public, "index.html", "site index"));
+    assert!(file_contains!(public, "hello/index.html", "Hello! 42 hi!"));
+
+    std::fs::write(
+        path.join("config.toml"),
+        "base_url = \"https://example.com\"\n[templates]\nload = [\"missing\"]\n",
+    )
+    .unwrap();
+    let err = Site::new(path, "config.toml").unwrap_err();
+    assert_eq!(err.to_string(), "The directory `missing` of `templates.load` doesn't exist");
+}
+
+#[test]
+fn loads_the_syntaxes_directory_once() {
+    let tmp_dir = tempdir().unwrap();
+    let path = tmp_dir.path();
+    let syntax = std::fs::read_to_string(
+        env::current_dir().unwrap().join("../../test_site/syntaxes/test.sublime-syntax"),
+    )
+    .unwrap();
+    let nested = path.join("syntaxes").join("nested");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::write(nested.join("test.sublime-syntax"), syntax).unwrap();
+
+    // The `syntaxes` directory is loaded without being listed
+    std::fs::write(
+        path.join("config.toml"),
+        "base_url = \"https://example.com\"\n[markdown]\nhighlight_code = true\n",
+    )
+    .unwrap();
+    let site = Site::new(path, "config.toml").unwrap();
+    let syntaxes = site.config.markdown.extra_syntax_set.unwrap();
+    assert_eq!(syntaxes.syntaxes().iter().filter(|s| s.name == "mylang").count(), 1);
+
+    // Listing it, or a directory inside it, doesn't load its syntaxes twice
+    std::fs::write(
+        path.join("config.toml"),
+        "base_url = \"https://example.com\"\n[markdown]\nhighlight_code = true\n\
+         extra_syntaxes_and_themes = [\"syntaxes/\", \"./syntaxes/nested\"]\n",
+    )
+    .unwrap();
+    let site = Site::new(path, "config.toml").unwrap();
+    let syntaxes = site.config.markdown.extra_syntax_set.unwrap();
+    assert_eq!(syntaxes.syntaxes().iter().filter(|s| s.name == "mylang").count(), 1);
+}
@@ -99,6 +99,7 @@ fn can_build_multilingual_site() {
     ));
     assert!(file_exists!(public, "fr/blog/something/index.html"));
     assert!(file_contains!(public, "fr/blog/something/index.html", "Language: fr"));
+    assert!(file_contains!(public, "fr/blog/something/index.html", "Date: mardi 9 octobre 2018"));
     assert!(file_contains!(
         public,
         "fr/blog/something/index.html",
@@ -128,6 +129,12 @@ fn can_build_multilingual_site() {
         "fr/atom.xml",
         r#"<feed xmlns="http://www.w3.org/2005/Atom" xml:lang="fr">"#
     ));
+    // The dates of feeds are not localized
+    assert!(file_contains!(
+        public,
+        "fr/atom.xml",
+        "<published>2018-10-09T00:00:00+00:00</published>"
+    ));
     // Italian doesn't have feed enabled
     assert!(!file_exists!(public, "it/atom.xml"));
 
@@ -183,7 +190,7 @@ fn correct_translations_on_all_pages() {
 
     let translations = find_expected_translations("test_site_i18n", &site.config.default_language);
 
-    for (path, link) in &site.permalinks {
+    for (path, link) in site.permalinks.iter() {
         // link ends with /, does not add index.html
         let link = format!("{}index.html", link);
 
@@ -199,3 +206,21 @@ fn correct_translations_on_all_pages() {
         assert!(ensure_translations_in_output(&site, path, &link));
     }
 }
+
+#[test]
+fn can_build_site_with_rtl_language() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site_i18n", |mut site| {
+        site.config.languages.get_mut("fr").unwrap().rtl = true;
+        (site, true)
+    });
+
+    assert!(file_contains!(public, "index.html", "Direction: ltr (RTL: false)"));
+    assert!(file_contains!(public, "fr/index.html", "Direction: rtl (RTL: true)"));
+    // Feed readers are told the direction of the content too
+    assert!(file_contains!(
+        public,
+        "fr/atom.xml",
+        "<content type=\"html\">&lt;div dir=\"rtl\"&gt;"
+    ));
+    assert!(!file_contains!(public, "atom.xml", "dir=\"rtl\""));
+}
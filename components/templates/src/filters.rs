@@ -1,45 +1,83 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::hash::BuildHasher;
+use std::sync::{Arc, Mutex};
 
-use config::Config;
+use config::{Config, Slugify};
 use libs::base64::{decode, encode};
+use libs::once_cell::sync::Lazy;
+use libs::regex::{Captures, Regex};
 use libs::tera::{
     to_value, try_get_value, Error as TeraError, Filter as TeraFilter, Result as TeraResult, Tera,
     Value,
 };
 use markdown::{render_content, RenderContext};
+use utils::slugs::{slugify_anchors, slugify_paths};
+use utils::templates::ShortcodeDefinition;
 
 #[derive(Debug)]
 pub struct MarkdownFilter {
     config: Config,
-    permalinks: HashMap<String, String>,
+    permalinks: Arc<HashMap<String, String>>,
     tera: Tera,
+    shortcode_definitions: HashMap<String, ShortcodeDefinition>,
 }
 
 impl MarkdownFilter {
-    pub fn new(config: Config, permalinks: HashMap<String, String>, tera: Tera) -> Self {
-        Self { config, permalinks, tera }
+    pub fn new(config: Config, permalinks: Arc<HashMap<String, String>>, tera: Tera) -> Self {
+        let shortcode_definitions = utils::templates::get_shortcodes(&tera);
+        Self { config, permalinks, tera, shortcode_definitions }
+    }
+
+    /// Uses the shortcodes of the site, with the ones registered from Rust, instead of only the
+    /// templates ones
+    pub fn set_shortcode_definitions(&mut self, definitions: HashMap<String, ShortcodeDefinition>) {
+        self.shortcode_definitions = definitions;
     }
 }
 
 impl TeraFilter for MarkdownFilter {
     fn filter(&self, value: &Value, args: &HashMap<String, Value>) -> TeraResult<Value> {
-        // NOTE: RenderContext below is not aware of the current language
-        // However, it should not be a problem because the surrounding tera
-        // template has language context, and will most likely call a piece of
-        // markdown respecting language preferences.
-        let mut context = RenderContext::from_config(&self.config);
-        context.permalinks = Cow::Borrowed(&self.permalinks);
-        context.tera = Cow::Borrowed(&self.tera);
-        let def = utils::templates::get_shortcodes(&self.tera);
-        context.set_shortcode_definitions(&def);
-
+        // NOTE: The filter doesn't know the template context it is called from, so the current
+        // language and page are only known if they are given as arguments. Otherwise the
+        // content is rendered in the default language.
         let s = try_get_value!("markdown", "value", String, value);
         let inline = match args.get("inline") {
             Some(val) => try_get_value!("markdown", "inline", bool, val),
             None => false,
         };
+        // Rendering with the page makes it available to the shortcodes like in the content of
+        // the page and resolves the links relative to it
+        let page = args.get("page").filter(|p| p.is_object());
+        let explicit_lang = match args.get("lang") {
+            Some(val) => Some(try_get_value!("markdown", "lang", String, val)),
+            None => page.and_then(|p| p["lang"].as_str()).map(|l| l.to_string()),
+        };
+        let lang = explicit_lang.clone().unwrap_or_else(|| self.config.default_language.clone());
+        let permalink = page.and_then(|p| p["permalink"].as_str()).unwrap_or_default();
+
+        let mut context = RenderContext::from_config(&self.config);
+        context.permalinks = Cow::Borrowed(&self.permalinks);
+        context.tera = Cow::Borrowed(&self.tera);
+        context.set_shortcode_definitions(&self.shortcode_definitions);
+        context.lang = &lang;
+        context.current_page_permalink = permalink;
+        if self.config.languages.contains_key(&lang) {
+            context.tera_context.insert("config", &self.config.serialize(&lang));
+        }
+        // Shortcodes can still be given a `lang` argument when the filter isn't
+        if explicit_lang.is_some() {
+            context.tera_context.insert("lang", &lang);
+        }
+        if let Some(page) = page {
+            context.tera_context.insert("page", page);
+            if let Some(path) = page["relative_path"].as_str() {
+                context.set_current_page_path(path);
+            }
+        }
+        if let Some(val) = args.get("heading_shift") {
+            context.heading_shift = try_get_value!("markdown", "heading_shift", u8, val);
+        }
         let mut html = match render_content(&s, &context) {
             Ok(res) => res.body,
             Err(e) => return Err(format!("Failed to render markdown filter: {:?}", e).into()),
@@ -62,7 +100,7 @@ pub fn base64_encode<S: BuildHasher>(
     _: &HashMap<String, Value, S>,
 ) -> TeraResult<Value> {
     let s = try_get_value!("base64_encode", "value", String, value);
-    Ok(to_value(&encode(s.as_bytes())).unwrap())
+    Ok(to_value(encode(s.as_bytes())).unwrap())
 }
 
 pub fn base64_decode<S: BuildHasher>(
@@ -70,7 +108,245 @@ pub fn base64_decode<S: BuildHasher>(
     _: &HashMap<String, Value, S>,
 ) -> TeraResult<Value> {
     let s = try_get_value!("base64_decode", "value", String, value);
-    Ok(to_value(&String::from_utf8(decode(s.as_bytes()).unwrap()).unwrap()).unwrap())
+    Ok(to_value(String::from_utf8(decode(s.as_bytes()).unwrap()).unwrap()).unwrap())
+}
+
+static IGNORED_ELEMENTS_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<(script|style)\b.*?</(script|style)>").unwrap());
+static LINE_BREAK_TAG_RE: Lazy<Regex> = Lazy::new(|| {
+    // The whitespace after the end of a list item is replaced too so items aren't separated by blank lines
+    Regex::new(
+        r"(?i)<br\s*/?>|</(li|dt|dd|tr)>\s*|</(p|h[1-6]|pre|blockquote|div|ul|ol|table|figure)>",
+    )
+    .unwrap()
+});
+static LIST_ITEM_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<li\b[^>]*>").unwrap());
+static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]*>").unwrap());
+static ENTITY_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").unwrap());
+static BLANK_LINES_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\n[ \t]*(\n[ \t]*)+\n").unwrap());
+
+fn decode_entity(caps: &Captures) -> String {
+    let entity = &caps[1];
+    let decoded = match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+            u32::from_str_radix(&entity[2..], 16).ok().and_then(char::from_u32)
+        }
+        _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(char::from_u32),
+        _ => None,
+    };
+    decoded.map(String::from).unwrap_or_else(|| caps[0].to_string())
+}
+
+/// Turns rendered HTML, such as `page.content`, into plain text: the tags are removed, blocks
+/// end with a line break and list items start with `- `
+pub fn plain_text<S: BuildHasher>(
+    value: &Value,
+    _: &HashMap<String, Value, S>,
+) -> TeraResult<Value> {
+    let s = try_get_value!("plain_text", "value", String, value);
+    let text = IGNORED_ELEMENTS_RE.replace_all(&s, "");
+    let text = LINE_BREAK_TAG_RE.replace_all(&text, "\n");
+    let text = LIST_ITEM_RE.replace_all(&text, "- ");
+    let text = TAG_RE.replace_all(&text, "");
+    let text = ENTITY_RE.replace_all(&text, decode_entity);
+    let text = BLANK_LINES_RE.replace_all(&text, "\n\n");
+    Ok(to_value(text.trim()).unwrap())
+}
+
+static HTML_TOKEN_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<!--.*?-->|<[^>]*>").unwrap());
+static TAG_NAME_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^</?([a-zA-Z][a-zA-Z0-9-]*)").unwrap());
+/// The elements without a closing tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// The elements that don't separate the words before and after them
+const INLINE_ELEMENTS: &[&str] = &[
+    "a", "abbr", "b", "code", "del", "em", "i", "ins", "kbd", "mark", "s", "small", "span",
+    "strong", "sub", "sup", "u",
+];
+
+/// Where to cut the text of an element to only keep `remaining` words or characters.
+/// Returns `None` if it can be kept whole, with `remaining` and `in_word` updated.
+fn find_cut(
+    text: &str,
+    by_words: bool,
+    remaining: &mut usize,
+    in_word: &mut bool,
+) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if by_words {
+            if c.is_whitespace() {
+                *in_word = false;
+                continue;
+            }
+            if !*in_word {
+                if *remaining == 0 {
+                    return Some(i);
+                }
+                *remaining -= 1;
+                *in_word = true;
+            }
+        } else {
+            if *remaining == 0 {
+                return Some(i);
+            }
+            *remaining -= 1;
+            // An entity is a single character
+            if c == '&' {
+                if let Some(end) =
+                    text[i..].find(';').filter(|e| ENTITY_RE.is_match(&text[i..=i + e]))
+                {
+                    while chars.peek().map_or(false, |(j, _)| *j <= i + end) {
+                        chars.next();
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Truncates rendered HTML, such as `page.content`, to `length` words, or characters with
+/// `by="chars"`, closing the elements left open. `end`, an ellipsis by default, is added
+/// when the HTML is truncated.
+pub fn truncate_html<S: BuildHasher>(
+    value: &Value,
+    args: &HashMap<String, Value, S>,
+) -> TeraResult<Value> {
+    let s = try_get_value!("truncate_html", "value", String, value);
+    let length = match args.get("length") {
+        Some(val) => try_get_value!("truncate_html", "length", usize, val),
+        None => return Err(TeraError::msg("Filter `truncate_html` needs a `length` argument")),
+    };
+    let by_words = match args.get("by") {
+        Some(val) => match try_get_value!("truncate_html", "by", String, val).as_str() {
+            "words" => true,
+            "chars" => false,
+            by => {
+                return Err(TeraError::msg(format!(
+                    "Filter `truncate_html` was called with an invalid `by` argument: `{}`. \
+                     It needs to be words or chars.",
+                    by
+                )))
+            }
+        },
+        None => true,
+    };
+    let end = match args.get("end") {
+        Some(val) => try_get_value!("truncate_html", "end", String, val),
+        None => "…".to_string(),
+    };
+
+    let mut remaining = length;
+    let mut in_word = false;
+    let mut open_tags: Vec<String> = Vec::new();
+    // Where the last text ends and the elements open there: the HTML is cut there instead of
+    // in elements that would be left empty
+    let mut last_text = (0, Vec::new());
+    let mut position = 0;
+    let mut tokens = HTML_TOKEN_RE.find_iter(&s);
+    let (cut, open_tags) = loop {
+        let token = tokens.next();
+        let text_end = token.as_ref().map(|t| t.start()).unwrap_or(s.len());
+        let text = &s[position..text_end];
+        if let Some(i) = find_cut(text, by_words, &mut remaining, &mut in_word) {
+            if text[..i].trim().is_empty() {
+                break last_text;
+            }
+            break (position + i, open_tags);
+        }
+        if !text.trim().is_empty() {
+            last_text = (position + text.trim_end().len(), open_tags.clone());
+        }
+        let token = match token {
+            Some(token) => token,
+            None => return Ok(to_value(s).unwrap()),
+        };
+        position = token.end();
+
+        let tag = token.as_str();
+        let name = match TAG_NAME_RE.captures(tag) {
+            Some(caps) => caps[1].to_lowercase(),
+            // A comment
+            None => continue,
+        };
+        if !INLINE_ELEMENTS.contains(&name.as_str()) {
+            in_word = false;
+        }
+        if tag.starts_with("</") {
+            if let Some(i) = open_tags.iter().rposition(|t| *t == name) {
+                open_tags.truncate(i);
+            }
+        } else if !tag.ends_with("/>") && !VOID_ELEMENTS.contains(&name.as_str()) {
+            open_tags.push(name);
+        }
+    };
+
+    let mut truncated = s[..cut].trim_end().to_string();
+    truncated.push_str(&end);
+    for tag in open_tags.iter().rev() {
+        truncated.push_str(&format!("</{}>", tag));
+    }
+    Ok(to_value(truncated).unwrap())
+}
+
+/// How many regexes of the `regex_replace` calls are kept
+const REGEX_CACHE_SIZE: usize = 32;
+
+/// The regexes of the last `regex_replace` calls by pattern, the most recently used last: a
+/// template usually calls it with the same few patterns for every page, but the patterns built
+/// from the content of the pages would otherwise all be kept
+static REGEX_CACHE: Lazy<Mutex<Vec<(String, Regex)>>> = Lazy::new(Default::default);
+
+fn cached_regex(pattern: String) -> TeraResult<Regex> {
+    let mut cache = REGEX_CACHE.lock().unwrap();
+    if let Some(i) = cache.iter().position(|(p, _)| *p == pattern) {
+        let entry = cache.remove(i);
+        let re = entry.1.clone();
+        cache.push(entry);
+        return Ok(re);
+    }
+    let re = Regex::new(&pattern).map_err(|e| {
+        TeraError::msg(format!(
+            "Filter `regex_replace` was called with an invalid `pattern` argument: {}",
+            e
+        ))
+    })?;
+    if cache.len() == REGEX_CACHE_SIZE {
+        cache.remove(0);
+    }
+    cache.push((pattern, re.clone()));
+    Ok(re)
+}
+
+/// Replaces the matches of the `pattern` regex with `rep`, which can refer to the groups of the
+/// pattern like `$1` or `${name}`
+pub fn regex_replace<S: BuildHasher>(
+    value: &Value,
+    args: &HashMap<String, Value, S>,
+) -> TeraResult<Value> {
+    let s = try_get_value!("regex_replace", "value", String, value);
+    let pattern = match args.get("pattern") {
+        Some(val) => try_get_value!("regex_replace", "pattern", String, val),
+        None => return Err(TeraError::msg("Filter `regex_replace` needs a `pattern` argument")),
+    };
+    let rep = match args.get("rep") {
+        Some(val) => try_get_value!("regex_replace", "rep", String, val),
+        None => return Err(TeraError::msg("Filter `regex_replace` needs a `rep` argument")),
+    };
+
+    let re = cached_regex(pattern)?;
+    Ok(to_value(re.replace_all(&s, rep.as_str())).unwrap())
 }
 
 #[derive(Debug)]
@@ -103,21 +379,202 @@ impl TeraFilter for NumFormatFilter {
     }
 }
 
+/// Hashes a string, eg for Gravatar URLs or cache keys: `hash(algorithm="md5")`.
+/// With a `key`, it is the HMAC of the string with that key instead.
+pub fn hash<S: BuildHasher>(value: &Value, args: &HashMap<String, Value, S>) -> TeraResult<Value> {
+    use libs::ring::{digest, hmac};
+
+    let s = try_get_value!("hash", "value", String, value);
+    let algorithm = match args.get("algorithm") {
+        Some(val) => try_get_value!("hash", "algorithm", String, val),
+        None => "sha256".to_string(),
+    };
+    let key = match args.get("key") {
+        Some(val) => Some(try_get_value!("hash", "key", String, val)),
+        None => None,
+    };
+    let as_base64 = match args.get("base64") {
+        Some(val) => try_get_value!("hash", "base64", bool, val),
+        None => false,
+    };
+
+    let bytes = match (algorithm.as_str(), key) {
+        ("md5", None) => libs::md5::compute(s.as_bytes()).to_vec(),
+        ("md5", Some(_)) => {
+            return Err(TeraError::msg("Filter `hash` can't compute a HMAC with `md5`"))
+        }
+        (algorithm, key) => {
+            let (digest_algorithm, hmac_algorithm) = match algorithm {
+                "sha1" => (&digest::SHA1_FOR_LEGACY_USE_ONLY, hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY),
+                "sha256" => (&digest::SHA256, hmac::HMAC_SHA256),
+                "sha384" => (&digest::SHA384, hmac::HMAC_SHA384),
+                "sha512" => (&digest::SHA512, hmac::HMAC_SHA512),
+                _ => {
+                    return Err(TeraError::msg(format!(
+                        "Filter `hash` was called with an invalid `algorithm` argument: `{}`. \
+                         It needs to be md5, sha1, sha256, sha384 or sha512.",
+                        algorithm
+                    )))
+                }
+            };
+            match key {
+                Some(key) => {
+                    let key = hmac::Key::new(hmac_algorithm, key.as_bytes());
+                    hmac::sign(&key, s.as_bytes()).as_ref().to_vec()
+                }
+                None => digest::digest(digest_algorithm, s.as_bytes()).as_ref().to_vec(),
+            }
+        }
+    };
+
+    if as_base64 {
+        Ok(to_value(encode(bytes)).unwrap())
+    } else {
+        Ok(to_value(bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>()).unwrap())
+    }
+}
+
+/// Replaces the `slugify` filter of Tera to slugify like the site does, with the strategy of
+/// the `slugify` config for `paths` (the default), `taxonomies` or `anchors`
+#[derive(Debug)]
+pub struct SlugifyFilter {
+    slugify: Slugify,
+}
+
+impl SlugifyFilter {
+    pub fn new(slugify: Slugify) -> Self {
+        Self { slugify }
+    }
+}
+
+impl TeraFilter for SlugifyFilter {
+    fn filter(&self, value: &Value, args: &HashMap<String, Value>) -> TeraResult<Value> {
+        let s = try_get_value!("slugify", "value", String, value);
+        let kind = match args.get("type") {
+            Some(val) => try_get_value!("slugify", "type", String, val),
+            None => "paths".to_string(),
+        };
+        let slug = match kind.as_str() {
+            "paths" => slugify_paths(&s, self.slugify.paths),
+            "taxonomies" => slugify_paths(&s, self.slugify.taxonomies),
+            "anchors" => slugify_anchors(&s, self.slugify.anchors),
+            _ => {
+                return Err(TeraError::msg(format!(
+                    "Filter `slugify` was called with an invalid `type` argument: `{}`. \
+                     It needs to be paths, taxonomies or anchors.",
+                    kind
+                )))
+            }
+        };
+        Ok(to_value(slug).unwrap())
+    }
+}
+
+/// Wraps the `date` filter of Tera to use the `locale` of the `lang` argument, and to format
+/// dates as RFC 2822 or RFC 3339 in English whatever the locale
+#[derive(Debug)]
+pub struct DateFilter {
+    config: Config,
+    /// Only used for its built-in `date` filter, which does the actual formatting
+    tera: Tera,
+}
+
+impl DateFilter {
+    pub fn new(config: Config) -> Self {
+        Self { config, tera: Tera::default() }
+    }
+}
+
+impl TeraFilter for DateFilter {
+    fn filter(&self, value: &Value, args: &HashMap<String, Value>) -> TeraResult<Value> {
+        let builtin = self.tera.get_filter("date")?;
+        let mut args = args.clone();
+
+        // The dates of feeds are always in English
+        let rfc_format = match args.get("format").and_then(Value::as_str) {
+            Some("rfc2822") => Some("%a, %d %b %Y %H:%M:%S %z"),
+            Some("rfc3339") => Some("%+"),
+            _ => None,
+        };
+        if let Some(rfc_format) = rfc_format {
+            args.insert("format".to_string(), to_value(rfc_format).unwrap());
+            args.remove("locale");
+            return builtin.filter(value, &args);
+        }
+
+        if !args.contains_key("locale") {
+            if let Some(val) = args.get("lang") {
+                let lang = try_get_value!("date", "lang", String, val);
+                let options = self.config.languages.get(&lang).ok_or_else(|| {
+                    TeraError::msg(format!(
+                        "Filter `date` was called with the language `{}`, which isn't configured",
+                        lang
+                    ))
+                })?;
+                if let Some(ref locale) = options.locale {
+                    args.insert("locale".to_string(), to_value(locale).unwrap());
+                }
+            }
+        }
+        builtin.filter(value, &args)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
+    use std::sync::Arc;
 
     use libs::tera::{to_value, Filter, Tera};
 
-    use super::{base64_decode, base64_encode, MarkdownFilter, NumFormatFilter};
-    use config::Config;
+    use super::{
+        base64_decode, base64_encode, cached_regex, hash, plain_text, regex_replace, truncate_html,
+        DateFilter, MarkdownFilter, NumFormatFilter, SlugifyFilter, REGEX_CACHE, REGEX_CACHE_SIZE,
+    };
+    use config::{Config, LanguageOptions, Slugify};
+    use utils::slugs::SlugifyStrategy;
+
+    #[test]
+    fn plain_text_filter() {
+        let html = r#"<h1 id="title">Title</h1>
+<p>Some <em>text</em> &amp; a <a href="https://example.com">link</a>.<br>Next line</p>
+<script>alert("hidden")</script>
+<ul>
+<li>One</li>
+<li>Two &#x2014; &#8220;quoted&#8221;</li>
+</ul>
+<pre><code>let a = 1 &lt; 2;
+</code></pre>
+"#;
+        let result = plain_text(&to_value(html).unwrap(), &HashMap::<String, _>::new());
+        assert_eq!(
+            result.unwrap(),
+            to_value(
+                "Title\n\nSome text & a link.\nNext line\n\n- One\n- Two \u{2014} \u{201c}quoted\u{201d}\n\nlet a = 1 < 2;"
+            )
+            .unwrap()
+        );
+    }
 
     #[test]
     fn markdown_filter() {
-        let result = MarkdownFilter::new(Config::default(), HashMap::new(), Tera::default())
-            .filter(&to_value(&"# Hey").unwrap(), &HashMap::new());
+        let result = MarkdownFilter::new(Config::default(), Arc::default(), Tera::default())
+            .filter(&to_value("# Hey").unwrap(), &HashMap::new());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), to_value(&"<h1 id=\"hey\">Hey</h1>\n").unwrap());
+        assert_eq!(result.unwrap(), to_value("<h1 id=\"hey\">Hey</h1>\n").unwrap());
+    }
+
+    #[test]
+    fn markdown_filter_heading_shift() {
+        let mut args = HashMap::new();
+        args.insert("heading_shift".to_string(), to_value(1).unwrap());
+        let result = MarkdownFilter::new(Config::default(), Arc::default(), Tera::default())
+            .filter(&to_value("# Hey\n## You").unwrap(), &args);
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap(),
+            to_value("<h2 id=\"hey\">Hey</h2>\n<h3 id=\"you\">You</h3>\n").unwrap()
+        );
     }
 
     #[test]
@@ -130,24 +587,61 @@ mod tests {
         let permalinks = HashMap::new();
         let mut tera = Tera::default();
         tera.add_raw_template("shortcodes/explicitlang.html", "a{{ lang }}a").unwrap();
-        let filter = MarkdownFilter { config, permalinks, tera };
-        let result = filter.filter(&to_value(&"{{ explicitlang(lang='jp') }}").unwrap(), &args);
+        let filter = MarkdownFilter::new(config, Arc::new(permalinks), tera);
+        let result = filter.filter(&to_value("{{ explicitlang(lang='jp') }}").unwrap(), &args);
         println!("{:?}", result);
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), to_value(&"ajpa").unwrap());
+        assert_eq!(result.unwrap(), to_value("ajpa").unwrap());
+    }
+
+    #[test]
+    fn markdown_filter_with_page() {
+        let mut config = Config::default_for_test();
+        config.markdown.smart_punctuation = true;
+        let mut permalinks = HashMap::new();
+        permalinks.insert("blog/other.md".to_string(), "https://example.com/other/".to_string());
+        let mut tera = Tera::default();
+        tera.add_raw_template(
+            "shortcodes/who.html",
+            "{{ page.title }} in {{ lang }} on {{ config.base_url | safe }}",
+        )
+        .unwrap();
+        let filter = MarkdownFilter::new(config, Arc::new(permalinks), tera);
+
+        let mut args = HashMap::new();
+        args.insert("inline".to_string(), to_value(true).unwrap());
+        args.insert(
+            "page".to_string(),
+            libs::serde_json::json!({
+                "title": "Hello",
+                "lang": "en",
+                "permalink": "https://example.com/blog/hello/",
+                "relative_path": "blog/hello.md",
+            }),
+        );
+        let result = filter
+            .filter(&to_value("{{ who() }} and \"[another](@/blog/other.md)\"").unwrap(), &args);
+        assert_eq!(
+            result.unwrap(),
+            to_value(
+                "Hello in en on http://a-website.com and \
+                 “<a href=\"https://example.com/other/\">another</a>”"
+            )
+            .unwrap()
+        );
     }
 
     #[test]
     fn markdown_filter_inline() {
         let mut args = HashMap::new();
         args.insert("inline".to_string(), to_value(true).unwrap());
-        let result = MarkdownFilter::new(Config::default(), HashMap::new(), Tera::default())
+        let result = MarkdownFilter::new(Config::default(), Arc::default(), Tera::default())
             .filter(
-                &to_value(&"Using `map`, `filter`, and `fold` instead of `for`").unwrap(),
+                &to_value("Using `map`, `filter`, and `fold` instead of `for`").unwrap(),
                 &args,
             );
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), to_value(&"Using <code>map</code>, <code>filter</code>, and <code>fold</code> instead of <code>for</code>").unwrap());
+        assert_eq!(result.unwrap(), to_value("Using <code>map</code>, <code>filter</code>, and <code>fold</code> instead of <code>for</code>").unwrap());
     }
 
     // https://github.com/Keats/gutenberg/issues/417
@@ -155,10 +649,10 @@ mod tests {
     fn markdown_filter_inline_tables() {
         let mut args = HashMap::new();
         args.insert("inline".to_string(), to_value(true).unwrap());
-        let result = MarkdownFilter::new(Config::default(), HashMap::new(), Tera::default())
+        let result = MarkdownFilter::new(Config::default(), Arc::default(), Tera::default())
             .filter(
                 &to_value(
-                    &r#"
+                    r#"
 |id|author_id|       timestamp_created|title                 |content           |
 |-:|--------:|-----------------------:|:---------------------|:-----------------|
 | 1|        1|2018-09-05 08:03:43.141Z|How to train your ORM |Badly written blog|
@@ -181,14 +675,14 @@ mod tests {
         config.markdown.external_links_target_blank = true;
 
         let md = "Hello <https://google.com> :smile: ...";
-        let result = MarkdownFilter::new(config.clone(), HashMap::new(), Tera::default())
-            .filter(&to_value(&md).unwrap(), &HashMap::new());
+        let result = MarkdownFilter::new(config.clone(), Arc::default(), Tera::default())
+            .filter(&to_value(md).unwrap(), &HashMap::new());
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), to_value(&"<p>Hello <a rel=\"noopener\" target=\"_blank\" href=\"https://google.com\">https://google.com</a> 😄 …</p>\n").unwrap());
+        assert_eq!(result.unwrap(), to_value("<p>Hello <a rel=\"noopener\" target=\"_blank\" href=\"https://google.com\">https://google.com</a> 😄 …</p>\n").unwrap());
 
         let md = "```py\ni=0\n```";
-        let result = MarkdownFilter::new(config, HashMap::new(), Tera::default())
-            .filter(&to_value(&md).unwrap(), &HashMap::new());
+        let result = MarkdownFilter::new(config, Arc::default(), Tera::default())
+            .filter(&to_value(md).unwrap(), &HashMap::new());
         assert!(result.is_ok());
         assert!(result.unwrap().as_str().unwrap().contains("style"));
     }
@@ -198,12 +692,12 @@ mod tests {
         let mut permalinks = HashMap::new();
         permalinks.insert("blog/_index.md".to_string(), "/foo/blog".to_string());
         let md = "Hello. Check out [my blog](@/blog/_index.md)!";
-        let result = MarkdownFilter::new(Config::default(), permalinks, Tera::default())
-            .filter(&to_value(&md).unwrap(), &HashMap::new());
+        let result = MarkdownFilter::new(Config::default(), Arc::new(permalinks), Tera::default())
+            .filter(&to_value(md).unwrap(), &HashMap::new());
         assert!(result.is_ok());
         assert_eq!(
             result.unwrap(),
-            to_value(&"<p>Hello. Check out <a href=\"/foo/blog\">my blog</a>!</p>\n").unwrap()
+            to_value("<p>Hello. Check out <a href=\"/foo/blog\">my blog</a>!</p>\n").unwrap()
         );
     }
 
@@ -285,4 +779,202 @@ mod tests {
             assert_eq!(result.unwrap(), to_value(expected).unwrap());
         }
     }
+
+    #[test]
+    fn truncate_html_filter() {
+        let html = "<p>One <em>two three</em> four</p>\n<p>Five<br>six &amp; seven</p>\n";
+        let tests = vec![
+            (2, "words", html, "<p>One <em>two…</em></p>"),
+            (3, "words", html, "<p>One <em>two three…</em></p>"),
+            (4, "words", html, "<p>One <em>two three</em> four…</p>"),
+            (6, "words", html, "<p>One <em>two three</em> four</p>\n<p>Five<br>six…</p>"),
+            (20, "words", html, html),
+            (6, "chars", html, "<p>One <em>tw…</em></p>"),
+            (28, "chars", html, "<p>One <em>two three</em> four</p>\n<p>Five<br>six &amp;…</p>"),
+            (
+                1,
+                "words",
+                "<div><!-- <p> -->Hello <img src=\"a.png\"/> world</div>",
+                "<div><!-- <p> -->Hello…</div>",
+            ),
+        ];
+        for (length, by, input, expected) in tests {
+            let mut args = HashMap::new();
+            args.insert("length".to_string(), to_value(length).unwrap());
+            args.insert("by".to_string(), to_value(by).unwrap());
+            let result = truncate_html(&to_value(input).unwrap(), &args);
+            assert_eq!(result.unwrap(), to_value(expected).unwrap(), "{} {}", length, by);
+        }
+
+        let mut args = HashMap::new();
+        args.insert("length".to_string(), to_value(1).unwrap());
+        args.insert("end".to_string(), to_value(" [...]").unwrap());
+        let result = truncate_html(&to_value(html).unwrap(), &args);
+        assert_eq!(result.unwrap(), to_value("<p>One [...]</p>").unwrap());
+        assert!(truncate_html(&to_value(html).unwrap(), &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn regex_replace_filter() {
+        let tests = vec![
+            ("2022-01-31", r"(\d+)-(\d+)-(\d+)", "$3/$2/$1", "31/01/2022"),
+            ("a  lot   of  spaces", r"\s+", " ", "a lot of spaces"),
+            ("Zola", "^(?P<first>.)", "[${first}]", "[Z]ola"),
+            ("nothing", "x", "y", "nothing"),
+        ];
+        for (input, pattern, rep, expected) in tests {
+            let mut args = HashMap::new();
+            args.insert("pattern".to_string(), to_value(pattern).unwrap());
+            args.insert("rep".to_string(), to_value(rep).unwrap());
+            let result = regex_replace(&to_value(input).unwrap(), &args);
+            assert_eq!(result.unwrap(), to_value(expected).unwrap());
+            // From the cache this time
+            let result = regex_replace(&to_value(input).unwrap(), &args);
+            assert_eq!(result.unwrap(), to_value(expected).unwrap());
+        }
+
+        let mut args = HashMap::new();
+        args.insert("pattern".to_string(), to_value("(").unwrap());
+        args.insert("rep".to_string(), to_value("").unwrap());
+        let err = regex_replace(&to_value("a").unwrap(), &args).unwrap_err();
+        assert!(err.to_string().contains("invalid `pattern` argument"));
+    }
+
+    #[test]
+    fn regex_replace_keeps_the_last_patterns() {
+        let patterns: Vec<_> = (0..=REGEX_CACHE_SIZE).map(|i| format!("^lru{}$", i)).collect();
+        for pattern in &patterns {
+            cached_regex(pattern.clone()).unwrap();
+        }
+        let cache = REGEX_CACHE.lock().unwrap();
+        assert_eq!(cache.len(), REGEX_CACHE_SIZE);
+        assert!(!cache.iter().any(|(p, _)| *p == patterns[0]));
+        assert!(cache.iter().any(|(p, _)| *p == patterns[REGEX_CACHE_SIZE]));
+    }
+
+    #[test]
+    fn hash_filter() {
+        let tests = vec![
+            ("", "md5", None, "d41d8cd98f00b204e9800998ecf8427e"),
+            ("abc", "sha1", None, "a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (
+                "abc",
+                "sha256",
+                None,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                "The quick brown fox jumps over the lazy dog",
+                "sha256",
+                Some("key"),
+                "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8",
+            ),
+        ];
+        for (input, algorithm, key, expected) in tests {
+            let mut args = HashMap::new();
+            args.insert("algorithm".to_string(), to_value(algorithm).unwrap());
+            if let Some(key) = key {
+                args.insert("key".to_string(), to_value(key).unwrap());
+            }
+            let result = hash(&to_value(input).unwrap(), &args);
+            assert_eq!(result.unwrap(), to_value(expected).unwrap());
+        }
+
+        // sha256 by default
+        let mut args = HashMap::new();
+        args.insert("base64".to_string(), to_value(true).unwrap());
+        let result = hash(&to_value("").unwrap(), &args);
+        assert_eq!(
+            result.unwrap(),
+            to_value("47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=").unwrap()
+        );
+
+        let mut args = HashMap::new();
+        args.insert("algorithm".to_string(), to_value("crc32").unwrap());
+        assert!(hash(&to_value("").unwrap(), &args).is_err());
+    }
+
+    #[test]
+    fn slugify_filter() {
+        let slugify = Slugify {
+            paths: SlugifyStrategy::On,
+            taxonomies: SlugifyStrategy::Safe,
+            anchors: SlugifyStrategy::Off,
+        };
+        let tests = vec![
+            (None, "hello-world"),
+            (Some("paths"), "hello-world"),
+            (Some("taxonomies"), "Hello World!"),
+            (Some("anchors"), "Hello_World!"),
+        ];
+        for (kind, expected) in tests {
+            let mut args = HashMap::new();
+            if let Some(kind) = kind {
+                args.insert("type".to_string(), to_value(kind).unwrap());
+            }
+            let result = SlugifyFilter::new(slugify.clone())
+                .filter(&to_value("Hello World!").unwrap(), &args);
+            assert_eq!(result.unwrap(), to_value(expected).unwrap());
+        }
+    }
+
+    #[test]
+    fn date_filter_uses_locale_names() {
+        let mut config = Config::default_for_test();
+        config.languages.get_mut("en").unwrap().locale = Some("fr_FR".to_string());
+        config.languages.insert(
+            "de".to_string(),
+            LanguageOptions { locale: Some("de_DE".to_string()), ..Default::default() },
+        );
+        config.languages.insert("it".to_string(), LanguageOptions::default());
+        let filter = DateFilter::new(config);
+        let date = to_value("2023-08-06T10:00:00+02:00").unwrap();
+
+        let tests = vec![
+            (Some("en"), None, "%A %-d %B %Y, %a %b", "dimanche 6 août 2023, dim. août"),
+            (None, None, "%A %-d %B %Y", "Sunday 6 August 2023"),
+            (Some("de"), None, "%A %-d. %B", "Sonntag 6. August"),
+            (Some("it"), None, "%A %B", "Sunday August"),
+            (None, Some("es_ES"), "%a %d %h", "dom 06 ago"),
+            (Some("en"), None, "100%% %Y", "100% 2023"),
+            (None, Some("pl_PL"), "%A %e %B", "niedziela  6 sierpnia"),
+            (None, None, "rfc2822", "Sun, 06 Aug 2023 10:00:00 +0200"),
+            (Some("en"), None, "rfc2822", "Sun, 06 Aug 2023 10:00:00 +0200"),
+            (Some("de"), None, "rfc3339", "2023-08-06T10:00:00+02:00"),
+        ];
+        for (lang, locale, format, expected) in tests {
+            let mut args = HashMap::new();
+            args.insert("format".to_string(), to_value(format).unwrap());
+            if let Some(lang) = lang {
+                args.insert("lang".to_string(), to_value(lang).unwrap());
+            }
+            if let Some(locale) = locale {
+                args.insert("locale".to_string(), to_value(locale).unwrap());
+            }
+            assert_eq!(filter.filter(&date, &args).unwrap(), to_value(expected).unwrap());
+        }
+    }
+
+    #[test]
+    fn date_filter_uses_timezone_for_names() {
+        let mut config = Config::default_for_test();
+        config.languages.get_mut("en").unwrap().locale = Some("fr_FR".to_string());
+        let mut args = HashMap::new();
+        args.insert("format".to_string(), to_value("%A %-d %B").unwrap());
+        args.insert("lang".to_string(), to_value("en").unwrap());
+        args.insert("timezone".to_string(), to_value("America/New_York").unwrap());
+        let result =
+            DateFilter::new(config).filter(&to_value("2023-09-01T02:00:00+00:00").unwrap(), &args);
+        assert_eq!(result.unwrap(), to_value("jeudi 31 août").unwrap());
+    }
+
+    #[test]
+    fn date_filter_errors_on_unknown_locale() {
+        let mut args = HashMap::new();
+        args.insert("format".to_string(), to_value("%B").unwrap());
+        args.insert("locale".to_string(), to_value("tlh").unwrap());
+        let result = DateFilter::new(Config::default_for_test())
+            .filter(&to_value("2023-08-06").unwrap(), &args);
+        assert!(result.unwrap_err().to_string().contains("Error parsing `tlh` as a locale"));
+    }
 }
@@ -1,9 +1,12 @@
-use content::{Library, Taxonomy};
-use libs::tera::{from_value, to_value, Function as TeraFn, Result, Value};
+use config::Config;
+use content::{Library, Page, Section, Taxonomy};
+use libs::sha2::{Digest, Sha256};
+use libs::tera::{from_value, to_value, Function as TeraFn, Map, Result, Value};
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use utils::slugs::{slugify_paths, SlugifyStrategy};
+use utils::suggestions::closest_matches;
 
 #[derive(Debug)]
 pub struct GetTaxonomyUrl {
@@ -90,9 +93,55 @@ impl TeraFn for GetPage {
         );
         let full_path = self.base_path.join(&path);
         let library = self.library.read().unwrap();
-        match library.pages.get(&full_path) {
-            Some(p) => Ok(to_value(p.serialize(&library)).unwrap()),
-            None => Err(format!("Page `{}` not found.", path).into()),
+        if let Some(p) = library.pages.get(&full_path) {
+            return Ok(to_value(p.serialize(&library)).unwrap());
+        }
+        if let Some(s) = library.sections.get(&full_path) {
+            return Ok(to_value(s.serialize(&library)).unwrap());
+        }
+
+        // Not a file, maybe a permalink or the path of a page
+        let wanted = path.trim_end_matches('/');
+        let is_url = |permalink: &str, path: &str| {
+            permalink.trim_end_matches('/') == wanted || path.trim_end_matches('/') == wanted
+        };
+        if let Some(p) = library.pages.values().find(|p| is_url(&p.permalink, &p.path)) {
+            return Ok(to_value(p.serialize(&library)).unwrap());
+        }
+        if let Some(s) = library.sections.values().find(|s| is_url(&s.permalink, &s.path)) {
+            return Ok(to_value(s.serialize(&library)).unwrap());
+        }
+
+        // Or a slug, which is only useful if a single page has it
+        let mut with_slug: Vec<&Page> = library.pages.values().filter(|p| p.slug == path).collect();
+        match with_slug.len() {
+            0 => (),
+            1 => return Ok(to_value(with_slug[0].serialize(&library)).unwrap()),
+            _ => {
+                with_slug.sort_by(|a, b| a.file.relative.cmp(&b.file.relative));
+                let files: Vec<_> =
+                    with_slug.iter().map(|p| format!("`{}`", p.file.relative)).collect();
+                return Err(format!(
+                    "`get_page`: several pages have the slug `{}`: {}. Use their path instead.",
+                    path,
+                    files.join(", ")
+                )
+                .into());
+            }
+        }
+
+        let files = library
+            .pages
+            .values()
+            .map(|p| p.file.relative.as_str())
+            .chain(library.sections.values().map(|s| s.file.relative.as_str()));
+        let suggestions: Vec<_> =
+            closest_matches(&path, files).iter().map(|f| format!("`{}`", f)).collect();
+        if suggestions.is_empty() {
+            Err(format!("Page `{}` not found.", path).into())
+        } else {
+            Err(format!("Page `{}` not found. Did you mean {}?", path, suggestions.join(", "))
+                .into())
         }
     }
 }
@@ -135,6 +184,396 @@ impl TeraFn for GetSection {
     }
 }
 
+/// A node of the tree returned by `get_section_tree`, `current` and `active` being set for each
+/// call by `select_tree_nodes`
+fn tree_node(
+    title: &Option<String>,
+    permalink: &str,
+    relative_path: &str,
+    weight: Option<usize>,
+    is_section: bool,
+    children: Vec<Value>,
+) -> Value {
+    let mut node = Map::new();
+    node.insert("title".to_string(), to_value(title).unwrap());
+    node.insert("permalink".to_string(), to_value(permalink).unwrap());
+    node.insert("relative_path".to_string(), to_value(relative_path).unwrap());
+    node.insert("weight".to_string(), to_value(weight).unwrap());
+    node.insert("is_section".to_string(), Value::Bool(is_section));
+    node.insert("current".to_string(), Value::Bool(false));
+    node.insert("active".to_string(), Value::Bool(false));
+    node.insert("children".to_string(), Value::Array(children));
+    Value::Object(node)
+}
+
+/// Copies `node` and its children down to `depth`, setting `current` on the node of the current
+/// page or section and `active` on it and on the nodes containing it so themes know which parts
+/// of a sidebar to expand. Only the returned nodes are copied, the cached tree being left as is.
+/// Returns the copy and whether the node is active.
+fn select_tree_nodes(node: &Value, current: Option<&str>, depth: Option<usize>) -> (Value, bool) {
+    let is_current =
+        current.map_or(false, |c| node["relative_path"] == c || node["permalink"] == c);
+    let mut active = is_current;
+    let mut children = Vec::new();
+    if depth != Some(0) {
+        for child in node["children"].as_array().into_iter().flatten() {
+            let (child, child_active) = select_tree_nodes(child, current, depth.map(|d| d - 1));
+            active |= child_active;
+            children.push(child);
+        }
+    }
+
+    let mut selected: Map<String, Value> = node
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| *key != "children")
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    selected.insert("current".to_string(), Value::Bool(is_current));
+    selected.insert("active".to_string(), Value::Bool(active));
+    selected.insert("children".to_string(), Value::Array(children));
+    (Value::Object(selected), active)
+}
+
+#[derive(Debug)]
+pub struct GetSectionTree {
+    base_path: PathBuf,
+    library: Arc<RwLock<Library>>,
+    /// The whole trees already built for the library version, by section path. They are behind
+    /// an `Arc` so the lock isn't held while copying the nodes a call returns
+    trees: Mutex<(usize, HashMap<PathBuf, Arc<Value>>)>,
+}
+impl GetSectionTree {
+    pub fn new(base_path: PathBuf, library: Arc<RwLock<Library>>) -> Self {
+        Self {
+            base_path: base_path.join("content"),
+            library,
+            trees: Mutex::new((0, HashMap::new())),
+        }
+    }
+
+    fn section_node(library: &Library, section: &Section) -> Value {
+        let mut children = Vec::new();
+        for path in &section.pages {
+            let page = &library.pages[path];
+            children.push(tree_node(
+                &page.meta.title,
+                &page.permalink,
+                &page.file.relative,
+                page.meta.weight,
+                false,
+                Vec::new(),
+            ));
+        }
+        for path in &section.subsections {
+            children.push(Self::section_node(library, &library.sections[path]));
+        }
+        tree_node(
+            &section.meta.title,
+            &section.permalink,
+            &section.file.relative,
+            Some(section.meta.weight),
+            true,
+            children,
+        )
+    }
+}
+impl TeraFn for GetSectionTree {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path = required_arg!(
+            String,
+            args.get("path"),
+            "`get_section_tree` requires a `path` argument with a string value"
+        );
+        let current = optional_arg!(
+            String,
+            args.get("current"),
+            "`get_section_tree`: `current` must be a string"
+        );
+        let depth =
+            optional_arg!(usize, args.get("depth"), "`get_section_tree`: `depth` must be a number");
+
+        let library = self.library.read().unwrap();
+        let section_path = self.base_path.join(&path);
+        let tree = {
+            let mut trees = self.trees.lock().unwrap();
+            let (version, trees) = &mut *trees;
+            if *version != library.version() {
+                *version = library.version();
+                trees.clear();
+            }
+            match trees.get(&section_path) {
+                Some(tree) => Arc::clone(tree),
+                None => match library.sections.get(&section_path) {
+                    Some(s) => {
+                        let tree = Arc::new(Self::section_node(&library, s));
+                        trees.insert(section_path, Arc::clone(&tree));
+                        tree
+                    }
+                    None => return Err(format!("Section `{}` not found.", path).into()),
+                },
+            }
+        };
+        Ok(select_tree_nodes(&tree, current.as_deref(), depth).0)
+    }
+}
+
+/// The `<link rel="alternate">` tags of the feeds of a context: the feed of the site in a language
+/// and the ones of a section and of a taxonomy term, if they have any entry
+#[derive(Debug)]
+pub struct FeedTags {
+    base_path: PathBuf,
+    config: Config,
+    library: Arc<RwLock<Library>>,
+}
+impl FeedTags {
+    pub fn new(base_path: PathBuf, config: Config, library: Arc<RwLock<Library>>) -> Self {
+        Self { base_path: base_path.join("content"), config, library }
+    }
+
+    fn link_tag(&self, title: &str, url: &str) -> String {
+        let feed_type = if self.config.feed_filename.contains("atom") { "atom" } else { "rss" };
+        format!(
+            r#"<link rel="alternate" type="application/{}+xml" title="{}" href="{}">"#,
+            feed_type,
+            libs::tera::escape_html(title),
+            url
+        )
+    }
+}
+impl TeraFn for FeedTags {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let lang = optional_arg!(String, args.get("lang"), "`feed_tags`: `lang` must be a string")
+            .unwrap_or_else(|| self.config.default_language.clone());
+        let section =
+            optional_arg!(String, args.get("section"), "`feed_tags`: `section` must be a string");
+        let taxonomy =
+            optional_arg!(String, args.get("taxonomy"), "`feed_tags`: `taxonomy` must be a string");
+        let term = args.get("term");
+        let options = match self.config.languages.get(&lang) {
+            Some(options) => options,
+            None => return Err(format!("`feed_tags`: unknown language `{}`", lang).into()),
+        };
+        let feed_filename = &self.config.feed_filename;
+        let library = self.library.read().unwrap();
+        let mut tags = Vec::new();
+
+        // The same feeds as the ones rendered by the site, see `feeds.opml`
+        if options.generate_feed {
+            let is_multilingual = self.config.is_multilingual();
+            let has_entries = library.pages.values().any(|p| {
+                p.is_in_feed() && !p.is_draft_preview() && (!is_multilingual || p.lang == lang)
+            });
+            if has_entries {
+                let path = if lang == self.config.default_language {
+                    feed_filename.to_string()
+                } else {
+                    format!("{}/{}", lang, feed_filename)
+                };
+                let title = options.title.as_ref().or(self.config.title.as_ref());
+                let title = title.unwrap_or(&self.config.base_url);
+                tags.push(self.link_tag(title, &self.config.make_permalink(&path)));
+            }
+        }
+
+        if let Some(path) = section {
+            let section = match library.sections.get(&self.base_path.join(&path)) {
+                Some(s) => s,
+                None => return Err(format!("`feed_tags`: section `{}` not found.", path).into()),
+            };
+            if section.meta.generate_feed
+                && section.pages.iter().any(|p| library.pages[p].is_in_feed())
+            {
+                let title = section.meta.title.as_ref().unwrap_or(&section.path);
+                let url = format!("{}{}", section.permalink, feed_filename);
+                tags.push(self.link_tag(title, &url));
+            }
+        }
+
+        match (taxonomy, term) {
+            (Some(taxonomy), Some(term)) => {
+                let kind =
+                    options.taxonomies.iter().find(|t| t.name == taxonomy).ok_or_else(|| {
+                        format!("`feed_tags`: taxonomy `{}` not found.", taxonomy)
+                    })?;
+                // The term of the template rather than one of the taxonomies of the site when the
+                // function was registered, which don't have the terms added since by `zola serve`
+                let (name, permalink, pages) = match (
+                    term.get("name").and_then(|n| n.as_str()),
+                    term.get("permalink").and_then(|p| p.as_str()),
+                    term.get("pages").and_then(|p| p.as_array()),
+                ) {
+                    (Some(name), Some(permalink), Some(pages)) => (name, permalink, pages),
+                    _ => return Err("`feed_tags`: `term` must be a taxonomy term".into()),
+                };
+                let has_entries = pages
+                    .iter()
+                    .filter_map(|p| p.get("relative_path").and_then(|r| r.as_str()))
+                    .filter_map(|r| library.pages.get(&self.base_path.join(r)))
+                    .any(|p| p.is_in_feed());
+                if kind.feed && has_entries {
+                    let title = format!("{}: {}", kind.name, name);
+                    let url = format!("{}{}", permalink, feed_filename);
+                    tags.push(self.link_tag(&title, &url));
+                }
+            }
+            (None, None) => (),
+            _ => return Err("`feed_tags`: `taxonomy` and `term` must be given together".into()),
+        }
+
+        Ok(to_value(tags.join("\n")).unwrap())
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// Whether an entry of a menu is the page being rendered (`current`) or one of its ancestors
+/// (`active`), from the sections the page is in rather than from its URL
+#[derive(Debug)]
+pub struct GetMenuState {
+    permalinks: Arc<HashMap<String, String>>,
+}
+impl GetMenuState {
+    pub fn new(permalinks: Arc<HashMap<String, String>>) -> Self {
+        Self { permalinks }
+    }
+
+    fn entry_state(
+        &self,
+        entry: &Value,
+        current: &str,
+        ancestors: &[String],
+    ) -> Map<String, Value> {
+        // An entry linking to an anchor of the page is for the page itself
+        let permalink = entry["permalink"].as_str().unwrap_or_default();
+        let permalink = permalink.split('#').next().unwrap_or_default();
+        let is_current = !permalink.is_empty() && permalink == current;
+        let active = is_current
+            || ancestors.iter().any(|a| self.permalinks.get(a).map_or(false, |p| p == permalink))
+            || entry["children"].as_array().into_iter().flatten().any(|child| {
+                self.entry_state(child, current, ancestors)["active"] == Value::Bool(true)
+            });
+
+        let mut state = Map::new();
+        state.insert("current".to_string(), Value::Bool(is_current));
+        state.insert("active".to_string(), Value::Bool(active));
+        state
+    }
+}
+impl TeraFn for GetMenuState {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let entry = match args.get("entry") {
+            Some(entry @ Value::Object(_)) => entry,
+            _ => {
+                return Err("`get_menu_state` requires an `entry` argument with a menu entry".into())
+            }
+        };
+        let (current, ancestors) = match args.get("current") {
+            Some(Value::String(url)) => (url.as_str(), Vec::new()),
+            Some(Value::Object(current)) => {
+                let permalink = current.get("permalink").and_then(|p| p.as_str());
+                let ancestors = current.get("ancestors").cloned().unwrap_or_default();
+                match (permalink, from_value::<Vec<String>>(ancestors)) {
+                    // The root section is an ancestor of everything, a menu entry for the home
+                    // page is only active on the home page
+                    (Some(permalink), Ok(ancestors)) => {
+                        (permalink, ancestors.into_iter().skip(1).collect())
+                    }
+                    _ => {
+                        return Err("`get_menu_state`: `current` must be a page or a section".into())
+                    }
+                }
+            }
+            _ => {
+                return Err("`get_menu_state` requires a `current` argument with a page, a \
+                            section or a URL"
+                    .into())
+            }
+        };
+
+        Ok(Value::Object(self.entry_state(entry, current, &ancestors)))
+    }
+}
+
+/// The position of a page in a random sample, derived from the seed so the same seed always
+/// gives the same sample
+fn sample_key(seed: &str, page: &Page) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(seed.as_bytes());
+    hasher.update(b"\0");
+    hasher.update(page.file.relative.as_bytes());
+    hasher.finalize().to_vec()
+}
+
+#[derive(Debug)]
+pub struct GetRandomPages {
+    base_path: PathBuf,
+    library: Arc<RwLock<Library>>,
+    default_lang: String,
+}
+impl GetRandomPages {
+    pub fn new(base_path: PathBuf, library: Arc<RwLock<Library>>, default_lang: &str) -> Self {
+        Self {
+            base_path: base_path.join("content"),
+            library,
+            default_lang: default_lang.to_string(),
+        }
+    }
+}
+impl TeraFn for GetRandomPages {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let n = optional_arg!(usize, args.get("n"), "`get_random_pages`: `n` must be a number")
+            .unwrap_or(1);
+        let section = optional_arg!(
+            String,
+            args.get("section"),
+            "`get_random_pages`: `section` must be a string"
+        );
+        let lang =
+            optional_arg!(String, args.get("lang"), "`get_random_pages`: `lang` must be a string")
+                .unwrap_or_else(|| self.default_lang.clone());
+        let seed = match args.get("seed") {
+            Some(Value::String(s)) => s.clone(),
+            Some(v) => v.to_string(),
+            None => String::new(),
+        };
+
+        let library = self.library.read().unwrap();
+        let candidates = match section {
+            Some(section) => {
+                let path = if section.ends_with(".md") {
+                    section
+                } else if section.trim_matches('/').is_empty() {
+                    "_index.md".to_string()
+                } else {
+                    format!("{}/_index.md", section.trim_matches('/'))
+                };
+                match library.sections.get(&self.base_path.join(&path)) {
+                    Some(s) => library.find_pages_by_path(&s.pages),
+                    None => {
+                        return Err(
+                            format!("`get_random_pages`: section `{}` not found.", path).into()
+                        )
+                    }
+                }
+            }
+            None => {
+                library.pages.values().filter(|p| p.lang == lang && !p.is_draft_preview()).collect()
+            }
+        };
+
+        let mut sample: Vec<_> =
+            candidates.into_iter().map(|p| (sample_key(&seed, p), p)).collect();
+        sample.sort_by(|a, b| a.0.cmp(&b.0));
+        let pages: Vec<_> =
+            sample.iter().take(n).map(|(_, p)| p.serialize_for_listing(&library)).collect();
+        Ok(to_value(pages).unwrap())
+    }
+}
+
 #[derive(Debug)]
 pub struct GetTaxonomy {
     library: Arc<RwLock<Library>>,
@@ -186,7 +625,212 @@ impl TeraFn for GetTaxonomy {
 mod tests {
     use super::*;
     use config::{Config, TaxonomyConfig};
-    use content::TaxonomyTerm;
+    use content::{Section, TaxonomyTerm};
+
+    fn create_library(config: &Config) -> Library {
+        let mut library = Library::new(config);
+        let mut section = Section {
+            file: content::FileInfo::new_section(
+                &PathBuf::from("content/blog/_index.md"),
+                &PathBuf::new(),
+            ),
+            ..Section::default()
+        };
+        for i in 1..=10 {
+            let mut page = Page::default();
+            page.file.path = PathBuf::from(format!("content/blog/{}.md", i));
+            page.file.relative = format!("blog/{}.md", i);
+            page.lang = config.default_language.clone();
+            page.meta.title = Some(i.to_string());
+            section.pages.push(page.file.path.clone());
+            library.insert_page(page);
+        }
+        let mut orphan = Page::default();
+        orphan.file.path = PathBuf::from("content/about.md");
+        orphan.file.relative = "about.md".to_string();
+        orphan.lang = config.default_language.clone();
+        orphan.meta.title = Some("About".to_string());
+        library.insert_page(orphan);
+        library.insert_section(section);
+        library
+    }
+
+    #[test]
+    fn can_get_menu_state() {
+        let mut permalinks = HashMap::new();
+        permalinks.insert("_index.md".to_string(), "https://a.com/".to_string());
+        permalinks.insert("docs/_index.md".to_string(), "https://a.com/docs/".to_string());
+        permalinks.insert("docs/api/_index.md".to_string(), "https://a.com/docs/api/".to_string());
+        let static_fn = GetMenuState::new(Arc::new(permalinks));
+        let page = libs::serde_json::json!({
+            "permalink": "https://a.com/docs/api/search/",
+            "ancestors": ["_index.md", "docs/_index.md", "docs/api/_index.md"],
+        });
+        let state = |entry: Value, current: &Value| {
+            let mut args = HashMap::new();
+            args.insert("entry".to_string(), entry);
+            args.insert("current".to_string(), current.clone());
+            let state = static_fn.call(&args).unwrap();
+            (state["current"].as_bool().unwrap(), state["active"].as_bool().unwrap())
+        };
+
+        let home = libs::serde_json::json!({"permalink": "https://a.com/", "children": []});
+        assert_eq!(state(home.clone(), &page), (false, false));
+        assert_eq!(state(home, &to_value("https://a.com/").unwrap()), (true, true));
+        let docs = libs::serde_json::json!({"permalink": "https://a.com/docs/", "children": []});
+        assert_eq!(state(docs, &page), (false, true));
+        let search = libs::serde_json::json!({"permalink": "https://a.com/docs/api/search/#query"});
+        assert_eq!(state(search.clone(), &page), (true, true));
+        // Active through its children even if it isn't a section of the page
+        let menu = libs::serde_json::json!({"permalink": "https://b.com/", "children": [search]});
+        assert_eq!(state(menu, &page), (false, true));
+        // A URL can't have ancestors
+        let docs = libs::serde_json::json!({"permalink": "https://a.com/docs/"});
+        assert_eq!(state(docs, &to_value("https://a.com/docs/api/").unwrap()), (false, false));
+    }
+
+    fn random_titles(static_fn: &GetRandomPages, args: &HashMap<String, Value>) -> Vec<String> {
+        static_fn
+            .call(args)
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["title"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn can_get_page_by_path_permalink_or_slug() {
+        let config = Config::default_for_test();
+        let mut library = create_library(&config);
+        for i in 1..=10 {
+            let page =
+                library.pages.get_mut(&PathBuf::from(format!("content/blog/{}.md", i))).unwrap();
+            page.slug = format!("post-{}", i);
+            page.path = format!("/blog/post-{}/", i);
+            page.permalink = format!("https://example.com/blog/post-{}/", i);
+        }
+        let mut translation = library.pages[&PathBuf::from("content/blog/1.md")].clone();
+        translation.file.path = PathBuf::from("content/blog/1.fr.md");
+        translation.file.relative = "blog/1.fr.md".to_string();
+        library.insert_page(translation);
+        let static_fn = GetPage::new(PathBuf::new(), Arc::new(RwLock::new(library)));
+
+        let title = |path: &str| {
+            let mut args = HashMap::new();
+            args.insert("path".to_string(), to_value(path).unwrap());
+            static_fn.call(&args).map(|p| p["title"].as_str().unwrap_or_default().to_string())
+        };
+        assert_eq!(title("blog/2.md").unwrap(), "2");
+        assert_eq!(title("https://example.com/blog/post-3/").unwrap(), "3");
+        assert_eq!(title("/blog/post-4").unwrap(), "4");
+        assert_eq!(title("post-5").unwrap(), "5");
+        // Sections work too
+        assert!(title("blog/_index.md").is_ok());
+
+        let error = |path: &str| format!("{:?}", title(path).unwrap_err());
+        assert!(error("post-1").contains("`blog/1.fr.md`, `blog/1.md`"));
+        assert!(error("blog/abot.md").contains("Did you mean `about.md`"));
+        assert!(error("nothing/like/it.md").contains("Page `nothing/like/it.md` not found."));
+    }
+
+    #[test]
+    fn can_get_section_tree() {
+        let config = Config::default_for_test();
+        let mut library = create_library(&config);
+        let mut docs = Section {
+            file: content::FileInfo::new_section(
+                &PathBuf::from("content/docs/_index.md"),
+                &PathBuf::new(),
+            ),
+            permalink: "https://example.com/docs/".to_string(),
+            ..Section::default()
+        };
+        docs.meta.title = Some("Docs".to_string());
+        docs.subsections.push(PathBuf::from("content/blog/_index.md"));
+        library.insert_section(docs);
+        for i in 1..=10 {
+            let page =
+                library.pages.get_mut(&PathBuf::from(format!("content/blog/{}.md", i))).unwrap();
+            page.permalink = format!("https://example.com/blog/{}/", i);
+        }
+        let library = Arc::new(RwLock::new(library));
+        let static_fn = GetSectionTree::new(PathBuf::new(), library.clone());
+
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("docs/_index.md").unwrap());
+        args.insert("current".to_string(), to_value("https://example.com/blog/2/").unwrap());
+        let tree = static_fn.call(&args).unwrap();
+        assert_eq!(tree["title"], "Docs");
+        assert_eq!(tree["is_section"], true);
+        assert_eq!(tree["current"], false);
+        assert_eq!(tree["active"], true);
+        let blog = &tree["children"][0];
+        assert_eq!(blog["relative_path"], "blog/_index.md");
+        assert_eq!(blog["active"], true);
+        assert_eq!(blog["children"].as_array().unwrap().len(), 10);
+        assert_eq!(blog["children"][1]["title"], "2");
+        assert_eq!(blog["children"][1]["is_section"], false);
+        assert_eq!(blog["children"][1]["current"], true);
+        assert_eq!(blog["children"][0]["current"], false);
+
+        args.insert("current".to_string(), to_value("blog/_index.md").unwrap());
+        args.insert("depth".to_string(), to_value(1).unwrap());
+        let tree = static_fn.call(&args).unwrap();
+        assert_eq!(tree["children"][0]["current"], true);
+        assert_eq!(tree["children"][0]["children"].as_array().unwrap().len(), 0);
+
+        // The cached tree isn't changed by the nodes selected for a call
+        args.remove("depth");
+        let tree = static_fn.call(&args).unwrap();
+        assert_eq!(tree["children"][0]["children"].as_array().unwrap().len(), 10);
+        assert_eq!(tree["children"][0]["children"][1]["current"], false);
+
+        // The cached tree is rebuilt once the library changes
+        let mut page = library.read().unwrap().pages[&PathBuf::from("content/blog/2.md")].clone();
+        page.meta.title = Some("Two".to_string());
+        library.write().unwrap().insert_page(page);
+        let tree = static_fn.call(&args).unwrap();
+        assert_eq!(tree["children"][0]["children"][1]["title"], "Two");
+
+        args.insert("path".to_string(), to_value("missing/_index.md").unwrap());
+        assert!(static_fn.call(&args).is_err());
+    }
+
+    #[test]
+    fn can_get_random_pages() {
+        let config = Config::default_for_test();
+        let library = Arc::new(RwLock::new(create_library(&config)));
+        let static_fn = GetRandomPages::new(PathBuf::new(), library, &config.default_language);
+
+        let mut args = HashMap::new();
+        args.insert("n".to_string(), to_value(3).unwrap());
+        args.insert("section".to_string(), to_value("blog").unwrap());
+        let titles = random_titles(&static_fn, &args);
+        assert_eq!(titles.len(), 3);
+        assert!(!titles.contains(&"About".to_string()));
+        // The same arguments always give the same pages
+        assert_eq!(random_titles(&static_fn, &args), titles);
+
+        // Another seed gives another sample
+        let samples: Vec<_> = (0..5)
+            .map(|seed| {
+                args.insert("seed".to_string(), to_value(seed).unwrap());
+                random_titles(&static_fn, &args)
+            })
+            .collect();
+        assert!(samples.iter().any(|s| s != &titles));
+
+        // Asking for more pages than there are gives all of them
+        let mut args = HashMap::new();
+        args.insert("n".to_string(), to_value(20).unwrap());
+        assert_eq!(random_titles(&static_fn, &args).len(), 11);
+
+        let mut args = HashMap::new();
+        args.insert("section".to_string(), to_value("unknown").unwrap());
+        assert!(static_fn.call(&args).is_err());
+    }
 
     #[test]
     fn can_get_taxonomy() {
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use libs::tera::{from_value, to_value, Function as TeraFn, Result, Value};
+use libs::time::format_description::well_known::Rfc3339;
+use libs::time::OffsetDateTime;
+
+/// Replaces the `now` function of Tera when `SOURCE_DATE_EPOCH` is set so the date of the build
+/// doesn't end up in the output.
+/// The date is always in UTC, since the local offset of the machine building the site would make
+/// it differ as well.
+#[derive(Debug)]
+pub struct Now {
+    datetime: OffsetDateTime,
+}
+impl Now {
+    pub fn new(datetime: OffsetDateTime) -> Self {
+        Self { datetime }
+    }
+}
+impl TeraFn for Now {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        // Only checked so the arguments are the same as the Tera function
+        optional_arg!(
+            bool,
+            args.get("utc"),
+            "Function `now` received `utc` but `utc` can only be a boolean"
+        );
+        let timestamp = optional_arg!(
+            bool,
+            args.get("timestamp"),
+            "Function `now` received `timestamp` but `timestamp` can only be a boolean"
+        )
+        .unwrap_or(false);
+
+        if timestamp {
+            return Ok(to_value(self.datetime.unix_timestamp()).unwrap());
+        }
+        let formatted = self
+            .datetime
+            .format(&Rfc3339)
+            .map_err(|e| format!("Function `now` failed to format the date: {}", e))?;
+        Ok(to_value(formatted).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_return_the_fixed_date() {
+        let static_fn = Now::new(OffsetDateTime::from_unix_timestamp(1654086600).unwrap());
+        let mut args = HashMap::new();
+        assert_eq!(static_fn.call(&args).unwrap(), "2022-06-01T12:30:00Z");
+
+        args.insert("utc".to_string(), to_value(true).unwrap());
+        assert_eq!(static_fn.call(&args).unwrap(), "2022-06-01T12:30:00Z");
+
+        args.insert("timestamp".to_string(), to_value(true).unwrap());
+        assert_eq!(static_fn.call(&args).unwrap(), 1654086600);
+    }
+
+    #[test]
+    fn errors_on_invalid_arguments() {
+        let static_fn = Now::new(OffsetDateTime::from_unix_timestamp(1654086600).unwrap());
+        let mut args = HashMap::new();
+        args.insert("timestamp".to_string(), to_value("yes").unwrap());
+        assert!(static_fn.call(&args).is_err());
+    }
+}
@@ -1,20 +1,20 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::{fs, io, result};
 
 use crate::global_fns::helpers::search_for_file;
-use config::Config;
+use config::{Config, Favicons};
 use libs::base64::encode as encode_b64;
 use libs::sha2::{digest, Sha256, Sha384, Sha512};
 use libs::tera::{from_value, to_value, Function as TeraFn, Result, Value};
 use libs::url;
 use utils::site::resolve_internal_link;
 
-fn compute_file_hash<D: digest::Digest>(
-    mut file: fs::File,
-    as_base64: bool,
-) -> result::Result<String, io::Error>
+fn compute_file_hash<D>(mut file: fs::File, as_base64: bool) -> result::Result<String, io::Error>
 where
+    D: digest::Digest,
     digest::Output<D>: core::fmt::LowerHex,
     D: std::io::Write,
 {
@@ -31,7 +31,7 @@ where
 pub struct GetUrl {
     base_path: PathBuf,
     config: Config,
-    permalinks: HashMap<String, String>,
+    permalinks: Arc<HashMap<String, String>>,
     output_path: PathBuf,
 }
 
@@ -39,7 +39,7 @@ impl GetUrl {
     pub fn new(
         base_path: PathBuf,
         config: Config,
-        permalinks: HashMap<String, String>,
+        permalinks: Arc<HashMap<String, String>>,
         output_path: PathBuf,
     ) -> Self {
         Self { base_path, config, permalinks, output_path }
@@ -87,10 +87,7 @@ impl TeraFn for GetUrl {
 
         // if it starts with @/, resolve it as an internal link
         if path.starts_with("@/") {
-            let path_with_lang = match make_path_with_lang(path, &lang, &self.config) {
-                Ok(x) => x,
-                Err(e) => return Err(e),
-            };
+            let path_with_lang = make_path_with_lang(path, &lang, &self.config)?;
 
             match resolve_internal_link(&path_with_lang, &self.permalinks) {
                 Ok(resolved) => Ok(to_value(resolved.permalink).unwrap()),
@@ -104,10 +101,10 @@ impl TeraFn for GetUrl {
             // anything else
             let mut segments = vec![];
 
-            if lang != self.config.default_language {
-                if path.is_empty() || !path[1..].starts_with(&lang) {
-                    segments.push(lang);
-                }
+            if lang != self.config.default_language
+                && (path.is_empty() || !path[1..].starts_with(&lang))
+            {
+                segments.push(lang);
             }
 
             segments.push(path);
@@ -165,15 +162,73 @@ impl TeraFn for GetUrl {
     }
 }
 
-#[derive(Debug)]
-pub struct GetFileHash {
+/// Finds the file at `path` and computes its hash, `fn_name` being the name of the Tera function
+/// used in the error messages
+fn hash_file(
+    fn_name: &str,
+    search: &FileSearch,
+    path: &str,
+    sha_type: u16,
+    base64: bool,
+) -> Result<String> {
+    let file_path =
+        match search_for_file(&search.base_path, path, &search.theme, &search.output_path)
+            .map_err(|e| format!("`{}`: {}", fn_name, e))?
+        {
+            Some((f, _)) => f,
+            None => {
+                return Err(format!("`{}`: Cannot find file: {}", fn_name, path).into());
+            }
+        };
+
+    let f = match std::fs::File::open(file_path) {
+        Ok(f) => f,
+        Err(e) => {
+            return Err(format!("File {} could not be open: {}", path, e).into());
+        }
+    };
+
+    let hash = match sha_type {
+        256 => compute_file_hash::<Sha256>(f, base64),
+        384 => compute_file_hash::<Sha384>(f, base64),
+        512 => compute_file_hash::<Sha512>(f, base64),
+        _ => return Err(format!("`{}`: Invalid sha value", fn_name).into()),
+    };
+    // The output now depends on the content of that file
+    search.used.store(true, Ordering::Relaxed);
+
+    hash.map_err(|_| format!("`{}`: could no compute hash", fn_name).into())
+}
+
+/// Where to look for the files to hash
+#[derive(Debug, Clone)]
+pub struct FileSearch {
     base_path: PathBuf,
     theme: Option<String>,
     output_path: PathBuf,
+    /// Set once a file has been hashed, so `zola serve` knows to re-render the site when an asset
+    /// changes
+    used: Arc<AtomicBool>,
+}
+
+impl FileSearch {
+    pub fn new(
+        base_path: PathBuf,
+        theme: Option<String>,
+        output_path: PathBuf,
+        used: Arc<AtomicBool>,
+    ) -> Self {
+        Self { base_path, theme, output_path, used }
+    }
+}
+
+#[derive(Debug)]
+pub struct GetFileHash {
+    search: FileSearch,
 }
 impl GetFileHash {
-    pub fn new(base_path: PathBuf, theme: Option<String>, output_path: PathBuf) -> Self {
-        Self { base_path, theme, output_path }
+    pub fn new(search: FileSearch) -> Self {
+        Self { search }
     }
 }
 
@@ -197,44 +252,105 @@ impl TeraFn for GetFileHash {
         )
         .unwrap_or(true);
 
-        let file_path =
-            match search_for_file(&self.base_path, &path, &self.theme, &self.output_path)
-                .map_err(|e| format!("`get_file_hash`: {}", e))?
-            {
-                Some((f, _)) => f,
-                None => {
-                    return Err(format!("`get_file_hash`: Cannot find file: {}", path).into());
-                }
-            };
-
-        let f = match std::fs::File::open(file_path) {
-            Ok(f) => f,
-            Err(e) => {
-                return Err(format!("File {} could not be open: {}", path, e).into());
-            }
-        };
+        hash_file("get_file_hash", &self.search, &path, sha_type, base64)
+            .map(|digest| to_value(digest).unwrap())
+    }
+}
 
-        let hash = match sha_type {
-            256 => compute_file_hash::<Sha256>(f, base64),
-            384 => compute_file_hash::<Sha384>(f, base64),
-            512 => compute_file_hash::<Sha512>(f, base64),
-            _ => return Err("`get_file_hash`: Invalid sha value".into()),
-        };
+#[derive(Debug)]
+pub struct GetIntegrity {
+    search: FileSearch,
+}
+impl GetIntegrity {
+    pub fn new(search: FileSearch) -> Self {
+        Self { search }
+    }
+}
+
+impl TeraFn for GetIntegrity {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path = required_arg!(
+            String,
+            args.get("path"),
+            "`get_integrity` requires a `path` argument with a string value"
+        );
+        let sha_type = optional_arg!(
+            u16,
+            args.get("sha_type"),
+            "`get_integrity`: `sha_type` must be 256, 384 or 512"
+        )
+        .unwrap_or(384);
+
+        hash_file("get_integrity", &self.search, &path, sha_type, true)
+            .map(|digest| to_value(format!("sha{}-{}", sha_type, digest)).unwrap())
+    }
 
-        match hash {
-            Ok(digest) => Ok(to_value(digest).unwrap()),
-            Err(_) => Err("`get_file_hash`: could no compute hash".into()),
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// The `<link>` tags of the icons generated by `[favicons]` and of their web manifest
+#[derive(Debug)]
+pub struct FaviconTags {
+    config: Config,
+}
+impl FaviconTags {
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+
+    fn url(&self, filename: &str) -> String {
+        let mut url = self.config.make_permalink(filename);
+        url.pop(); // Removes the slash
+        url
+    }
+}
+impl TeraFn for FaviconTags {
+    fn call(&self, _: &HashMap<String, Value>) -> Result<Value> {
+        // Themes can call it whether the site generates favicons or not
+        if !self.config.favicons.is_enabled() {
+            return Ok(to_value("").unwrap());
+        }
+
+        let mut tags = vec![format!(
+            r#"<link rel="icon" type="image/x-icon" href="{}">"#,
+            self.url("favicon.ico")
+        )];
+        for icon in Favicons::ICONS {
+            if let Some(rel) = icon.rel {
+                let type_attr = if rel == "icon" { r#" type="image/png""# } else { "" };
+                tags.push(format!(
+                    r#"<link rel="{rel}"{type_attr} sizes="{size}x{size}" href="{url}">"#,
+                    rel = rel,
+                    type_attr = type_attr,
+                    size = icon.size,
+                    url = self.url(icon.filename)
+                ));
+            }
         }
+        tags.push(format!(r#"<link rel="manifest" href="{}">"#, self.url("site.webmanifest")));
+        tags.push(format!(
+            r#"<meta name="theme-color" content="{}">"#,
+            libs::tera::escape_html(&self.config.favicons.theme_color)
+        ));
+        Ok(to_value(tags.join("\n")).unwrap())
+    }
+
+    fn is_safe(&self) -> bool {
+        true
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{GetFileHash, GetUrl};
+    use super::{FaviconTags, FileSearch, GetFileHash, GetIntegrity, GetUrl};
 
     use std::collections::HashMap;
     use std::fs::create_dir;
     use std::path::PathBuf;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
 
     use libs::tera::{to_value, Function};
     use tempfile::{tempdir, TempDir};
@@ -242,6 +358,10 @@ mod tests {
     use config::Config;
     use utils::fs::create_file;
 
+    fn file_search(dir: TempDir) -> FileSearch {
+        FileSearch::new(dir.into_path(), None, PathBuf::new(), Arc::new(AtomicBool::new(false)))
+    }
+
     fn create_temp_dir() -> TempDir {
         let dir = tempdir().unwrap();
         create_file(&dir.path().join("app.css"), "// Hello world!").expect("Failed to create file");
@@ -260,13 +380,36 @@ title = "Un titre"
 title = "A title"
 "#;
 
+    #[test]
+    fn can_render_favicon_tags() {
+        let mut config = Config::default();
+        let static_fn = FaviconTags::new(config.clone());
+        assert_eq!(static_fn.call(&HashMap::new()).unwrap(), "");
+
+        config.favicons.source = Some("static/logo.png".to_string());
+        let static_fn = FaviconTags::new(config);
+        let tags = static_fn.call(&HashMap::new()).unwrap();
+        let tags: Vec<_> = tags.as_str().unwrap().lines().collect();
+        assert_eq!(
+            tags,
+            [
+                r#"<link rel="icon" type="image/x-icon" href="http://a-website.com/favicon.ico">"#,
+                r#"<link rel="icon" type="image/png" sizes="16x16" href="http://a-website.com/favicon-16x16.png">"#,
+                r#"<link rel="icon" type="image/png" sizes="32x32" href="http://a-website.com/favicon-32x32.png">"#,
+                r#"<link rel="apple-touch-icon" sizes="180x180" href="http://a-website.com/apple-touch-icon.png">"#,
+                r#"<link rel="manifest" href="http://a-website.com/site.webmanifest">"#,
+                r##"<meta name="theme-color" content="#ffffff">"##,
+            ]
+        );
+    }
+
     #[test]
     fn can_add_cachebust_to_url() {
         let dir = create_temp_dir();
         let static_fn = GetUrl::new(
             dir.path().to_path_buf(),
             Config::default(),
-            HashMap::new(),
+            Arc::default(),
             PathBuf::new(),
         );
         let mut args = HashMap::new();
@@ -281,7 +424,7 @@ title = "A title"
         let static_fn = GetUrl::new(
             dir.path().to_path_buf(),
             Config::default(),
-            HashMap::new(),
+            Arc::default(),
             PathBuf::new(),
         );
         let mut args = HashMap::new();
@@ -296,7 +439,7 @@ title = "A title"
         let static_fn = GetUrl::new(
             dir.path().to_path_buf(),
             Config::default(),
-            HashMap::new(),
+            Arc::default(),
             PathBuf::new(),
         );
         let mut args = HashMap::new();
@@ -312,7 +455,7 @@ title = "A title"
         let static_fn = GetUrl::new(
             dir.path().to_path_buf(),
             Config::default(),
-            HashMap::new(),
+            Arc::default(),
             PathBuf::new(),
         );
         let mut args = HashMap::new();
@@ -333,7 +476,7 @@ title = "A title"
             .expect("Failed to create file in output directory");
 
         let static_fn =
-            GetUrl::new(dir.path().to_path_buf(), Config::default(), HashMap::new(), public);
+            GetUrl::new(dir.path().to_path_buf(), Config::default(), Arc::default(), public);
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("style.css").unwrap());
         assert_eq!(static_fn.call(&args).unwrap(), "http://a-website.com/style.css");
@@ -344,7 +487,7 @@ title = "A title"
         let config = Config::parse(CONFIG_DATA).unwrap();
         let dir = create_temp_dir();
         let static_fn =
-            GetUrl::new(dir.path().to_path_buf(), config, HashMap::new(), PathBuf::new());
+            GetUrl::new(dir.path().to_path_buf(), config, Arc::default(), PathBuf::new());
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("@/a_section/a_page.md").unwrap());
         args.insert("lang".to_string(), to_value("it").unwrap());
@@ -371,7 +514,7 @@ title = "A title"
         let static_fn = GetUrl::new(
             dir.path().to_path_buf(),
             config.clone(),
-            permalinks.clone(),
+            Arc::new(permalinks.clone()),
             PathBuf::new(),
         );
         let mut args = HashMap::new();
@@ -396,7 +539,8 @@ title = "A title"
             "https://remplace-par-ton-url.fr/en/a_section/a_page/".to_string(),
         );
         let dir = create_temp_dir();
-        let static_fn = GetUrl::new(dir.path().to_path_buf(), config, permalinks, PathBuf::new());
+        let static_fn =
+            GetUrl::new(dir.path().to_path_buf(), config, Arc::new(permalinks), PathBuf::new());
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("@/a_section/a_page.md").unwrap());
         args.insert("lang".to_string(), to_value("en").unwrap());
@@ -419,7 +563,8 @@ title = "A title"
             "https://remplace-par-ton-url.fr/en/a_section/a_page/".to_string(),
         );
         let dir = create_temp_dir();
-        let static_fn = GetUrl::new(dir.path().to_path_buf(), config, permalinks, PathBuf::new());
+        let static_fn =
+            GetUrl::new(dir.path().to_path_buf(), config, Arc::new(permalinks), PathBuf::new());
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("/en/a_section/a_page/").unwrap());
         args.insert("lang".to_string(), to_value("en").unwrap());
@@ -434,7 +579,7 @@ title = "A title"
         let config = Config::parse(CONFIG_DATA).unwrap();
         let dir = create_temp_dir();
         let static_fn =
-            GetUrl::new(dir.path().to_path_buf(), config.clone(), HashMap::new(), PathBuf::new());
+            GetUrl::new(dir.path().to_path_buf(), config.clone(), Arc::default(), PathBuf::new());
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value(config.feed_filename).unwrap());
         args.insert("lang".to_string(), to_value("fr").unwrap());
@@ -446,7 +591,7 @@ title = "A title"
         let config = Config::parse(CONFIG_DATA).unwrap();
         let dir = create_temp_dir();
         let static_fn =
-            GetUrl::new(dir.path().to_path_buf(), config.clone(), HashMap::new(), PathBuf::new());
+            GetUrl::new(dir.path().to_path_buf(), config.clone(), Arc::default(), PathBuf::new());
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value(config.feed_filename).unwrap());
         args.insert("lang".to_string(), to_value("en").unwrap());
@@ -456,7 +601,7 @@ title = "A title"
     #[test]
     fn can_get_file_hash_sha256_no_base64() {
         let dir = create_temp_dir();
-        let static_fn = GetFileHash::new(dir.into_path(), None, PathBuf::new());
+        let static_fn = GetFileHash::new(file_search(dir));
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("app.css").unwrap());
         args.insert("sha_type".to_string(), to_value(256).unwrap());
@@ -470,7 +615,7 @@ title = "A title"
     #[test]
     fn can_get_file_hash_sha256_base64() {
         let dir = create_temp_dir();
-        let static_fn = GetFileHash::new(dir.into_path(), None, PathBuf::new());
+        let static_fn = GetFileHash::new(file_search(dir));
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("app.css").unwrap());
         args.insert("sha_type".to_string(), to_value(256).unwrap());
@@ -481,7 +626,7 @@ title = "A title"
     #[test]
     fn can_get_file_hash_sha384_no_base64() {
         let dir = create_temp_dir();
-        let static_fn = GetFileHash::new(dir.into_path(), None, PathBuf::new());
+        let static_fn = GetFileHash::new(file_search(dir));
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("app.css").unwrap());
         args.insert("base64".to_string(), to_value(false).unwrap());
@@ -494,7 +639,7 @@ title = "A title"
     #[test]
     fn can_get_file_hash_sha384() {
         let dir = create_temp_dir();
-        let static_fn = GetFileHash::new(dir.into_path(), None, PathBuf::new());
+        let static_fn = GetFileHash::new(file_search(dir));
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("app.css").unwrap());
         assert_eq!(
@@ -506,7 +651,7 @@ title = "A title"
     #[test]
     fn can_get_file_hash_sha512_no_base64() {
         let dir = create_temp_dir();
-        let static_fn = GetFileHash::new(dir.into_path(), None, PathBuf::new());
+        let static_fn = GetFileHash::new(file_search(dir));
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("app.css").unwrap());
         args.insert("sha_type".to_string(), to_value(512).unwrap());
@@ -520,7 +665,7 @@ title = "A title"
     #[test]
     fn can_get_file_hash_sha512() {
         let dir = create_temp_dir();
-        let static_fn = GetFileHash::new(dir.into_path(), None, PathBuf::new());
+        let static_fn = GetFileHash::new(file_search(dir));
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("app.css").unwrap());
         args.insert("sha_type".to_string(), to_value(512).unwrap());
@@ -535,7 +680,7 @@ title = "A title"
         let config = Config::parse(CONFIG_DATA).unwrap();
         let dir = create_temp_dir();
         let static_fn =
-            GetUrl::new(dir.path().to_path_buf(), config, HashMap::new(), PathBuf::new());
+            GetUrl::new(dir.path().to_path_buf(), config, Arc::default(), PathBuf::new());
         let mut args = HashMap::new();
         args.insert(
             "path".to_string(),
@@ -554,11 +699,32 @@ title = "A title"
     #[test]
     fn error_when_file_not_found_for_hash() {
         let dir = create_temp_dir();
-        let static_fn = GetFileHash::new(dir.into_path(), None, PathBuf::new());
+        let static_fn = GetFileHash::new(file_search(dir));
         let mut args = HashMap::new();
         args.insert("path".to_string(), to_value("doesnt-exist").unwrap());
         let err = format!("{}", static_fn.call(&args).unwrap_err());
 
         assert!(err.contains("Cannot find file"));
     }
+
+    #[test]
+    fn can_get_integrity() {
+        let dir = create_temp_dir();
+        let used = Arc::new(AtomicBool::new(false));
+        let static_fn =
+            GetIntegrity::new(FileSearch::new(dir.into_path(), None, PathBuf::new(), used.clone()));
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("app.css").unwrap());
+        assert_eq!(
+            static_fn.call(&args).unwrap(),
+            "sha384-FBwJvSiJl3O3crvgZNi3GPodbyhSt+r9XtZonSa3SIO3ni6BTNadW1KrR2qihMQU"
+        );
+        assert!(used.load(Ordering::Relaxed));
+
+        args.insert("sha_type".to_string(), to_value(256).unwrap());
+        assert_eq!(
+            static_fn.call(&args).unwrap(),
+            "sha256-Vy5pHcaMP81lOuRjJhvbOPNdxvAXFdnOaHmTGd0ViEA="
+        );
+    }
 }
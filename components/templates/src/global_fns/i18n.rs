@@ -27,6 +27,33 @@ impl TeraFn for Trans {
     }
 }
 
+/// The value of the `dir` HTML attribute for a language, `rtl` or `ltr`
+#[derive(Debug)]
+pub struct TextDirection {
+    config: Config,
+}
+impl TextDirection {
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+}
+impl TeraFn for TextDirection {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let lang =
+            optional_arg!(String, args.get("lang"), "`text_direction`: `lang` must be a string.")
+                .unwrap_or_else(|| self.config.default_language.clone());
+
+        match self.config.languages.get(&lang) {
+            Some(options) => Ok(to_value(options.text_direction()).unwrap()),
+            None => Err(format!("`text_direction`: language `{}` is not configured", lang).into()),
+        }
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,4 +107,30 @@ title = "A title" "#;
         let error = Trans::new(config).call(&args).unwrap_err();
         assert_eq!("Failed to retrieve term translation", format!("{}", error));
     }
+
+    #[test]
+    fn can_get_text_direction() {
+        let config = Config::parse(
+            r#"
+base_url = "https://example.com"
+default_language = "ar"
+rtl = true
+
+[languages.en]
+"#,
+        )
+        .unwrap();
+        let static_fn = TextDirection::new(config);
+        let mut args = HashMap::new();
+        assert_eq!(static_fn.call(&args).unwrap(), "rtl");
+
+        args.insert("lang".to_string(), to_value("en").unwrap());
+        assert_eq!(static_fn.call(&args).unwrap(), "ltr");
+
+        args.insert("lang".to_string(), to_value("he").unwrap());
+        assert_eq!(
+            static_fn.call(&args).unwrap_err().to_string(),
+            "`text_direction`: language `he` is not configured"
+        );
+    }
 }
@@ -2,7 +2,8 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
-use libs::tera::{from_value, to_value, Function as TeraFn, Result, Value};
+use config::Images;
+use libs::tera::{from_value, to_value, Function as TeraFn, Map, Result, Value};
 
 use crate::global_fns::helpers::search_for_file;
 
@@ -80,6 +81,123 @@ impl TeraFn for ResizeImage {
     }
 }
 
+#[derive(Debug)]
+pub struct ResponsiveImage {
+    /// The base path of the Zola site
+    base_path: PathBuf,
+    theme: Option<String>,
+    imageproc: Arc<Mutex<imageproc::Processor>>,
+    output_path: PathBuf,
+    images: Images,
+}
+
+impl ResponsiveImage {
+    pub fn new(
+        base_path: PathBuf,
+        imageproc: Arc<Mutex<imageproc::Processor>>,
+        theme: Option<String>,
+        output_path: PathBuf,
+        images: Images,
+    ) -> Self {
+        Self { base_path, imageproc, theme, output_path, images }
+    }
+}
+
+impl TeraFn for ResponsiveImage {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path = required_arg!(
+            String,
+            args.get("path"),
+            "`responsive_image` requires a `path` argument with a string value"
+        );
+        let widths = optional_arg!(
+            Vec<u32>,
+            args.get("widths"),
+            "`responsive_image`: `widths` must be an array of non-negative integers"
+        )
+        .unwrap_or_else(|| self.images.breakpoints.clone());
+        if widths.is_empty() {
+            return Err("`responsive_image`: no `widths` given and no `breakpoints` in the `[images]` config"
+                .to_string()
+                .into());
+        }
+        let format = optional_arg!(
+            String,
+            args.get("format"),
+            "`responsive_image`: `format` must be a string"
+        )
+        .unwrap_or_else(|| DEFAULT_FMT.to_string());
+        let quality = optional_arg!(
+            u8,
+            args.get("quality"),
+            "`responsive_image`: `quality` must be a number"
+        );
+        if let Some(quality) = quality {
+            if quality == 0 || quality > 100 {
+                return Err("`responsive_image`: `quality` must be in range 1-100"
+                    .to_string()
+                    .into());
+            }
+        }
+        let webp =
+            optional_arg!(bool, args.get("webp"), "`responsive_image`: `webp` must be a boolean")
+                .unwrap_or(self.images.webp)
+                && format != "webp";
+
+        let (file_path, unified_path) =
+            match search_for_file(&self.base_path, &path, &self.theme, &self.output_path)
+                .map_err(|e| format!("`responsive_image`: {}", e))?
+            {
+                Some(f) => f,
+                None => {
+                    return Err(format!("`responsive_image`: Cannot find file: {}", path).into());
+                }
+            };
+        let orig_width = imageproc::read_image_metadata(&file_path)
+            .map_err(|e| format!("`responsive_image`: {}", e))?
+            .width;
+
+        // No point in upscaling: the widths bigger than the image are replaced by its own width
+        let mut widths: Vec<_> = widths.into_iter().map(|w| w.min(orig_width)).collect();
+        widths.sort_unstable();
+        widths.dedup();
+
+        let mut imageproc = self.imageproc.lock().unwrap();
+        let mut make_srcset = |format: &str| -> Result<(String, imageproc::EnqueueResponse)> {
+            let mut srcset = Vec::with_capacity(widths.len());
+            let mut largest = None;
+            for width in &widths {
+                let response = imageproc
+                    .enqueue(
+                        unified_path.clone(),
+                        file_path.clone(),
+                        "fit_width",
+                        Some(*width),
+                        None,
+                        format,
+                        quality,
+                    )
+                    .map_err(|e| format!("`responsive_image`: {}", e))?;
+                srcset.push(format!("{} {}w", response.url, response.width));
+                largest = Some(response);
+            }
+            Ok((srcset.join(", "), largest.expect("at least one width")))
+        };
+        let (srcset, largest) = make_srcset(&format)?;
+        // The WebP versions are offered next to the original format, e.g. in a `<picture>`
+        let webp_srcset = if webp { Some(make_srcset("webp")?.0) } else { None };
+
+        let mut res = Map::new();
+        res.insert("src".to_string(), to_value(&largest.url).unwrap());
+        res.insert("srcset".to_string(), to_value(srcset).unwrap());
+        res.insert("webp_srcset".to_string(), to_value(webp_srcset).unwrap());
+        res.insert("sizes".to_string(), to_value(&self.images.sizes).unwrap());
+        res.insert("width".to_string(), to_value(largest.width).unwrap());
+        res.insert("height".to_string(), to_value(largest.height).unwrap());
+        Ok(Value::Object(res))
+    }
+}
+
 #[derive(Debug)]
 pub struct GetImageMetadata {
     /// The base path of the Zola site
@@ -138,13 +256,13 @@ impl TeraFn for GetImageMetadata {
 
 #[cfg(test)]
 mod tests {
-    use super::{GetImageMetadata, ResizeImage};
+    use super::{GetImageMetadata, ResizeImage, ResponsiveImage};
 
     use std::collections::HashMap;
     use std::fs::{copy, create_dir_all};
 
     use config::Config;
-    use libs::tera::{to_value, Function};
+    use libs::tera::{to_value, Function, Value};
     use std::path::{Path, PathBuf};
     use std::sync::{Arc, Mutex};
     use tempfile::{tempdir, TempDir};
@@ -194,7 +312,7 @@ mod tests {
 
         assert_eq!(
             data["static_path"],
-            to_value(&format!("{}", static_path.join("6a89d6483cdc5f7700.jpg").display())).unwrap()
+            to_value(format!("{}", static_path.join("6a89d6483cdc5f7700.jpg").display())).unwrap()
         );
         assert_eq!(
             data["url"],
@@ -206,7 +324,7 @@ mod tests {
         let data = static_fn.call(&args).unwrap().as_object().unwrap().clone();
         assert_eq!(
             data["static_path"],
-            to_value(&format!("{}", static_path.join("202d9263f4dbc95900.jpg").display())).unwrap()
+            to_value(format!("{}", static_path.join("202d9263f4dbc95900.jpg").display())).unwrap()
         );
         assert_eq!(
             data["url"],
@@ -228,7 +346,7 @@ mod tests {
         let data = static_fn.call(&args).unwrap().as_object().unwrap().clone();
         assert_eq!(
             data["static_path"],
-            to_value(&format!("{}", static_path.join("6296a3c153f701be00.jpg").display())).unwrap()
+            to_value(format!("{}", static_path.join("6296a3c153f701be00.jpg").display())).unwrap()
         );
         assert_eq!(
             data["url"],
@@ -239,7 +357,7 @@ mod tests {
         args.insert("path".to_string(), to_value("in-theme.jpg").unwrap());
         assert_eq!(
             data["static_path"],
-            to_value(&format!("{}", static_path.join("6296a3c153f701be00.jpg").display())).unwrap()
+            to_value(format!("{}", static_path.join("6296a3c153f701be00.jpg").display())).unwrap()
         );
         assert_eq!(
             data["url"],
@@ -247,6 +365,76 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_make_responsive_image() {
+        let dir = create_dir_with_image();
+        let imageproc = imageproc::Processor::new(dir.path().to_path_buf(), &Config::default());
+        let images = config::Images {
+            breakpoints: vec![100, 20, 100_000],
+            sizes: Some("50vw".to_string()),
+            markdown_srcset: false,
+            webp: false,
+            strip_exif: false,
+        };
+        let static_fn = ResponsiveImage::new(
+            dir.path().to_path_buf(),
+            Arc::new(Mutex::new(imageproc)),
+            None,
+            PathBuf::new(),
+            images,
+        );
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("gallery/asset.jpg").unwrap());
+        let data = static_fn.call(&args).unwrap().as_object().unwrap().clone();
+
+        // the breakpoint wider than the image is replaced by the image width
+        let orig_width = imageproc::read_image_metadata(
+            dir.path().join("content").join("gallery").join("asset.jpg"),
+        )
+        .unwrap()
+        .width;
+        assert_eq!(data["width"], to_value(orig_width).unwrap());
+        assert_eq!(data["sizes"], to_value("50vw").unwrap());
+        let srcset = data["srcset"].as_str().unwrap();
+        let candidates: Vec<_> = srcset.split(", ").collect();
+        assert_eq!(candidates.len(), 3);
+        assert!(candidates[0].starts_with("http://a-website.com/processed_images/"));
+        assert!(candidates[0].ends_with(".jpg 20w"));
+        assert!(candidates[1].ends_with(".jpg 100w"));
+        assert!(candidates[2].ends_with(&format!(".jpg {}w", orig_width)));
+        assert!(candidates[2].starts_with(data["src"].as_str().unwrap()));
+
+        // widths given as argument take precedence over the breakpoints
+        args.insert("widths".to_string(), to_value(vec![50]).unwrap());
+        let data = static_fn.call(&args).unwrap().as_object().unwrap().clone();
+        assert!(data["srcset"].as_str().unwrap().ends_with(" 50w"));
+        assert_eq!(data["width"], to_value(50).unwrap());
+        assert_eq!(data["webp_srcset"], Value::Null);
+
+        // and WebP versions can be generated alongside the original format
+        args.insert("webp".to_string(), to_value(true).unwrap());
+        let data = static_fn.call(&args).unwrap().as_object().unwrap().clone();
+        assert!(data["srcset"].as_str().unwrap().ends_with(".jpg 50w"));
+        assert!(data["webp_srcset"].as_str().unwrap().ends_with(".webp 50w"));
+        assert!(data["src"].as_str().unwrap().ends_with(".jpg"));
+    }
+
+    #[test]
+    fn responsive_image_requires_widths() {
+        let dir = create_dir_with_image();
+        let imageproc = imageproc::Processor::new(dir.path().to_path_buf(), &Config::default());
+        let static_fn = ResponsiveImage::new(
+            dir.path().to_path_buf(),
+            Arc::new(Mutex::new(imageproc)),
+            None,
+            PathBuf::new(),
+            config::Images::default(),
+        );
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("gallery/asset.jpg").unwrap());
+        assert!(static_fn.call(&args).is_err());
+    }
+
     // TODO: consider https://github.com/getzola/zola/issues/1161
     #[test]
     fn can_get_image_metadata() {
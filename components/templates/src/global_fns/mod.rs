@@ -2,14 +2,19 @@
 mod macros;
 
 mod content;
+mod dates;
 mod files;
 mod helpers;
 mod i18n;
 mod images;
 mod load_data;
 
-pub use self::content::{GetPage, GetSection, GetTaxonomy, GetTaxonomyUrl};
-pub use self::files::{GetFileHash, GetUrl};
-pub use self::i18n::Trans;
-pub use self::images::{GetImageMetadata, ResizeImage};
+pub use self::content::{
+    FeedTags, GetMenuState, GetPage, GetRandomPages, GetSection, GetSectionTree, GetTaxonomy,
+    GetTaxonomyUrl,
+};
+pub use self::dates::Now;
+pub use self::files::{FaviconTags, FileSearch, GetFileHash, GetIntegrity, GetUrl};
+pub use self::i18n::{TextDirection, Trans};
+pub use self::images::{GetImageMetadata, ResizeImage, ResponsiveImage};
 pub use self::load_data::LoadData;
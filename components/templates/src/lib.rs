@@ -10,14 +10,27 @@ use libs::tera::{Context, Tera};
 use errors::{bail, Context as ErrorContext, Result};
 use utils::templates::rewrite_theme_paths;
 
+/// The `.txt` and `.gmi` templates, which are not picked up by the templates glob
+const TXT_TEMPLATES: [&str; 6] =
+    ["robots.txt", "humans.txt", "security.txt", "page.txt", "page.gmi", "section.gmi"];
+
 pub static ZOLA_TERA: Lazy<Tera> = Lazy::new(|| {
     let mut tera = Tera::default();
     tera.add_raw_templates(vec![
         ("__zola_builtins/404.html", include_str!("builtins/404.html")),
         ("__zola_builtins/atom.xml", include_str!("builtins/atom.xml")),
         ("__zola_builtins/rss.xml", include_str!("builtins/rss.xml")),
+        ("__zola_builtins/podcast.xml", include_str!("builtins/podcast.xml")),
+        ("__zola_builtins/feeds.opml", include_str!("builtins/feeds.opml")),
         ("__zola_builtins/sitemap.xml", include_str!("builtins/sitemap.xml")),
         ("__zola_builtins/robots.txt", include_str!("builtins/robots.txt")),
+        ("__zola_builtins/humans.txt", include_str!("builtins/humans.txt")),
+        ("__zola_builtins/security.txt", include_str!("builtins/security.txt")),
+        ("__zola_builtins/page.txt", include_str!("builtins/page.txt")),
+        ("__zola_builtins/page.gmi", include_str!("builtins/page.gmi")),
+        ("__zola_builtins/section.gmi", include_str!("builtins/section.gmi")),
+        ("__zola_builtins/newsletter.html", include_str!("builtins/newsletter.html")),
+        ("__zola_builtins/orphans.html", include_str!("builtins/orphans.html")),
         (
             "__zola_builtins/split_sitemap_index.xml",
             include_str!("builtins/split_sitemap_index.xml"),
@@ -33,11 +46,21 @@ pub static ZOLA_TERA: Lazy<Tera> = Lazy::new(|| {
             "__zola_builtins/shortcodes/streamable.html",
             include_str!("builtins/shortcodes/streamable.html"),
         ),
+        ("__zola_builtins/shortcodes/image.html", include_str!("builtins/shortcodes/image.html")),
+        (
+            "__zola_builtins/shortcodes/picture.html",
+            include_str!("builtins/shortcodes/picture.html"),
+        ),
         ("internal/alias.html", include_str!("builtins/internal/alias.html")),
+        ("internal/protected.html", include_str!("builtins/internal/protected.html")),
     ])
     .unwrap();
     tera.register_filter("base64_encode", filters::base64_encode);
     tera.register_filter("base64_decode", filters::base64_decode);
+    tera.register_filter("plain_text", filters::plain_text);
+    tera.register_filter("hash", filters::hash);
+    tera.register_filter("truncate_html", filters::truncate_html);
+    tera.register_filter("regex_replace", filters::regex_replace);
     tera
 });
 
@@ -60,9 +83,30 @@ pub fn load_tera(path: &Path, config: &Config) -> Result<Tera> {
     let mut tera =
         Tera::parse(&tpl_glob).context("Error parsing templates from the /templates directory")?;
 
+    // Extending only adds the templates that are not loaded yet, so the ones of the site come
+    // first, then the ones of `templates.load` in order and then the ones of the theme
+    for dir in &config.templates.load {
+        let dir_path = path.join(dir);
+        if !dir_path.is_dir() {
+            bail!("The directory `{}` of `templates.load` doesn't exist", dir);
+        }
+        let extra_tpl_glob = format!(
+            "{}/**/*.{{*ml,md}}",
+            dir_path.to_string_lossy().replace('\\', "/").trim_end_matches('/')
+        );
+        let mut tera_extra = Tera::parse(&extra_tpl_glob)
+            .with_context(|| format!("Error parsing templates from `{}`", dir))?;
+        for name in TXT_TEMPLATES {
+            if dir_path.join(name).exists() {
+                tera_extra.add_template_file(dir_path.join(name), Some(name))?;
+            }
+        }
+        tera.extend(&tera_extra)?;
+    }
+
     if let Some(ref theme) = config.theme {
         // Test that the templates folder exist for that theme
-        let theme_path = path.join("themes").join(&theme);
+        let theme_path = path.join("themes").join(theme);
         if !theme_path.join("templates").exists() {
             bail!("Theme `{}` is missing a templates folder", theme);
         }
@@ -77,19 +121,21 @@ pub fn load_tera(path: &Path, config: &Config) -> Result<Tera> {
         rewrite_theme_paths(&mut tera_theme, theme);
 
         // TODO: add tests for theme-provided robots.txt (https://github.com/getzola/zola/pull/1722)
-        if theme_path.join("templates").join("robots.txt").exists() {
-            tera_theme.add_template_file(
-                theme_path.join("templates").join("robots.txt"),
-                Some("robots.txt"),
-            )?;
+        for name in TXT_TEMPLATES {
+            if theme_path.join("templates").join(name).exists() {
+                tera_theme
+                    .add_template_file(theme_path.join("templates").join(name), Some(name))?;
+            }
         }
         tera.extend(&tera_theme)?;
     }
     tera.extend(&ZOLA_TERA)?;
     tera.build_inheritance_chains()?;
 
-    if path.join("templates").join("robots.txt").exists() {
-        tera.add_template_file(path.join("templates").join("robots.txt"), Some("robots.txt"))?;
+    for name in TXT_TEMPLATES {
+        if path.join("templates").join(name).exists() {
+            tera.add_template_file(path.join("templates").join(name), Some(name))?;
+        }
     }
 
     Ok(tera)
@@ -1,11 +1,15 @@
 use libs::filetime::{set_file_mtime, FileTime};
-use libs::walkdir::WalkDir;
-use std::fs::{copy, create_dir_all, metadata, File};
+use libs::globset::GlobSet;
+use libs::log::trace;
+use libs::rayon::prelude::*;
+use libs::walkdir::{self, DirEntry, WalkDir};
+use std::fs::{copy, create_dir_all, metadata, remove_file, File};
 use std::io::prelude::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::sync_channel;
 use std::time::SystemTime;
 
-use errors::{Context, Result};
+use errors::{bail, Context, Result};
 
 pub fn is_path_in_directory(parent: &Path, path: &Path) -> Result<bool> {
     let canonical_path = path
@@ -21,7 +25,7 @@ pub fn is_path_in_directory(parent: &Path, path: &Path) -> Result<bool> {
 /// Create a file with the content given
 pub fn create_file(path: &Path, content: &str) -> Result<()> {
     let mut file =
-        File::create(&path).with_context(|| format!("Failed to create file {}", path.display()))?;
+        File::create(path).with_context(|| format!("Failed to create file {}", path.display()))?;
     file.write_all(content.as_bytes())?;
     Ok(())
 }
@@ -78,61 +82,198 @@ pub fn copy_file(src: &Path, dest: &Path, base_path: &Path, hard_link: bool) ->
 /// 1. A file with the same name already exists in the dest path.
 /// 2. Its modification timestamp is identical to that of the src file.
 /// 3. Its filesize is identical to that of the src file.
+///
+/// If `hard_link` is set, the file is hard-linked instead, falling back to a copy if that's not
+/// possible, eg when the destination is on another filesystem.
+/// Errors have both paths and the error of the OS.
 pub fn copy_file_if_needed(src: &Path, dest: &Path, hard_link: bool) -> Result<()> {
+    copy_or_link_file(src, dest, hard_link).with_context(|| {
+        format!(
+            "Failed to {} {} to {}",
+            if hard_link { "hard-link" } else { "copy" },
+            src.display(),
+            dest.display()
+        )
+    })
+}
+
+fn copy_or_link_file(src: &Path, dest: &Path, hard_link: bool) -> Result<()> {
     if let Some(parent_directory) = dest.parent() {
         create_dir_all(parent_directory).with_context(|| {
             format!("Failed to create directory {}", parent_directory.display())
         })?;
     }
 
-    if hard_link {
-        std::fs::hard_link(src, dest)?
-    } else {
-        let src_metadata = metadata(src)
-            .with_context(|| format!("Failed to get metadata of {}", src.display()))?;
-        let src_mtime = FileTime::from_last_modification_time(&src_metadata);
-        if Path::new(&dest).is_file() {
-            let target_metadata = metadata(&dest)?;
-            let target_mtime = FileTime::from_last_modification_time(&target_metadata);
-            if !(src_mtime == target_mtime && src_metadata.len() == target_metadata.len()) {
-                copy(src, &dest).with_context(|| {
-                    format!("Was not able to copy file {} to {}", src.display(), dest.display())
-                })?;
-                set_file_mtime(&dest, src_mtime)?;
-            }
-        } else {
-            copy(src, &dest).with_context(|| {
-                format!("Was not able to copy directory {} to {}", src.display(), dest.display())
-            })?;
-            set_file_mtime(&dest, src_mtime)?;
+    let src_metadata =
+        metadata(src).with_context(|| format!("Failed to get metadata of {}", src.display()))?;
+    let src_mtime = FileTime::from_last_modification_time(&src_metadata);
+    if Path::new(&dest).is_file() {
+        let target_metadata = metadata(dest)
+            .with_context(|| format!("Failed to get metadata of {}", dest.display()))?;
+        let target_mtime = FileTime::from_last_modification_time(&target_metadata);
+        // A hard link has the same timestamp and size as well
+        if src_mtime == target_mtime && src_metadata.len() == target_metadata.len() {
+            trace!(target: "copy", "Skipping {}: unchanged", src.display());
+            return Ok(());
+        }
+        if hard_link {
+            // Linking can't replace a file
+            remove_file(dest)
+                .with_context(|| format!("Failed to remove the previous {}", dest.display()))?;
         }
     }
+
+    if hard_link && std::fs::hard_link(src, dest).is_ok() {
+        trace!(target: "copy", "Hard-linked {} to {}", src.display(), dest.display());
+        return Ok(());
+    }
+    trace!(target: "copy", "Copying {} to {}", src.display(), dest.display());
+    copy(src, dest)?;
+    set_file_mtime(dest, src_mtime)
+        .with_context(|| format!("Failed to set the modification time of {}", dest.display()))?;
     Ok(())
 }
 
-pub fn copy_directory(src: &Path, dest: &Path, hard_link: bool) -> Result<()> {
-    for entry in
-        WalkDir::new(src).follow_links(true).into_iter().filter_map(std::result::Result::ok)
-    {
-        let relative_path = entry.path().strip_prefix(src).unwrap();
-        let target_path = dest.join(relative_path);
+/// Errors on a symlink pointing to one of its ancestors, which can only be found when following
+/// symlinks. The other errors of the walk, like unreadable files, are skipped.
+pub fn check_symlink_loop(err: &walkdir::Error) -> Result<()> {
+    if let Some(ancestor) = err.loop_ancestor() {
+        bail!(
+            "Symlink loop: `{}` points to its ancestor `{}`",
+            err.path().map(|p| p.display().to_string()).unwrap_or_default(),
+            ancestor.display()
+        );
+    }
+    Ok(())
+}
 
-        if entry.path().is_dir() {
-            if !target_path.exists() {
-                create_directory(&target_path)?;
-            }
-        } else {
-            copy_file(entry.path(), dest, src, hard_link).with_context(|| {
-                format!(
-                    "Was not able to copy {} to {} (hard_link={})",
-                    entry.path().display(),
-                    dest.display(),
-                    hard_link
-                )
-            })?;
+/// How long the path of an output file can be, from the output directory, before it is at risk
+/// of going over the 260 characters limit of Windows once the site is checked out somewhere
+pub const MAX_PORTABLE_PATH_LENGTH: usize = 200;
+
+/// Names that Windows reserves for devices, with or without an extension
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Finds why the output path `path`, relative to the output directory, can't be created on
+/// Windows: a component being a reserved name like `con` or `aux`, ending with a dot or a space,
+/// containing a character Windows doesn't allow or the path being too long.
+pub fn find_unportable_path_issue(path: &str) -> Option<String> {
+    for component in path.split('/').filter(|c| !c.is_empty()) {
+        let stem = component.split('.').next().unwrap_or(component).trim_end();
+        if WINDOWS_RESERVED_NAMES.contains(&stem.to_lowercase().as_str()) {
+            return Some(format!("`{}` is a reserved name on Windows", component));
+        }
+        if component.ends_with('.') || component.ends_with(' ') {
+            return Some(format!("`{}` ends with a dot or a space", component));
+        }
+        if let Some(c) = component.chars().find(|c| c.is_control() || r#"<>:"|?*\"#.contains(*c)) {
+            return Some(format!("`{}` contains the character {:?}", component, c));
         }
     }
-    Ok(())
+    let length = path.trim_matches('/').chars().count();
+    if length > MAX_PORTABLE_PATH_LENGTH {
+        return Some(format!(
+            "it is {} characters long, more than the {} characters that are safe on Windows",
+            length, MAX_PORTABLE_PATH_LENGTH
+        ));
+    }
+    None
+}
+
+/// Whether the walk should skip that entry: symlinks are skipped when they are not followed
+pub fn is_unfollowed_symlink(entry: &DirEntry, follow_symlinks: bool) -> bool {
+    !follow_symlinks && entry.depth() > 0 && entry.path_is_symlink()
+}
+
+/// Whether that file was created by the OS or an editor and should never be published,
+/// like `.DS_Store`, `Thumbs.db` or the swap files of Vim
+pub fn is_junk_file(path: &Path) -> bool {
+    let name = match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+    matches!(name, ".DS_Store" | "Thumbs.db" | "ehthumbs.db" | "desktop.ini")
+        || name.starts_with("._")
+        || name.starts_with(".#")
+        || (name.starts_with('#') && name.ends_with('#'))
+        || name.ends_with('~')
+        || (name.starts_with('.') && [".swp", ".swo", ".swx"].iter().any(|e| name.ends_with(e)))
+}
+
+/// Whether that file should not be copied: a junk file or one matching the `ignored` globs,
+/// `relative_path` being the path from the directory being copied
+pub fn is_ignored_file(relative_path: &Path, ignored: Option<&GlobSet>) -> bool {
+    is_junk_file(relative_path) || ignored.map_or(false, |g| g.is_match(relative_path))
+}
+
+/// How many files the directory walker of `copy_directory` can find ahead of the copies
+const COPY_QUEUE_SIZE: usize = 256;
+
+/// Copies the content of `src` in `dest`, following the symlinks if `follow_symlinks` is set
+/// and leaving them out otherwise. The junk files and the files or directories matching
+/// `ignored` are not copied.
+/// The files of at least `hard_link_min_size` bytes are hard-linked instead, if it is set.
+/// The directory is walked on its own thread while the files it finds are copied in parallel.
+/// Returns how many files were copied, counting the ones that were already up to date.
+pub fn copy_directory(
+    src: &Path,
+    dest: &Path,
+    hard_link_min_size: Option<u64>,
+    follow_symlinks: bool,
+    ignored: Option<&GlobSet>,
+) -> Result<usize> {
+    let (sender, receiver) = sync_channel::<(PathBuf, bool)>(COPY_QUEUE_SIZE);
+
+    let walker = {
+        let (src, dest, ignored) = (src.to_path_buf(), dest.to_path_buf(), ignored.cloned());
+        std::thread::spawn(move || -> Result<()> {
+            let walker =
+                WalkDir::new(&src).follow_links(follow_symlinks).into_iter().filter_entry(|e| {
+                    e.depth() == 0
+                        || !is_ignored_file(e.path().strip_prefix(&src).unwrap(), ignored.as_ref())
+                });
+            for entry in walker {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(err) => {
+                        check_symlink_loop(&err)?;
+                        continue;
+                    }
+                };
+                if is_unfollowed_symlink(&entry, follow_symlinks) {
+                    continue;
+                }
+
+                if entry.path().is_dir() {
+                    let target_path = dest.join(entry.path().strip_prefix(&src).unwrap());
+                    if !target_path.exists() {
+                        create_directory(&target_path)?;
+                    }
+                } else {
+                    let hard_link = match (hard_link_min_size, entry.metadata()) {
+                        (Some(min_size), Ok(metadata)) => metadata.len() >= min_size,
+                        _ => false,
+                    };
+                    // The copies stopped because one of them failed
+                    if sender.send((entry.into_path(), hard_link)).is_err() {
+                        break;
+                    }
+                }
+            }
+            Ok(())
+        })
+    };
+
+    let copied = receiver
+        .into_iter()
+        .par_bridge()
+        .map(|(path, hard_link)| copy_file(&path, dest, src, hard_link))
+        .collect::<Result<Vec<_>>>();
+    walker.join().expect("The directory walker panicked")?;
+    Ok(copied?.len())
 }
 
 pub fn get_file_time(path: &Path) -> Option<SystemTime> {
@@ -176,7 +317,7 @@ mod tests {
     use libs::filetime;
     use tempfile::tempdir_in;
 
-    use super::copy_file;
+    use super::{copy_file, find_unportable_path_issue};
 
     #[test]
     fn test_copy_file_timestamp_preserved() {
@@ -241,4 +382,191 @@ mod tests {
         assert_eq!(read_to_string(&src_file_path).unwrap(), "file1");
         assert_eq!(read_to_string(&dest_file_path).unwrap(), "file1");
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn can_follow_symlinks_when_copying_directories() {
+        use std::fs::create_dir_all;
+        use std::os::unix::fs::symlink;
+
+        use tempfile::tempdir;
+
+        use super::copy_directory;
+
+        let tmp_dir = tempdir().unwrap();
+        let shared = tmp_dir.path().join("shared");
+        create_dir_all(&shared).unwrap();
+        File::create(shared.join("logo.png")).unwrap().write_all(b"png").unwrap();
+        let src = tmp_dir.path().join("static");
+        create_dir_all(&src).unwrap();
+        File::create(src.join("style.css")).unwrap();
+        symlink(&shared, src.join("images")).unwrap();
+
+        let dest = tmp_dir.path().join("public");
+        copy_directory(&src, &dest, None, true, None).unwrap();
+        assert_eq!(read_to_string(dest.join("images").join("logo.png")).unwrap(), "png");
+
+        let dest = tmp_dir.path().join("public-without-symlinks");
+        copy_directory(&src, &dest, None, false, None).unwrap();
+        assert!(dest.join("style.css").exists());
+        assert!(!dest.join("images").exists());
+
+        // A symlink to its own parent would be followed forever
+        symlink(&src, src.join("loop")).unwrap();
+        let err = copy_directory(&src, &tmp_dir.path().join("loop"), None, true, None).unwrap_err();
+        assert!(err.to_string().starts_with("Symlink loop"));
+    }
+
+    #[test]
+    fn can_find_junk_files() {
+        use std::path::Path;
+
+        use super::is_junk_file;
+
+        for name in
+            [".DS_Store", "Thumbs.db", "._logo.png", ".style.css.swp", "notes.md~", "#notes.md#"]
+        {
+            assert!(is_junk_file(&Path::new("images").join(name)), "{}", name);
+        }
+        for name in [".htaccess", ".nojekyll", "style.css", "swp", "logo#1.png"] {
+            assert!(!is_junk_file(&Path::new("images").join(name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn can_leave_out_ignored_files_when_copying_directories() {
+        use std::fs::create_dir_all;
+
+        use libs::globset::{Glob, GlobSetBuilder};
+        use tempfile::tempdir;
+
+        use super::copy_directory;
+
+        let tmp_dir = tempdir().unwrap();
+        let src = tmp_dir.path().join("static");
+        create_dir_all(src.join("js")).unwrap();
+        create_dir_all(src.join("drafts")).unwrap();
+        for file in ["js/app.js", "js/app.js.map", "js/.DS_Store", "drafts/logo.png", ".htaccess"] {
+            File::create(src.join(file)).unwrap();
+        }
+        let mut builder = GlobSetBuilder::new();
+        builder.add(Glob::new("**/*.map").unwrap());
+        builder.add(Glob::new("drafts").unwrap());
+        let ignored = builder.build().unwrap();
+
+        let dest = tmp_dir.path().join("public");
+        assert_eq!(copy_directory(&src, &dest, None, true, Some(&ignored)).unwrap(), 2);
+        assert!(dest.join("js").join("app.js").exists());
+        assert!(dest.join(".htaccess").exists());
+        assert!(!dest.join("js").join("app.js.map").exists());
+        assert!(!dest.join("js").join(".DS_Store").exists());
+        assert!(!dest.join("drafts").exists());
+    }
+
+    #[test]
+    fn can_copy_directories_with_more_files_than_the_copy_queue() {
+        use std::fs::create_dir_all;
+
+        use tempfile::tempdir;
+
+        use super::{copy_directory, COPY_QUEUE_SIZE};
+
+        let tmp_dir = tempdir().unwrap();
+        let src = tmp_dir.path().join("static");
+        let count = COPY_QUEUE_SIZE * 3;
+        for i in 0..count {
+            let dir = src.join(format!("images/{}", i % 10));
+            create_dir_all(&dir).unwrap();
+            File::create(dir.join(format!("{}.png", i))).unwrap().write_all(&[i as u8]).unwrap();
+        }
+
+        let dest = tmp_dir.path().join("public");
+        assert_eq!(copy_directory(&src, &dest, None, true, None).unwrap(), count);
+        for i in 0..count {
+            let path = dest.join(format!("images/{}/{}.png", i % 10, i));
+            assert_eq!(std::fs::read(path).unwrap(), vec![i as u8]);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn can_hard_link_large_files_when_copying_directories() {
+        use std::fs::create_dir_all;
+        use std::os::unix::fs::MetadataExt;
+
+        use tempfile::tempdir;
+
+        use super::copy_directory;
+
+        let tmp_dir = tempdir().unwrap();
+        let src = tmp_dir.path().join("static");
+        create_dir_all(&src).unwrap();
+        File::create(src.join("video.mp4")).unwrap().write_all(&[0; 100]).unwrap();
+        File::create(src.join("style.css")).unwrap().write_all(b"body {}").unwrap();
+        let dest = tmp_dir.path().join("public");
+        let inode = |path: PathBuf| metadata(path).unwrap().ino();
+
+        copy_directory(&src, &dest, Some(50), true, None).unwrap();
+        assert_eq!(inode(src.join("video.mp4")), inode(dest.join("video.mp4")));
+        assert_ne!(inode(src.join("style.css")), inode(dest.join("style.css")));
+
+        // Linking again the unchanged files is fine
+        copy_directory(&src, &dest, Some(50), true, None).unwrap();
+        assert_eq!(inode(src.join("video.mp4")), inode(dest.join("video.mp4")));
+
+        // A file that was copied before is replaced by a link
+        filetime::set_file_mtime(src.join("style.css"), filetime::FileTime::from_unix_time(0, 0))
+            .unwrap();
+        copy_directory(&src, &dest, Some(0), true, None).unwrap();
+        assert_eq!(inode(src.join("style.css")), inode(dest.join("style.css")));
+        assert_eq!(read_to_string(dest.join("style.css")).unwrap(), "body {}");
+    }
+
+    #[test]
+    fn copy_errors_have_both_paths_and_the_os_error() {
+        use tempfile::tempdir;
+
+        use super::copy_file_if_needed;
+
+        let tmp_dir = tempdir().unwrap();
+        let src = tmp_dir.path().join("missing.png");
+        let dest = tmp_dir.path().join("public").join("missing.png");
+        let err = copy_file_if_needed(&src, &dest, false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("Failed to copy {} to {}", src.display(), dest.display())
+        );
+        let io_error = err.root_cause().downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io_error.kind(), std::io::ErrorKind::NotFound);
+
+        // The destination directory can't be created where there is a file
+        let src = tmp_dir.path().join("logo.png");
+        File::create(&src).unwrap();
+        let dest = src.join("logo.png");
+        let err = copy_file_if_needed(&src, &dest, true).unwrap_err();
+        let messages: Vec<_> = err.chain().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages[0],
+            format!("Failed to hard-link {} to {}", src.display(), dest.display())
+        );
+        assert_eq!(messages[1], format!("Failed to create directory {}", src.display()));
+        assert!(err.root_cause().downcast_ref::<std::io::Error>().is_some());
+    }
+
+    #[test]
+    fn can_find_unportable_paths() {
+        assert_eq!(find_unportable_path_issue("/posts/hello-world/"), None);
+        assert_eq!(find_unportable_path_issue("/posts/console/"), None);
+        assert_eq!(find_unportable_path_issue("/v1.2/about.html"), None);
+        for path in ["/tags/con/", "/AUX/", "/posts/nul.txt", "/com1/", "/Lpt9.tar.gz"] {
+            let issue = find_unportable_path_issue(path).unwrap();
+            assert!(issue.contains("reserved name on Windows"), "{}", issue);
+        }
+        assert!(find_unportable_path_issue("/posts/hello./").unwrap().contains("ends with a dot"));
+        assert!(find_unportable_path_issue("/tags/c++ /").unwrap().contains("or a space"));
+        assert!(find_unportable_path_issue("/tags/what?/").unwrap().contains("'?'"));
+        assert!(find_unportable_path_issue("/a:b/").unwrap().contains("':'"));
+        let long = format!("/posts/{}/", "a".repeat(200));
+        assert!(find_unportable_path_issue(&long).unwrap().contains("characters long"));
+    }
 }
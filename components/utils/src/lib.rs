@@ -4,6 +4,7 @@ pub mod fs;
 pub mod net;
 pub mod site;
 pub mod slugs;
+pub mod suggestions;
 pub mod table_of_contents;
 pub mod templates;
 pub mod types;
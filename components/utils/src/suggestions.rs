@@ -0,0 +1,71 @@
+/// How many suggestions are given at most
+const MAX_SUGGESTIONS: usize = 5;
+
+/// The number of single character edits to go from one string to the other
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The file name of a path without its extension, eg `hello` for `blog/hello.md`
+fn stem(path: &str) -> &str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.split('.').next().unwrap_or(name)
+}
+
+/// Finds the candidates close to `name`, closest first, to suggest them when `name` doesn't exist.
+/// Candidates are compared as a whole and by their file name, so `hello` finds `blog/hello.md`.
+pub fn closest_matches<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<&'a str> {
+    let max_distance = (name.chars().count() / 4).max(2);
+    let mut matches: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let distance =
+                edit_distance(name, candidate).min(edit_distance(stem(name), stem(candidate)));
+            if distance <= max_distance {
+                Some((distance, candidate))
+            } else {
+                None
+            }
+        })
+        .collect();
+    matches.sort();
+    matches.dedup();
+    matches.into_iter().take(MAX_SUGGESTIONS).map(|(_, c)| c).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_compute_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("page.html", "page.html"), 0);
+        assert_eq!(edit_distance("héllo", "hello"), 1);
+    }
+
+    #[test]
+    fn can_find_closest_matches() {
+        let candidates = ["blog/hello.md", "blog/help.md", "about.md", "blog/_index.md"];
+        assert_eq!(
+            closest_matches("blog/helo.md", candidates),
+            vec!["blog/hello.md", "blog/help.md"]
+        );
+        assert_eq!(closest_matches("hello", candidates), vec!["blog/hello.md", "blog/help.md"]);
+        assert_eq!(closest_matches("contact.md", candidates), Vec::<&str>::new());
+    }
+}
@@ -1,7 +1,10 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
 
-use libs::tera::{Context, Tera};
+use libs::tera::{Context, Map, Tera, Value};
 
+use crate::suggestions::closest_matches;
 use errors::{bail, Result};
 
 static DEFAULT_TPL: &str = include_str!("default_tpl.html");
@@ -21,16 +24,57 @@ pub enum ShortcodeFileType {
     Html,
 }
 
+/// The signature of a shortcode written in Rust: it is given the arguments of the call and the
+/// context a template shortcode would get, with the page, the config, `body` and `nth`, and
+/// returns the HTML or Markdown to insert
+pub type NativeShortcodeFn = dyn Fn(&Map<String, Value>, &Context) -> Result<String> + Send + Sync;
+
+/// A shortcode registered from Rust by a library user instead of being a template
+#[derive(Clone)]
+pub struct NativeShortcode(pub Arc<NativeShortcodeFn>);
+
+impl fmt::Debug for NativeShortcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("NativeShortcode")
+    }
+}
+
+impl PartialEq for NativeShortcode {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ShortcodeDefinition {
     pub file_type: ShortcodeFileType,
     pub tera_name: String,
+    /// Set for the shortcodes registered from Rust, which don't have a template
+    pub native: Option<NativeShortcode>,
 }
 impl ShortcodeDefinition {
     pub fn new(file_type: ShortcodeFileType, tera_name: &str) -> ShortcodeDefinition {
         let tera_name = tera_name.to_string();
 
-        ShortcodeDefinition { file_type, tera_name }
+        ShortcodeDefinition { file_type, tera_name, native: None }
+    }
+
+    /// A shortcode written in Rust. Its `tera_name` doesn't match any template but still has
+    /// the extension of its file type like the template ones.
+    pub fn native(
+        name: &str,
+        file_type: ShortcodeFileType,
+        render: impl Fn(&Map<String, Value>, &Context) -> Result<String> + Send + Sync + 'static,
+    ) -> ShortcodeDefinition {
+        let extension = match file_type {
+            ShortcodeFileType::Markdown => "md",
+            ShortcodeFileType::Html => "html",
+        };
+        ShortcodeDefinition {
+            file_type,
+            tera_name: format!("__zola_native/shortcodes/{}.{}", name, extension),
+            native: Some(NativeShortcode(Arc::new(render))),
+        }
     }
 }
 
@@ -93,10 +137,16 @@ pub fn render_template(
             name,
             "https://www.getzola.org/documentation/templates/pages-sections/#page-variables"
         ),
+        "archive.html" => {
+            render_default_tpl!(name, "https://www.getzola.org/documentation/templates/archive/")
+        }
+        "series.html" => {
+            render_default_tpl!(name, "https://www.getzola.org/documentation/templates/series/")
+        }
         "single.html" | "list.html" => {
             render_default_tpl!(name, "https://www.getzola.org/documentation/templates/taxonomies/")
         }
-        _ => bail!("Tried to render `{}` but the template wasn't found", name),
+        _ => bail!(missing_template_message(name, tera, theme)),
     }
 }
 
@@ -117,6 +167,42 @@ pub fn rewrite_theme_paths(tera_theme: &mut Tera, theme: &str) {
     tera_theme.templates.extend(new_templates);
 }
 
+/// Explains where a template that wasn't found was looked for, along with the templates having
+/// a similar name in case of a typo
+fn missing_template_message(name: &str, tera: &Tera, theme: &Option<String>) -> String {
+    let mut message =
+        format!("Tried to render `{}` but the template wasn't found. Looked for:\n", name);
+    message.push_str(&format!("- `templates/{}` in the site\n", name));
+    if let Some(ref t) = *theme {
+        message.push_str(&format!("- `themes/{}/templates/{}` in the theme\n", t, name));
+    }
+    message.push_str(&format!("- `{}` in the built-in templates", name));
+
+    // Only the templates of the same type are suggested, eg not `page.txt` for `pages.html`
+    let extension = name.rsplit_once('.').map(|(_, ext)| ext).unwrap_or_default();
+    let theme_prefix = theme.as_ref().map(|t| format!("{}/templates/", t));
+    let mut names: Vec<&str> = tera
+        .templates
+        .keys()
+        .filter(|k| !k.starts_with("internal/") && k.ends_with(extension))
+        .map(|k| {
+            let k = k.strip_prefix("__zola_builtins/").unwrap_or(k);
+            match theme_prefix {
+                Some(ref prefix) => k.strip_prefix(prefix.as_str()).unwrap_or(k),
+                None => k,
+            }
+        })
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    let suggestions: Vec<_> =
+        closest_matches(name, names).iter().map(|n| format!("`{}`", n)).collect();
+    if !suggestions.is_empty() {
+        message.push_str(&format!("\nDid you mean {}?", suggestions.join(", ")));
+    }
+    message
+}
+
 /// Checks for the presence of a given template. If none is found, also looks for a
 /// fallback in theme and default templates. Returns the path of the most specific
 /// template found, or none if none are present.
@@ -149,10 +235,10 @@ pub fn check_template_fallbacks<'a>(
 
 #[cfg(test)]
 mod tests {
-    use crate::templates::{check_template_fallbacks, get_shortcodes};
+    use crate::templates::{check_template_fallbacks, get_shortcodes, render_template};
 
     use super::rewrite_theme_paths;
-    use libs::tera::Tera;
+    use libs::tera::{Context, Tera};
 
     #[test]
     fn can_rewrite_all_paths_of_theme() {
@@ -196,6 +282,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn explains_where_missing_templates_were_looked_for() {
+        let mut tera = Tera::default();
+        tera.add_raw_template("blog-post.html", "Hello").unwrap();
+        tera.add_raw_template("hyde/templates/blog-posts.html", "Hello").unwrap();
+        tera.add_raw_template("__zola_builtins/blog-pot.html", "Hello").unwrap();
+        tera.add_raw_template("hyde/templates/archive.html", "Hello").unwrap();
+
+        let theme = Some("hyde".to_string());
+        let err = render_template("blog_post.html", &tera, Context::new(), &theme).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Tried to render `blog_post.html` but the template wasn't found. Looked for:
+- `templates/blog_post.html` in the site
+- `themes/hyde/templates/blog_post.html` in the theme
+- `blog_post.html` in the built-in templates
+Did you mean `blog-post.html`, `blog-posts.html`, `blog-pot.html`?"
+        );
+
+        let err = render_template("nothing-like-it.xml", &tera, Context::new(), &None).unwrap_err();
+        assert!(err.to_string().ends_with("- `nothing-like-it.xml` in the built-in templates"));
+    }
+
     #[test]
     fn can_overwrite_builtin_shortcodes() {
         let mut tera = Tera::parse("test-templates/*.html").unwrap();
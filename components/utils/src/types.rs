@@ -14,3 +14,37 @@ impl InsertAnchor {
         matches!(self, InsertAnchor::Left | InsertAnchor::Right)
     }
 }
+
+/// What to do with the raw HTML written in the Markdown of a section and its pages
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RawHtml {
+    /// Output it as is
+    Pass,
+    /// Output it as text, eg `<b>` is shown rather than making the text bold
+    Escape,
+    /// Remove the tags and attributes that aren't allowed, as well as comments and scripts
+    Sanitize,
+}
+
+impl Default for RawHtml {
+    fn default() -> Self {
+        RawHtml::Pass
+    }
+}
+
+impl RawHtml {
+    /// The stricter of two policies, escaping being stricter than sanitizing
+    pub fn strictest(self, other: Self) -> Self {
+        let strictness = |policy| match policy {
+            RawHtml::Pass => 0,
+            RawHtml::Sanitize => 1,
+            RawHtml::Escape => 2,
+        };
+        if strictness(other) > strictness(self) {
+            other
+        } else {
+            self
+        }
+    }
+}
@@ -13,6 +13,20 @@ pub struct Cli {
     #[clap(short = 'c', long, default_value = "config.toml")]
     pub config: PathBuf,
 
+    /// Merge the overlay of that environment into the config, eg `config.staging.toml` for
+    /// `staging`
+    #[clap(short = 'e', long, env = "ZOLA_ENV")]
+    pub env: Option<String>,
+
+    /// Print what the build, the watcher and the copy of static files are doing: `-v` for the
+    /// main steps and `-vv` for every file
+    #[clap(short = 'v', long, parse(from_occurrences), global = true)]
+    pub verbose: u8,
+
+    /// Only print warnings and errors
+    #[clap(short = 'q', long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
     #[clap(subcommand)]
     pub command: Command,
 }
@@ -43,6 +57,16 @@ pub enum Command {
         /// Include drafts when loading the site
         #[clap(long)]
         drafts: bool,
+
+        /// Rewrites the internal links of the HTML files to relative paths ending with
+        /// `index.html`, so the output can be browsed from the filesystem without a web server
+        #[clap(long)]
+        offline_mirror: bool,
+
+        /// Writes a JSON report of the build summary and of every generated file, with its source
+        /// and size, to the given path
+        #[clap(long)]
+        output_report: Option<PathBuf>,
     },
 
     /// Serve the site. Rebuild and reload on change automatically
@@ -64,6 +88,10 @@ pub enum Command {
         #[clap(short = 'u', long, default_value = "127.0.0.1")]
         base_url: String,
 
+        /// Serve the site under the given path, eg `/preview/`, to proxy it under a subpath
+        #[clap(long)]
+        path_prefix: Option<String>,
+
         /// Include drafts when loading the site
         #[clap(long)]
         drafts: bool,
@@ -75,6 +103,23 @@ pub enum Command {
         /// Only rebuild the minimum on change - useful when working on a specific page/section
         #[clap(short = 'f', long)]
         fast: bool,
+
+        /// Serve the site over HTTPS, with a self-signed certificate unless `--cert` and `--key` are given
+        #[clap(long)]
+        https: bool,
+
+        /// The PEM certificate to use with `--https`
+        #[clap(long, requires_all = &["https", "key"])]
+        cert: Option<PathBuf>,
+
+        /// The PEM private key of the certificate given to `--cert`
+        #[clap(long, requires_all = &["https", "cert"])]
+        key: Option<PathBuf>,
+
+        /// Poll for changes every given number of milliseconds instead of relying on filesystem
+        /// events, which are not available on NFS mounts and some Docker bind mounts
+        #[clap(long, value_name = "INTERVAL")]
+        watch_poll: Option<u64>,
     },
 
     /// Try to build the project without rendering it. Checks links
@@ -82,5 +127,72 @@ pub enum Command {
         /// Include drafts when loading the site
         #[clap(long)]
         drafts: bool,
+
+        /// Also report the images without alt text, the links without text and the headings
+        /// skipping a level in the content
+        #[clap(long)]
+        accessibility: bool,
+
+        /// Also render the pages and sections to report the unclosed tags and duplicate ids
+        /// of their HTML
+        #[clap(long)]
+        validate_html: bool,
+    },
+
+    /// Build the site in a temporary directory and list the files that changed compared to the last build
+    Diff {
+        /// Force the base URL to be that value (defaults to the one in config.toml)
+        #[clap(short = 'u', long)]
+        base_url: Option<String>,
+
+        /// The build to compare to (by default 'public' dir in project root)
+        #[clap(long)]
+        against: Option<PathBuf>,
+
+        /// Include drafts when loading the site
+        #[clap(long)]
+        drafts: bool,
+
+        /// Ignore whitespace changes in HTML files and print the lines that changed
+        #[clap(long)]
+        html: bool,
+    },
+
+    /// Upload the built site to the target set in `[deploy]`, skipping unchanged files
+    Deploy {
+        /// The target to deploy to: `rsync`, `s3` or `github-pages`. Defaults to `deploy.target`
+        #[clap(short = 't', long)]
+        target: Option<String>,
+
+        /// Only print the commands that would change the deployed site
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// Build a synthesized site in a temporary directory and report how long loading, rendering
+    /// and writing it takes
+    Bench {
+        /// How many pages the site has
+        #[clap(long, default_value_t = 1000)]
+        pages: usize,
+
+        /// How many sections the pages are spread over
+        #[clap(long, default_value_t = 10)]
+        sections: usize,
+
+        /// How many tags the pages are tagged with, two per page
+        #[clap(long, default_value_t = 50)]
+        terms: usize,
+
+        /// How many times the site is built, after a first build that is not counted
+        #[clap(long, default_value_t = 5)]
+        runs: usize,
+    },
+
+    /// Notify the WebSub hubs and search engines set in `[ping]` that the deployed site changed
+    Ping {
+        /// Only print the notifications that would be sent
+        #[clap(long)]
+        dry_run: bool,
     },
 }
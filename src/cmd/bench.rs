@@ -0,0 +1,240 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use errors::{bail, Result};
+use site::{Site, SITE_BINARY_CONTENT, SITE_CONTENT};
+use tempfile::Builder;
+use utils::fs::{create_directory, create_file};
+
+const CONFIG: &str = r#"base_url = "https://example.com"
+title = "Benchmark"
+taxonomies = [{ name = "tags", feed = true }]
+
+[markdown]
+highlight_code = true
+"#;
+
+const TEMPLATES: &[(&str, &str)] = &[
+    (
+        "base.html",
+        r#"<!DOCTYPE html>
+<html lang="{{ lang }}">
+<head><title>{% block title %}{{ config.title }}{% endblock title %}</title></head>
+<body>{% block content %}{% endblock content %}</body>
+</html>"#,
+    ),
+    (
+        "index.html",
+        r#"{% extends "base.html" %}
+{% block content %}
+<ul>{% for s in section.subsections %}{% set sub = get_section(path=s, metadata_only=true) %}<li><a href="{{ sub.permalink }}">{{ sub.title }}</a></li>{% endfor %}</ul>
+{% endblock content %}"#,
+    ),
+    (
+        "section.html",
+        r#"{% extends "base.html" %}
+{% block title %}{{ section.title }}{% endblock title %}
+{% block content %}
+<h1>{{ section.title }}</h1>
+<ul>{% for page in paginator.pages %}<li><a href="{{ page.permalink }}">{{ page.title }}</a> {{ page.date }} {{ page.reading_time }} min</li>{% endfor %}</ul>
+{% if paginator.previous %}<a href="{{ paginator.previous }}">Previous</a>{% endif %}
+{% if paginator.next %}<a href="{{ paginator.next }}">Next</a>{% endif %}
+{% endblock content %}"#,
+    ),
+    (
+        "page.html",
+        r#"{% extends "base.html" %}
+{% block title %}{{ page.title }}{% endblock title %}
+{% block content %}
+<h1>{{ page.title }}</h1>
+<nav>{% for h in page.toc %}<a href="{{ h.permalink }}">{{ h.title }}</a>{% endfor %}</nav>
+{{ page.content | safe }}
+{% if page.taxonomies.tags %}<ul>{% for tag in page.taxonomies.tags %}<li><a href="{{ get_taxonomy_url(kind="tags", name=tag) }}">{{ tag }}</a></li>{% endfor %}</ul>{% endif %}
+{% if page.lower %}<a href="{{ page.lower.permalink }}">{{ page.lower.title }}</a>{% endif %}
+{% if page.higher %}<a href="{{ page.higher.permalink }}">{{ page.higher.title }}</a>{% endif %}
+{% endblock content %}"#,
+    ),
+    (
+        "tags/list.html",
+        r#"{% extends "base.html" %}
+{% block content %}
+<ul>{% for term in terms %}<li><a href="{{ term.permalink }}">{{ term.name }}</a> {{ term.pages | length }}</li>{% endfor %}</ul>
+{% endblock content %}"#,
+    ),
+    (
+        "tags/single.html",
+        r#"{% extends "base.html" %}
+{% block content %}
+<h1>{{ term.name }}</h1>
+<ul>{% for page in term.pages %}<li><a href="{{ page.permalink }}">{{ page.title }}</a></li>{% endfor %}</ul>
+{% endblock content %}"#,
+    ),
+];
+
+const PAGE_CONTENT: &str = r#"
+Lorem markdownum litora, care ponto nomina, et ut aspicit gelidas sui et purpureo genuit.
+Tamen colla venientis [delphina](https://example.com/ecquis) Tusci et **temptata** citaeque
+curam isto ubi vult vulnere reppulit.
+
+## Ut vita
+
+- Seque vidit flendoque de quodam
+- Dabit minimos deiecto caputque noctis pluma
+- Leti coniunx est Helicen
+
+> Clivo sub inprovisoque nostrum minus fama est, discordia patrem petebat precatur absumitur,
+> poena per sit.
+
+### Praeceps Corinthus
+
+```rust
+fn main() {
+    let pages: Vec<_> = (0..10).map(|i| format!("page-{}", i)).collect();
+    println!("{:?}", pages);
+}
+```
+
+| Name | Value |
+|------|-------|
+| a    | 1     |
+| b    | 2     |
+"#;
+
+/// How many pages a section lists per page of its pagination
+const PAGINATE_BY: usize = 10;
+
+/// Writes a site of `pages` pages spread over `sections` sections and tagged with `terms` tags
+fn generate_site(dir: &Path, pages: usize, sections: usize, terms: usize) -> Result<()> {
+    create_directory(dir)?;
+    create_file(&dir.join("config.toml"), CONFIG)?;
+    for (name, template) in TEMPLATES {
+        let path = dir.join("templates").join(name);
+        create_directory(path.parent().unwrap())?;
+        create_file(&path, template)?;
+    }
+
+    let content = dir.join("content");
+    create_directory(&content)?;
+    create_file(&content.join("_index.md"), "+++\ntitle = \"Home\"\n+++\n")?;
+    for i in 0..sections {
+        let section = content.join(format!("section-{}", i));
+        create_directory(&section)?;
+        create_file(
+            &section.join("_index.md"),
+            &format!(
+                "+++\ntitle = \"Section {}\"\nsort_by = \"date\"\npaginate_by = {}\n+++\n",
+                i, PAGINATE_BY
+            ),
+        )?;
+    }
+    for i in 0..pages {
+        let tags = if terms == 0 {
+            String::new()
+        } else {
+            format!("\n[taxonomies]\ntags = [\"tag-{}\", \"tag-{}\"]", i % terms, i * 7 % terms)
+        };
+        let page = format!(
+            "+++\ntitle = \"Page {}\"\ndate = {}-{:02}-{:02}{}\n+++\n{}",
+            i,
+            2000 + i / 336,
+            i / 28 % 12 + 1,
+            i % 28 + 1,
+            tags,
+            PAGE_CONTENT
+        );
+        let path = content.join(format!("section-{}", i % sections)).join(format!("page-{}.md", i));
+        create_file(&path, &page)?;
+    }
+    Ok(())
+}
+
+/// The time taken by each step of one build of the synthesized site
+struct Timings {
+    load: Duration,
+    render: Duration,
+    write: Duration,
+}
+
+fn time_build(dir: &Path) -> Result<Timings> {
+    let start = Instant::now();
+    let mut site = Site::new(dir, "config.toml")?;
+    site.set_output_path(dir.join("public"));
+    site.load()?;
+    let load = start.elapsed();
+
+    let start = Instant::now();
+    site.build()?;
+    let build = start.elapsed();
+
+    // Rendering in memory gives the time taken by the templates alone
+    site.enable_serve_mode();
+    let start = Instant::now();
+    site.build()?;
+    let render = start.elapsed();
+    SITE_CONTENT.write().unwrap().clear();
+    SITE_BINARY_CONTENT.write().unwrap().clear();
+
+    Ok(Timings { load, render, write: build.saturating_sub(render) })
+}
+
+fn report(step: &str, mut durations: Vec<Duration>) {
+    durations.sort();
+    let ms = |d: &Duration| d.as_secs_f64() * 1000.0;
+    console::plain(&format!(
+        "{:<8} median {:>9.1}ms   min {:>9.1}ms   max {:>9.1}ms",
+        step,
+        ms(&durations[durations.len() / 2]),
+        ms(&durations[0]),
+        ms(&durations[durations.len() - 1]),
+    ));
+}
+
+/// Builds a synthesized site `runs` times after a warm-up build and prints how long loading,
+/// rendering and writing it took
+pub fn bench(pages: usize, sections: usize, terms: usize, runs: usize) -> Result<()> {
+    if pages == 0 || sections == 0 || runs == 0 {
+        bail!("The benchmark needs at least one page, one section and one run");
+    }
+
+    // The site is deleted when `tmp_dir` is dropped, even if the benchmark fails
+    let tmp_dir = Builder::new().prefix("zola-bench").tempdir()?;
+    let dir = tmp_dir.path();
+    console::info(&format!(
+        "Benchmarking a site of {} pages in {} sections with {} tags in {}",
+        pages,
+        sections,
+        terms,
+        dir.display()
+    ));
+    generate_site(dir, pages, sections, terms)?;
+    time_build(dir)?;
+    let timings = (0..runs).map(|_| time_build(dir)).collect::<Result<Vec<_>>>()?;
+
+    report("load", timings.iter().map(|t| t.load).collect());
+    report("render", timings.iter().map(|t| t.render).collect());
+    report("write", timings.iter().map(|t| t.write).collect());
+    report("total", timings.iter().map(|t| t.load + t.render + t.write).collect());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::generate_site;
+    use site::Site;
+    use tempfile::tempdir;
+
+    #[test]
+    fn can_load_synthesized_site() {
+        let tmp_dir = tempdir().unwrap();
+        generate_site(tmp_dir.path(), 25, 3, 4).unwrap();
+        let mut site = Site::new(tmp_dir.path(), "config.toml").unwrap();
+        site.load().unwrap();
+        {
+            let library = site.library.read().unwrap();
+            assert_eq!(library.pages.len(), 25);
+            // The index and the 3 sections
+            assert_eq!(library.sections.len(), 4);
+        }
+        assert_eq!(site.taxonomies[0].items.len(), 4);
+    }
+}
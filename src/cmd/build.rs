@@ -1,4 +1,5 @@
 use std::path::Path;
+use std::time::Instant;
 
 use errors::{Error, Result};
 use site::Site;
@@ -8,14 +9,19 @@ use crate::prompt::ask_bool_timeout;
 
 const BUILD_PROMPT_TIMEOUT_MILLIS: u64 = 10_000;
 
+#[allow(clippy::too_many_arguments)]
 pub fn build(
     root_dir: &Path,
     config_file: &Path,
+    env: Option<&str>,
     base_url: Option<&str>,
     output_dir: Option<&Path>,
     include_drafts: bool,
+    offline_mirror: bool,
+    output_report: Option<&Path>,
 ) -> Result<()> {
-    let mut site = Site::new(root_dir, config_file)?;
+    let start = Instant::now();
+    let mut site = Site::new_in_env(root_dir, config_file, env)?;
     if let Some(output_dir) = output_dir {
         // Check whether output directory exists or not
         // This way we don't replace already existing files.
@@ -46,8 +52,24 @@ pub fn build(
     if include_drafts {
         site.include_drafts();
     }
+    if offline_mirror {
+        site.enable_offline_mirror();
+    }
+    if output_report.is_some() {
+        site.enable_build_report();
+    }
     site.load()?;
     messages::notify_site_size(&site);
     messages::warn_about_ignored_pages(&site);
-    site.build()
+    site.build()?;
+    // Measuring the output directory isn't free, skip it when the summary isn't used
+    if console::is_quiet() && output_report.is_none() {
+        return Ok(());
+    }
+    let summary = site.build_summary(start.elapsed())?;
+    messages::print_build_summary(&summary);
+    if let Some(output_report) = output_report {
+        site.write_build_report(output_report, &summary)?;
+    }
+    Ok(())
 }
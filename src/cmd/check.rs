@@ -1,19 +1,24 @@
 use std::path::{Path, PathBuf};
 
 use errors::Result;
+use site::html_validation::validate_site_html;
 use site::Site;
 
 use crate::messages;
 
+#[allow(clippy::too_many_arguments)]
 pub fn check(
     root_dir: &Path,
     config_file: &Path,
+    env: Option<&str>,
     base_path: Option<&str>,
     base_url: Option<&str>,
     include_drafts: bool,
+    accessibility: bool,
+    validate_html: bool,
 ) -> Result<()> {
     let bp = base_path.map(PathBuf::from).unwrap_or_else(|| PathBuf::from(root_dir));
-    let mut site = Site::new(bp, config_file)?;
+    let mut site = Site::new_in_env(bp, config_file, env)?;
     // Force the checking of external links
     site.config.enable_check_mode();
     if let Some(b) = base_url {
@@ -25,5 +30,12 @@ pub fn check(
     site.load()?;
     messages::check_site_summary(&site);
     messages::warn_about_ignored_pages(&site);
+    messages::warn_about_unused_templates(&site);
+    if accessibility {
+        messages::warn_about_accessibility_issues(&site);
+    }
+    if validate_html {
+        messages::warn_about_html_issues(validate_site_html(&site)?);
+    }
     Ok(())
 }
@@ -0,0 +1,360 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use libs::serde_json;
+use libs::sha2::{Digest, Sha256};
+use libs::walkdir::WalkDir;
+
+use config::{GithubPages, Rsync, S3};
+use errors::{bail, Context, Result};
+use site::Site;
+
+/// The file keeping the hashes of the deployed files in the S3 bucket
+const S3_MANIFEST: &str = ".zola-deploy.json";
+
+/// Runs the commands changing the deployed site, or only prints them in dry-run mode.
+/// Commands only reading data are always run.
+struct Runner<'a> {
+    /// The commands run in the directory of the site, where its git repository is
+    root_dir: &'a Path,
+    dry_run: bool,
+}
+
+impl<'a> Runner<'a> {
+    fn run(&self, mut command: Command) -> Result<()> {
+        let display = display_command(&command);
+        if self.dry_run {
+            console::info(&format!("Would run: {}", display));
+            return Ok(());
+        }
+        console::info(&format!("Running: {}", display));
+        let status = command
+            .current_dir(self.root_dir)
+            .status()
+            .with_context(|| format!("Failed to run `{}`", display))?;
+        if !status.success() {
+            bail!("`{}` failed with {}", display, status);
+        }
+        Ok(())
+    }
+
+    /// Runs a command without side effects and returns its output, `None` if it failed
+    fn read(&self, mut command: Command) -> Result<Option<String>> {
+        let display = display_command(&command);
+        let output = command
+            .current_dir(self.root_dir)
+            .output()
+            .with_context(|| format!("Failed to run `{}`", display))?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&output.stdout).trim().to_string()))
+    }
+
+    /// Same as `read` but a failure of the command is an error
+    fn read_ok(&self, mut command: Command) -> Result<String> {
+        let display = display_command(&command);
+        let output = command
+            .current_dir(self.root_dir)
+            .output()
+            .with_context(|| format!("Failed to run `{}`", display))?;
+        if !output.status.success() {
+            bail!(
+                "`{}` failed with {}: {}",
+                display,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+fn display_command(command: &Command) -> String {
+    let mut parts = vec![command.get_program().to_string_lossy().into_owned()];
+    parts.extend(command.get_args().map(|a| a.to_string_lossy().into_owned()));
+    parts.join(" ")
+}
+
+/// Where a site can be deployed to
+trait Target {
+    fn deploy(&self, output_path: &Path, runner: &Runner<'_>) -> Result<()>;
+}
+
+impl Target for Rsync {
+    fn deploy(&self, output_path: &Path, runner: &Runner<'_>) -> Result<()> {
+        runner.run(rsync_command(self, output_path))
+    }
+}
+
+fn rsync_command(rsync: &Rsync, output_path: &Path) -> Command {
+    let mut command = Command::new("rsync");
+    // `--checksum` compares the content of the files rather than their modification time,
+    // which changes on every build
+    command.args(["--recursive", "--links", "--compress", "--checksum", "--delete"]);
+    command.args(&rsync.args);
+    // The trailing slash copies the content of the directory rather than the directory itself
+    command.arg(format!("{}/", output_path.display()));
+    command.arg(&rsync.destination);
+    command
+}
+
+/// A target tracking the deployed files itself: the hashes of the files are kept next
+/// to them to only upload the ones that changed
+impl Target for S3 {
+    fn deploy(&self, output_path: &Path, runner: &Runner<'_>) -> Result<()> {
+        let remote: BTreeMap<String, String> = match runner
+            .read(aws_command(self, &["s3", "cp", &s3_url(self, S3_MANIFEST), "-"]))?
+        {
+            Some(json) => serde_json::from_str(&json)
+                .with_context(|| format!("Invalid {} in the bucket", S3_MANIFEST))?,
+            None => {
+                console::info(&format!("No {} in the bucket, uploading everything", S3_MANIFEST));
+                BTreeMap::new()
+            }
+        };
+        let local = hash_files(output_path)?;
+        let (changed, removed) = diff(&local, &remote);
+        if changed.is_empty() && removed.is_empty() {
+            console::info("Nothing changed since the last deploy");
+            return Ok(());
+        }
+
+        for path in changed {
+            let file = output_path.join(path);
+            runner.run(aws_command(
+                self,
+                &["s3", "cp", &file.to_string_lossy(), &s3_url(self, path)],
+            ))?;
+        }
+        for path in removed {
+            runner.run(aws_command(self, &["s3", "rm", &s3_url(self, path)]))?;
+        }
+
+        let manifest =
+            std::env::temp_dir().join(format!("zola-deploy-{}.json", std::process::id()));
+        std::fs::write(&manifest, serde_json::to_string(&local)?)?;
+        let res = runner.run(aws_command(
+            self,
+            &["s3", "cp", &manifest.to_string_lossy(), &s3_url(self, S3_MANIFEST)],
+        ));
+        std::fs::remove_file(&manifest)?;
+        res
+    }
+}
+
+fn s3_url(s3: &S3, path: &str) -> String {
+    let prefix = s3.prefix.trim_matches('/');
+    if prefix.is_empty() {
+        format!("s3://{}/{}", s3.bucket, path)
+    } else {
+        format!("s3://{}/{}/{}", s3.bucket, prefix, path)
+    }
+}
+
+fn aws_command(s3: &S3, args: &[&str]) -> Command {
+    let mut command = Command::new("aws");
+    command.args(args);
+    if let Some(ref endpoint) = s3.endpoint {
+        command.args(["--endpoint-url", endpoint]);
+    }
+    if let Some(ref region) = s3.region {
+        command.args(["--region", region]);
+    }
+    command
+}
+
+/// Git is content-addressed: committing the output directory on top of the current branch
+/// only pushes the files that changed
+impl Target for GithubPages {
+    fn deploy(&self, output_path: &Path, runner: &Runner<'_>) -> Result<()> {
+        let git = |args: &[&str]| {
+            let mut command = Command::new("git");
+            command.args(args);
+            command
+        };
+
+        // Fetching doesn't change the deployed site, and is needed to find what changed
+        runner.read(git(&["fetch", &self.remote, &self.branch]))?;
+        let remote_branch = format!("refs/remotes/{}/{}", self.remote, self.branch);
+        let parent = runner.read(git(&["rev-parse", "--verify", "--quiet", &remote_branch]))?;
+
+        // A temporary index so the one of the repository isn't touched
+        let index = std::env::temp_dir().join(format!("zola-deploy-index-{}", std::process::id()));
+        let tree = (|| {
+            let with_index = |args: &[&str]| {
+                let mut command = git(args);
+                command.env("GIT_INDEX_FILE", &index);
+                command
+            };
+            let work_tree = format!("--work-tree={}", output_path.display());
+            runner.read_ok(with_index(&[&work_tree, "add", "--all", "--force", "."]))?;
+            // GitHub Pages would otherwise run Jekyll, ignoring the files starting with `_`
+            let mut hash_empty = git(&["hash-object", "-w", "--stdin"]);
+            hash_empty.stdin(Stdio::null());
+            let empty = runner.read_ok(hash_empty)?;
+            runner.read_ok(with_index(&[
+                "update-index",
+                "--add",
+                "--cacheinfo",
+                &format!("100644,{},.nojekyll", empty),
+            ]))?;
+            runner.read_ok(with_index(&["write-tree"]))
+        })();
+        // The index doesn't exist if git failed before creating it
+        let _ = std::fs::remove_file(&index);
+        let tree = tree?;
+
+        if let Some(ref parent) = parent {
+            let parent_tree =
+                runner.read_ok(git(&["rev-parse", &format!("{}^{{tree}}", parent)]))?;
+            if parent_tree == tree {
+                console::info("Nothing changed since the last deploy");
+                return Ok(());
+            }
+        }
+
+        let mut commit_args = vec!["commit-tree", tree.as_str(), "-m", self.message.as_str()];
+        if let Some(ref parent) = parent {
+            commit_args.extend(["-p", parent.as_str()]);
+        }
+        // Creating the commit object doesn't change anything until it's pushed
+        let commit = runner.read_ok(git(&commit_args))?;
+        runner.run(git(&["push", &self.remote, &format!("{}:refs/heads/{}", commit, self.branch)]))
+    }
+}
+
+/// The SHA-256 of every file of the output directory, by path relative to it
+fn hash_files(output_path: &Path) -> Result<BTreeMap<String, String>> {
+    let mut hashes = BTreeMap::new();
+    for entry in WalkDir::new(output_path).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry
+            .path()
+            .strip_prefix(output_path)
+            .expect("Walked outside the output directory")
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let mut hasher = Sha256::new();
+        let mut file = File::open(entry.path())?;
+        io::copy(&mut file, &mut hasher)?;
+        hashes.insert(relative, format!("{:x}", hasher.finalize()));
+    }
+    Ok(hashes)
+}
+
+/// Returns the files that are new or changed and the ones that were removed
+fn diff<'a>(
+    local: &'a BTreeMap<String, String>,
+    remote: &'a BTreeMap<String, String>,
+) -> (Vec<&'a str>, Vec<&'a str>) {
+    let changed = local
+        .iter()
+        .filter(|(path, hash)| remote.get(*path) != Some(hash))
+        .map(|(path, _)| path.as_str())
+        .collect();
+    let removed =
+        remote.keys().filter(|path| !local.contains_key(*path)).map(|p| p.as_str()).collect();
+    (changed, removed)
+}
+
+pub fn deploy(
+    root_dir: &Path,
+    config_file: &Path,
+    env: Option<&str>,
+    target: Option<&str>,
+    dry_run: bool,
+) -> Result<()> {
+    let site = Site::new_in_env(root_dir, config_file, env)?;
+    let output_path = &site.output_path;
+    if !output_path.exists() {
+        bail!(
+            "The output directory {} doesn't exist, build the site with `zola build` first",
+            output_path.display()
+        );
+    }
+
+    let deploy = &site.config.deploy;
+    let target_name = match target.or(deploy.target.as_deref()) {
+        Some(t) => t,
+        None => bail!("No deploy target: pass one with `--target` or set `deploy.target`"),
+    };
+    let target: &dyn Target = match target_name {
+        "rsync" => match deploy.rsync {
+            Some(ref rsync) => rsync,
+            None => bail!("The `rsync` target requires a `[deploy.rsync]` section"),
+        },
+        "s3" => match deploy.s3 {
+            Some(ref s3) => s3,
+            None => bail!("The `s3` target requires a `[deploy.s3]` section"),
+        },
+        "github-pages" => &deploy.github_pages,
+        _ => bail!(
+            "Unknown deploy target `{}`, expected one of `rsync`, `s3` or `github-pages`",
+            target_name
+        ),
+    };
+
+    console::info(&format!("Deploying {} with {}", output_path.display(), target_name));
+    target.deploy(output_path, &Runner { root_dir, dry_run })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    use super::{aws_command, diff, display_command, rsync_command, s3_url};
+    use config::{Rsync, S3};
+
+    #[test]
+    fn can_diff_deployed_files() {
+        let hashes = |files: &[(&str, &str)]| -> BTreeMap<String, String> {
+            files.iter().map(|(p, h)| (p.to_string(), h.to_string())).collect()
+        };
+        let local = hashes(&[("index.html", "a"), ("style.css", "b"), ("new.js", "c")]);
+        let remote = hashes(&[("index.html", "a"), ("style.css", "old"), ("gone.html", "d")]);
+
+        let (changed, removed) = diff(&local, &remote);
+        assert_eq!(changed, vec!["new.js", "style.css"]);
+        assert_eq!(removed, vec!["gone.html"]);
+    }
+
+    #[test]
+    fn can_make_rsync_command() {
+        let rsync = Rsync {
+            destination: "user@example.com:/var/www/site".to_string(),
+            args: vec!["--exclude".to_string(), ".well-known".to_string()],
+        };
+        assert_eq!(
+            display_command(&rsync_command(&rsync, Path::new("/site/public"))),
+            "rsync --recursive --links --compress --checksum --delete --exclude .well-known /site/public/ user@example.com:/var/www/site"
+        );
+    }
+
+    #[test]
+    fn can_make_s3_commands() {
+        let mut s3 = S3 {
+            bucket: "my-site".to_string(),
+            prefix: String::new(),
+            endpoint: None,
+            region: None,
+        };
+        assert_eq!(s3_url(&s3, "index.html"), "s3://my-site/index.html");
+
+        s3.prefix = "/blog/".to_string();
+        s3.endpoint = Some("https://storage.example.com".to_string());
+        assert_eq!(s3_url(&s3, "index.html"), "s3://my-site/blog/index.html");
+        assert_eq!(
+            display_command(&aws_command(&s3, &["s3", "rm", &s3_url(&s3, "a.html")])),
+            "aws s3 rm s3://my-site/blog/a.html --endpoint-url https://storage.example.com"
+        );
+    }
+}
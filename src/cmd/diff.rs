@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use libs::once_cell::sync::Lazy;
+use libs::regex::Regex;
+use libs::walkdir::WalkDir;
+
+use errors::{bail, Context, Result};
+use site::Site;
+
+/// How many lines of a changed HTML file are printed at most
+const MAX_PRINTED_LINES: usize = 20;
+
+static WHITESPACE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").unwrap());
+static BETWEEN_TAGS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r">\s*<").unwrap());
+
+/// The differences between two builds, by path relative to the output directory
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Changes {
+    added: Vec<String>,
+    removed: Vec<String>,
+    changed: Vec<String>,
+}
+
+impl Changes {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Lists the files of a directory, by path relative to it with `/` as separator
+fn list_files(dir: &Path) -> BTreeMap<String, PathBuf> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| {
+            let relative = e
+                .path()
+                .strip_prefix(dir)
+                .expect("Walked outside the output directory")
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            (relative, e.path().to_path_buf())
+        })
+        .collect()
+}
+
+fn is_html(path: &str) -> bool {
+    path.ends_with(".html")
+}
+
+/// Puts every tag on its own line and collapses the whitespace, so changes in the indentation
+/// or the line breaks of the templates are ignored
+fn normalize_html(html: &str) -> String {
+    let collapsed = WHITESPACE_RE.replace_all(html.trim(), " ");
+    BETWEEN_TAGS_RE.replace_all(&collapsed, ">\n<").into_owned()
+}
+
+fn read_for_comparison(path: &Path, html: bool) -> Result<Vec<u8>> {
+    let content = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if html && is_html(&path.to_string_lossy()) {
+        return Ok(normalize_html(&String::from_utf8_lossy(&content)).into_bytes());
+    }
+    Ok(content)
+}
+
+fn compare_dirs(old_dir: &Path, new_dir: &Path, html: bool) -> Result<Changes> {
+    let old = list_files(old_dir);
+    let new = list_files(new_dir);
+    let mut changes = Changes::default();
+
+    for (path, new_path) in &new {
+        match old.get(path) {
+            None => changes.added.push(path.clone()),
+            Some(old_path) => {
+                if read_for_comparison(old_path, html)? != read_for_comparison(new_path, html)? {
+                    changes.changed.push(path.clone());
+                }
+            }
+        }
+    }
+    changes.removed = old.keys().filter(|p| !new.contains_key(*p)).cloned().collect();
+
+    Ok(changes)
+}
+
+/// Returns the lines that differ between the two versions once the lines they have in common
+/// at the start and at the end are removed
+fn changed_lines<'a>(old: &'a str, new: &'a str) -> (Vec<&'a str>, Vec<&'a str>) {
+    let old: Vec<_> = old.lines().collect();
+    let new: Vec<_> = new.lines().collect();
+    let prefix = old.iter().zip(new.iter()).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (old[prefix..old.len() - suffix].to_vec(), new[prefix..new.len() - suffix].to_vec())
+}
+
+fn print_html_diff(old_path: &Path, new_path: &Path) -> Result<()> {
+    let old = normalize_html(&fs::read_to_string(old_path)?);
+    let new = normalize_html(&fs::read_to_string(new_path)?);
+    let (removed, added) = changed_lines(&old, &new);
+    let lines = removed
+        .iter()
+        .map(|l| format!("    - {}", l))
+        .chain(added.iter().map(|l| format!("    + {}", l)))
+        .collect::<Vec<_>>();
+    for line in lines.iter().take(MAX_PRINTED_LINES) {
+        println!("{}", line);
+    }
+    if lines.len() > MAX_PRINTED_LINES {
+        println!("    ... and {} more lines", lines.len() - MAX_PRINTED_LINES);
+    }
+    Ok(())
+}
+
+pub fn diff(
+    root_dir: &Path,
+    config_file: &Path,
+    env: Option<&str>,
+    base_url: Option<&str>,
+    against: Option<&Path>,
+    include_drafts: bool,
+    html: bool,
+) -> Result<()> {
+    let mut site = Site::new_in_env(root_dir, config_file, env)?;
+    let old_dir = against.map(PathBuf::from).unwrap_or_else(|| site.output_path.clone());
+    if !old_dir.exists() {
+        bail!("There is no previous build to compare to at {}", old_dir.display());
+    }
+
+    let new_dir = std::env::temp_dir().join(format!("zola-diff-{}", std::process::id()));
+    site.set_output_path(&new_dir);
+    if let Some(b) = base_url {
+        site.set_base_url(b.to_string());
+    }
+    if include_drafts {
+        site.include_drafts();
+    }
+    site.load()?;
+    let changes = site.build().and_then(|_| compare_dirs(&old_dir, &new_dir, html));
+    let changes = match changes {
+        Ok(changes) => changes,
+        Err(e) => {
+            // The temporary build might not exist if the build failed early
+            let _ = fs::remove_dir_all(&new_dir);
+            return Err(e);
+        }
+    };
+
+    if changes.is_empty() {
+        console::success(&format!("No changes compared to {}", old_dir.display()));
+    } else {
+        for path in &changes.added {
+            println!("+ {}", path);
+        }
+        for path in &changes.removed {
+            println!("- {}", path);
+        }
+        for path in &changes.changed {
+            println!("~ {}", path);
+            if html && is_html(path) {
+                print_html_diff(&old_dir.join(path), &new_dir.join(path))?;
+            }
+        }
+        console::info(&format!(
+            "{} added, {} removed and {} changed files compared to {}",
+            changes.added.len(),
+            changes.removed.len(),
+            changes.changed.len(),
+            old_dir.display()
+        ));
+    }
+
+    fs::remove_dir_all(&new_dir)
+        .with_context(|| format!("Failed to remove the temporary build {}", new_dir.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{changed_lines, normalize_html};
+
+    #[test]
+    fn can_normalize_html() {
+        let html = "<ul>\n    <li>A  page</li>\n\n<li>Another</li>\n</ul>\n";
+        assert_eq!(normalize_html(html), "<ul>\n<li>A page</li>\n<li>Another</li>\n</ul>");
+        // Only whitespace changes disappear once normalized
+        assert_eq!(
+            normalize_html(html),
+            normalize_html("<ul><li>A page</li><li>Another</li></ul>")
+        );
+    }
+
+    #[test]
+    fn can_find_changed_lines() {
+        let old = "<ul>\n<li>A</li>\n<li>B</li>\n</ul>";
+        let new = "<ul>\n<li>A</li>\n<li>C</li>\n<li>D</li>\n</ul>";
+        assert_eq!(changed_lines(old, new), (vec!["<li>B</li>"], vec!["<li>C</li>", "<li>D</li>"]));
+        assert_eq!(changed_lines(old, old), (vec![], vec![]));
+        // Lines appended at the end
+        assert_eq!(changed_lines("a\nb", "a\nb\nc"), (vec![], vec!["c"]));
+    }
+}
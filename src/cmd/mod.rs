@@ -1,9 +1,17 @@
+mod bench;
 mod build;
 mod check;
+mod deploy;
+mod diff;
 mod init;
+mod ping;
 mod serve;
 
+pub use self::bench::bench;
 pub use self::build::build;
 pub use self::check::check;
+pub use self::deploy::deploy;
+pub use self::diff::diff;
 pub use self::init::create_new_project;
+pub use self::ping::ping;
 pub use self::serve::serve;
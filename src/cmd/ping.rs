@@ -0,0 +1,164 @@
+use std::path::Path;
+
+use libs::reqwest::blocking::Client;
+use libs::url::Url;
+
+use errors::{bail, Context, Result};
+use site::Site;
+
+#[derive(Debug, PartialEq, Eq)]
+enum PingRequest {
+    /// Notifies a WebSub hub that a feed was updated
+    WebSub { hub: String, feed: String },
+    /// Sends the sitemap URL to a search engine
+    Sitemap { url: String },
+}
+
+impl PingRequest {
+    fn send(&self, client: &Client) -> Result<()> {
+        let response = match self {
+            PingRequest::WebSub { hub, feed } => {
+                client.post(hub).form(&[("hub.mode", "publish"), ("hub.url", feed)]).send()
+            }
+            PingRequest::Sitemap { url } => client.get(url).send(),
+        }
+        .with_context(|| self.to_string())?;
+
+        if !response.status().is_success() {
+            bail!("{}: got status {}", self, response.status());
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for PingRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PingRequest::WebSub { hub, feed } => write!(f, "POST {} (hub.url={})", hub, feed),
+            PingRequest::Sitemap { url } => write!(f, "GET {}", url),
+        }
+    }
+}
+
+/// Finds every notification to send from the `[ping]` config
+fn find_requests(site: &Site) -> Result<Vec<PingRequest>> {
+    let config = &site.config;
+    let mut feeds = Vec::new();
+    let mut languages: Vec<_> = config.languages.iter().collect();
+    languages.sort_by_key(|(code, _)| *code);
+    for (code, language) in languages {
+        if !language.generate_feed {
+            continue;
+        }
+        if code == &config.default_language {
+            feeds.push(config.make_permalink(&language.feed_filename));
+        } else {
+            feeds.push(config.make_permalink(&format!("{}/{}", code, language.feed_filename)));
+        }
+    }
+
+    let mut requests = Vec::new();
+    for hub in &config.ping.websub_hubs {
+        for feed in &feeds {
+            requests.push(PingRequest::WebSub { hub: hub.clone(), feed: feed.clone() });
+        }
+    }
+
+    let mut sitemap = config.make_permalink("sitemap.xml");
+    // `make_permalink` adds a trailing slash to anything that isn't the feed
+    if sitemap.ends_with('/') {
+        sitemap.pop();
+    }
+    for endpoint in &config.ping.sitemap_endpoints {
+        let url = Url::parse_with_params(endpoint, &[("sitemap", &sitemap)])
+            .with_context(|| format!("Invalid ping endpoint `{}`", endpoint))?;
+        requests.push(PingRequest::Sitemap { url: url.to_string() });
+    }
+
+    Ok(requests)
+}
+
+pub fn ping(root_dir: &Path, config_file: &Path, env: Option<&str>, dry_run: bool) -> Result<()> {
+    let site = Site::new_in_env(root_dir, config_file, env)?;
+    let requests = find_requests(&site)?;
+    if requests.is_empty() {
+        console::warn("Nothing to ping: set `websub_hubs` or `sitemap_endpoints` in `[ping]`");
+        return Ok(());
+    }
+
+    if dry_run {
+        for request in &requests {
+            console::info(&format!("Would send {}", request));
+        }
+        return Ok(());
+    }
+
+    let client = Client::builder()
+        .user_agent(concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .expect("reqwest client build");
+    let mut errors = 0;
+    for request in &requests {
+        match request.send(&client) {
+            Ok(()) => console::info(&format!("Sent {}", request)),
+            Err(e) => {
+                errors += 1;
+                console::error(&format!("Failed to ping: {:#}", e));
+            }
+        }
+    }
+    if errors > 0 {
+        bail!("{} ping(s) out of {} failed", errors, requests.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{find_requests, PingRequest};
+    use site::Site;
+
+    fn test_site() -> Site {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_site");
+        Site::new(&path, path.join("config.toml")).unwrap()
+    }
+
+    #[test]
+    fn can_find_ping_requests() {
+        let mut site = test_site();
+        site.config.ping.websub_hubs = vec!["https://hub.example.com/".to_string()];
+        site.config.ping.sitemap_endpoints = vec!["https://search.example.com/ping".to_string()];
+
+        assert_eq!(
+            find_requests(&site).unwrap(),
+            vec![
+                PingRequest::WebSub {
+                    hub: "https://hub.example.com/".to_string(),
+                    feed: "https://replace-this-with-your-url.com/atom.xml".to_string(),
+                },
+                PingRequest::Sitemap {
+                    url: "https://search.example.com/ping?sitemap=https%3A%2F%2Freplace-this-with-your-url.com%2Fsitemap.xml".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn no_websub_requests_without_feeds() {
+        let mut site = test_site();
+        site.config.ping.websub_hubs = vec!["https://hub.example.com/".to_string()];
+        for language in site.config.languages.values_mut() {
+            language.generate_feed = false;
+        }
+        assert!(find_requests(&site).unwrap().is_empty());
+    }
+
+    #[test]
+    fn errors_on_invalid_endpoint() {
+        let mut site = test_site();
+        site.config.ping.sitemap_endpoints = vec!["not a url".to_string()];
+        assert!(find_requests(&site).is_err());
+    }
+}
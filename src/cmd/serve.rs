@@ -22,34 +22,38 @@
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
 use std::fs::{read_dir, remove_dir_all};
-use std::net::{SocketAddrV4, TcpListener};
+use std::net::{SocketAddr, SocketAddrV4, TcpListener};
 use std::path::{Path, PathBuf, MAIN_SEPARATOR};
 use std::sync::mpsc::channel;
+use std::sync::{Arc, RwLock};
 use std::thread;
 use std::time::{Duration, Instant};
 
-use hyper::header;
+use hyper::header::{self, HeaderMap, HeaderName, HeaderValue};
+use hyper::server::conn::Http;
 use hyper::server::Server;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Method, Request, Response, StatusCode};
-use mime_guess::from_path as mimetype_from_path;
-use time::macros::format_description;
-use time::{OffsetDateTime, UtcOffset};
-
+use libs::log::{debug, trace};
 use libs::percent_encoding;
 use libs::serde_json;
-use notify::{watcher, RecursiveMode, Watcher};
+use mime_guess::from_path as mimetype_from_path;
+use notify::{watcher, DebouncedEvent, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::TlsAcceptor;
 use ws::{Message, Sender, WebSocket};
 
+use config::env_overlay_path;
 use errors::{anyhow, Context, Result};
 use libs::globset::GlobSet;
 use libs::relative_path::{RelativePath, RelativePathBuf};
 use pathdiff::diff_paths;
 use site::sass::compile_sass;
-use site::{Site, SITE_CONTENT};
+use site::{Site, SITE_BINARY_CONTENT, SITE_CONTENT};
 use utils::fs::copy_file;
 
 use crate::messages;
+use crate::tls;
 use std::ffi::OsStr;
 
 #[derive(Debug, PartialEq)]
@@ -69,13 +73,94 @@ enum WatchMode {
     Condition(bool),
 }
 
+/// The native watchers miss events on some filesystems, like NFS mounts or some Docker
+/// bind mounts, where polling is the only option
+enum SiteWatcher {
+    Native(RecommendedWatcher),
+    Poll(PollWatcher),
+}
+
+impl SiteWatcher {
+    fn new(
+        tx: std::sync::mpsc::Sender<DebouncedEvent>,
+        poll_interval: Option<Duration>,
+    ) -> notify::Result<SiteWatcher> {
+        match poll_interval {
+            Some(interval) => Ok(SiteWatcher::Poll(PollWatcher::new(tx, interval)?)),
+            None => Ok(SiteWatcher::Native(watcher(tx, Duration::from_secs(1))?)),
+        }
+    }
+
+    fn watch(&mut self, path: PathBuf, mode: RecursiveMode) -> notify::Result<()> {
+        match self {
+            SiteWatcher::Native(w) => w.watch(path, mode),
+            SiteWatcher::Poll(w) => w.watch(path, mode),
+        }
+    }
+}
+
 static METHOD_NOT_ALLOWED_TEXT: &[u8] = b"Method Not Allowed";
 static NOT_FOUND_TEXT: &[u8] = b"Not Found";
 
 // This is dist/livereload.min.js from the LiveReload.js v3.2.4 release
 const LIVE_RELOAD: &str = include_str!("livereload.js");
 
-async fn handle_request(req: Request<Body>, mut root: PathBuf) -> Result<Response<Body>> {
+/// Makes sure the prefix given to `--path-prefix` starts with a slash and doesn't end with one,
+/// so `preview`, `/preview` and `/preview/` all serve the site under `/preview/`
+fn normalize_path_prefix(prefix: &str) -> String {
+    let prefix = prefix.trim_matches('/');
+    if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", prefix)
+    }
+}
+
+/// Removes the path prefix from the path of a request, `None` if it's outside of the prefix
+fn strip_path_prefix<'a>(path: &'a str, path_prefix: &str) -> Option<&'a str> {
+    let stripped = path.strip_prefix(path_prefix)?;
+    if stripped.is_empty() {
+        Some("/")
+    } else if stripped.starts_with('/') {
+        Some(stripped)
+    } else {
+        None
+    }
+}
+
+/// Parses the `serve.headers` of the configuration
+fn parse_headers(site: &Site) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &site.config.serve.headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .with_context(|| format!("Invalid header name `{}` in `serve.headers`", name))?;
+        let value = HeaderValue::from_str(value).with_context(|| {
+            format!("Invalid value for the header `{}` in `serve.headers`", name)
+        })?;
+        headers.insert(name, value);
+    }
+    Ok(headers)
+}
+
+/// Adds the headers set in the configuration to the response, to match the production ones
+async fn handle_request_with_headers(
+    req: Request<Body>,
+    root: PathBuf,
+    path_prefix: String,
+    headers: Arc<RwLock<HeaderMap>>,
+) -> Result<Response<Body>> {
+    let mut response = handle_request(req, root, path_prefix).await?;
+    for (name, value) in headers.read().unwrap().iter() {
+        response.headers_mut().insert(name, value.clone());
+    }
+    Ok(response)
+}
+
+async fn handle_request(
+    req: Request<Body>,
+    mut root: PathBuf,
+    path_prefix: String,
+) -> Result<Response<Body>> {
     let original_root = root.clone();
     let mut path = RelativePathBuf::new();
     // https://zola.discourse.group/t/percent-encoding-for-slugs/736
@@ -83,6 +168,10 @@ async fn handle_request(req: Request<Body>, mut root: PathBuf) -> Result<Respons
         Ok(d) => d,
         Err(_) => return Ok(not_found()),
     };
+    let decoded = match strip_path_prefix(&decoded, &path_prefix) {
+        Some(d) => d,
+        None => return Ok(not_found()),
+    };
 
     for c in decoded.split('/') {
         path.push(c);
@@ -100,6 +189,16 @@ async fn handle_request(req: Request<Body>, mut root: PathBuf) -> Result<Respons
     if let Some(content) = SITE_CONTENT.read().unwrap().get(&path) {
         return Ok(in_memory_content(&path, content));
     }
+    if let Some(content) = SITE_BINARY_CONTENT.read().unwrap().get(&path) {
+        return Ok(Response::builder()
+            .header(
+                header::CONTENT_TYPE,
+                mimetype_from_path(path.as_str()).first_or_octet_stream().essence_str(),
+            )
+            .status(StatusCode::OK)
+            .body(content.clone().into())
+            .expect("Could not build the in-memory file response"));
+    }
 
     // Handle only `GET`/`HEAD` requests
     match *req.method() {
@@ -239,29 +338,35 @@ fn create_new_site(
     interface_port: u16,
     output_dir: Option<&Path>,
     base_url: &str,
+    path_prefix: &str,
+    https: bool,
     config_file: &Path,
+    env: Option<&str>,
     include_drafts: bool,
     ws_port: Option<u16>,
 ) -> Result<(Site, String)> {
     SITE_CONTENT.write().unwrap().clear();
+    SITE_BINARY_CONTENT.write().unwrap().clear();
 
-    let mut site = Site::new(root_dir, config_file)?;
+    let mut site = Site::new_in_env(root_dir, config_file, env)?;
     let address = format!("{}:{}", interface, interface_port);
 
     let base_url = if base_url == "/" {
-        String::from("/")
+        format!("{}/", path_prefix)
     } else {
-        let base_address = format!("{}:{}", base_url, interface_port);
+        let base_address = format!("{}:{}{}", base_url, interface_port, path_prefix);
+        let scheme = if https { "https" } else { "http" };
 
         if site.config.base_url.ends_with('/') {
-            format!("http://{}/", base_address)
+            format!("{}://{}/", scheme, base_address)
         } else {
-            format!("http://{}", base_address)
+            format!("{}://{}", scheme, base_address)
         }
     };
 
     site.enable_serve_mode();
     site.set_base_url(base_url);
+    site.set_serve_path_prefix(path_prefix);
     if let Some(output_dir) = output_dir {
         site.set_output_path(output_dir);
     }
@@ -280,27 +385,35 @@ fn create_new_site(
     Ok((site, address))
 }
 
-#[allow(clippy::too_many_arguments)]
+#[allow(clippy::too_many_arguments, clippy::result_large_err)]
 pub fn serve(
     root_dir: &Path,
     interface: &str,
     interface_port: u16,
     output_dir: Option<&Path>,
     base_url: &str,
+    path_prefix: Option<&str>,
     config_file: &Path,
+    env: Option<&str>,
     open: bool,
     include_drafts: bool,
     fast_rebuild: bool,
-    utc_offset: UtcOffset,
+    watch_poll: Option<Duration>,
+    tls_config: Option<Arc<ServerConfig>>,
 ) -> Result<()> {
     let start = Instant::now();
+    let https = tls_config.is_some();
+    let path_prefix = normalize_path_prefix(path_prefix.unwrap_or_default());
     let (mut site, address) = create_new_site(
         root_dir,
         interface,
         interface_port,
         output_dir,
         base_url,
+        &path_prefix,
+        https,
         config_file,
+        env,
         include_drafts,
         None,
     )?;
@@ -311,29 +424,55 @@ pub fn serve(
         Ok(a) => a,
         Err(_) => return Err(anyhow!("Invalid address: {}.", address)),
     };
-    if (TcpListener::bind(&bind_address)).is_err() {
+    if (TcpListener::bind(bind_address)).is_err() {
         return Err(anyhow!("Cannot start server on address {}.", address));
     }
 
     let config_path = PathBuf::from(config_file);
-    let config_path_rel =
-        diff_paths(&config_path, &root_dir).unwrap_or_else(|| config_path.clone());
+    let config_path_rel = diff_paths(&config_path, root_dir).unwrap_or_else(|| config_path.clone());
+    // The overlay of the environment is watched like the config file
+    let mut config_paths = vec![config_path.clone()];
+    let mut config_paths_rel = vec![config_path_rel.to_string_lossy().to_string()];
+    if let Some(env) = env {
+        let overlay_path = env_overlay_path(&config_path, env)?;
+        config_paths_rel.push(
+            diff_paths(&overlay_path, root_dir)
+                .unwrap_or_else(|| overlay_path.clone())
+                .to_string_lossy()
+                .to_string(),
+        );
+        config_paths.push(overlay_path);
+    }
 
     // An array of (path, WatchMode) where the path should be watched for changes,
     // and the WatchMode value indicates whether this file/folder must exist for
     // zola serve to operate
-    let watch_this = vec![
-        (config_path_rel.to_str().unwrap_or("config.toml"), WatchMode::Required),
+    let mut watch_this: Vec<_> =
+        config_paths_rel.iter().map(|p| (p.as_str(), WatchMode::Required)).collect();
+    watch_this.extend([
         ("content", WatchMode::Required),
         ("sass", WatchMode::Condition(site.config.compile_sass)),
         ("static", WatchMode::Optional),
         ("templates", WatchMode::Optional),
         ("themes", WatchMode::Condition(site.config.theme.is_some())),
-    ];
+    ]);
+    watch_this.extend(site.config.templates.load.iter().map(|d| (d.as_str(), WatchMode::Optional)));
+    // The paths of the events are in the canonical root directory
+    let template_dirs: Vec<_> = site
+        .config
+        .templates
+        .load
+        .iter()
+        .filter_map(|d| root_dir.join(d).canonicalize().ok())
+        .collect();
 
     // Setup watchers
+    if watch_poll == Some(Duration::ZERO) {
+        return Err(anyhow!("The polling interval of the watcher needs to be above 0."));
+    }
     let (tx, rx) = channel();
-    let mut watcher = watcher(tx, Duration::from_secs(1)).unwrap();
+    let mut watcher =
+        SiteWatcher::new(tx, watch_poll).context("Failed to start the file watcher")?;
 
     // We watch for changes on the filesystem for every entry in watch_this
     // Will fail if either:
@@ -363,36 +502,75 @@ pub fn serve(
     // output path is going to need to be moved later on, so clone it for the
     // http closure to avoid contention.
     let static_root = output_path.clone();
+    let server_path_prefix = path_prefix.clone();
+    let headers = Arc::new(RwLock::new(parse_headers(&site)?));
+    let server_headers = headers.clone();
+    let scheme = if https { "https" } else { "http" };
+    let server_url = format!("{}://{}{}/", scheme, address, path_prefix);
+    let server_tls_config = tls_config.clone();
     let broadcaster = {
         thread::spawn(move || {
-            let addr = address.parse().unwrap();
+            let addr: SocketAddr = address.parse().unwrap();
 
             let rt = tokio::runtime::Builder::new_current_thread()
                 .enable_all()
                 .build()
                 .expect("Could not build tokio runtime");
 
-            rt.block_on(async {
-                let make_service = make_service_fn(move |_| {
-                    let static_root = static_root.clone();
-
-                    async {
-                        Ok::<_, hyper::Error>(service_fn(move |req| {
-                            handle_request(req, static_root.clone())
-                        }))
-                    }
-                });
-
-                let server = Server::bind(&addr).serve(make_service);
+            let service = move || {
+                let static_root = static_root.clone();
+                let path_prefix = server_path_prefix.clone();
+                let headers = server_headers.clone();
+                service_fn(move |req| {
+                    handle_request_with_headers(
+                        req,
+                        static_root.clone(),
+                        path_prefix.clone(),
+                        headers.clone(),
+                    )
+                })
+            };
 
-                println!("Web server is available at http://{}\n", &address);
+            rt.block_on(async {
+                console::plain(&format!("Web server is available at {}\n", &server_url));
                 if open {
-                    if let Err(err) = open::that(format!("http://{}", &address)) {
-                        eprintln!("Failed to open URL in your browser: {}", err);
+                    if let Err(err) = open::that(&server_url) {
+                        console::warn(&format!("Failed to open URL in your browser: {}", err));
                     }
                 }
 
-                server.await.expect("Could not start web server");
+                match server_tls_config {
+                    None => {
+                        let make_service = make_service_fn(move |_| {
+                            let service = service();
+                            async { Ok::<_, hyper::Error>(service) }
+                        });
+                        Server::bind(&addr)
+                            .serve(make_service)
+                            .await
+                            .expect("Could not start web server");
+                    }
+                    Some(config) => {
+                        let listener = tokio::net::TcpListener::bind(addr)
+                            .await
+                            .expect("Could not start web server");
+                        let acceptor = TlsAcceptor::from(config);
+                        loop {
+                            let stream = match listener.accept().await {
+                                Ok((stream, _)) => stream,
+                                Err(_) => continue,
+                            };
+                            let acceptor = acceptor.clone();
+                            let service = service();
+                            tokio::spawn(async move {
+                                // Browsers close the connection when they refuse the certificate
+                                if let Ok(stream) = acceptor.accept(stream).await {
+                                    let _ = Http::new().serve_connection(stream, service).await;
+                                }
+                            });
+                        }
+                    }
+                }
             });
         });
 
@@ -417,9 +595,33 @@ pub fn serve(
 
         let broadcaster = ws_server.broadcaster();
 
-        let ws_server = ws_server
-            .bind(&*ws_address)
-            .map_err(|_| anyhow!("Cannot bind to address {} for the websocket server. Maybe the port is already in use?", &ws_address))?;
+        let ws_bind_error = || {
+            anyhow!("Cannot bind to address {} for the websocket server. Maybe the port is already in use?", &ws_address)
+        };
+        let ws_server = match tls_config {
+            None => ws_server.bind(&*ws_address).map_err(|_| ws_bind_error())?,
+            Some(config) => {
+                // Pages served over HTTPS can only connect to secure websockets: the websocket
+                // server listens locally and TLS is handled in front of it
+                let ws_server = ws_server.bind("127.0.0.1:0").map_err(|_| ws_bind_error())?;
+                let ws_local_address = ws_server.local_addr()?;
+                let listener =
+                    std::net::TcpListener::bind(&*ws_address).map_err(|_| ws_bind_error())?;
+                listener.set_nonblocking(true)?;
+                thread::spawn(move || {
+                    let rt = tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()
+                        .expect("Could not build tokio runtime");
+                    rt.block_on(async {
+                        let listener = tokio::net::TcpListener::from_std(listener)
+                            .expect("Could not start the websocket server");
+                        tls::forward_tls(listener, ws_local_address, config).await;
+                    });
+                });
+                ws_server
+            }
+        };
 
         thread::spawn(move || {
             ws_server.run().unwrap();
@@ -428,19 +630,19 @@ pub fn serve(
         broadcaster
     };
 
-    println!(
+    console::plain(&format!(
         "Listening for changes in {}{}{{{}}}",
         root_dir.display(),
         MAIN_SEPARATOR,
         watchers.join(",")
-    );
+    ));
 
-    println!("Press Ctrl+C to stop\n");
+    console::plain("Press Ctrl+C to stop\n");
     // Delete the output folder on ctrl+C
     ctrlc::set_handler(move || {
         match remove_dir_all(&output_path) {
             Ok(()) => (),
-            Err(e) => println!("Errored while deleting output folder: {}", e),
+            Err(e) => console::error(&format!("Errored while deleting output folder: {}", e)),
         }
         ::std::process::exit(0);
     })
@@ -455,20 +657,27 @@ pub fn serve(
             format!("-> Sass file changed {}", path.display())
         };
         console::info(&msg);
-        rebuild_done_handling(
-            &broadcaster,
-            compile_sass(&site.base_path, &site.output_path),
-            &partial_path.to_string_lossy(),
-        );
+        // The pages need to be rendered again if they contain the hash of a compiled file.
+        // Reloading only the CSS would make it fail the integrity check of the old page
+        if site.uses_file_hashes() {
+            rebuild_done_handling(&broadcaster, site.build(), "/x.js");
+        } else {
+            rebuild_done_handling(
+                &broadcaster,
+                compile_sass(&site.base_path, &site.output_path),
+                &partial_path.to_string_lossy(),
+            );
+        }
     };
 
     let reload_templates = |site: &mut Site, path: &Path| {
-        rebuild_done_handling(&broadcaster, site.reload_templates(), &path.to_string_lossy());
+        rebuild_done_handling(&broadcaster, site.reload_template(path), &path.to_string_lossy());
     };
 
     let copy_static = |site: &Site, path: &Path, partial_path: &Path| {
-        // Do nothing if the file/dir was deleted
-        if !path.exists() {
+        // Do nothing if the file/dir was deleted or isn't copied
+        if !path.exists() || site.is_ignored_static_file(path) {
+            debug!(target: "watch", "Ignoring {}: deleted or not copied", path.display());
             return;
         }
 
@@ -479,7 +688,10 @@ pub fn serve(
         };
 
         console::info(&msg);
-        if path.is_dir() {
+        if site.uses_file_hashes() {
+            // Building copies the static files as well, and a full page reload picks up the new hashes
+            rebuild_done_handling(&broadcaster, site.build(), "/x.js");
+        } else if path.is_dir() {
             rebuild_done_handling(
                 &broadcaster,
                 site.copy_static_directories(),
@@ -488,7 +700,12 @@ pub fn serve(
         } else {
             rebuild_done_handling(
                 &broadcaster,
-                copy_file(path, &site.output_path, &site.static_path, site.config.hard_link_static),
+                copy_file(
+                    path,
+                    &site.output_path,
+                    &site.static_path,
+                    site.hard_links_static_file(path),
+                ),
                 &partial_path.to_string_lossy(),
             );
         }
@@ -500,14 +717,24 @@ pub fn serve(
         interface_port,
         output_dir,
         base_url,
+        &path_prefix,
+        https,
         config_file,
+        env,
         include_drafts,
         ws_port,
     ) {
-        Ok((s, _)) => {
-            rebuild_done_handling(&broadcaster, Ok(()), "/x.js");
-            Some(s)
-        }
+        Ok((s, _)) => match parse_headers(&s) {
+            Ok(h) => {
+                *headers.write().unwrap() = h;
+                rebuild_done_handling(&broadcaster, Ok(()), "/x.js");
+                Some(s)
+            }
+            Err(e) => {
+                messages::unravel_errors("Failed to build the site", &e);
+                None
+            }
+        },
         Err(e) => {
             messages::unravel_errors("Failed to build the site", &e);
             None
@@ -517,6 +744,7 @@ pub fn serve(
     loop {
         match rx.recv() {
             Ok(event) => {
+                trace!(target: "watch", "{:?}", event);
                 let can_do_fast_reload = !matches!(event, Remove(_));
 
                 match event {
@@ -524,31 +752,29 @@ pub fn serve(
                     // https://github.com/passcod/notify/issues/150#issuecomment-494912080
                     Rename(_, path) | Create(path) | Write(path) | Remove(path) | Chmod(path) => {
                         if is_ignored_file(&site.config.ignored_content_globset, &path) {
+                            debug!(
+                                target: "watch",
+                                "Ignoring {}: matches `ignored_content`",
+                                path.display()
+                            );
                             continue;
                         }
 
                         if is_temp_file(&path) {
+                            debug!(target: "watch", "Ignoring {}: temporary file", path.display());
                             continue;
                         }
 
                         // We only care about changes in non-empty folders
                         if path.is_dir() && is_folder_empty(&path) {
+                            debug!(target: "watch", "Ignoring {}: empty folder", path.display());
                             continue;
                         }
 
-                        let format =
-                            format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
-                        let current_time =
-                            OffsetDateTime::now_utc().to_offset(utc_offset).format(&format);
-                        if let Ok(time_str) = current_time {
-                            println!("Change detected @ {}", time_str);
-                        } else {
-                            // if formatting fails for some reason
-                            println!("Change detected");
-                        };
+                        console::plain("Change detected");
 
                         let start = Instant::now();
-                        match detect_change_kind(root_dir, &path, &config_path) {
+                        match detect_change_kind(root_dir, &path, &config_paths, &template_dirs) {
                             (ChangeKind::Content, _) => {
                                 console::info(&format!("-> Content changed {}", path.display()));
 
@@ -606,7 +832,7 @@ pub fn serve(
                                         site = s;
                                     }
                                 } else {
-                                    println!("Reloading only template");
+                                    console::plain("Reloading only template");
                                     // A normal template changed, no need to re-render Markdown.
                                     reload_templates(&mut site, &path)
                                 }
@@ -630,7 +856,7 @@ pub fn serve(
                         };
                         messages::report_elapsed_time(start);
                     }
-                    _ => {}
+                    _ => debug!(target: "watch", "Ignoring {:?}", event),
                 }
             }
             Err(e) => console::error(&format!("Watch error: {:?}", e)),
@@ -674,13 +900,21 @@ fn is_temp_file(path: &Path) -> bool {
 
 /// Detect what changed from the given path so we have an idea what needs
 /// to be reloaded
-fn detect_change_kind(pwd: &Path, path: &Path, config_path: &Path) -> (ChangeKind, PathBuf) {
+/// The directories of `templates.load` are reloaded like a theme, with the whole site.
+fn detect_change_kind(
+    pwd: &Path,
+    path: &Path,
+    config_paths: &[PathBuf],
+    template_dirs: &[PathBuf],
+) -> (ChangeKind, PathBuf) {
     let mut partial_path = PathBuf::from("/");
     partial_path.push(path.strip_prefix(pwd).unwrap_or(path));
 
     let change_kind = if partial_path.starts_with("/templates") {
         ChangeKind::Templates
-    } else if partial_path.starts_with("/themes") {
+    } else if partial_path.starts_with("/themes")
+        || template_dirs.iter().any(|d| path.starts_with(d))
+    {
         ChangeKind::Themes
     } else if partial_path.starts_with("/content") {
         ChangeKind::Content
@@ -688,7 +922,7 @@ fn detect_change_kind(pwd: &Path, path: &Path, config_path: &Path) -> (ChangeKin
         ChangeKind::StaticFiles
     } else if partial_path.starts_with("/sass") {
         ChangeKind::Sass
-    } else if path == config_path {
+    } else if config_paths.iter().any(|p| p == path) {
         ChangeKind::Config
     } else {
         unreachable!("Got a change in an unexpected path: {}", partial_path.display());
@@ -708,7 +942,11 @@ fn is_folder_empty(dir: &Path) -> bool {
 mod tests {
     use std::path::{Path, PathBuf};
 
-    use super::{detect_change_kind, is_temp_file, ChangeKind};
+    use super::{
+        detect_change_kind, is_temp_file, normalize_path_prefix, parse_headers, strip_path_prefix,
+        ChangeKind,
+    };
+    use site::Site;
 
     #[test]
     fn can_recognize_temp_files() {
@@ -777,10 +1015,46 @@ mod tests {
         ];
 
         for (expected, pwd, path, config_filename) in test_cases {
-            assert_eq!(expected, detect_change_kind(pwd, path, config_filename));
+            assert_eq!(
+                expected,
+                detect_change_kind(pwd, path, &[config_filename.to_path_buf()], &[])
+            );
         }
     }
 
+    #[test]
+    fn can_detect_changes_of_env_overlay() {
+        let config_paths = vec![
+            PathBuf::from("/home/vincent/site/config.toml"),
+            PathBuf::from("/home/vincent/site/config.staging.toml"),
+        ];
+        assert_eq!(
+            (ChangeKind::Config, PathBuf::from("/config.staging.toml")),
+            detect_change_kind(
+                Path::new("/home/vincent/site"),
+                Path::new("/home/vincent/site/config.staging.toml"),
+                &config_paths,
+                &[]
+            )
+        );
+    }
+
+    #[test]
+    fn can_detect_changes_of_loaded_template_dirs() {
+        let config_paths = vec![PathBuf::from("/home/vincent/site/config.toml")];
+        let template_dirs = vec![PathBuf::from("/home/vincent/shared/templates")];
+        assert_eq!(
+            ChangeKind::Themes,
+            detect_change_kind(
+                Path::new("/home/vincent/site"),
+                Path::new("/home/vincent/shared/templates/base.html"),
+                &config_paths,
+                &template_dirs
+            )
+            .0
+        );
+    }
+
     #[test]
     #[cfg(windows)]
     fn windows_path_handling() {
@@ -788,7 +1062,7 @@ mod tests {
         let pwd = Path::new(r#"C:\Users\johan\site"#);
         let path = Path::new(r#"C:\Users\johan\site\templates\hello.html"#);
         let config_filename = Path::new(r#"C:\Users\johan\site\config.toml"#);
-        assert_eq!(expected, detect_change_kind(pwd, path, config_filename));
+        assert_eq!(expected, detect_change_kind(pwd, path, &[config_filename.to_path_buf()], &[]));
     }
 
     #[test]
@@ -797,6 +1071,40 @@ mod tests {
         let pwd = Path::new("/home/johan/site");
         let path = Path::new("templates/hello.html");
         let config_filename = Path::new("config.toml");
-        assert_eq!(expected, detect_change_kind(pwd, path, config_filename));
+        assert_eq!(expected, detect_change_kind(pwd, path, &[config_filename.to_path_buf()], &[]));
+    }
+
+    #[test]
+    fn can_normalize_path_prefix() {
+        assert_eq!(normalize_path_prefix(""), "");
+        assert_eq!(normalize_path_prefix("/"), "");
+        assert_eq!(normalize_path_prefix("preview"), "/preview");
+        assert_eq!(normalize_path_prefix("/preview/"), "/preview");
+        assert_eq!(normalize_path_prefix("/company/preview/"), "/company/preview");
+    }
+
+    #[test]
+    fn can_strip_path_prefix() {
+        assert_eq!(strip_path_prefix("/posts/", ""), Some("/posts/"));
+        assert_eq!(strip_path_prefix("/preview/posts/", "/preview"), Some("/posts/"));
+        assert_eq!(strip_path_prefix("/preview", "/preview"), Some("/"));
+        assert_eq!(strip_path_prefix("/previews/posts/", "/preview"), None);
+        assert_eq!(strip_path_prefix("/posts/", "/preview"), None);
+    }
+
+    #[test]
+    fn can_parse_configured_headers() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_site");
+        let mut site = Site::new(&path, path.join("config.toml")).unwrap();
+        site.config
+            .serve
+            .headers
+            .insert("Cross-Origin-Opener-Policy".to_string(), "same-origin".to_string());
+
+        let headers = parse_headers(&site).unwrap();
+        assert_eq!(headers["cross-origin-opener-policy"], "same-origin");
+
+        site.config.serve.headers.insert("Not a header".to_string(), "value".to_string());
+        assert!(parse_headers(&site).is_err());
     }
 }
@@ -1,5 +1,5 @@
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use cli::{Cli, Command};
 use utils::net::{get_available_port, port_is_available};
@@ -11,16 +11,17 @@ mod cli;
 mod cmd;
 mod messages;
 mod prompt;
+mod tls;
 
 fn get_config_file_path(dir: &Path, config_path: &Path) -> (PathBuf, PathBuf) {
     let root_dir = dir
         .ancestors()
-        .find(|a| a.join(&config_path).exists())
+        .find(|a| a.join(config_path).exists())
         .unwrap_or_else(|| panic!("could not find directory containing config file"));
 
     // if we got here we found root_dir so config file should exist so we can unwrap safely
     let config_file = root_dir
-        .join(&config_path)
+        .join(config_path)
         .canonicalize()
         .unwrap_or_else(|_| panic!("could not find directory containing config file"));
     (root_dir.to_path_buf(), config_file)
@@ -28,9 +29,11 @@ fn get_config_file_path(dir: &Path, config_path: &Path) -> (PathBuf, PathBuf) {
 
 fn main() {
     let cli = Cli::parse();
+    console::init(cli.verbose, cli.quiet);
     let cli_dir: PathBuf = cli.root.canonicalize().unwrap_or_else(|_| {
         panic!("Could not find canonical path of root dir: {}", cli.root.display())
     });
+    let env = cli.env.as_deref().filter(|e| !e.is_empty());
 
     match cli.command {
         Command::Init { name, force } => {
@@ -39,16 +42,19 @@ fn main() {
                 std::process::exit(1);
             }
         }
-        Command::Build { base_url, output_dir, drafts } => {
+        Command::Build { base_url, output_dir, drafts, offline_mirror, output_report } => {
             console::info("Building site...");
             let start = Instant::now();
             let (root_dir, config_file) = get_config_file_path(&cli_dir, &cli.config);
             match cmd::build(
                 &root_dir,
                 &config_file,
+                env,
                 base_url.as_deref(),
                 output_dir.as_deref(),
                 drafts,
+                offline_mirror,
+                output_report.as_deref(),
             ) {
                 Ok(()) => messages::report_elapsed_time(start),
                 Err(e) => {
@@ -57,7 +63,20 @@ fn main() {
                 }
             }
         }
-        Command::Serve { interface, mut port, output_dir, base_url, drafts, open, fast } => {
+        Command::Serve {
+            interface,
+            mut port,
+            output_dir,
+            base_url,
+            path_prefix,
+            drafts,
+            open,
+            fast,
+            watch_poll,
+            https,
+            cert,
+            key,
+        } => {
             if port != 1111 && !port_is_available(port) {
                 console::error("The requested port is not available");
                 std::process::exit(1);
@@ -70,7 +89,27 @@ fn main() {
                 });
             }
 
+            let tls_config = if https {
+                let cert_and_key = cert.as_deref().zip(key.as_deref());
+                let mut hosts = vec!["localhost".to_string(), "127.0.0.1".to_string()];
+                for host in [&interface, &base_url] {
+                    if host != "/" && !hosts.contains(host) {
+                        hosts.push(host.clone());
+                    }
+                }
+                match tls::server_config(cert_and_key, hosts) {
+                    Ok(config) => Some(config),
+                    Err(e) => {
+                        messages::unravel_errors("Failed to set up HTTPS", &e);
+                        std::process::exit(1);
+                    }
+                }
+            } else {
+                None
+            };
+
             let (root_dir, config_file) = get_config_file_path(&cli_dir, &cli.config);
+            console::enable_timestamps(UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC));
             console::info("Building site...");
             if let Err(e) = cmd::serve(
                 &root_dir,
@@ -78,21 +117,33 @@ fn main() {
                 port,
                 output_dir.as_deref(),
                 &base_url,
+                path_prefix.as_deref(),
                 &config_file,
+                env,
                 open,
                 drafts,
                 fast,
-                UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
+                watch_poll.map(Duration::from_millis),
+                tls_config,
             ) {
                 messages::unravel_errors("Failed to serve the site", &e);
                 std::process::exit(1);
             }
         }
-        Command::Check { drafts } => {
+        Command::Check { drafts, accessibility, validate_html } => {
             console::info("Checking site...");
             let start = Instant::now();
             let (root_dir, config_file) = get_config_file_path(&cli_dir, &cli.config);
-            match cmd::check(&root_dir, &config_file, None, None, drafts) {
+            match cmd::check(
+                &root_dir,
+                &config_file,
+                env,
+                None,
+                None,
+                drafts,
+                accessibility,
+                validate_html,
+            ) {
                 Ok(()) => messages::report_elapsed_time(start),
                 Err(e) => {
                     messages::unravel_errors("Failed to check the site", &e);
@@ -100,5 +151,41 @@ fn main() {
                 }
             }
         }
+        Command::Diff { base_url, against, drafts, html } => {
+            console::info("Building site to compare it...");
+            let (root_dir, config_file) = get_config_file_path(&cli_dir, &cli.config);
+            if let Err(e) = cmd::diff(
+                &root_dir,
+                &config_file,
+                env,
+                base_url.as_deref(),
+                against.as_deref(),
+                drafts,
+                html,
+            ) {
+                messages::unravel_errors("Failed to compare the builds", &e);
+                std::process::exit(1);
+            }
+        }
+        Command::Deploy { target, dry_run } => {
+            let (root_dir, config_file) = get_config_file_path(&cli_dir, &cli.config);
+            if let Err(e) = cmd::deploy(&root_dir, &config_file, env, target.as_deref(), dry_run) {
+                messages::unravel_errors("Failed to deploy the site", &e);
+                std::process::exit(1);
+            }
+        }
+        Command::Bench { pages, sections, terms, runs } => {
+            if let Err(e) = cmd::bench(pages, sections, terms, runs) {
+                messages::unravel_errors("Failed to run the benchmark", &e);
+                std::process::exit(1);
+            }
+        }
+        Command::Ping { dry_run } => {
+            let (root_dir, config_file) = get_config_file_path(&cli_dir, &cli.config);
+            if let Err(e) = cmd::ping(&root_dir, &config_file, env, dry_run) {
+                messages::unravel_errors("Failed to ping", &e);
+                std::process::exit(1);
+            }
+        }
     }
 }
@@ -2,30 +2,39 @@ use libs::time::Duration;
 use std::convert::TryInto;
 use std::time::Instant;
 
-use errors::Error;
+use errors::{Error, SourceError};
+use site::html_validation::HtmlIssues;
+use site::report::BuildSummary;
 use site::Site;
 
 /// Display in the console the number of pages/sections in the site
+/// and the orphan pages, which are not reachable from any section
 pub fn notify_site_size(site: &Site) {
     let library = site.library.read().unwrap();
-    println!(
+    let mut orphans = library.get_all_orphan_pages();
+    console::plain(&format!(
         "-> Creating {} pages ({} orphan) and {} sections",
         library.pages.len(),
-        library.get_all_orphan_pages().len(),
+        orphans.len(),
         library.sections.len() - 1, // -1 since we do not count the index as a section there
-    );
+    ));
+
+    orphans.sort_by(|a, b| a.file.path.cmp(&b.file.path));
+    for orphan in orphans {
+        console::plain(&format!("   - Orphan page: {} ({})", orphan.file.relative, orphan.path));
+    }
 }
 
 /// Display in the console only the number of pages/sections in the site
 pub fn check_site_summary(site: &Site) {
     let library = site.library.read().unwrap();
     let orphans = library.get_all_orphan_pages();
-    println!(
+    console::plain(&format!(
         "-> Site content: {} pages ({} orphan), {} sections",
         library.pages.len(),
         orphans.len(),
         library.sections.len() - 1, // -1 since we do not count the index as a section there
-    );
+    ));
 
     for orphan in orphans {
         console::warn(&format!("Orphan page found: {}", orphan.path));
@@ -52,6 +61,90 @@ pub fn warn_about_ignored_pages(site: &Site) {
     }
 }
 
+/// Display a warning in the console for the templates and shortcodes of the site that are never used
+pub fn warn_about_unused_templates(site: &Site) {
+    let unused = site.find_unused_templates();
+    if !unused.templates.is_empty() {
+        console::warn(&format!("{} template(s) never used:", unused.templates.len()));
+        for name in unused.templates {
+            console::warn(&format!("- templates/{}", name));
+        }
+    }
+    if !unused.shortcodes.is_empty() {
+        console::warn(&format!("{} shortcode(s) never used:", unused.shortcodes.len()));
+        for name in unused.shortcodes {
+            console::warn(&format!("- {}", name));
+        }
+    }
+}
+
+/// Display a warning in the console for each file whose content has accessibility issues
+pub fn warn_about_accessibility_issues(site: &Site) {
+    let files = site::accessibility::check_accessibility(site);
+    if files.is_empty() {
+        return;
+    }
+    let count: usize = files.iter().map(|(_, issues)| issues.len()).sum();
+    console::warn(&format!("{} accessibility issue(s) found in {} file(s):", count, files.len()));
+    for (path, issues) in files {
+        console::warn(&format!("- {}", path.display()));
+        for issue in issues {
+            console::warn(&format!("  - {}", issue));
+        }
+    }
+}
+
+/// Display a warning in the console for each page or section whose HTML is invalid, with the
+/// template that rendered it
+pub fn warn_about_html_issues(files: Vec<HtmlIssues>) {
+    if files.is_empty() {
+        return;
+    }
+    let count: usize = files.iter().map(|f| f.issues.len()).sum();
+    console::warn(&format!("{} HTML issue(s) found in {} file(s):", count, files.len()));
+    for file in files {
+        console::warn(&format!("- {} (rendered with `{}`)", file.source.display(), file.template));
+        for issue in file.issues {
+            console::warn(&format!("  - {}", issue));
+        }
+    }
+}
+
+/// Formats a size in bytes with the largest unit that keeps it above 1, eg `1.4 MB`
+fn format_size(bytes: u64) -> String {
+    let mut size = bytes as f64;
+    for unit in ["B", "kB", "MB"] {
+        if size < 1000.0 {
+            return if unit == "B" {
+                format!("{} B", bytes)
+            } else {
+                format!("{:.1} {}", size, unit)
+            };
+        }
+        size /= 1000.0;
+    }
+    format!("{:.1} GB", size)
+}
+
+/// Display in the console what the build generated
+pub fn print_build_summary(summary: &BuildSummary) {
+    console::plain("-> Build summary:");
+    console::plain(&format!(
+        "   {} pages ({} orphan), {} sections, {} taxonomy terms and {} paginated pages",
+        summary.pages,
+        summary.orphan_pages,
+        summary.sections,
+        summary.taxonomy_terms,
+        summary.paginated_pages
+    ));
+    console::plain(&format!("   {} static files copied", summary.static_files));
+    console::plain(&format!(
+        "   {} files in the output directory, {} in total",
+        summary.output_files,
+        format_size(summary.output_size)
+    ));
+}
+
 /// Print the time elapsed rounded to 1 decimal
 pub fn report_elapsed_time(instant: Instant) {
     let duration: Duration = instant.elapsed().try_into().unwrap();
@@ -76,4 +169,8 @@ pub fn unravel_errors(message: &str, error: &Error) {
         console::error(&format!("Reason: {}", e));
         cause = e.source();
     }
+    // Show the lines of the file the error comes from, if we know them
+    if let Some(source_error) = error.downcast_ref::<SourceError>() {
+        console::source_snippet(&source_error.location(), &source_error.lines, source_error.column);
+    }
 }
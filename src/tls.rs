@@ -0,0 +1,102 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use rustls_pemfile::Item;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::rustls::{Certificate, PrivateKey, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+use errors::{anyhow, bail, Context, Result};
+
+fn read_pem(path: &Path) -> Result<Vec<Item>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    rustls_pemfile::read_all(&mut BufReader::new(file))
+        .with_context(|| format!("Failed to read {} as PEM", path.display()))
+}
+
+/// Loads the certificate chain and private key given to `zola serve --cert --key`
+fn load_certificate(cert: &Path, key: &Path) -> Result<(Vec<Certificate>, PrivateKey)> {
+    let certs: Vec<_> = read_pem(cert)?
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::X509Certificate(der) => Some(Certificate(der)),
+            _ => None,
+        })
+        .collect();
+    if certs.is_empty() {
+        bail!("No certificate found in {}", cert.display());
+    }
+
+    let key = read_pem(key)?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::PKCS8Key(der) | Item::RSAKey(der) | Item::ECKey(der) => Some(PrivateKey(der)),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("No private key found in {}", key.display()))?;
+
+    Ok((certs, key))
+}
+
+/// Generates a self-signed certificate for the given host names. Browsers will show a
+/// warning the first time the site is opened.
+fn generate_certificate(hosts: Vec<String>) -> Result<(Vec<Certificate>, PrivateKey)> {
+    let generated = rcgen::generate_simple_self_signed(hosts)
+        .context("Failed to generate a self-signed certificate")?;
+    let cert = generated.serialize_der().context("Failed to generate a self-signed certificate")?;
+    let key = generated.serialize_private_key_der();
+    Ok((vec![Certificate(cert)], PrivateKey(key)))
+}
+
+/// The TLS configuration of `zola serve --https`: the given certificate if there is one,
+/// otherwise a self-signed one for `hosts`.
+pub fn server_config(
+    cert_and_key: Option<(&Path, &Path)>,
+    hosts: Vec<String>,
+) -> Result<Arc<ServerConfig>> {
+    let (certs, key) = match cert_and_key {
+        Some((cert, key)) => load_certificate(cert, key)?,
+        None => generate_certificate(hosts)?,
+    };
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("Invalid certificate or private key")?;
+    Ok(Arc::new(config))
+}
+
+/// Terminates TLS on `listener` and forwards the decrypted connections to `target`.
+/// Used for the live reload websocket server, which only speaks plain websockets.
+pub async fn forward_tls(listener: TcpListener, target: SocketAddr, config: Arc<ServerConfig>) {
+    let acceptor = TlsAcceptor::from(config);
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(_) => continue,
+        };
+        let acceptor = acceptor.clone();
+        tokio::spawn(async move {
+            let mut tls_stream = match acceptor.accept(stream).await {
+                Ok(s) => s,
+                Err(_) => return,
+            };
+            if let Ok(mut target_stream) = TcpStream::connect(target).await {
+                let _ = tokio::io::copy_bidirectional(&mut tls_stream, &mut target_stream).await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::server_config;
+
+    #[test]
+    fn can_generate_self_signed_config() {
+        assert!(server_config(None, vec!["localhost".to_string()]).is_ok());
+    }
+}